@@ -1,10 +1,17 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, Duration};
 use uuid::Uuid;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
 
+use crate::crypto::{derive_key_hkdf, EncryptedData, EncryptionAlgorithm, MasterKey};
 use crate::error::{VaultError, Result};
 
+mod strength;
+mod totp;
+pub use strength::*;
+pub use totp::*;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Role {
     Admin,
@@ -40,6 +47,10 @@ pub struct Session {
     pub role: Role,
     pub created_at: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
+    #[serde(default)]
+    pub namespace: Option<String>, // Set by `vault use`; commands fall back to it instead of "default"
+    #[serde(default)]
+    pub last_accessed: Option<DateTime<Utc>>, // Last successful command, for the inactivity lock
 }
 
 impl Session {
@@ -52,6 +63,8 @@ impl Session {
             role,
             created_at: now,
             expires_at: now + Duration::hours(duration_hours),
+            namespace: None,
+            last_accessed: Some(now),
         }
     }
 
@@ -67,56 +80,388 @@ impl Session {
     pub fn time_until_expiry(&self) -> Duration {
         self.expires_at - Utc::now()
     }
+
+    /// Whether no command has used the session for more than `minutes`.
+    /// Sessions saved before `last_accessed` existed count from login.
+    pub fn is_idle(&self, minutes: u64) -> bool {
+        let last_accessed = self.last_accessed.unwrap_or(self.created_at);
+        Utc::now() - last_accessed > Duration::minutes(minutes as i64)
+    }
 }
 
 pub struct SessionManager;
 
+/// Associated data of the sealed session file, so no other blob encrypted
+/// under the same key can be passed off as a session.
+const SESSION_FILE_AAD: &[u8] = b"vault-session-file";
+
 impl SessionManager {
-    pub fn get_session_path() -> PathBuf {
-        dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("vault")
-            .join("session")
+    /// One sealed file per session, named by its id, so several logins
+    /// (from different shells, say) can be listed and revoked one by one.
+    fn sessions_dir(config_dir: &Path) -> PathBuf {
+        config_dir.join("sessions")
     }
 
-    pub fn save_session(session: &Session) -> Result<()> {
-        let path = Self::get_session_path();
+    /// Id of the session commands run as; each login makes its own current.
+    fn current_session_path(config_dir: &Path) -> PathBuf {
+        config_dir.join("current_session")
+    }
+
+    /// The single session file earlier versions wrote.
+    fn legacy_session_path(config_dir: &Path) -> PathBuf {
+        config_dir.join("session")
+    }
+
+    #[cfg(not(test))]
+    fn wrapping_key_path() -> PathBuf {
+        crate::config::config_dir().join("session.key")
+    }
+
+    // Unit tests unlock vaults constantly; keep their key out of the real config dir
+    #[cfg(test)]
+    fn wrapping_key_path() -> PathBuf {
+        std::env::temp_dir().join("rvault-test-session.key")
+    }
+
+    /// Random per-install key that encrypts the master keys cached for
+    /// auto-unlock. It lives outside the database, so a copied `.db` alone
+    /// reveals no key. Created with 0600 permissions on first use.
+    pub fn session_wrapping_key() -> Result<Zeroizing<[u8; 32]>> {
+        let path = Self::wrapping_key_path();
+        loop {
+            match std::fs::read(&path).map(Zeroizing::new) {
+                Ok(bytes) if bytes.len() == 32 => {
+                    let mut key = Zeroizing::new([0u8; 32]);
+                    key.copy_from_slice(&bytes);
+                    return Ok(key);
+                }
+                Ok(_) => {
+                    return Err(VaultError::Config(format!(
+                        "{} is corrupt; delete it and log in again", path.display()
+                    )));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::create_wrapping_key(&path)?,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn create_wrapping_key(path: &Path) -> Result<()> {
+        use rand::RngCore;
+        use std::io::Write;
+
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
+        let mut key = Zeroizing::new([0u8; 32]);
+        rand::rngs::OsRng.fill_bytes(key.as_mut());
 
-        let content = serde_json::to_string_pretty(session)?;
-        std::fs::write(path, content)?;
+        // Write under a unique name, then link into place: linking fails if
+        // another process got there first, and readers never see a partial key
+        let staging = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(&staging)?.write_all(key.as_ref())?;
+        let linked = std::fs::hard_link(&staging, path);
+        std::fs::remove_file(&staging)?;
+        match linked {
+            Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Advisory lock serializing session file writes across vault processes.
+    /// Released when the returned file is dropped.
+    fn lock_session_files(config_dir: &Path) -> Result<std::fs::File> {
+        use fs2::FileExt;
+        std::fs::create_dir_all(config_dir)?;
+        let lock = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(config_dir.join("session.lock"))?;
+        lock.lock_exclusive()?;
+        Ok(lock)
+    }
+
+    /// Key the session file is sealed with, derived from the per-install
+    /// wrapping key so a session file only opens on the install that wrote it.
+    fn session_file_key() -> Result<MasterKey> {
+        let wrapping_key = Self::session_wrapping_key()?;
+        let key = derive_key_hkdf(wrapping_key.as_ref(), SESSION_FILE_AAD, b"session file")?;
+        Ok(MasterKey {
+            key: secrecy::Secret::new(*key),
+            algorithm: EncryptionAlgorithm::Aes256Gcm,
+        })
+    }
+
+    /// Encrypt a session for disk. The AES-GCM tag authenticates it, so an
+    /// edited file (a Reader's role changed to Admin, say) fails to open.
+    fn seal_session(session: &Session) -> Result<Vec<u8>> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(session)?);
+        let sealed = Self::session_file_key()?.encrypt_with_aad(&plaintext, SESSION_FILE_AAD)?;
+        Ok(bincode::serialize(&sealed)?)
+    }
+
+    fn open_session(data: &[u8]) -> Result<Session> {
+        let tampered = || VaultError::Auth("Session file was modified or written by another install; log in again".to_string());
+        let sealed: EncryptedData = bincode::deserialize(data).map_err(|_| tampered())?;
+        let plaintext = Zeroizing::new(Self::session_file_key()?
+            .decrypt_with_aad(&sealed, SESSION_FILE_AAD)
+            .map_err(|_| tampered())?);
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Save `session` and make it the current one.
+    pub fn save_session(session: &Session) -> Result<()> {
+        let config_dir = crate::config::config_dir();
+        let _lock = Self::lock_session_files(&config_dir)?;
+        Self::store_session(&config_dir, session)
+    }
+
+    fn store_session(config_dir: &Path, session: &Session) -> Result<()> {
+        Self::write_session_file(&Self::sessions_dir(config_dir).join(session.id.to_string()), session)?;
+        let current = Self::current_session_path(config_dir);
+        let staging = current.with_extension("tmp");
+        std::fs::write(&staging, session.id.to_string())?;
+        std::fs::rename(staging, current)?;
         Ok(())
     }
 
+    fn write_session_file(path: &Path, session: &Session) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write then rename so concurrent readers never see a partial file
+        let staging = path.with_extension("tmp");
+        std::fs::write(&staging, Self::seal_session(session)?)?;
+        std::fs::rename(staging, path)?;
+        Ok(())
+    }
+
+    /// The current session, or `None` if there is none, it expired or it
+    /// was revoked. A file that fails authentication is a `VaultError::Auth`.
     pub fn load_session() -> Result<Option<Session>> {
-        let path = Self::get_session_path();
-        if !path.exists() {
+        Self::load_session_in(&crate::config::config_dir())
+    }
+
+    fn load_session_in(config_dir: &Path) -> Result<Option<Session>> {
+        Self::migrate_legacy_session(config_dir)?;
+        let Some(id) = Self::current_session_id(config_dir)? else {
             return Ok(None);
+        };
+        match Self::read_session_file(&Self::sessions_dir(config_dir).join(id.to_string()))? {
+            Some(session) if session.is_valid() => Ok(Some(session)),
+            Some(_) => {
+                Self::clear_session_in(config_dir)?;
+                Ok(None)
+            }
+            None => Ok(None),
         }
+    }
 
-        let content = std::fs::read_to_string(path)?;
-        let session: Session = serde_json::from_str(&content)?;
+    fn current_session_id(config_dir: &Path) -> Result<Option<Uuid>> {
+        match std::fs::read_to_string(Self::current_session_path(config_dir)) {
+            Ok(id) => Ok(Uuid::parse_str(id.trim()).ok()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
 
-        if session.is_valid() {
-            Ok(Some(session))
-        } else {
-            Self::clear_session()?;
-            Ok(None)
+    /// Move a session saved by an earlier version into `sessions/`.
+    fn migrate_legacy_session(config_dir: &Path) -> Result<()> {
+        let legacy = Self::legacy_session_path(config_dir);
+        if !legacy.exists() {
+            return Ok(());
+        }
+        let _lock = Self::lock_session_files(config_dir)?;
+        // An unreadable one is dropped like an expired one; the user logs in again
+        if let Ok(Some(session)) = Self::read_session_file(&legacy) {
+            Self::store_session(config_dir, &session)?;
+        }
+        match std::fs::remove_file(&legacy) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
         }
     }
 
-    pub fn clear_session() -> Result<()> {
-        let path = Self::get_session_path();
-        if path.exists() {
+    /// Every unexpired session on this install, oldest first. Expired ones
+    /// are deleted along the way; unreadable ones are skipped.
+    pub fn list_sessions() -> Result<Vec<Session>> {
+        Self::list_sessions_in(&crate::config::config_dir())
+    }
+
+    fn list_sessions_in(config_dir: &Path) -> Result<Vec<Session>> {
+        Self::migrate_legacy_session(config_dir)?;
+        let _lock = Self::lock_session_files(config_dir)?;
+        let entries = match std::fs::read_dir(Self::sessions_dir(config_dir)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut sessions = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some() {
+                continue; // Staging file of a write in progress
+            }
+            match Self::read_session_file(&path) {
+                Ok(Some(session)) if session.is_valid() => sessions.push(session),
+                Ok(Some(_)) => std::fs::remove_file(&path)?,
+                _ => {}
+            }
+        }
+        sessions.sort_by_key(|session| session.created_at);
+        Ok(sessions)
+    }
+
+    /// Delete the session with `id`, logging out whichever shell uses it.
+    /// Returns whether it existed.
+    pub fn revoke_session(id: Uuid) -> Result<bool> {
+        Self::revoke_session_in(&crate::config::config_dir(), id)
+    }
+
+    fn revoke_session_in(config_dir: &Path, id: Uuid) -> Result<bool> {
+        let _lock = Self::lock_session_files(config_dir)?;
+        if Self::current_session_id(config_dir)? == Some(id) {
+            std::fs::remove_file(Self::current_session_path(config_dir))?;
+        }
+        match std::fs::remove_file(Self::sessions_dir(config_dir).join(id.to_string())) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn read_session_file(path: &Path) -> Result<Option<Session>> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        // Earlier versions wrote the session as plain JSON; drop it and log in again
+        if data.first() == Some(&b'{') {
             std::fs::remove_file(path)?;
+            return Ok(None);
         }
+        Self::open_session(&data).map(Some)
+    }
+
+    /// Log out of the current session.
+    pub fn clear_session() -> Result<()> {
+        Self::clear_session_in(&crate::config::config_dir())
+    }
+
+    fn clear_session_in(config_dir: &Path) -> Result<()> {
+        let Some(id) = Self::current_session_id(config_dir)? else {
+            return Ok(());
+        };
+        Self::revoke_session_in(config_dir, id)?;
         Ok(())
     }
 
+    /// Change the session's working namespace (`None` resets it to "default").
+    pub fn set_namespace(namespace: Option<String>) -> Result<Session> {
+        let mut session = Self::get_current_session()?;
+        session.namespace = namespace;
+        Self::save_session(&session)?;
+        Ok(session)
+    }
+
+    /// Record that the current session was just used.
+    pub fn touch_session() -> Result<()> {
+        let Some(mut session) = Self::load_session()? else {
+            return Ok(());
+        };
+        session.last_accessed = Some(Utc::now());
+        Self::save_session(&session)
+    }
+
     pub fn get_current_session() -> Result<Session> {
         Self::load_session()?
             .ok_or_else(|| VaultError::Auth("No valid session found".to_string()))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_file_is_sealed_and_edits_are_rejected() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("session");
+        let session = Session::new("acme".to_string(), "bob@acme.com".to_string(), Role::Reader, 24);
+
+        SessionManager::write_session_file(&path, &session).unwrap();
+        let data = std::fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&data).contains("Reader"));
+        let loaded = SessionManager::read_session_file(&path).unwrap().unwrap();
+        assert_eq!(loaded.id, session.id);
+        assert!(matches!(loaded.role, Role::Reader));
+
+        // Flipping any byte of the sealed file invalidates it
+        let mut tampered = data.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        std::fs::write(&path, tampered).unwrap();
+        assert!(matches!(SessionManager::read_session_file(&path), Err(VaultError::Auth(_))));
+
+        // A plaintext session from an earlier version is discarded, not trusted
+        let mut forged = session.clone();
+        forged.role = Role::Admin;
+        std::fs::write(&path, serde_json::to_vec(&forged).unwrap()).unwrap();
+        assert!(SessionManager::read_session_file(&path).unwrap().is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_session_goes_idle_after_inactivity_window() {
+        let mut session = Session::new("acme".to_string(), "bob@acme.com".to_string(), Role::Reader, 24);
+        assert!(!session.is_idle(15));
+
+        session.last_accessed = Some(Utc::now() - Duration::minutes(20));
+        assert!(session.is_idle(15));
+        assert!(!session.is_idle(30));
+
+        // Sessions written before the field existed count from login
+        session.last_accessed = None;
+        session.created_at = Utc::now() - Duration::hours(1);
+        assert!(session.is_idle(15));
+    }
+
+    #[test]
+    fn test_sessions_can_be_listed_and_revoked() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_dir = dir.path();
+        let first = Session::new("acme".to_string(), "alice@acme.com".to_string(), Role::Admin, 24);
+        let mut second = Session::new("globex".to_string(), "bob@globex.com".to_string(), Role::Reader, 24);
+        second.created_at = first.created_at + Duration::seconds(1);
+        let mut expired = Session::new("acme".to_string(), "carol@acme.com".to_string(), Role::Writer, 24);
+        expired.expires_at = Utc::now() - Duration::hours(1);
+
+        // A session saved by an earlier version is picked up as the current one
+        SessionManager::write_session_file(&SessionManager::legacy_session_path(config_dir), &first).unwrap();
+        assert_eq!(SessionManager::load_session_in(config_dir).unwrap().unwrap().id, first.id);
+        assert!(!SessionManager::legacy_session_path(config_dir).exists());
+
+        // Each login becomes current; expired sessions are dropped when listing
+        SessionManager::store_session(config_dir, &expired).unwrap();
+        SessionManager::store_session(config_dir, &second).unwrap();
+        assert_eq!(SessionManager::load_session_in(config_dir).unwrap().unwrap().id, second.id);
+        let ids = |sessions: Vec<Session>| sessions.into_iter().map(|session| session.id).collect::<Vec<_>>();
+        assert_eq!(ids(SessionManager::list_sessions_in(config_dir).unwrap()), [first.id, second.id]);
+
+        // Revoking the current session logs this shell out and leaves the rest
+        assert!(SessionManager::revoke_session_in(config_dir, second.id).unwrap());
+        assert!(!SessionManager::revoke_session_in(config_dir, second.id).unwrap());
+        assert!(SessionManager::load_session_in(config_dir).unwrap().is_none());
+        assert_eq!(ids(SessionManager::list_sessions_in(config_dir).unwrap()), [first.id]);
+    }
+}
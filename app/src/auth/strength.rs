@@ -0,0 +1,57 @@
+use zxcvbn::time_estimates::CrackTimes;
+
+/// Password strength as estimated by zxcvbn: a 0-4 score from its guess
+/// thresholds and the guesses behind it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PasswordStrength {
+    pub score: u8,
+    pub guesses_log10: f64,
+    crack_times: CrackTimes,
+}
+
+impl PasswordStrength {
+    pub fn label(&self) -> &'static str {
+        ["very weak", "weak", "fair", "strong", "very strong"][self.score as usize]
+    }
+    
+    /// Estimated time for an offline attacker to guess the password, at the
+    /// rate of a slow hash such as Argon2.
+    pub fn crack_time_display(&self) -> String {
+        self.crack_times.offline_slow_hashing_1e4_per_second().to_string()
+    }
+}
+
+/// Estimate how hard `password` is to guess. `user_inputs` are details an
+/// attacker would try first, such as the user's email or tenant name.
+pub fn estimate_strength(password: &str, user_inputs: &[&str]) -> PasswordStrength {
+    // zxcvbn matches user inputs as whole words, so split out their parts
+    let user_parts: Vec<&str> = user_inputs.iter()
+        .flat_map(|input| std::iter::once(*input).chain(input.split(|c: char| !c.is_alphanumeric())))
+        .filter(|part| part.chars().count() >= 3)
+        .collect();
+    
+    let entropy = zxcvbn::zxcvbn(password, &user_parts);
+    PasswordStrength {
+        score: entropy.score().into(),
+        guesses_log10: entropy.guesses_log10(),
+        crack_times: entropy.crack_times(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_strength_penalises_guessable_patterns() {
+        assert_eq!(estimate_strength("password", &[]).score, 0);
+        assert_eq!(estimate_strength("P@ssw0rd", &[]).score, 0);
+        assert!(estimate_strength("qwertyuiop", &[]).score <= 1);
+        assert!(estimate_strength("zorblax2024", &["zorblax@example.com"]).guesses_log10 < estimate_strength("zorblax2024", &[]).guesses_log10);
+        
+        let passphrase = estimate_strength("correct horse battery staple", &[]);
+        assert_eq!(passphrase.score, 4);
+        assert_eq!(passphrase.crack_time_display(), "centuries");
+        assert_eq!(estimate_strength("", &[]).crack_time_display(), "less than a second");
+    }
+}
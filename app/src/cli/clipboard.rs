@@ -0,0 +1,184 @@
+// System clipboard access for `vault get --copy`. Values are piped to the
+// platform's clipboard tool over stdin so they never show up in argv, and
+// nothing derived from them does either.
+
+use std::io::{BufRead, Write};
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Result};
+use rand::{rngs::OsRng, RngCore};
+use ring::hmac;
+
+/// Clipboard readers for this platform, tried in order.
+fn paste_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "windows") {
+        vec![("powershell", &["-NoProfile", "-Command", "Get-Clipboard -Raw"])]
+    } else if cfg!(target_os = "macos") {
+        vec![("pbpaste", &[])]
+    } else {
+        let mut commands: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(("wl-paste", &["--no-newline"]));
+        }
+        commands.push(("xclip", &["-selection", "clipboard", "-o"]));
+        commands.push(("xsel", &["--clipboard", "--output"]));
+        commands
+    }
+}
+
+/// Clipboard writers for this platform, tried in order.
+fn copy_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "windows") {
+        vec![("clip", &[])]
+    } else if cfg!(target_os = "macos") {
+        vec![("pbcopy", &[])]
+    } else {
+        let mut commands: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(("wl-copy", &[]));
+        }
+        commands.push(("xclip", &["-selection", "clipboard"]));
+        commands.push(("xsel", &["--clipboard", "--input"]));
+        commands
+    }
+}
+
+/// Put `value` on the system clipboard using the first available tool.
+pub fn copy_to_clipboard(value: &str) -> Result<()> {
+    let commands = copy_commands();
+    let mut failures = Vec::new();
+    for (program, args) in &commands {
+        let mut child = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(anyhow!("Failed to run {}: {}", program, e)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(value.as_bytes())?;
+        }
+        // e.g. xclip without an X display exits non-zero; fall through to the next tool
+        match child.wait()? {
+            status if status.success() => return Ok(()),
+            status => failures.push(format!("{} exited with {}", program, status)),
+        }
+    }
+
+    let tools: Vec<_> = commands.iter().map(|(program, _)| *program).collect();
+    if failures.is_empty() {
+        Err(anyhow!("No clipboard tool found, install one of: {}", tools.join(", ")))
+    } else {
+        Err(anyhow!("Could not copy to the clipboard ({})", failures.join("; ")))
+    }
+}
+
+fn read_clipboard() -> Result<String> {
+    for (program, args) in paste_commands() {
+        match Command::new(program).args(args).stderr(Stdio::null()).output() {
+            Ok(output) if output.status.success() => {
+                return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+            }
+            Ok(_) => continue,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(anyhow!("Failed to run {}: {}", program, e)),
+        }
+    }
+    Err(anyhow!("No clipboard tool found to read the clipboard"))
+}
+
+/// How the `clear-clipboard` child recognises the copied value: an HMAC of
+/// it under a one-off random key. Both reach the child over its stdin, and
+/// without the key the tag can't be used to guess the value.
+struct CopiedValue {
+    key: [u8; 32],
+    tag: Vec<u8>,
+}
+
+impl CopiedValue {
+    fn new(value: &str) -> Self {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &key), value.as_bytes()).as_ref().to_vec();
+        CopiedValue { key, tag }
+    }
+
+    fn to_line(&self) -> String {
+        format!("{} {}\n", hex::encode(self.key), hex::encode(&self.tag))
+    }
+
+    fn parse(line: &str) -> Result<Self> {
+        let (key, tag) = line.trim().split_once(' ')
+            .ok_or_else(|| anyhow!("Malformed clipboard fingerprint"))?;
+        let key = hex::decode(key)?.try_into()
+            .map_err(|_| anyhow!("Malformed clipboard fingerprint"))?;
+        Ok(CopiedValue { key, tag: hex::decode(tag)? })
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        hmac::verify(&hmac::Key::new(hmac::HMAC_SHA256, &self.key), value.as_bytes(), &self.tag).is_ok()
+    }
+}
+
+/// The detached `vault clear-clipboard` process; it learns what to look for
+/// from its stdin.
+fn clear_command(seconds: u64) -> Result<Command> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command.args(["clear-clipboard", "--after", &seconds.to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    Ok(command)
+}
+
+/// Clear the clipboard after `seconds` from a detached `vault clear-clipboard`
+/// process, so it still happens after this one exits.
+pub fn schedule_clear(value: &str, seconds: u64) -> Result<()> {
+    let mut child = clear_command(seconds)?.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(CopiedValue::new(value).to_line().as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Wait `seconds`, then empty the clipboard unless something else has been
+/// copied since. The value to look for is read from stdin first.
+pub async fn clear_if_unchanged(seconds: u64) -> Result<()> {
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    let copied = CopiedValue::parse(&line)?;
+
+    tokio::time::sleep(std::time::Duration::from_secs(seconds)).await;
+
+    let current = read_clipboard()?;
+    // Some readers (e.g. Get-Clipboard) append a line ending
+    let unchanged = copied.matches(&current) || copied.matches(current.trim_end_matches(['\r', '\n']));
+    if unchanged {
+        copy_to_clipboard("")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clear_command_argv_holds_nothing_from_the_value() {
+        let copied = CopiedValue::new("hunter2");
+        let command = clear_command(30).unwrap();
+        let args: Vec<_> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert_eq!(args, ["clear-clipboard", "--after", "30"]);
+
+        // What the child needs travels over stdin instead
+        let received = CopiedValue::parse(&copied.to_line()).unwrap();
+        assert!(received.matches("hunter2"));
+        assert!(!received.matches("hunter3"));
+        // A fresh key per copy, so equal values don't share a tag
+        assert_ne!(CopiedValue::new("hunter2").tag, copied.tag);
+    }
+}
@@ -1,14 +1,16 @@
 use anyhow::Result;
-use dialoguer::Confirm;
-use owo_colors::OwoColorize;
+use comfy_table::{Cell, Color};
+use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::{
-    storage::{AuditLogger, AuditEntry},
-    cli::{RoleAction, AuditAction, output},
-    auth::{SessionManager, Role},
+    config::Config,
+    crypto::EncryptionAlgorithm,
+    storage::{VaultStorage, AuditLogger, AuditEntry},
+    cli::{open_storage, RoleAction, AuditAction, commands::parse_duration, output::{self, Colorize}},
+    auth::{Session, SessionManager, Role},
 };
 
-pub async fn roles_command(action: RoleAction) -> Result<()> {
+pub async fn roles_command(action: RoleAction, storage: &VaultStorage) -> Result<()> {
     if let Ok(session) = SessionManager::get_current_session() {
         if !session.role.can_admin() {
             output::print_error("Admin permissions required for role management");
@@ -42,7 +44,7 @@ pub async fn roles_command(action: RoleAction) -> Result<()> {
                     format!("User {} added with role {}", user, role),
                     session.user_id.clone(),
                 );
-                let _ = AuditLogger::log_event(&audit_entry);
+                AuditLogger::log_event(storage, &audit_entry)?;
                 
                 // Also log role change
                 let role_entry = AuditEntry::new(
@@ -51,16 +53,13 @@ pub async fn roles_command(action: RoleAction) -> Result<()> {
                     format!("User {} assigned role {}", user, role),
                     session.user_id,
                 );
-                let _ = AuditLogger::log_event(&role_entry);
+                AuditLogger::log_event(storage, &role_entry)?;
             }
             
             output::print_success(&format!("User {} added to tenant {} with role {}", user, tenant, role));
         }
         RoleAction::Remove { tenant, user } => {
-            if Confirm::new()
-                .with_prompt(format!("Remove user {} from tenant {}?", user, tenant))
-                .interact()?
-            {
+            if output::confirm(format!("Remove user {} from tenant {}?", user, tenant), false)? {
                 println!("{} Removing user {} from tenant {}", "➖".red(), user.cyan(), tenant.cyan());
                 
                 if let Ok(session) = SessionManager::get_current_session() {
@@ -70,7 +69,7 @@ pub async fn roles_command(action: RoleAction) -> Result<()> {
                         &format!("User {} removed from tenant", user),
                         &session.user_id,
                     );
-                    let _ = AuditLogger::log_event(&audit_entry);
+                    AuditLogger::log_event(storage, &audit_entry)?;
                 }
                 
                 output::print_success(&format!("User {} removed from tenant {}", user, tenant));
@@ -81,140 +80,431 @@ pub async fn roles_command(action: RoleAction) -> Result<()> {
         RoleAction::List { tenant } => {
             println!("{} Users in tenant {}:", "📊".cyan(), tenant.cyan());
             
-            let users = vec![
+            let users = [
                 ("admin@example.com", "Admin", "2024-01-01"),
                 ("user@example.com", "Writer", "2024-01-15"),
                 ("reader@example.com", "Reader", "2024-02-01"),
                 ("auditor@example.com", "Auditor", "2024-02-10"),
             ];
             
-            output::print_table_header(&["Email", "Role", "Added"]);
+            let mut table = output::table(&["Email", "Role", "Added"]);
             
             for (email, role, added) in &users {
-                let role_colored = match *role {
-                    "Admin" => role.red().to_string(),
-                    "Owner" => role.purple().to_string(),
-                    "Writer" => role.green().to_string(),
-                    "Reader" => role.blue().to_string(),
-                    "Auditor" => role.yellow().to_string(),
-                    _ => role.white().to_string(),
+                let role_color = match *role {
+                    "Admin" => Color::Red,
+                    "Owner" => Color::Magenta,
+                    "Writer" => Color::Green,
+                    "Reader" => Color::Blue,
+                    "Auditor" => Color::Yellow,
+                    _ => Color::White,
                 };
-                println!("  {} {} | {} | {}", "•".green(), email.cyan(), role_colored, added);
+                table.add_row(vec![
+                    output::cell(email, Color::Cyan),
+                    output::cell(role, role_color),
+                    Cell::new(added),
+                ]);
             }
             
+            println!("{}", table);
             println!("\nTotal: {} users", users.len());
         }
     }
     Ok(())
 }
 
-pub async fn audit_command(action: AuditAction) -> Result<()> {
-    if let Ok(session) = SessionManager::get_current_session() {
-        if !session.role.can_audit() {
-            output::print_error("Audit permissions required to view logs");
+pub async fn audit_command(action: AuditAction, config: &Config) -> Result<()> {
+    let session = match SessionManager::get_current_session() {
+        Ok(session) => session,
+        Err(_) => {
+            output::print_error("Please login first");
             return Ok(());
         }
-    } else {
-        output::print_error("Please login first");
+    };
+    if !session.role.can_audit() {
+        output::print_error("Audit permissions required to view logs");
         return Ok(());
     }
     
     match action {
         AuditAction::Tail { lines, follow } => {
-            let limit = lines.unwrap_or(50);
-            println!("{} Showing last {} audit entries:", "📜".cyan(), limit);
-            
-            let now = chrono::Utc::now();
-            let audit_entries = vec![
-                (now - chrono::Duration::hours(2), "LOGIN", "User admin@example.com logged in"),
-                (now - chrono::Duration::hours(1), "SECRET_CREATED", "Secret development/api-key created"),
-                (now - chrono::Duration::minutes(30), "SECRET_ACCESSED", "Secret development/api-key accessed"),
-                (now - chrono::Duration::minutes(15), "SYNC_PUSH", "Pushed 5 secrets to cloud"),
-                (now - chrono::Duration::minutes(5), "USER_ADDED", "User reader@example.com added with role Reader"),
-            ];
-            
-            output::print_table_header(&["Timestamp", "Event", "Description"]);
-            
-            for (timestamp, event_type, description) in audit_entries.iter().take(limit) {
-                let event_colored = match *event_type {
-                    "LOGIN" | "LOGOUT" => event_type.blue().to_string(),
-                    "SECRET_CREATED" | "SECRET_UPDATED" => event_type.green().to_string(),
-                    "SECRET_ACCESSED" => event_type.yellow().to_string(),
-                    "SECRET_DELETED" => event_type.red().to_string(),
-                    "SYNC_PUSH" | "SYNC_PULL" => event_type.purple().to_string(),
-                    "USER_ADDED" | "USER_REMOVED" => event_type.cyan().to_string(),
-                    _ => event_type.white().to_string(),
-                };
-                
-                println!(
-                    "[{}] {} - {}",
-                    timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
-                    event_colored,
-                    description
-                );
-            }
-            
-            if follow {
-                println!("\n{} Following audit log (Ctrl+C to stop)...", "👀".cyan());
-                
-                for i in 0..5 {
-                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-                    
-                    let new_event = match i % 3 {
-                        0 => "SECRET_ACCESSED - Secret production/database-url accessed",
-                        1 => "LOGIN - User user@example.com logged in",
-                        _ => "SECRET_CREATED - Secret staging/redis-password created",
-                    };
-                    
-                    println!(
-                        "[{}] {} - {}",
-                        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
-                        "LIVE".bright_green(),
-                        new_event
-                    );
-                }
-            }
+            audit_tail(config, &session, lines, follow).await?;
         }
         AuditAction::Search { query, since, until } => {
-            println!("{} Searching audit logs for: {}", "🔍".cyan(), query.yellow());
-            
-            if let Some(since_date) = since {
-                println!("Since: {}", since_date);
-            }
-            if let Some(until_date) = until {
-                println!("Until: {}", until_date);
+            let bounds = since.as_deref().map(parse_since).transpose()
+                .and_then(|since| Ok((since, until.as_deref().map(parse_until).transpose()?)));
+            let (since, until) = match bounds {
+                Ok(bounds) => bounds,
+                Err(e) => {
+                    output::print_error(&e);
+                    return Ok(());
+                }
+            };
+            if let (Some(since), Some(until)) = (since, until) {
+                if since > until {
+                    output::print_error(&format!(
+                        "--since ({}) is after --until ({})",
+                        output::format_date(&since),
+                        output::format_date(&until)
+                    ));
+                    return Ok(());
+                }
             }
             
-            let search_results = vec![
-                ("2024-01-15 10:30:00", "SECRET_CREATED", "Secret development/github-token created"),
-                ("2024-01-15 14:22:00", "SECRET_ACCESSED", "Secret development/github-token accessed"),
-                ("2024-01-16 09:15:00", "SECRET_UPDATED", "Secret development/github-token updated"),
-            ];
+            println!("{} Searching audit logs for: {}", "🔍".cyan(), query.yellow());
             
-            let matching_results: Vec<_> = search_results
-                .iter()
-                .filter(|(_, event, desc)| {
-                    event.to_lowercase().contains(&query.to_lowercase()) ||
-                    desc.to_lowercase().contains(&query.to_lowercase())
+            let storage = open_storage(config, false, true)?;
+            let needle = query.to_lowercase();
+            let matching: Vec<_> = storage.audit_entries_since(&session.tenant_id, since)?
+                .into_iter()
+                .filter(|entry| until.is_none_or(|until| entry.timestamp <= until))
+                .filter(|entry| {
+                    entry.event_type.to_lowercase().contains(&needle) ||
+                    entry.description.to_lowercase().contains(&needle)
                 })
                 .collect();
             
-            if matching_results.is_empty() {
+            if matching.is_empty() {
                 output::print_info("No matching audit entries found");
             } else {
-                println!("\nFound {} matching entries:", matching_results.len());
+                println!("\nFound {} matching entries:", matching.len());
                 output::print_table_header(&["Timestamp", "Event", "Description"]);
                 
-                for (timestamp, event_type, description) in matching_results {
-                    println!(
-                        "[{}] {} - {}",
-                        timestamp,
-                        event_type.yellow(),
-                        description
-                    );
+                for entry in &matching {
+                    print_audit_entry(entry);
+                }
+            }
+        }
+        AuditAction::Export { since, format, output, follow } => {
+            audit_export(config, &session, since.as_deref(), &format, output.as_deref(), follow).await?;
+        }
+        AuditAction::Duplicates => {
+            if !session.role.can_read() {
+                output::print_error("Read permissions required to scan secret values");
+                return Ok(());
+            }
+            
+            let storage = open_storage(config, false, true)?;
+            let duplicates = storage.find_duplicate_values().await?;
+            
+            let audit_entry = AuditEntry::new(
+                session.tenant_id.clone(),
+                AuditLogger::EVENT_SECRET_ACCESSED.to_string(),
+                format!("Scanned secrets for reused values: {} groups found", duplicates.len()),
+                session.user_id.clone(),
+            );
+            AuditLogger::log_event(&storage, &audit_entry)?;
+            
+            if duplicates.is_empty() {
+                output::print_success("No reused secret values found");
+                return Ok(());
+            }
+            
+            output::print_warning(&format!("{} groups of secrets share a value:", duplicates.len()));
+            for (i, group) in duplicates.iter().enumerate() {
+                println!("\n{} {}", "Group".bold(), i + 1);
+                for (namespace, key) in group {
+                    println!("  {}/{}", namespace, key.cyan());
                 }
             }
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+pub async fn reencrypt_command(storage: &mut VaultStorage, config: &Config, config_path: Option<&str>, to: &str, force: bool) -> Result<()> {
+    let session = match SessionManager::get_current_session() {
+        Ok(session) => session,
+        Err(_) => {
+            output::print_error("Please login first");
+            return Ok(());
+        }
+    };
+    if !session.role.can_admin() {
+        output::print_error("Admin permissions required to re-encrypt the vault");
+        return Ok(());
+    }
+    
+    let algorithm = EncryptionAlgorithm::from_name(to)
+        .unwrap_or(EncryptionAlgorithm::Aes256Gcm);
+    
+    if !force && !output::confirm(format!("Re-encrypt all secrets in tenant '{}' with {}?", session.tenant_id, to), false)? {
+        output::print_info("Operation cancelled");
+        return Ok(());
+    }
+    
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")?
+            .progress_chars("#>-")
+    );
+    pb.set_message("Re-encrypting secrets");
+    let result = storage.reencrypt_secrets_with_progress(algorithm, |done, total| {
+        pb.set_length(total as u64);
+        pb.set_position(done as u64);
+    }).await;
+    pb.finish_and_clear();
+    
+    match result {
+        Ok(count) => {
+            output::print_success(&format!("Re-encrypted {} secrets with {}; new secrets will use it too", count, to));
+            if config.security.encryption_algorithm != to {
+                // New tenants start on the same cipher
+                let mut updated = config.clone();
+                updated.security.encryption_algorithm = to.to_string();
+                updated.save(config_path)?;
+                output::print_info(&format!("Saved {} as the default cipher in the config", to));
+            }
+        }
+        Err(e) => output::print_error(&format!("Re-encryption failed, no secrets were changed; run it again to retry: {}", e)),
+    }
+    
+    Ok(())
+}
+
+/// Audit entries for `tenant_id` at or after `since`, read through a
+/// short-lived read-only handle so polling never holds the vault lock.
+fn read_audit_entries(config: &Config, tenant_id: &str, since: Option<chrono::DateTime<chrono::Utc>>) -> Result<Vec<AuditEntry>> {
+    let storage = VaultStorage::open_readonly(&config.storage_path)?;
+    Ok(storage.audit_entries_since(tenant_id, since)?)
+}
+
+/// How far `--follow` has read the audit log: the newest timestamp seen and
+/// the ids of the entries at it. Entries can share a timestamp, so each poll
+/// reads from that timestamp again and skips only the ids already seen.
+#[derive(Default)]
+struct AuditCursor {
+    timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    seen: Vec<uuid::Uuid>,
+}
+
+impl AuditCursor {
+    fn since(timestamp: Option<chrono::DateTime<chrono::Utc>>) -> Self {
+        Self { timestamp, seen: Vec::new() }
+    }
+    
+    /// New audit entries for `tenant_id`, oldest first. The vault is opened
+    /// for each poll because sled holds its lock for as long as a handle
+    /// lives, and a handle kept open would stop writers recording entries.
+    fn poll(&mut self, config: &Config, tenant_id: &str) -> Result<Vec<AuditEntry>> {
+        let entries = read_audit_entries(config, tenant_id, self.timestamp)?;
+        Ok(self.advance(entries))
+    }
+    
+    /// Drop the entries already seen from `entries` (oldest first) and move
+    /// the cursor past the rest.
+    fn advance(&mut self, entries: Vec<AuditEntry>) -> Vec<AuditEntry> {
+        let fresh: Vec<_> = entries.into_iter()
+            .filter(|entry| Some(entry.timestamp) != self.timestamp || !self.seen.contains(&entry.id))
+            .collect();
+        for entry in &fresh {
+            if Some(entry.timestamp) != self.timestamp {
+                self.timestamp = Some(entry.timestamp);
+                self.seen.clear();
+            }
+            self.seen.push(entry.id);
+        }
+        fresh
+    }
+}
+
+fn print_audit_entry(entry: &AuditEntry) {
+    let event_type = entry.event_type.to_uppercase();
+    let event_colored = match event_type.as_str() {
+        "LOGIN" | "LOGOUT" => event_type.blue().to_string(),
+        "SECRET_CREATED" | "SECRET_UPDATED" => event_type.green().to_string(),
+        "SECRET_ACCESSED" => event_type.yellow().to_string(),
+        "SECRET_DELETED" => event_type.red().to_string(),
+        "SYNC_PUSH" | "SYNC_PULL" => event_type.purple().to_string(),
+        "USER_ADDED" | "USER_REMOVED" => event_type.cyan().to_string(),
+        _ => event_type.white().to_string(),
+    };
+    
+    println!(
+        "[{}] {} - {} ({})",
+        output::format_date(&entry.timestamp),
+        event_colored,
+        entry.description,
+        entry.user_id.dimmed()
+    );
+}
+
+/// Print the newest audit entries, then keep printing new ones with `follow`.
+/// Every poll opens its own short-lived handle, so the vault lock is never held.
+async fn audit_tail(config: &Config, session: &Session, lines: Option<usize>, follow: bool) -> Result<()> {
+    let mut cursor = AuditCursor::default();
+    let entries = cursor.poll(config, &session.tenant_id)?;
+    let newest = &entries[entries.len().saturating_sub(lines.unwrap_or(50))..];
+    println!("{} Showing last {} audit entries:", "📜".cyan(), newest.len());
+    
+    output::print_table_header(&["Timestamp", "Event", "Description"]);
+    for entry in newest {
+        print_audit_entry(entry);
+    }
+    
+    if follow {
+        println!("\n{} Following audit log (Ctrl+C to stop)...", "👀".cyan());
+        
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                _ = tokio::time::sleep(std::time::Duration::from_secs(2)) => {}
+            }
+            
+            for entry in &cursor.poll(config, &session.tenant_id)? {
+                print_audit_entry(entry);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Write audit entries as JSON lines or CSV, polling like `audit_tail` with `follow`.
+async fn audit_export(config: &Config, session: &Session, since: Option<&str>, format: &str, output_path: Option<&str>, follow: bool) -> Result<()> {
+    use std::io::Write;
+    
+    let csv = match format.to_lowercase().as_str() {
+        "jsonl" => false,
+        "csv" => true,
+        other => {
+            output::print_error(&format!("Unsupported audit export format: {} (use jsonl or csv)", other));
+            return Ok(());
+        }
+    };
+    let mut cursor = match since.map(parse_since).transpose() {
+        Ok(since) => AuditCursor::since(since),
+        Err(e) => {
+            output::print_error(&e);
+            return Ok(());
+        }
+    };
+    
+    let mut sink: Box<dyn Write> = match output_path {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout()),
+    };
+    if csv {
+        writeln!(sink, "{}", AuditEntry::CSV_HEADER)?;
+    }
+    
+    let mut exported = 0;
+    loop {
+        let entries = cursor.poll(config, &session.tenant_id)?;
+        
+        for entry in &entries {
+            if csv {
+                writeln!(sink, "{}", entry.to_csv_row())?;
+            } else {
+                writeln!(sink, "{}", serde_json::to_string(entry)?)?;
+            }
+        }
+        sink.flush()?;
+        exported += entries.len();
+        
+        if !follow {
+            break;
+        }
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = tokio::time::sleep(std::time::Duration::from_secs(2)) => {}
+        }
+    }
+    
+    let audit_entry = AuditEntry::new(
+        session.tenant_id.clone(),
+        AuditLogger::EVENT_EXPORT.to_string(),
+        format!("Exported {} audit entries as {}", exported, format),
+        session.user_id.clone(),
+    );
+    // Best effort: another process may be holding the vault
+    if let Ok(storage) = VaultStorage::new(&config.storage_path) {
+        AuditLogger::log_event(&storage, &audit_entry)?;
+    }
+    
+    if let Some(path) = output_path {
+        output::print_success(&format!("Exported {} audit entries to {}", exported, path));
+    }
+    
+    Ok(())
+}
+
+/// Parse `--since`: an RFC 3339 timestamp, a `YYYY-MM-DD` date, or a
+/// relative duration such as `30m`, `24h` or `7d`.
+fn parse_since(value: &str) -> std::result::Result<chrono::DateTime<chrono::Utc>, String> {
+    parse_time_bound("--since", value)
+}
+
+/// Parse `--until` like `--since`, except a bare date covers the whole day.
+fn parse_until(value: &str) -> std::result::Result<chrono::DateTime<chrono::Utc>, String> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let end_of_day = date.and_hms_nano_opt(23, 59, 59, 999_999_999).unwrap_or_default();
+        return Ok(end_of_day.and_utc());
+    }
+    parse_time_bound("--until", value)
+}
+
+fn parse_time_bound(flag: &str, value: &str) -> std::result::Result<chrono::DateTime<chrono::Utc>, String> {
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&chrono::Utc));
+    }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+    
+    let duration = parse_duration(value)
+        .ok_or_else(|| format!("Invalid {} value: {} (use RFC 3339, YYYY-MM-DD, or e.g. 24h)", flag, value))?;
+    Ok(chrono::Utc::now() - duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_since_formats() {
+        let date = parse_since("2024-01-15").unwrap();
+        assert_eq!(date.to_rfc3339(), "2024-01-15T00:00:00+00:00");
+        
+        let exact = parse_since("2024-01-15T10:30:00Z").unwrap();
+        assert_eq!(exact.to_rfc3339(), "2024-01-15T10:30:00+00:00");
+        
+        let relative = parse_since("24h").unwrap();
+        let expected = chrono::Utc::now() - chrono::Duration::hours(24);
+        assert!((relative - expected).num_seconds().abs() < 5);
+        
+        assert!(parse_since("yesterday").is_err());
+    }
+    
+    #[test]
+    fn test_parse_until_covers_whole_day() {
+        let until = parse_until("2024-01-15").unwrap();
+        assert_eq!(until.date_naive(), chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+        assert!(parse_since("2024-01-15T23:59:59Z").unwrap() < until);
+        
+        let exact = parse_until("2024-01-15T10:30:00Z").unwrap();
+        assert_eq!(exact.to_rfc3339(), "2024-01-15T10:30:00+00:00");
+        assert!(parse_until("tomorrow").unwrap_err().contains("--until"));
+    }
+    
+    #[test]
+    fn test_audit_cursor_keeps_entries_sharing_a_timestamp() {
+        let at = chrono::Utc::now();
+        let entry = |description: &str| {
+            let mut entry = AuditEntry::new("t".into(), "login".into(), description.into(), "u".into());
+            entry.timestamp = at;
+            entry
+        };
+        let first = entry("first");
+        let second = entry("second");
+        
+        let mut cursor = AuditCursor::default();
+        assert_eq!(cursor.advance(vec![first.clone()]).len(), 1);
+        // A later poll reads from `at` again: the entry written in the same
+        // nanosecond is new, the one already printed is not
+        let fresh = cursor.advance(vec![first, second]);
+        assert_eq!(fresh.len(), 1);
+        assert_eq!(fresh[0].description, "second");
+        assert_eq!(cursor.timestamp, Some(at));
+    }
+}
@@ -1,12 +1,13 @@
 use anyhow::Result;
-use dialoguer::{Password, Confirm};
-use owo_colors::OwoColorize;
+use dialoguer::Password;
 use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::{
     storage::{VaultStorage, AuditLogger, AuditEntry},
-    cli::output,
-    auth::{SessionManager, Session, Role},
+    cli::output::{self, Colorize},
+    auth::{SessionManager, Session, Role, generate_totp_secret, verify_totp, totp_uri, base32_encode},
+    cli::{SessionAction, TwoFactorAction, commands::check_password_strength},
+    error::VaultError,
 };
 
 pub async fn init_command(
@@ -14,22 +15,20 @@ pub async fn init_command(
     tenant: &str,
     admin: &str,
     force: bool,
+    algorithm: crate::crypto::EncryptionAlgorithm,
+    kdf_params: crate::crypto::KeyDerivationParams,
+    min_password_score: u8,
 ) -> Result<()> {
-    if !force && storage.tenant_exists(tenant)? {
-        if !Confirm::new()
-            .with_prompt(format!("Tenant '{}' already exists. Reinitialize?", tenant))
-            .interact()?
-        {
-            println!("{} Initialization cancelled", "ℹ".blue());
-            return Ok(());
-        }
+    if !force && storage.tenant_exists(tenant)? && !output::confirm(format!("Tenant '{}' already exists. Reinitialize?", tenant), false)? {
+        println!("{} Initialization cancelled", "ℹ".blue());
+        return Ok(());
     }
     
     // Ask for master password
-    let master_password = Password::new()
-        .with_prompt("Create master password")
-        .with_confirmation("Confirm master password", "Passwords do not match")
-        .interact()?;
+    let master_password = output::password("Create master password", Some("Confirm master password"))?;
+    if !check_password_strength(&master_password, &[tenant, admin], min_password_score) {
+        return Ok(());
+    }
     
     // Validate password strength
     let pb = ProgressBar::new_spinner();
@@ -43,18 +42,9 @@ pub async fn init_command(
         output::print_warning("Using fallback initialization without password validation");
         storage.init_tenant(tenant, admin).await?;
     } else {
-        storage.init_tenant_with_password(tenant, admin, &master_password).await?;
+        storage.init_tenant_with_settings(tenant, admin, &master_password, algorithm, &kdf_params).await?;
     }
     
-    // Log tenant creation
-    let audit_entry = AuditEntry::new(
-        tenant.to_string(),
-        AuditLogger::EVENT_TENANT_CREATED.to_string(),
-        format!("Tenant {} created by {}", tenant, admin),
-        admin.to_string(),
-    );
-    let _ = AuditLogger::log_event(&audit_entry);
-    
     pb.finish_with_message(format!("{} Vault initialized for tenant: {}", "✓".green(), tenant.cyan()));
     println!("Admin: {}", admin.cyan());
     println!("\nNext steps:");
@@ -90,14 +80,12 @@ pub async fn login_command(
         };
         
         let pwd = Password::new()
-            .with_prompt(&format!("Password for {}", email))
+            .with_prompt(format!("Password for {}", email))
             .interact()?;
             
         (email, pwd)
     } else {
-        let pwd = Password::new()
-            .with_prompt("Enter master passphrase")
-            .interact()?;
+        let pwd = output::password("Enter master passphrase", None)?;
         ("admin".to_string(), pwd)
     };
     
@@ -106,11 +94,12 @@ pub async fn login_command(
     pb.set_message("Authenticating...");
     pb.enable_steady_tick(std::time::Duration::from_millis(100));
     
-    match storage.unlock(tenant, &passphrase) {
+    // The key is only cached for later commands once the second factor passes
+    match storage.unlock_transient(tenant, &passphrase) {
         Ok(_) => {
-            pb.finish_with_message(format!("{} Logged in to tenant: {}", "✓".green(), tenant.cyan()));
+            pb.finish_and_clear();
             
-            let duration_hours = if remember { 168 } else { 24 };
+            let duration_hours = config.session_duration_hours(remember);
             let (user_id, role) = if is_collaborative {
                 // In collaborative mode, get user role from storage
                 match storage.get_user_role(tenant, &user_email).await {
@@ -125,6 +114,21 @@ pub async fn login_command(
                 ("admin".to_string(), Role::Admin)
             };
             
+            storage.set_current_user(&user_id);
+            
+            let second_factor_passed = match check_second_factor(storage, &user_id)? {
+                Some(passed) => passed,
+                None if config.security.require_2fa => {
+                    output::print_warning("This vault requires two-factor login; enroll an authenticator app to continue");
+                    enroll_totp(storage, tenant, &user_id).await?
+                }
+                None => true,
+            };
+            if !second_factor_passed {
+                return Ok(());
+            }
+            storage.cache_session_key()?;
+            
             let session = Session::new(
                 tenant.to_string(),
                 user_id,
@@ -138,9 +142,9 @@ pub async fn login_command(
                 tenant.to_string(),
                 AuditLogger::EVENT_LOGIN.to_string(),
                 format!("User logged in to tenant {}", tenant),
-                "admin".to_string(),
+                session.user_id.clone(),
             );
-            let _ = AuditLogger::log_event(&audit_entry);
+            AuditLogger::log_event(storage, &audit_entry)?;
             
             output::print_success(&format!("Successfully logged in to tenant: {}", tenant));
         }
@@ -153,7 +157,143 @@ pub async fn login_command(
     Ok(())
 }
 
-pub async fn logout_command() -> Result<()> {
+pub async fn passwd_command(storage: &mut VaultStorage, min_password_score: u8) -> Result<()> {
+    let session = match SessionManager::get_current_session() {
+        Ok(session) => session,
+        Err(_) => {
+            output::print_error("Please login first");
+            return Ok(());
+        }
+    };
+    if !session.role.can_admin() {
+        output::print_error("Admin permissions required to change the passphrase");
+        return Ok(());
+    }
+    
+    let old_passphrase = Password::new()
+        .with_prompt(format!("Current passphrase for tenant '{}'", session.tenant_id))
+        .interact()?;
+    let new_passphrase = Password::new()
+        .with_prompt("New passphrase")
+        .with_confirmation("Confirm new passphrase", "Passphrases do not match")
+        .interact()?;
+    if new_passphrase.len() < 8 {
+        output::print_error("Passphrase must be at least 8 characters long");
+        return Ok(());
+    }
+    if !check_password_strength(&new_passphrase, &[&session.tenant_id], min_password_score) {
+        return Ok(());
+    }
+    
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")?
+            .progress_chars("#>-")
+    );
+    pb.set_message("Deriving keys...");
+    let result = storage.change_passphrase_with_progress(&old_passphrase, &new_passphrase, |done, total| {
+        if done == 1 {
+            pb.set_message("Re-encrypting secrets");
+        }
+        pb.set_length(total as u64);
+        pb.set_position(done as u64);
+    }).await;
+    pb.finish_and_clear();
+    
+    match result {
+        Ok(count) => {
+            // Other sessions of this tenant were signed out of the old key; keep this one
+            storage.cache_session_key()?;
+            output::print_success(&format!("Passphrase changed; {} secrets re-encrypted", count));
+        }
+        Err(VaultError::InvalidPassphrase) => {
+            output::print_error("Current passphrase is incorrect");
+        }
+        Err(e) => output::print_error(&format!("Passphrase change failed, the old passphrase still works: {}", e)),
+    }
+    
+    Ok(())
+}
+
+pub async fn two_factor_command(action: TwoFactorAction, storage: &VaultStorage) -> Result<()> {
+    let session = match SessionManager::get_current_session() {
+        Ok(session) => session,
+        Err(_) => {
+            output::print_error("Please login first");
+            return Ok(());
+        }
+    };
+    let enrolled = storage.totp_secret(&session.user_id)?;
+    
+    match action {
+        TwoFactorAction::Enroll => {
+            if enrolled.is_some() {
+                output::print_error("Two-factor login is already enabled; run 'vault 2fa disable' first to enroll a new device");
+                return Ok(());
+            }
+            if enroll_totp(storage, &session.tenant_id, &session.user_id).await? {
+                output::print_success("Two-factor login enabled");
+            }
+        }
+        TwoFactorAction::Disable => {
+            if enrolled.is_none() {
+                output::print_info("Two-factor login is not enabled");
+                return Ok(());
+            }
+            if !storage.accept_totp_code(&session.user_id, &prompt_totp_code()?, unix_now())? {
+                output::print_error("Invalid authentication code");
+                return Ok(());
+            }
+            storage.remove_totp_secret(&session.user_id).await?;
+            output::print_success("Two-factor login disabled");
+        }
+    }
+    
+    Ok(())
+}
+
+/// Ask `user_id` of the unlocked tenant for an authentication code if they
+/// enrolled in two-factor login. `None` means they didn't; otherwise whether
+/// the code was accepted. Each code is accepted only once.
+pub fn check_second_factor(storage: &VaultStorage, user_id: &str) -> Result<Option<bool>> {
+    if storage.totp_secret(user_id)?.is_none() {
+        return Ok(None);
+    }
+    let accepted = storage.accept_totp_code(user_id, &prompt_totp_code()?, unix_now())?;
+    if !accepted {
+        output::print_error("Invalid authentication code");
+    }
+    Ok(Some(accepted))
+}
+
+/// Show a new TOTP secret for the user to add to an authenticator app and
+/// store it once they confirm a code from it. Returns whether they did.
+async fn enroll_totp(storage: &VaultStorage, tenant: &str, user_id: &str) -> Result<bool> {
+    let secret = zeroize::Zeroizing::new(generate_totp_secret());
+    println!("Add this account to your authenticator app:");
+    println!("  {}", totp_uri(secret.as_slice(), tenant, user_id));
+    println!("Or enter the key manually: {}", base32_encode(secret.as_slice()).cyan());
+    
+    if !verify_totp(secret.as_slice(), &prompt_totp_code()?, unix_now()) {
+        output::print_error("Invalid authentication code; two-factor login was not enabled");
+        return Ok(false);
+    }
+    storage.set_totp_secret(user_id, secret.as_slice()).await?;
+    Ok(true)
+}
+
+fn prompt_totp_code() -> Result<String> {
+    Ok(dialoguer::Input::<String>::new()
+        .with_prompt("Authentication code")
+        .interact()?)
+}
+
+fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+pub async fn logout_command(storage: &VaultStorage) -> Result<()> {
     if let Ok(session) = SessionManager::get_current_session() {
         let audit_entry = AuditEntry::new(
             session.tenant_id.clone(),
@@ -161,18 +301,10 @@ pub async fn logout_command() -> Result<()> {
             "User logged out".to_string(),
             session.user_id,
         );
-        let _ = AuditLogger::log_event(&audit_entry);
-        
-        // Clear session key from storage
-        let config_path = dirs::home_dir()
-            .unwrap_or_default()
-            .join(".vault")
-            .join("vault.db");
-        if let Ok(storage) = VaultStorage::new(&config_path.to_string_lossy()) {
-            let _ = storage.clear_session_key(&session.tenant_id);
-        }
+        AuditLogger::log_event(storage, &audit_entry)?;
         
         SessionManager::clear_session()?;
+        forget_key_if_unused(storage, &session.tenant_id)?;
         output::print_success("Logged out successfully");
     } else {
         output::print_info("No active session found");
@@ -181,15 +313,142 @@ pub async fn logout_command() -> Result<()> {
     Ok(())
 }
 
-pub async fn whoami_command() -> Result<()> {
+pub async fn sessions_command(action: SessionAction, storage: &VaultStorage) -> Result<()> {
+    let sessions = SessionManager::list_sessions()?;
+    let current = SessionManager::load_session().ok().flatten().map(|session| session.id);
+    
+    let revoked: Vec<&Session> = match action {
+        SessionAction::List => {
+            if sessions.is_empty() {
+                output::print_info("No active sessions");
+                return Ok(());
+            }
+            let mut table = output::table(&["ID", "Tenant", "User", "Role", "Created", "Expires"]);
+            for session in &sessions {
+                let id = if Some(session.id) == current {
+                    format!("{} (current)", session.id)
+                } else {
+                    session.id.to_string()
+                };
+                table.add_row(vec![
+                    id,
+                    session.tenant_id.clone(),
+                    session.user_id.clone(),
+                    format!("{:?}", session.role),
+                    output::format_date(&session.created_at),
+                    output::format_date(&session.expires_at),
+                ]);
+            }
+            println!("{}", table);
+            return Ok(());
+        }
+        SessionAction::Revoke { id } => {
+            let Some(session) = sessions.iter().find(|session| session.id == id) else {
+                output::print_error(&format!("No active session {}", id));
+                return Ok(());
+            };
+            vec![session]
+        }
+        SessionAction::RevokeAll => {
+            if sessions.is_empty() {
+                output::print_info("No active sessions");
+                return Ok(());
+            }
+            if !output::confirm(format!("Log out all {} sessions?", sessions.len()), false)? {
+                println!("{} Cancelled", "ℹ".blue());
+                return Ok(());
+            }
+            sessions.iter().collect()
+        }
+    };
+    
+    for session in &revoked {
+        SessionManager::revoke_session(session.id)?;
+        let audit_entry = AuditEntry::new(
+            session.tenant_id.clone(),
+            AuditLogger::EVENT_LOGOUT.to_string(),
+            format!("Session {} revoked", session.id),
+            session.user_id.clone(),
+        );
+        AuditLogger::log_event(storage, &audit_entry)?;
+    }
+    for session in &revoked {
+        forget_key_if_unused(storage, &session.tenant_id)?;
+    }
+    output::print_success(&format!("Revoked {} session(s)", revoked.len()));
+    
+    Ok(())
+}
+
+/// Drop the tenant key cached for auto-unlock once no session of the
+/// tenant is left to use it.
+fn forget_key_if_unused(storage: &VaultStorage, tenant_id: &str) -> Result<()> {
+    if !SessionManager::list_sessions()?.iter().any(|session| session.tenant_id == tenant_id) {
+        let _ = storage.clear_session_key(tenant_id);
+    }
+    Ok(())
+}
+
+pub async fn use_command(namespace: Option<&str>, clear: bool) -> Result<()> {
+    let session = match SessionManager::get_current_session() {
+        Ok(session) => session,
+        Err(_) => {
+            output::print_error("Please login first");
+            return Ok(());
+        }
+    };
+    
+    if clear {
+        SessionManager::set_namespace(None)?;
+        output::print_success(&format!("Now using namespace: {}", "default".cyan()));
+    } else if let Some(namespace) = namespace {
+        SessionManager::set_namespace(Some(namespace.to_string()))?;
+        output::print_success(&format!("Now using namespace: {}", namespace.cyan()));
+    } else {
+        println!("{}", session.namespace.as_deref().unwrap_or("default"));
+    }
+    
+    Ok(())
+}
+
+pub async fn whoami_command(json: bool) -> Result<()> {
+    if json {
+        let info = match SessionManager::get_current_session() {
+            Ok(session) => serde_json::json!({
+                "logged_in": session.is_valid(),
+                "tenant": session.tenant_id,
+                "user": session.user_id,
+                "role": session.role,
+                "namespace": session.namespace.as_deref().unwrap_or("default"),
+                "profile": crate::config::profile_name(),
+                "created_at": session.created_at,
+                "expires_at": session.expires_at,
+                "permissions": {
+                    "read": session.role.can_read(),
+                    "write": session.role.can_write(),
+                    "admin": session.role.can_admin(),
+                    "audit": session.role.can_audit(),
+                },
+            }),
+            Err(_) => serde_json::json!({
+                "logged_in": false,
+                "profile": crate::config::profile_name(),
+            }),
+        };
+        output::print_json(&info)?;
+        return Ok(());
+    }
+    
     match SessionManager::get_current_session() {
         Ok(session) => {
             println!("{} Current Session", "👤".cyan());
+            println!("Profile: {}", crate::config::profile_name().cyan());
             println!("Tenant: {}", session.tenant_id.cyan());
             println!("User: {}", session.user_id.cyan());
             println!("Role: {:?}", session.role);
-            println!("Created: {}", session.created_at.format("%Y-%m-%d %H:%M:%S UTC"));
-            println!("Expires: {}", session.expires_at.format("%Y-%m-%d %H:%M:%S UTC"));
+            println!("Namespace: {}", session.namespace.as_deref().unwrap_or("default").cyan());
+            println!("Created: {}", output::format_date(&session.created_at));
+            println!("Expires: {}", output::format_date(&session.expires_at));
             
             if session.is_valid() {
                 let time_left = session.time_until_expiry();
@@ -228,7 +487,7 @@ pub async fn whoami_command() -> Result<()> {
             }
         }
         Err(_) => {
-            output::print_info("Not logged in");
+            output::print_info(&format!("Not logged in (profile '{}')", crate::config::profile_name()));
             println!("Run 'vault login --tenant <tenant>' to authenticate");
         }
     }
@@ -4,6 +4,7 @@ pub mod sync;
 pub mod admin;
 pub mod utils;
 pub mod users;
+mod password_managers;
 
 pub use auth::*;
 pub use secrets::*;
@@ -0,0 +1,382 @@
+//! Parsers for other password managers' JSON exports, used by
+//! `vault import --format bitwarden|1password`.
+//!
+//! Folders (Bitwarden) and vaults (1Password) become namespaces, item names
+//! become keys and the password or note becomes the value. Notes, TOTP seeds
+//! and custom fields are kept as separate `<key>.<field>` secrets so they stay
+//! encrypted, while usernames, URLs and tags become tags.
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+
+pub(super) struct ForeignItem {
+    pub namespace: Option<String>, // None = the import's target namespace
+    pub key: String,
+    pub value: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Default)]
+pub(super) struct ForeignImport {
+    pub items: Vec<ForeignItem>,
+    pub imported: BTreeMap<&'static str, usize>, // Items per category, not secrets
+    pub skipped: BTreeMap<&'static str, usize>,
+    pub warnings: Vec<String>,
+    taken: HashSet<(Option<String>, String)>,
+}
+
+impl ForeignImport {
+    /// Queue an item's main value plus its extra fields, renaming the key
+    /// when an earlier item in the same namespace already claimed it.
+    fn push(
+        &mut self,
+        category: &'static str,
+        namespace: Option<String>,
+        name: &str,
+        value: String,
+        tags: Vec<String>,
+        extras: Vec<(String, String)>,
+    ) {
+        let base = slug(name);
+        let mut key = base.clone();
+        let mut n = 2;
+        while self.taken.contains(&(namespace.clone(), key.clone())) {
+            key = format!("{}-{}", base, n);
+            n += 1;
+        }
+        
+        for (field, extra) in extras {
+            if extra.is_empty() {
+                continue;
+            }
+            let extra_key = format!("{}.{}", key, slug(&field));
+            if self.taken.insert((namespace.clone(), extra_key.clone())) {
+                self.items.push(ForeignItem { namespace: namespace.clone(), key: extra_key, value: extra, tags: tags.clone() });
+            } else {
+                self.warnings.push(format!("Dropping duplicate field '{}' of '{}'", field, name));
+            }
+        }
+        
+        self.taken.insert((namespace.clone(), key.clone()));
+        self.items.push(ForeignItem { namespace, key, value, tags });
+        *self.imported.entry(category).or_default() += 1;
+    }
+    
+    fn skip(&mut self, category: &'static str, reason: String) {
+        self.warnings.push(reason);
+        *self.skipped.entry(category).or_default() += 1;
+    }
+}
+
+/// Lowercase `name` and replace anything outside `[a-z0-9._-]` with dashes.
+fn slug(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            out.push(c);
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    let out = out.trim_matches('-');
+    if out.is_empty() { "item".to_string() } else { out.to_string() }
+}
+
+#[derive(Deserialize)]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<BitwardenFolder>,
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenFolder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem {
+    #[serde(rename = "type")]
+    kind: u8,
+    name: String,
+    folder_id: Option<String>,
+    notes: Option<String>,
+    #[serde(default)]
+    favorite: bool,
+    fields: Option<Vec<BitwardenField>>,
+    login: Option<BitwardenLogin>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenField {
+    name: Option<String>,
+    value: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenLogin {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    uris: Option<Vec<BitwardenUri>>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenUri {
+    uri: Option<String>,
+}
+
+/// Parse Bitwarden's unencrypted JSON export (Tools → Export vault → .json).
+pub(super) fn parse_bitwarden(content: &str) -> Result<ForeignImport, String> {
+    let export: BitwardenExport = serde_json::from_str(content)
+        .map_err(|e| format!("not a Bitwarden JSON export: {}", e))?;
+    if export.encrypted {
+        return Err("encrypted Bitwarden exports are not supported, export as unencrypted .json".to_string());
+    }
+    
+    let folders: BTreeMap<String, String> = export.folders.into_iter()
+        .map(|folder| (folder.id, slug(&folder.name)))
+        .collect();
+    
+    let mut import = ForeignImport::default();
+    for item in export.items {
+        let namespace = item.folder_id.as_ref().and_then(|id| folders.get(id)).cloned();
+        let mut tags = vec!["source:bitwarden".to_string()];
+        if item.favorite {
+            tags.push("favorite".to_string());
+        }
+        let mut extras: Vec<(String, String)> = item.fields.unwrap_or_default().into_iter()
+            .filter_map(|field| Some((field.name.unwrap_or_else(|| "field".to_string()), field.value?)))
+            .collect();
+        
+        match item.kind {
+            1 => {
+                let login = item.login.unwrap_or(BitwardenLogin { username: None, password: None, totp: None, uris: None });
+                let Some(password) = login.password.filter(|p| !p.is_empty()) else {
+                    import.skip("logins", format!("Skipping login '{}': it has no password", item.name));
+                    continue;
+                };
+                if let Some(username) = login.username.filter(|u| !u.is_empty()) {
+                    tags.push(format!("username:{}", username));
+                }
+                if let Some(uri) = login.uris.unwrap_or_default().into_iter().find_map(|u| u.uri) {
+                    tags.push(format!("url:{}", uri));
+                }
+                if let Some(totp) = login.totp {
+                    extras.push(("totp".to_string(), totp));
+                }
+                if let Some(notes) = item.notes {
+                    extras.push(("notes".to_string(), notes));
+                }
+                import.push("logins", namespace, &item.name, password, tags, extras);
+            }
+            2 => match item.notes.filter(|n| !n.is_empty()) {
+                Some(notes) => import.push("secure notes", namespace, &item.name, notes, tags, extras),
+                None => import.skip("secure notes", format!("Skipping secure note '{}': it is empty", item.name)),
+            },
+            3 => import.skip("cards", format!("Skipping card '{}': cards are not supported", item.name)),
+            4 => import.skip("identities", format!("Skipping identity '{}': identities are not supported", item.name)),
+            _ => import.skip("other items", format!("Skipping '{}': unsupported item type {}", item.name, item.kind)),
+        }
+    }
+    
+    Ok(import)
+}
+
+#[derive(Deserialize)]
+struct OnePuxExport {
+    accounts: Vec<OnePuxAccount>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxAccount {
+    vaults: Vec<OnePuxVault>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxVault {
+    attrs: OnePuxVaultAttrs,
+    items: Vec<OnePuxItem>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxVaultAttrs {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OnePuxItem {
+    category_uuid: String,
+    state: Option<String>,
+    overview: OnePuxOverview,
+    details: OnePuxDetails,
+}
+
+#[derive(Deserialize)]
+struct OnePuxOverview {
+    title: String,
+    url: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OnePuxDetails {
+    #[serde(default)]
+    login_fields: Vec<OnePuxLoginField>,
+    notes_plain: Option<String>,
+    #[serde(default)]
+    sections: Vec<OnePuxSection>,
+    password: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxLoginField {
+    value: String,
+    designation: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxSection {
+    #[serde(default)]
+    fields: Vec<OnePuxSectionField>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxSectionField {
+    title: String,
+    value: serde_json::Value, // e.g. {"concealed": "..."} or {"string": "..."}
+}
+
+/// Parse the `export.data` JSON from a 1Password `.1pux` export.
+pub(super) fn parse_1password(content: &str) -> Result<ForeignImport, String> {
+    let export: OnePuxExport = serde_json::from_str(content)
+        .map_err(|e| format!("not a 1Password export.data file (unzip the .1pux first): {}", e))?;
+    
+    let mut import = ForeignImport::default();
+    for vault in export.accounts.into_iter().flat_map(|account| account.vaults) {
+        let namespace = Some(slug(&vault.attrs.name));
+        for item in vault.items {
+            let title = item.overview.title;
+            if item.state.as_deref() == Some("archived") {
+                import.skip("archived items", format!("Skipping '{}': it is archived", title));
+                continue;
+            }
+            
+            let mut tags = vec!["source:1password".to_string()];
+            tags.extend(item.overview.tags);
+            if let Some(url) = item.overview.url.filter(|u| !u.is_empty()) {
+                tags.push(format!("url:{}", url));
+            }
+            let mut extras: Vec<(String, String)> = item.details.sections.into_iter()
+                .flat_map(|section| section.fields)
+                .filter_map(|field| {
+                    let value = field.value.as_object()?.values().find_map(|v| v.as_str())?.to_string();
+                    Some((field.title, value))
+                })
+                .collect();
+            if let Some(notes) = item.details.notes_plain {
+                extras.push(("notes".to_string(), notes));
+            }
+            
+            match item.category_uuid.as_str() {
+                "001" => {
+                    let field = |designation: &str| item.details.login_fields.iter()
+                        .find(|f| f.designation.as_deref() == Some(designation) && !f.value.is_empty())
+                        .map(|f| f.value.clone());
+                    let Some(password) = field("password") else {
+                        import.skip("logins", format!("Skipping login '{}': it has no password", title));
+                        continue;
+                    };
+                    if let Some(username) = field("username") {
+                        tags.push(format!("username:{}", username));
+                    }
+                    import.push("logins", namespace.clone(), &title, password, tags, extras);
+                }
+                "005" => match item.details.password.filter(|p| !p.is_empty()) {
+                    Some(password) => import.push("passwords", namespace.clone(), &title, password, tags, extras),
+                    None => import.skip("passwords", format!("Skipping password '{}': it is empty", title)),
+                },
+                "003" => match extras.iter().position(|(field, _)| field == "notes") {
+                    Some(i) => {
+                        let (_, notes) = extras.remove(i);
+                        import.push("secure notes", namespace.clone(), &title, notes, tags, extras);
+                    }
+                    None => import.skip("secure notes", format!("Skipping secure note '{}': it is empty", title)),
+                },
+                "002" => import.skip("cards", format!("Skipping card '{}': cards are not supported", title)),
+                "004" => import.skip("identities", format!("Skipping identity '{}': identities are not supported", title)),
+                "006" => import.skip("documents", format!("Skipping document '{}': documents are not supported", title)),
+                other => import.skip("other items", format!("Skipping '{}': unsupported category {}", title, other)),
+            }
+        }
+    }
+    
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_bitwarden_maps_folders_and_skips_cards() {
+        let export = r#"{"encrypted": false,
+            "folders": [{"id": "f1", "name": "Work Stuff"}],
+            "items": [
+                {"type": 1, "name": "GitHub", "folderId": "f1", "notes": "2fa on phone", "favorite": true,
+                 "fields": [{"name": "API Token", "value": "ghp_x", "type": 1}],
+                 "login": {"username": "me", "password": "pw", "totp": null, "uris": [{"match": null, "uri": "https://github.com"}]}},
+                {"type": 1, "name": "GitHub", "folderId": "f1", "notes": null, "fields": null,
+                 "login": {"username": null, "password": "pw2", "totp": null, "uris": null}},
+                {"type": 2, "name": "Wifi", "folderId": null, "notes": "hunter2", "secureNote": {"type": 0}},
+                {"type": 3, "name": "Visa", "folderId": null, "notes": null, "card": {}}
+            ]}"#;
+        let import = parse_bitwarden(export).unwrap();
+        
+        let keys: Vec<_> = import.items.iter().map(|i| (i.namespace.as_deref(), i.key.as_str())).collect();
+        assert_eq!(keys, vec![
+            (Some("work-stuff"), "github.api-token"),
+            (Some("work-stuff"), "github.notes"),
+            (Some("work-stuff"), "github"),
+            (Some("work-stuff"), "github-2"),
+            (None, "wifi"),
+        ]);
+        let github = &import.items[2];
+        assert_eq!(github.value, "pw");
+        assert!(github.tags.contains(&"username:me".to_string()));
+        assert!(github.tags.contains(&"url:https://github.com".to_string()));
+        assert_eq!(import.imported["logins"], 2);
+        assert_eq!(import.skipped["cards"], 1);
+        
+        assert!(parse_bitwarden(r#"{"encrypted": true, "items": []}"#).is_err());
+    }
+    
+    #[test]
+    fn test_parse_1password_uses_vault_as_namespace() {
+        let export = r#"{"accounts": [{"attrs": {}, "vaults": [{"attrs": {"name": "Personal"}, "items": [
+            {"uuid": "a", "categoryUuid": "001", "state": "active",
+             "overview": {"title": "Email", "url": "https://mail.example", "tags": ["home"]},
+             "details": {"loginFields": [{"value": "me@example", "designation": "username"}, {"value": "pw", "designation": "password"}],
+                         "sections": [{"title": "", "fields": [{"title": "PIN", "id": "p", "value": {"concealed": "1234"}}]}]}},
+            {"uuid": "b", "categoryUuid": "003", "state": "active", "overview": {"title": "Recovery"}, "details": {"notesPlain": "codes"}},
+            {"uuid": "c", "categoryUuid": "002", "state": "active", "overview": {"title": "Amex"}, "details": {}},
+            {"uuid": "d", "categoryUuid": "001", "state": "archived", "overview": {"title": "Old"}, "details": {}}
+        ]}]}]}"#;
+        let import = parse_1password(export).unwrap();
+        
+        let keys: Vec<_> = import.items.iter().map(|i| i.key.as_str()).collect();
+        assert_eq!(keys, vec!["email.pin", "email", "recovery"]);
+        assert!(import.items.iter().all(|i| i.namespace.as_deref() == Some("personal")));
+        assert_eq!(import.items[2].value, "codes");
+        assert!(import.items[1].tags.contains(&"home".to_string()));
+        assert_eq!(import.skipped["cards"], 1);
+        assert_eq!(import.skipped["archived items"], 1);
+    }
+}
@@ -1,127 +1,189 @@
 use anyhow::Result;
-use dialoguer::{Password, Confirm, Input, Select};
-use owo_colors::OwoColorize;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use dialoguer::{Password, Input, Select};
 use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
 
 use crate::{
-    storage::{VaultStorage, AuditLogger, AuditEntry, SecretGenerator},
-    cli::output,
+    config::Config,
+    crypto::{SharedSecret, seal_shared_secret, open_shared_secret},
+    error::VaultError,
+    storage::{VaultStorage, SecretMetadata, ListOptions, SearchOptions, SecretGenerator, SortBy, SshKeyType},
+    cli::{clipboard, TagsAction, TrashAction, commands::{check_password_strength, parse_duration, read_piped_value}, output::{self, Colorize}},
     auth::SessionManager,
 };
 
+/// `get` flag that writes a value to a file, named in hints for binary secrets.
+pub const OUTPUT_FILE_FLAG: &str = "--output-file";
+
+/// Parse `put --ttl`, e.g. `12h` or `90d`.
+pub fn parse_ttl(value: &str) -> std::result::Result<chrono::Duration, String> {
+    match parse_duration(value) {
+        Some(ttl) if ttl > chrono::Duration::zero() => Ok(ttl),
+        _ => Err(format!("Invalid TTL: {} (use a positive duration such as 12h or 90d)", value)),
+    }
+}
+
+/// Parse `list --sort`: `key`, `created`, `updated` or `version`.
+pub fn parse_sort(value: &str) -> std::result::Result<SortBy, String> {
+    match value {
+        "key" => Ok(SortBy::Key),
+        "created" => Ok(SortBy::CreatedAt),
+        "updated" => Ok(SortBy::UpdatedAt),
+        "version" => Ok(SortBy::Version),
+        _ => Err(format!("Invalid sort field: {} (use key, created, updated or version)", value)),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn put_command(
     storage: &VaultStorage,
     key: &str,
     namespace: Option<&str>,
     value: Option<&str>,
+    from_stdin: bool,
+    file: Option<&str>,
     tags: &[String],
     force: bool,
+    ttl: Option<chrono::Duration>,
 ) -> Result<()> {
     let ns = namespace.unwrap_or("default");
-    
-    if !force && storage.get(key, ns).await?.is_some() {
-        if !Confirm::new()
-            .with_prompt(format!("Secret '{}/{}' already exists. Overwrite?", ns, key))
-            .interact()?
-        {
-            output::print_info("Operation cancelled");
+    // A piped value never goes through the interactive prompts below
+    let piped = value.is_none() && file.is_none() && (from_stdin || !std::io::stdin().is_terminal());
+    let expires_at = match ttl.map(|ttl| chrono::Utc::now().checked_add_signed(ttl)) {
+        Some(None) => {
+            output::print_error("TTL is out of range");
             return Ok(());
         }
+        Some(expires_at) => expires_at,
+        None => None,
+    };
+    
+    if !force && storage.exists(key, ns).await? && !output::confirm(format!("Secret '{}/{}' already exists. Overwrite?", ns, key), false)? {
+        output::print_info("Operation cancelled");
+        return Ok(());
     }
     
-    let secret_value = match value {
-        Some(v) => v.to_string(),
-        None => {
-            let secret_type_choice = Select::new()
-                .with_prompt("What type of secret?")
-                .items(&[
-                    "Simple Password",
-                    "API Key", 
-                    "Database Credentials",
-                    "SSH Key Pair",
-                    "Custom Text",
-                    "UUID",
-                    "Hex Key"
-                ])
-                .default(0)
-                .interact()?;
-            
-            match secret_type_choice {
-                0 => {
-                    let generate = Confirm::new()
-                        .with_prompt("Generate secure password?")
-                        .default(true)
-                        .interact()?;
-                    
-                    if generate {
-                        let length = Input::<usize>::new()
-                            .with_prompt("Password length")
-                            .default(32)
+    // File contents are kept byte for byte, trailing newline included; only
+    // content that is not UTF-8 becomes a binary secret
+    let file_value = match file {
+        Some(path) => match read_secret_file(storage, path) {
+            Some(bytes) => Some(bytes),
+            None => return Ok(()),
+        },
+        None => None,
+    };
+    let binary = file_value.as_ref().is_some_and(|bytes| std::str::from_utf8(bytes).is_err());
+    
+    let secret_value = match file_value {
+        Some(bytes) => bytes,
+        None => match value {
+            Some(v) => v.to_string(),
+            None if piped => {
+                let value = read_piped_value(std::io::stdin())?;
+                if value.is_empty() {
+                    output::print_error("No secret value on stdin");
+                    return Ok(());
+                }
+                value
+            }
+            None => {
+                let secret_type_choice = Select::new()
+                    .with_prompt("What type of secret?")
+                    .items(&[
+                        "Simple Password",
+                        "API Key", 
+                        "Database Credentials",
+                        "SSH Key Pair",
+                        "Custom Text",
+                        "UUID",
+                        "Hex Key"
+                    ])
+                    .default(0)
+                    .interact()?;
+                
+                match secret_type_choice {
+                    0 => {
+                        let generate = output::ask("Generate secure password?", true)?;
+                        
+                        if generate {
+                            let length = Input::<usize>::new()
+                                .with_prompt("Password length")
+                                .default(32)
+                                .interact()?;
+                            
+                            let include_symbols = output::ask("Include symbols?", true)?;
+                            
+                            SecretGenerator::generate_password(length, include_symbols)
+                        } else {
+                            let password = Password::new()
+                                .with_prompt("Enter password")
+                                .interact()?;
+                            check_password_strength(&password, &[key], 0);
+                            password
+                        }
+                    }
+                    1 => {
+                        let prefix = Input::<String>::new()
+                            .with_prompt("API key prefix (optional)")
+                            .allow_empty(true)
                             .interact()?;
                         
-                        let include_symbols = Confirm::new()
-                            .with_prompt("Include symbols?")
-                            .default(true)
+                        let prefix_opt = if prefix.is_empty() { None } else { Some(prefix.as_str()) };
+                        SecretGenerator::generate_api_key(prefix_opt)
+                    }
+                    2 => {
+                        let db_type = Select::new()
+                            .with_prompt("Database type")
+                            .items(&["postgres", "mysql", "redis", "mongodb"])
+                            .default(0)
                             .interact()?;
                         
-                        SecretGenerator::generate_password(length, include_symbols)
-                    } else {
+                        let db_name = ["postgres", "mysql", "redis", "mongodb"][db_type];
+                        let creds = SecretGenerator::generate_database_credentials(db_name);
+                        serde_json::to_string_pretty(&creds).unwrap_or_default()
+                    }
+                    3 => {
+                        let key_type = Select::new()
+                            .with_prompt("Key type")
+                            .items(&["ed25519", "rsa"])
+                            .default(0)
+                            .interact()?;
+                        
+                        let comment = Input::<String>::new()
+                            .with_prompt("Key comment")
+                            .default(format!("{}@vault", key))
+                            .interact()?;
+                        
+                        let key_type = if key_type == 1 { SshKeyType::Rsa } else { SshKeyType::Ed25519 };
+                        let (private_key, public_key) = SecretGenerator::generate_ssh_key(key_type, &comment)?;
+                        format!("Private Key:\n{}\n\nPublic Key:\n{}", private_key, public_key)
+                    }
+                    4 => {
+                        Input::<String>::new()
+                            .with_prompt("Enter custom text")
+                            .interact()?
+                    }
+                    5 => SecretGenerator::generate_uuid(),
+                    6 => {
+                        let length = Input::<usize>::new()
+                            .with_prompt("Hex key length")
+                            .default(32)
+                            .interact()?;
+                        
+                        SecretGenerator::generate_hex_key(length)
+                    }
+                    _ => {
                         Password::new()
-                            .with_prompt("Enter password")
+                            .with_prompt("Enter secret value")
                             .interact()?
                     }
                 }
-                1 => {
-                    let prefix = Input::<String>::new()
-                        .with_prompt("API key prefix (optional)")
-                        .allow_empty(true)
-                        .interact()?;
-                    
-                    let prefix_opt = if prefix.is_empty() { None } else { Some(prefix.as_str()) };
-                    SecretGenerator::generate_api_key(prefix_opt)
-                }
-                2 => {
-                    let db_type = Select::new()
-                        .with_prompt("Database type")
-                        .items(&["postgres", "mysql", "redis", "mongodb"])
-                        .default(0)
-                        .interact()?;
-                    
-                    let db_name = ["postgres", "mysql", "redis", "mongodb"][db_type];
-                    let creds = SecretGenerator::generate_database_credentials(db_name);
-                    serde_json::to_string_pretty(&creds).unwrap_or_default()
-                }
-                3 => {
-                    let (private_key, public_key) = SecretGenerator::generate_ssh_key();
-                    format!("Private Key:\n{}\n\nPublic Key:\n{}", private_key, public_key)
-                }
-                4 => {
-                    Input::<String>::new()
-                        .with_prompt("Enter custom text")
-                        .interact()?
-                }
-                5 => SecretGenerator::generate_uuid(),
-                6 => {
-                    let length = Input::<usize>::new()
-                        .with_prompt("Hex key length")
-                        .default(32)
-                        .interact()?;
-                    
-                    SecretGenerator::generate_hex_key(length)
-                }
-                _ => {
-                    Password::new()
-                        .with_prompt("Enter secret value")
-                        .interact()?
-                }
             }
-        }
+        }.into_bytes(),
     };
     
-    let password_protected = Confirm::new()
-        .with_prompt("Password protect this secret?")
-        .default(false)
-        .interact()?;
+    let password_protected = output::ask("Password protect this secret?", false)?;
     
     let access_password = if password_protected {
         Some(Password::new()
@@ -137,38 +199,62 @@ pub async fn put_command(
     pb.set_message("Storing secret...");
     pb.enable_steady_tick(std::time::Duration::from_millis(100));
     
-    if tags.is_empty() {
-        storage.put_with_protection(key, &secret_value, ns, &[], access_password.as_deref()).await?;
-    } else {
-        storage.put_with_protection(key, &secret_value, ns, tags, access_password.as_deref()).await?;
-    }
+    storage.put_value(key, &secret_value, binary, ns, tags, access_password.as_deref(), expires_at).await?;
     
-    if let Ok(session) = SessionManager::get_current_session() {
-        let audit_entry = AuditEntry::new(
-            session.tenant_id,
-            AuditLogger::EVENT_SECRET_CREATED.to_string(),
-            format!("Secret {}/{} created", ns, key),
-            session.user_id,
-        )
-        .with_resource("secret".to_string(), format!("{}/{}", ns, key))
-        .with_metadata(serde_json::json!({"namespace": ns, "key": key, "tags": tags}));
-        let _ = AuditLogger::log_event(&audit_entry);
+    // The spinner is hidden without a terminal, which would swallow the
+    // confirmation of a piped put
+    pb.finish_and_clear();
+    match expires_at {
+        Some(expires_at) => output::print_success_to_stderr(&format!(
+            "Secret stored: {}/{} (expires {})", ns.cyan(), key.cyan(), output::format_date(&expires_at)
+        )),
+        None => output::print_success_to_stderr(&format!("Secret stored: {}/{}", ns.cyan(), key.cyan())),
     }
     
-    pb.finish_with_message(format!("{} Secret stored: {}/{}", "✓".green(), ns.cyan(), key.cyan()));
-    
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_command(
     storage: &VaultStorage,
     key: &str,
     namespace: Option<&str>,
     copy: bool,
+    clear_after: u64,
     metadata: bool,
+    json: bool,
+    raw: bool,
+    output_file: Option<&str>,
+    show: bool,
+    require_show: bool,
 ) -> Result<()> {
     let ns = namespace.unwrap_or("default");
     
+    // With ui.require_show_flag, printing the value to the terminal needs
+    // --show; copying or writing it to a file does not. A hidden value is
+    // never decrypted, so it isn't audited as read
+    let echoes = json || (output_file.is_none() && (raw || !copy));
+    if require_show && !show && echoes {
+        match storage.get_metadata(key, ns).await? {
+            Some(meta) if json => {
+                let entry = serde_json::json!({
+                    "namespace": ns,
+                    "key": key,
+                    "value": null,
+                    "redacted": true,
+                    "metadata": serde_json::to_value(&meta)?,
+                });
+                output::print_json(&entry)?;
+            }
+            Some(meta) => {
+                println!("{} {}", "********".dimmed(), "(hidden, pass --show to reveal)".dimmed());
+                print_metadata(&meta);
+            }
+            None => output::print_error(&format!("Secret not found: {}/{}", ns, key)),
+        }
+        return Ok(());
+    }
+    
     // Check if secret is password protected
     let is_protected = storage.is_secret_password_protected(key, ns).await?;
     let access_password = if is_protected {
@@ -179,43 +265,91 @@ pub async fn get_command(
         None
     };
     
-    match storage.get_with_metadata_and_password(key, ns, access_password.as_deref()).await? {
-        Some((value, meta)) => {
-            if copy {
-                #[cfg(target_os = "windows")]
-                {
-                    use std::process::Command;
-                    let mut child = Command::new("cmd")
-                        .args(["/C", "echo", &value, "|", "clip"])
-                        .spawn()?;
-                    child.wait()?;
+    // A file gets the exact stored bytes, text or binary
+    if let Some(path) = output_file.filter(|_| !json) {
+        let read = match access_password.as_deref() {
+            Some(password) => storage.get_bytes_with_metadata_and_password(key, ns, Some(password)).await
+                .map(|found| found.map(|(bytes, _)| bytes)),
+            None => storage.get_bytes(key, ns).await,
+        };
+        match read {
+            Err(VaultError::InvalidAccessPassword) => output::print_error("Wrong access password"),
+            Ok(Some(bytes)) => {
+                std::fs::write(path, &bytes)?;
+                output::print_success(&format!("Wrote {} bytes to {}", bytes.len(), path));
+                if metadata {
+                    if let Some(meta) = storage.get_metadata(key, ns).await? {
+                        print_metadata(&meta);
+                    }
                 }
-                
-                output::print_success("Secret copied to clipboard");
-            } else {
-                println!("{}", value);
             }
-            
-            if metadata {
-                println!("\n{}", "Metadata:".bold());
-                println!("  ID: {}", meta.id);
-                println!("  Created: {}", meta.created_at.format("%Y-%m-%d %H:%M:%S UTC"));
-                println!("  Updated: {}", meta.updated_at.format("%Y-%m-%d %H:%M:%S UTC"));
-                println!("  Version: {}", meta.version);
-                if !meta.tags.is_empty() {
-                    println!("  Tags: {}", meta.tags.join(", ").yellow());
+            Ok(None) => output::print_error(&format!("Secret not found: {}/{}", ns, key)),
+            Err(e) => return Err(e.into()),
+        }
+        return Ok(());
+    }
+    
+    let found = match storage.get_bytes_with_metadata_and_password(key, ns, access_password.as_deref()).await {
+        Err(VaultError::InvalidAccessPassword) => {
+            output::print_error("Wrong access password");
+            return Ok(());
+        }
+        result => result?,
+    };
+    match found {
+        Some((bytes, meta)) => {
+            if json {
+                // Binary values are carried as base64 so the output stays valid JSON
+                let (value, encoding) = if meta.binary {
+                    (STANDARD.encode(&bytes), "base64")
+                } else {
+                    (String::from_utf8(bytes)?, "utf8")
+                };
+                let entry = serde_json::json!({
+                    "namespace": ns,
+                    "key": key,
+                    "value": value,
+                    "encoding": encoding,
+                    "metadata": serde_json::to_value(&meta)?,
+                });
+                output::print_json(&entry)?;
+            } else if raw && meta.binary && std::io::stdout().is_terminal() {
+                output::print_error(&format!(
+                    "Secret {}/{} holds binary data; refusing to write it to a terminal. Use {} <path> or redirect stdout", ns, key, OUTPUT_FILE_FLAG
+                ));
+                return Ok(());
+            } else if raw {
+                use std::io::Write;
+                let mut stdout = std::io::stdout();
+                stdout.write_all(&bytes)?;
+                stdout.flush()?;
+            } else if meta.binary {
+                output::print_error(&format!(
+                    "Secret {}/{} holds binary data. Use {} <path>, or --raw with stdout redirected", ns, key, OUTPUT_FILE_FLAG
+                ));
+                return Ok(());
+            } else {
+                let value = String::from_utf8(bytes)?;
+                if copy {
+                    if let Err(e) = clipboard::copy_to_clipboard(&value) {
+                        output::print_error(&e.to_string());
+                        return Ok(());
+                    }
+                    
+                    output::print_success("Secret copied to clipboard");
+                    if clear_after > 0 {
+                        match clipboard::schedule_clear(&value, clear_after) {
+                            Ok(()) => output::print_info(&format!("Clipboard will clear in {}s", clear_after)),
+                            Err(e) => output::print_warning(&format!("Could not schedule clipboard clear: {}", e)),
+                        }
+                    }
+                } else {
+                    println!("{}", value);
                 }
             }
             
-            if let Ok(session) = SessionManager::get_current_session() {
-                let audit_entry = AuditEntry::new(
-                    session.tenant_id,
-                    AuditLogger::EVENT_SECRET_ACCESSED.to_string(),
-                    format!("Secret {}/{} accessed", ns, key),
-                    session.user_id,
-                )
-                .with_context(Some("127.0.0.1".to_string()), Some("vault-cli".to_string()));
-                let _ = AuditLogger::log_event(&audit_entry);
+            if metadata && !json {
+                print_metadata(&meta);
             }
         }
         None => {
@@ -226,17 +360,284 @@ pub async fn get_command(
     Ok(())
 }
 
+fn print_metadata(meta: &SecretMetadata) {
+    println!("\n{}", "Metadata:".bold());
+    println!("  ID: {}", meta.id);
+    println!("  Created: {} by {}", output::format_date(&meta.created_at), meta.created_by);
+    println!("  Updated: {} by {}", output::format_date(&meta.updated_at), meta.updated_by);
+    println!("  Version: {}", meta.version);
+    if meta.binary {
+        println!("  Type: binary");
+    }
+    if !meta.tags.is_empty() {
+        println!("  Tags: {}", meta.tags.join(", ").yellow());
+    }
+}
+
+/// `get --keys`: print several secrets as a table or JSON. Entries are
+/// `namespace/key` or a key in `namespace`; `-` reads them from stdin, one
+/// per line.
+pub async fn get_many_command(
+    storage: &VaultStorage,
+    keys: &[String],
+    namespace: Option<&str>,
+    json: bool,
+    show: bool,
+    require_show: bool,
+) -> Result<()> {
+    let ns = namespace.unwrap_or("default");
+    
+    let entries = if keys.iter().any(|key| key == "-") {
+        std::io::read_to_string(std::io::stdin())?
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect()
+    } else {
+        keys.to_vec()
+    };
+    let paths: Vec<(String, String)> = entries.iter()
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once('/') {
+            Some((ns, key)) => (ns.to_string(), key.to_string()),
+            None => (ns.to_string(), entry.clone()),
+        })
+        .collect();
+    if paths.is_empty() {
+        output::print_error("No keys given");
+        return Ok(());
+    }
+    
+    // Hidden values are only looked up, not decrypted, so they aren't
+    // audited as read
+    let hidden = require_show && !show;
+    let values = if hidden {
+        let mut found = Vec::with_capacity(paths.len());
+        for (ns, key) in paths {
+            let exists = storage.get_metadata(&key, &ns).await?.is_some();
+            found.push((ns, key, exists.then(String::new)));
+        }
+        Ok(found)
+    } else {
+        storage.get_many(&paths).await
+    };
+    let values = match values {
+        Ok(values) => values,
+        Err(e) => {
+            output::print_error(&format!("Batch get failed: {}", e));
+            return Ok(());
+        }
+    };
+    
+    if json {
+        let entries: Vec<_> = values.iter()
+            .map(|(ns, key, value)| serde_json::json!({
+                "namespace": ns,
+                "key": key,
+                "value": if hidden { None } else { value.as_deref() },
+                "found": value.is_some(),
+            }))
+            .collect();
+        output::print_json(&entries)?;
+        return Ok(());
+    }
+    
+    let mut table = output::table(&["Secret", "Value"]);
+    for (ns, key, value) in &values {
+        let value = match value {
+            None => "(not found)".to_string(),
+            Some(_) if hidden => "********".to_string(),
+            Some(value) => value.clone(),
+        };
+        table.add_row(vec![format!("{}/{}", ns, key), value]);
+    }
+    println!("{}", table);
+    if hidden {
+        output::print_info("Values hidden, pass --show to reveal");
+    }
+    
+    Ok(())
+}
+
+/// Read a file to store as a secret, reporting why when it is too large or
+/// unreadable. The size is checked first so an oversized file is never read
+/// into memory.
+fn read_secret_file(storage: &VaultStorage, file: &str) -> Option<Vec<u8>> {
+    let limit = storage.max_secret_bytes();
+    if let Ok(file_meta) = std::fs::metadata(file) {
+        if file_meta.len() > limit as u64 {
+            output::print_error(&format!(
+                "{} is {} bytes, over the {} byte limit (raise security.max_secret_bytes to store it)",
+                file, file_meta.len(), limit
+            ));
+            return None;
+        }
+    }
+    
+    match std::fs::read(file) {
+        Ok(bytes) => Some(bytes),
+        Err(e) => {
+            output::print_error(&format!("Failed to read {}: {}", file, e));
+            None
+        }
+    }
+}
+
+pub async fn share_command(
+    storage: &VaultStorage,
+    config: &Config,
+    key: &str,
+    namespace: Option<&str>,
+    password: Option<&str>,
+) -> Result<()> {
+    let ns = namespace.unwrap_or("default");
+    
+    let access_password = if storage.is_secret_password_protected(key, ns).await? {
+        Some(Password::new()
+            .with_prompt("Enter access password for this secret")
+            .interact()?)
+    } else {
+        None
+    };
+    
+    let found = match storage.get_bytes_with_metadata_and_password(key, ns, access_password.as_deref()).await {
+        Err(VaultError::InvalidAccessPassword) => {
+            output::print_error("Wrong access password");
+            return Ok(());
+        }
+        result => result?,
+    };
+    let Some((value, meta)) = found else {
+        output::print_error(&format!("Secret not found: {}/{}", ns, key));
+        return Ok(());
+    };
+    
+    let shared_password = match password {
+        Some(p) => p.to_string(),
+        None => Password::new()
+            .with_prompt("Shared password")
+            .with_confirmation("Confirm shared password", "Passwords do not match")
+            .interact()?,
+    };
+    
+    let shared = SharedSecret {
+        key: key.to_string(),
+        value,
+        binary: meta.binary,
+    };
+    let blob = seal_shared_secret(&shared, &shared_password, config.get_encryption_algorithm())?;
+    
+    println!("{}", blob);
+    
+    Ok(())
+}
+
+pub async fn receive_command(
+    storage: &VaultStorage,
+    blob: &str,
+    namespace: Option<&str>,
+    key: Option<&str>,
+    password: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    let ns = namespace.unwrap_or("default");
+    
+    let shared_password = match password {
+        Some(p) => p.to_string(),
+        None => Password::new()
+            .with_prompt("Shared password")
+            .interact()?,
+    };
+    
+    let shared = match open_shared_secret(blob, &shared_password) {
+        Ok(shared) => shared,
+        Err(e) => {
+            output::print_error(&e.to_string());
+            return Ok(());
+        }
+    };
+    let key = key.unwrap_or(&shared.key);
+    
+    if !force && storage.exists(key, ns).await? && !output::confirm(format!("Secret '{}/{}' already exists. Overwrite?", ns, key), false)? {
+        output::print_info("Operation cancelled");
+        return Ok(());
+    }
+    
+    if shared.binary {
+        storage.put_bytes(key, &shared.value, ns, &[]).await?;
+    } else {
+        let value = String::from_utf8(shared.value)?;
+        storage.put_with_tags(key, &value, ns, &[]).await?;
+    }
+    
+    output::print_success(&format!("Secret stored: {}/{}", ns.cyan(), key.cyan()));
+    
+    Ok(())
+}
+
+pub async fn link_command(
+    storage: &VaultStorage,
+    alias: &str,
+    target: &str,
+    namespace: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    let ns = namespace.unwrap_or("default");
+    
+    match storage.link(alias, ns, target, force).await {
+        Ok(_) => {
+            output::print_success(&format!("Alias created: {}/{} -> {}", ns.cyan(), alias.cyan(), target.cyan()));
+        }
+        Err(e) => output::print_error(&e.to_string()),
+    }
+    
+    Ok(())
+}
+
 pub async fn list_command(
     storage: &VaultStorage,
     namespace: Option<&str>,
     tag: Option<&str>,
     detailed: bool,
+    count: bool,
+    page: ListOptions,
+    json: bool,
 ) -> Result<()> {
     let ns = namespace.unwrap_or("default");
-    let secrets = storage.list_with_metadata(ns, tag).await?;
+    
+    if count {
+        print_count(storage.count_secrets(ns, tag).await?, json)?;
+        return Ok(());
+    }
+    
+    let (secrets, corrupted) = storage.list_with_metadata_checked(ns, tag, &page).await?;
+    if !corrupted.is_empty() && !json {
+        output::print_warning(&format!(
+            "Skipped {} unreadable records. Run 'vault doctor' for details",
+            corrupted.len()
+        ));
+    }
+    
+    if json {
+        let entries: Vec<_> = secrets.iter()
+            .map(|(key, meta)| serde_json::json!({
+                "namespace": ns,
+                "key": key,
+                "version": meta.version,
+                "tags": meta.tags,
+                "alias_of": meta.alias_of,
+                "created_at": meta.created_at,
+                "updated_at": meta.updated_at,
+            }))
+            .collect();
+        output::print_json(&entries)?;
+        return Ok(());
+    }
     
     if secrets.is_empty() {
-        if let Some(tag_filter) = tag {
+        let total = storage.count_secrets(ns, tag).await?;
+        if page.offset > 0 && total > 0 {
+            output::print_info(&format!("No secrets past offset {} in namespace: {} ({} in total)", page.offset, ns, total));
+        } else if let Some(tag_filter) = tag {
             output::print_info(&format!("No secrets found with tag '{}' in namespace: {}", tag_filter, ns));
         } else {
             output::print_info(&format!("No secrets found in namespace: {}", ns));
@@ -250,14 +651,46 @@ pub async fn list_command(
         println!("Secrets in {}:", ns.cyan());
     }
     
-    if detailed {
-        output::print_table_header(&["Key", "Created", "Version", "Tags"]);
-        output::print_secret_list(&secrets, true);
+    output::print_secret_list(&secrets, detailed);
+    
+    if page.offset > 0 || page.limit.is_some() {
+        // Records deleted since the page was read could leave the count short of it
+        let total = storage.count_secrets(ns, tag).await?.max(page.offset + secrets.len());
+        println!(
+            "\n{} Showing {}–{} of {} secret(s)",
+            "📊".cyan(),
+            page.offset + 1,
+            page.offset + secrets.len(),
+            total
+        );
     } else {
-        output::print_secret_list(&secrets, false);
+        println!("\n{} Total: {} secret(s)", "📊".cyan(), secrets.len());
+    }
+    
+    Ok(())
+}
+
+pub async fn namespaces_command(storage: &VaultStorage, json: bool) -> Result<()> {
+    let namespaces = storage.list_namespaces().await?;
+    
+    if json {
+        let entries: Vec<_> = namespaces.iter()
+            .map(|(ns, count)| serde_json::json!({ "namespace": ns, "secrets": count }))
+            .collect();
+        output::print_json(&entries)?;
+        return Ok(());
+    }
+    
+    if namespaces.is_empty() {
+        output::print_info("No secrets stored yet");
+        return Ok(());
     }
     
-    println!("\n{} Total: {} secret(s)", "📊".cyan(), secrets.len());
+    let mut table = output::table(&["Namespace", "Secrets"]);
+    for (ns, count) in &namespaces {
+        table.add_row(vec![ns.clone(), count.to_string()]);
+    }
+    println!("{}", table);
     
     Ok(())
 }
@@ -267,34 +700,383 @@ pub async fn delete_command(
     key: &str,
     namespace: Option<&str>,
     force: bool,
+    hard: bool,
 ) -> Result<()> {
     let ns = namespace.unwrap_or("default");
     
-    if !force {
-        if !Confirm::new()
-            .with_prompt(format!("Delete secret '{}/{}'?", ns, key))
-            .interact()?
-        {
-            println!("{} Operation cancelled", "ℹ".blue());
+    let prompt = if hard {
+        format!("Permanently delete secret '{}/{}'? This cannot be undone", ns, key)
+    } else {
+        format!("Delete secret '{}/{}'?", ns, key)
+    };
+    if !force && !output::confirm(prompt, false)? {
+        println!("{} Operation cancelled", "ℹ".blue());
+        return Ok(());
+    }
+    
+    let result = if hard {
+        storage.delete_hard(key, ns).await
+    } else {
+        storage.delete(key, ns).await
+    };
+    match result {
+        Ok(_) => {
+            if hard {
+                println!("{} Secret deleted: {}/{}", "✓".green(), ns.cyan(), key.cyan());
+            } else {
+                println!("{} Secret moved to trash: {}/{}", "✓".green(), ns.cyan(), key.cyan());
+                output::print_info(&format!("Restore it with 'vault trash restore {}/{}'", ns, key));
+            }
+            
+        }
+        Err(_) => println!("{} Secret not found: {}/{}", "✗".red(), ns, key),
+    }
+    
+    Ok(())
+}
+
+pub async fn delete_namespace_command(
+    storage: &VaultStorage,
+    namespace: &str,
+    force: bool,
+) -> Result<()> {
+    let count = storage.count_secrets(namespace, None).await?;
+    if count == 0 {
+        output::print_info(&format!("No secrets found in namespace: {}", namespace));
+        return Ok(());
+    }
+    
+    if !force && !output::confirm(format!("Delete all {} secret(s) in namespace '{}'? This cannot be undone", count, namespace), false)? {
+        println!("{} Operation cancelled", "ℹ".blue());
+        return Ok(());
+    }
+    
+    match storage.delete_namespace(namespace, force).await {
+        Ok(deleted) => {
+            println!("{} Deleted {} secret(s) from namespace {}", "✓".green(), deleted, namespace.cyan());
+        }
+        Err(VaultError::InvalidAlias(reason)) => {
+            output::print_error(&format!("{}. Remove the alias first or use --force", reason));
+        }
+        Err(e) => return Err(e.into()),
+    }
+    
+    Ok(())
+}
+
+pub async fn trash_command(action: TrashAction, storage: &VaultStorage, session_namespace: Option<&str>, json: bool) -> Result<()> {
+    match action {
+        TrashAction::List => {
+            let entries = storage.list_trash().await?;
+            
+            if json {
+                let entries: Vec<_> = entries.iter()
+                    .map(|(ns, key, deleted_at)| serde_json::json!({ "namespace": ns, "key": key, "deleted_at": deleted_at }))
+                    .collect();
+                output::print_json(&entries)?;
+                return Ok(());
+            }
+            
+            if entries.is_empty() {
+                output::print_info("Trash is empty");
+                return Ok(());
+            }
+            
+            let mut table = output::table(&["Secret", "Deleted"]);
+            for (ns, key, deleted_at) in &entries {
+                table.add_row(vec![format!("{}/{}", ns, key), output::format_date(deleted_at)]);
+            }
+            println!("{}", table);
+        }
+        TrashAction::Restore { path, namespace } => {
+            let (ns, key) = match namespace.as_deref() {
+                Some(ns) => (ns, path.as_str()),
+                None => match path.split_once('/') {
+                    Some((ns, key)) => (ns, key),
+                    None => (session_namespace.unwrap_or("default"), path.as_str()),
+                },
+            };
+            
+            match storage.restore(key, ns).await {
+                Ok(()) => println!("{} Secret restored: {}/{}", "✓".green(), ns.cyan(), key.cyan()),
+                Err(VaultError::SecretNotFound(_)) => {
+                    output::print_error(&format!("No deleted secret {}/{} in the trash", ns, key));
+                }
+                Err(VaultError::SecretExists(_)) => {
+                    output::print_error(&format!("Secret {}/{} exists again; move or delete it before restoring", ns, key));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        TrashAction::Empty { older_than, force } => {
+            let prompt = match older_than {
+                Some(age) => {
+                    let age = match (age.num_days(), age.num_hours(), age.num_minutes()) {
+                        (0, 0, 0) => format!("{}s", age.num_seconds()),
+                        (0, 0, minutes) => format!("{}m", minutes),
+                        (0, hours, _) => format!("{}h", hours),
+                        (days, _, _) => format!("{}d", days),
+                    };
+                    format!("Permanently delete secrets trashed more than {} ago?", age)
+                }
+                None => "Permanently delete everything in the trash?".to_string(),
+            };
+            if !force && !output::confirm(prompt, false)? {
+                println!("{} Operation cancelled", "ℹ".blue());
+                return Ok(());
+            }
+            
+            let purged = storage.purge_trash(older_than.unwrap_or_else(chrono::Duration::zero)).await?;
+            println!("{} Permanently deleted {} secret(s) from the trash", "✓".green(), purged);
+        }
+    }
+    
+    Ok(())
+}
+
+pub async fn history_command(
+    storage: &VaultStorage,
+    key: &str,
+    namespace: Option<&str>,
+    detailed: bool,
+    reveal: Option<u64>,
+) -> Result<()> {
+    match SessionManager::get_current_session() {
+        Ok(session) if !session.role.can_read() => {
+            output::print_error("Read permissions required to view secret history");
+            return Ok(());
+        }
+        Ok(_) => {}
+        Err(_) => {
+            output::print_error("Please login first");
             return Ok(());
         }
     }
     
-    match storage.delete(key, ns).await {
-        Ok(_) => {
-            println!("{} Secret deleted: {}/{}", "✓".green(), ns.cyan(), key.cyan());
+    let ns = namespace.unwrap_or("default");
+    
+    if let Some(version) = reveal {
+        match storage.get_version(key, ns, version).await {
+            Ok(Some(value)) => match String::from_utf8(value) {
+                Ok(text) => println!("{}", text),
+                Err(_) => output::print_error(&format!("Version {} of {}/{} holds binary data", version, ns, key)),
+            },
+            Ok(None) => output::print_error(&format!("Version {} of {}/{} not found", version, ns, key)),
+            Err(e) => output::print_error(&format!("Failed to read version {}: {}", version, e)),
+        }
+        return Ok(());
+    }
+    
+    let Some(versions) = storage.secret_history(key, ns).await? else {
+        output::print_error(&format!("Secret not found: {}/{}", ns, key));
+        return Ok(());
+    };
+    
+    let mut headers = vec!["Version", "Created", "By", "Description"];
+    if detailed {
+        headers.extend(["Cipher", "Type"]);
+    }
+    let mut table = output::table(&headers);
+    for (index, entry) in versions.iter().enumerate() {
+        let version = if index == 0 {
+            format!("{} (current)", entry.version)
+        } else {
+            entry.version.to_string()
+        };
+        let mut row = vec![
+            version,
+            output::format_date(&entry.created_at),
+            entry.created_by.clone(),
+            entry.change_description.clone().unwrap_or_default(),
+        ];
+        if detailed {
+            row.push(format!("{:?}", entry.encrypted_value.algorithm));
+            row.push(if entry.binary { "binary" } else { "text" }.to_string());
+        }
+        table.add_row(row);
+    }
+    
+    println!("History of {}/{}:", ns.cyan(), key.cyan());
+    println!("{}", table);
+    output::print_info("Run with --reveal <version> to print a version's value");
+    
+    Ok(())
+}
+
+pub async fn move_command(
+    storage: &VaultStorage,
+    key: &str,
+    namespace: Option<&str>,
+    new_key: Option<&str>,
+    new_namespace: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    match SessionManager::get_current_session() {
+        Ok(session) if !session.role.can_write() => {
+            output::print_error("Write permissions required to move a secret");
+            return Ok(());
+        }
+        Ok(_) => {}
+        Err(_) => {
+            output::print_error("Please login first");
+            return Ok(());
+        }
+    }
+    
+    let ns = namespace.unwrap_or("default");
+    let new_key = new_key.unwrap_or(key);
+    let new_ns = new_namespace.unwrap_or(ns);
+    
+    match storage.move_secret(key, ns, new_key, new_ns, force).await {
+        Ok(_) => output::print_success(&format!(
+            "Moved {}/{} to {}/{}", ns.cyan(), key.cyan(), new_ns.cyan(), new_key.cyan()
+        )),
+        Err(VaultError::SecretExists(path)) => output::print_error(&format!(
+            "{} already exists; use --force to replace it", path
+        )),
+        Err(e) => output::print_error(&format!("Move failed: {}", e)),
+    }
+    
+    Ok(())
+}
+
+pub async fn copy_command(
+    storage: &VaultStorage,
+    key: &str,
+    namespace: Option<&str>,
+    to_namespace: Option<&str>,
+    new_key: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    match SessionManager::get_current_session() {
+        Ok(session) if !session.role.can_write() => {
+            output::print_error("Write permissions required to copy a secret");
+            return Ok(());
+        }
+        Ok(_) => {}
+        Err(_) => {
+            output::print_error("Please login first");
+            return Ok(());
+        }
+    }
+    
+    let ns = namespace.unwrap_or("default");
+    let to_ns = to_namespace.unwrap_or(ns);
+    let dest_key = new_key.unwrap_or(key);
+    
+    if !force && storage.exists(dest_key, to_ns).await? && !output::confirm(format!("Secret '{}/{}' already exists. Overwrite?", to_ns, dest_key), false)? {
+        output::print_info("Operation cancelled");
+        return Ok(());
+    }
+    
+    match storage.copy_secret(key, ns, to_ns, new_key).await {
+        Ok(_) => output::print_success(&format!(
+            "Copied {}/{} to {}/{}", ns.cyan(), key.cyan(), to_ns.cyan(), dest_key.cyan()
+        )),
+        Err(e) => output::print_error(&format!("Copy failed: {}", e)),
+    }
+    
+    Ok(())
+}
+
+pub async fn tag_command(
+    storage: &VaultStorage,
+    key: &str,
+    namespace: Option<&str>,
+    add: &[String],
+    remove: &[String],
+) -> Result<()> {
+    match SessionManager::get_current_session() {
+        Ok(session) if !session.role.can_write() => {
+            output::print_error("Write permissions required to change tags");
+            return Ok(());
+        }
+        Ok(_) => {}
+        Err(_) => {
+            output::print_error("Please login first");
+            return Ok(());
+        }
+    }
+    
+    if add.is_empty() && remove.is_empty() {
+        output::print_error("Nothing to do: pass --add and/or --remove");
+        return Ok(());
+    }
+    
+    let ns = namespace.unwrap_or("default");
+    match storage.update_tags(key, ns, add, remove).await {
+        Ok(tags) if tags.is_empty() => output::print_success(&format!("{}/{} has no tags", ns.cyan(), key.cyan())),
+        Ok(tags) => output::print_success(&format!(
+            "Tags of {}/{}: {}", ns.cyan(), key.cyan(), tags.join(", ").yellow()
+        )),
+        Err(e) => output::print_error(&format!("Tag update failed: {}", e)),
+    }
+    
+    Ok(())
+}
+
+pub async fn tags_command(action: TagsAction, storage: &VaultStorage) -> Result<()> {
+    match SessionManager::get_current_session() {
+        Ok(session) if !session.role.can_write() => {
+            output::print_error("Write permissions required to change tags");
+            return Ok(());
+        }
+        Ok(_) => {}
+        Err(_) => {
+            output::print_error("Please login first");
+            return Ok(());
+        }
+    }
+    
+    match action {
+        TagsAction::Rename { from, to, namespace } => {
+            if from == to {
+                output::print_error("--from and --to are the same tag");
+                return Ok(());
+            }
             
-            if let Ok(session) = SessionManager::get_current_session() {
-                let audit_entry = AuditEntry::new(
-                    session.tenant_id,
-                    AuditLogger::EVENT_SECRET_UPDATED.to_string(),
-                    format!("Secret {}/{} updated (deleted)", ns, key),
-                    session.user_id,
-                );
-                let _ = AuditLogger::log_event(&audit_entry);
+            match storage.rename_tag(&from, &to, namespace.as_deref()).await {
+                Ok(0) => output::print_info(&format!("No secrets tagged '{}'", from)),
+                Ok(count) => output::print_success(&format!(
+                    "Renamed tag '{}' to '{}' on {} secret(s)", from.yellow(), to.yellow(), count
+                )),
+                Err(e) => output::print_error(&format!("Tag rename failed: {}", e)),
             }
         }
-        Err(_) => println!("{} Secret not found: {}/{}", "✗".red(), ns, key),
+    }
+    
+    Ok(())
+}
+
+pub async fn rollback_command(
+    storage: &VaultStorage,
+    key: &str,
+    namespace: Option<&str>,
+    version: u64,
+) -> Result<()> {
+    match SessionManager::get_current_session() {
+        Ok(session) if !session.role.can_write() => {
+            output::print_error("Write permissions required to roll back a secret");
+            return Ok(());
+        }
+        Ok(_) => {}
+        Err(_) => {
+            output::print_error("Please login first");
+            return Ok(());
+        }
+    }
+    
+    let ns = namespace.unwrap_or("default");
+    if !output::confirm(format!("Restore version {} of '{}/{}' as its current value?", version, ns, key), false)? {
+        output::print_info("Operation cancelled");
+        return Ok(());
+    }
+    
+    match storage.rollback_secret(key, ns, version).await {
+        Ok(new_version) => output::print_success(&format!(
+            "Restored {}/{} from version {} as version {}", ns, key, version, new_version
+        )),
+        Err(e) => output::print_error(&format!("Rollback failed: {}", e)),
     }
     
     Ok(())
@@ -304,8 +1086,50 @@ pub async fn search_command(
     storage: &VaultStorage,
     query: &str,
     namespace: Option<&str>,
+    options: &SearchOptions,
+    count: bool,
+    json: bool,
 ) -> Result<()> {
-    let results = storage.search(query, namespace).await?;
+    if options.values {
+        match SessionManager::get_current_session() {
+            Ok(session) if !session.role.can_read() => {
+                output::print_error("Read permissions required to search secret values");
+                return Ok(());
+            }
+            Ok(_) => {}
+            Err(_) => {
+                output::print_error("Please login first");
+                return Ok(());
+            }
+        }
+    }
+    
+    let warn_unreadable = |unreadable: &[String]| {
+        if !unreadable.is_empty() && !json {
+            output::print_warning(&format!(
+                "Skipped {} unreadable records. Run 'vault doctor' for details",
+                unreadable.len()
+            ));
+        }
+    };
+    
+    if count {
+        let (count, unreadable) = storage.count_search(query, namespace, options).await?;
+        warn_unreadable(&unreadable);
+        print_count(count, json)?;
+        return Ok(());
+    }
+    
+    let (results, unreadable) = storage.search(query, namespace, options).await?;
+    warn_unreadable(&unreadable);
+    
+    if json {
+        let entries: Vec<_> = results.iter()
+            .map(|(ns, key)| serde_json::json!({ "namespace": ns, "key": key }))
+            .collect();
+        output::print_json(&entries)?;
+        return Ok(());
+    }
     
     if results.is_empty() {
         println!("No secrets found matching: {}", query.yellow());
@@ -318,4 +1142,50 @@ pub async fn search_command(
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+fn print_count(count: usize, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::json!({ "count": count }));
+    } else {
+        println!("{}", count);
+    }
+    Ok(())
+}
+
+/// Print `count` freshly generated values of `kind` without storing them.
+pub async fn gen_command(
+    kind: &str,
+    length: Option<usize>,
+    symbols: bool,
+    prefix: Option<&str>,
+    count: usize,
+    key_type: &str,
+    comment: &str,
+) -> Result<()> {
+    if length == Some(0) {
+        output::print_error("Length must be greater than zero");
+        return Ok(());
+    }
+    
+    for _ in 0..count {
+        let value = match kind {
+            "password" => SecretGenerator::generate_password(length.unwrap_or(32), symbols),
+            "api-key" => SecretGenerator::generate_api_key(prefix),
+            "uuid" => SecretGenerator::generate_uuid(),
+            "hex" => SecretGenerator::generate_hex_key(length.unwrap_or(32)),
+            "ssh" => {
+                let key_type = if key_type == "rsa" { SshKeyType::Rsa } else { SshKeyType::Ed25519 };
+                let (private_key, public_key) = SecretGenerator::generate_ssh_key(key_type, comment)?;
+                format!("{}{}", private_key, public_key)
+            }
+            db_type => {
+                let creds = SecretGenerator::generate_database_credentials(db_type);
+                serde_json::to_string_pretty(&creds)?
+            }
+        };
+        println!("{}", value);
+    }
+    
+    Ok(())
+}
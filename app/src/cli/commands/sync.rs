@@ -1,24 +1,26 @@
 use anyhow::Result;
-use dialoguer::{Select, Input};
-use owo_colors::OwoColorize;
+use dialoguer::{Select, Input, Password};
 use indicatif::{ProgressBar, ProgressStyle};
+use comfy_table::{Cell, Color};
 
 use crate::{
-    storage::{VaultStorage, AuditLogger, AuditEntry},
+    storage::{AuditLogger, AuditEntry},
     config::Config,
-    cli::{SyncAction, output},
+    cli::{open_storage, SyncAction, output::{self, Colorize}},
     auth::SessionManager,
-    sync::{SyncManager, ConflictResolver, AutoResolveStrategy},
+    sync::{SyncManager, ConflictResolver, AutoResolveStrategy, S3AccessKey, store_s3_access_key, store_database_password},
 };
 
-pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
-    if let Ok(session) = SessionManager::get_current_session() {
-        if !session.role.can_write() {
-            output::print_error("Insufficient permissions for sync operations");
+pub async fn sync_command(action: SyncAction, config: &Config, json: bool) -> Result<()> {
+    let session = match SessionManager::get_current_session() {
+        Ok(session) => session,
+        Err(_) => {
+            output::print_error("Please login first");
             return Ok(());
         }
-    } else {
-        output::print_error("Please login first");
+    };
+    if !session.role.can_write() {
+        output::print_error("Insufficient permissions for sync operations");
         return Ok(());
     }
     
@@ -35,7 +37,7 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                     }
                 }
                 
-                let storage = VaultStorage::new(&config.storage_path)?;
+                let storage = open_storage(config, false, true)?;
                 let sync_manager = SyncManager::from_config(cloud_config, storage)?;
                 
                 let pb = ProgressBar::new_spinner();
@@ -43,8 +45,9 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                 pb.set_message("Pushing secrets to cloud...");
                 pb.enable_steady_tick(std::time::Duration::from_millis(100));
                 
-                match sync_manager.push(force).await {
-                    Ok(result) => {
+                match sync_manager.push(&session.tenant_id, force).await {
+                    Ok(record) => {
+                        let result = &record.result;
                         pb.finish_with_message(format!("{} Push completed", "✓".green()));
                         println!("Pushed: {} secrets", result.pushed);
                         
@@ -58,6 +61,7 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                                 println!("  - {}", error);
                             }
                         }
+                        println!("{}", record.summary().dimmed());
                         
                         if let Ok(session) = SessionManager::get_current_session() {
                             let audit_entry = AuditEntry::new(
@@ -66,7 +70,7 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                                 format!("Pushed {} secrets to cloud", result.pushed),
                                 session.user_id,
                             );
-                            let _ = AuditLogger::log_event(&audit_entry);
+                            AuditLogger::log_event(sync_manager.storage(), &audit_entry)?;
                         }
                     }
                     Err(e) => {
@@ -90,7 +94,7 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                     }
                 }
                 
-                let storage = VaultStorage::new(&config.storage_path)?;
+                let storage = open_storage(config, false, true)?;
                 let sync_manager = SyncManager::from_config(cloud_config, storage)?;
                 
                 if !force {
@@ -99,11 +103,12 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                     pb.set_message("Checking for conflicts...");
                     pb.enable_steady_tick(std::time::Duration::from_millis(100));
                     
-                    match sync_manager.status().await {
-                        Ok(status) => {
+                    match sync_manager.diff(&session.tenant_id).await {
+                        Ok(diff) => {
                             pb.finish();
-                            if status.conflicts > 0 {
-                                output::print_warning(&format!("Found {} conflicts", status.conflicts));
+                            let conflicts = diff.diverged;
+                            if !conflicts.is_empty() {
+                                output::print_warning(&format!("Found {} conflicts", conflicts.len()));
                                 
                                 let resolve_strategy = Select::new()
                                     .with_prompt("How would you like to resolve conflicts?")
@@ -116,32 +121,28 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                                     .default(0)
                                     .interact()?;
                                 
-                                match resolve_strategy {
-                                    0 => {
-                                        let _ = ConflictResolver::auto_resolve_conflicts(&[], AutoResolveStrategy::PreferLocal);
-                                        output::print_info("Conflicts resolved using local versions");
-                                    }
-                                    1 => {
-                                        let _ = ConflictResolver::auto_resolve_conflicts(&[], AutoResolveStrategy::PreferRemote);
-                                        output::print_info("Conflicts resolved using remote versions");
-                                    }
-                                    2 => {
-                                        let _ = ConflictResolver::auto_resolve_conflicts(&[], AutoResolveStrategy::PreferNewer);
-                                        output::print_info("Conflicts resolved using newer versions");
-                                    }
-                                    3 => {
-                                        // Use manual resolution strategy
-                                        let _ = ConflictResolver::auto_resolve_conflicts(&[], AutoResolveStrategy::Manual);
-                                        output::print_info("Manual conflict resolution selected");
+                                let (strategy, description) = match resolve_strategy {
+                                    0 => (AutoResolveStrategy::PreferLocal, "local versions"),
+                                    1 => (AutoResolveStrategy::PreferRemote, "remote versions"),
+                                    2 => (AutoResolveStrategy::PreferNewer, "newer versions"),
+                                    _ => {
                                         output::print_info("Operation cancelled");
                                         return Ok(());
                                     }
-                                    _ => {}
+                                };
+                                
+                                let resolutions = ConflictResolver::auto_resolve_conflicts(&conflicts, strategy)?;
+                                match sync_manager.resolve_and_apply(resolutions).await {
+                                    Ok(applied) => output::print_info(&format!("Conflicts resolved using {} ({} secrets updated locally)", description, applied)),
+                                    Err(e) => {
+                                        output::print_error(&format!("Failed to apply conflict resolutions: {}", e));
+                                        return Ok(());
+                                    }
                                 }
                             }
                         }
                         Err(e) => {
-                            pb.finish_with_message(format!("{} Status check failed", "✗".red()));
+                            pb.finish_with_message(format!("{} Conflict check failed", "✗".red()));
                             output::print_error(&format!("Error: {}", e));
                         }
                     }
@@ -152,8 +153,9 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                 pb.set_message("Pulling secrets from cloud...");
                 pb.enable_steady_tick(std::time::Duration::from_millis(100));
                 
-                match sync_manager.pull(force).await {
-                    Ok(result) => {
+                match sync_manager.pull(&session.tenant_id, force).await {
+                    Ok(record) => {
+                        let result = &record.result;
                         pb.finish_with_message(format!("{} Pull completed", "✓".green()));
                         println!("Pulled: {} secrets", result.pulled);
                         
@@ -164,6 +166,7 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                         if !result.errors.is_empty() {
                             output::print_error(&format!("Encountered {} errors", result.errors.len()));
                         }
+                        println!("{}", record.summary().dimmed());
                         
                         if let Ok(session) = SessionManager::get_current_session() {
                             let audit_entry = AuditEntry::new(
@@ -172,7 +175,7 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                                 format!("Pulled {} secrets from cloud", result.pulled),
                                 session.user_id,
                             );
-                            let _ = AuditLogger::log_event(&audit_entry);
+                            AuditLogger::log_event(sync_manager.storage(), &audit_entry)?;
                         }
                     }
                     Err(e) => {
@@ -184,7 +187,7 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                 output::print_warning("Cloud sync not configured. Run 'vault sync configure' first.");
             }
         }
-        SyncAction::Status => {
+        SyncAction::Status { refresh } => {
             println!("{} Sync Status", "📊".cyan());
             
             if let Some(cloud_config) = &config.cloud {
@@ -201,13 +204,18 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                         println!("Cloud mode: {}", "Collaborative".purple());
                     }
                 }
-                let storage = VaultStorage::new(&config.storage_path)?;
+                let storage = open_storage(config, false, true)?;
                 let sync_manager = SyncManager::from_config(cloud_config, storage)?;
                 
-                match sync_manager.status().await {
+                match sync_manager.status(&session.tenant_id, refresh).await {
                     Ok(status) => {
+                        if let Some(error) = &status.remote_error {
+                            output::print_warning(&format!("Remote unreachable, showing cached state ({})", error));
+                        } else if status.from_cache {
+                            output::print_info("Showing cached state from the last sync (use --refresh to check the backend)");
+                        }
                         println!("Backend: {}", status.backend);
-                        println!("Last sync: {}", status.last_sync.format("%Y-%m-%d %H:%M:%S UTC"));
+                        println!("Last sync: {}", output::format_date(&status.last_sync));
                         println!("Local secrets: {}", status.local_secrets);
                         println!("Remote secrets: {}", status.remote_secrets);
                         println!("Conflicts: {}", status.conflicts);
@@ -232,6 +240,89 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                 println!("Status: {}", "Not configured".yellow());
             }
         }
+        SyncAction::Diff => {
+            let Some(cloud_config) = &config.cloud else {
+                output::print_warning("Cloud sync not configured. Run 'vault sync configure' first.");
+                return Ok(());
+            };
+            if let crate::config::CloudMode::None = cloud_config.mode {
+                output::print_warning("Cloud features are disabled. Set cloud.mode to 'backup' or 'collaborative' to enable sync.");
+                return Ok(());
+            }
+            
+            let storage = open_storage(config, false, true)?;
+            let sync_manager = SyncManager::from_config(cloud_config, storage)?;
+            
+            let diff = match sync_manager.diff(&session.tenant_id).await {
+                Ok(diff) => diff,
+                Err(e) => {
+                    output::print_error(&format!("Failed to compute sync diff: {}", e));
+                    return Ok(());
+                }
+            };
+            
+            if json {
+                output::print_json(&diff)?;
+                return Ok(());
+            }
+            
+            if diff.is_empty() {
+                output::print_success("Local vault and remote are in sync");
+                return Ok(());
+            }
+            
+            println!("{} ({})", "Local only".green().bold(), diff.local_only.len());
+            for entry in &diff.local_only {
+                println!("  {} {}/{} (v{})", "+".green(), entry.namespace, entry.secret_key, entry.local_version);
+            }
+            println!("{} ({})", "Remote only".red().bold(), diff.remote_only.len());
+            for entry in &diff.remote_only {
+                println!("  {} {}/{} (v{})", "-".red(), entry.namespace, entry.secret_key, entry.remote_version);
+            }
+            println!("{} ({})", "Diverged".yellow().bold(), diff.diverged.len());
+            for entry in &diff.diverged {
+                println!("  {} {}/{} (local v{}, remote v{})", "~".yellow(), entry.namespace, entry.secret_key, entry.local_version, entry.remote_version);
+            }
+        }
+        SyncAction::Auto { interval } => {
+            let Some(cloud_config) = &config.cloud else {
+                output::print_warning("Cloud sync not configured. Run 'vault sync configure' first.");
+                return Ok(());
+            };
+            if let crate::config::CloudMode::None = cloud_config.mode {
+                output::print_warning("Cloud features are disabled. Set cloud.mode to 'backup' or 'collaborative' to enable sync.");
+                return Ok(());
+            }
+            
+            // The CLI flag wins over config, which falls back to half-hourly
+            let minutes = interval.or(cloud_config.sync_interval_minutes).unwrap_or(30);
+            let Some(period) = auto_sync_interval(minutes) else {
+                output::print_error(&format!("Sync interval must be between 1 and {} minutes", u64::MAX / 60));
+                return Ok(());
+            };
+            
+            auto_sync(config.clone(), session.tenant_id, session.user_id, minutes, period).await?;
+        }
+        SyncAction::History { limit } => {
+            let storage = open_storage(config, false, true)?;
+            let history = storage.sync_history(&session.tenant_id, Some(limit))?;
+            
+            if history.is_empty() {
+                output::print_info("No syncs recorded yet");
+                return Ok(());
+            }
+            
+            let mut table = output::table(&["Started", "Operation", "Result"]);
+            for record in &history {
+                let result_color = if record.result.errors.is_empty() { Color::Green } else { Color::Red };
+                table.add_row(vec![
+                    Cell::new(output::format_date(&record.started_at)),
+                    Cell::new(record.operation.to_string()),
+                    output::cell(record.summary(), result_color),
+                ]);
+            }
+            println!("{}", table);
+        }
         SyncAction::Configure => {
             println!("{} Sync Configuration Wizard", "🔧".cyan());
             
@@ -245,10 +336,7 @@ pub async fn sync_command(action: SyncAction, config: &Config) -> Result<()> {
                 .default(1)
                 .interact()?;
             
-            let config_path = dirs::config_dir()
-                .unwrap_or_default()
-                .join("vault")
-                .join("config.toml");
+            let config_path = crate::config::default_config_path();
             
             if cloud_mode == 0 {
                 let config_content = r#"storage_path = "~/.vault/vault.db"
@@ -268,14 +356,11 @@ mode = "none"
             
             let backend = Select::new()
                 .with_prompt("Select sync backend")
-                .items(&["Amazon S3", "PostgreSQL Database"])
+                .items(&["Amazon S3", "PostgreSQL Database", "Local or synced directory (Dropbox, NFS, ...)"])
                 .default(0)
                 .interact()?;
             
-            let config_path = dirs::config_dir()
-                .unwrap_or_default()
-                .join("vault")
-                .join("config.toml");
+            let config_path = crate::config::default_config_path();
             
             match backend {
                 0 => {
@@ -287,6 +372,19 @@ mode = "none"
                         .default("us-east-1".to_string())
                         .interact()?;
                     
+                    let access_key_id = Input::<String>::new()
+                        .with_prompt("AWS access key ID (leave empty to use the default AWS credential chain)")
+                        .allow_empty(true)
+                        .interact()?;
+                    let in_keyring = !access_key_id.is_empty();
+                    if in_keyring {
+                        let secret_access_key = Password::new()
+                            .with_prompt("AWS secret access key")
+                            .interact()?;
+                        store_s3_access_key(&bucket, &S3AccessKey { access_key_id, secret_access_key })?;
+                        output::print_info("Access keys stored in the OS keyring");
+                    }
+                    
                     let mode_str = match cloud_mode {
                         1 => "backup",
                         2 => "collaborative",
@@ -301,9 +399,10 @@ mode = "{}"
 backend = "S3"
 region = "{}"
 bucket = "{}"
+credentials_in_keyring = {}
 sync_interval_minutes = 30
 "#,
-                        mode_str, region, bucket
+                        mode_str, region, bucket, in_keyring
                     );
                     
                     if let Some(parent) = config_path.parent() {
@@ -318,6 +417,12 @@ sync_interval_minutes = 30
                         .with_prompt("PostgreSQL connection URL")
                         .interact()?;
                     
+                    // Keep the password out of config.toml
+                    let (redacted_url, in_keyring) = store_database_password(&url)?;
+                    if in_keyring {
+                        output::print_info("Database password stored in the OS keyring");
+                    }
+                    
                     let mode_str = match cloud_mode {
                         1 => "backup",
                         2 => "collaborative",
@@ -331,9 +436,10 @@ sync_interval_minutes = 30
 mode = "{}"
 backend = "Postgres"
 database_url = "{}"
+credentials_in_keyring = {}
 sync_interval_minutes = 30
 "#,
-                        mode_str, url
+                        mode_str, redacted_url, in_keyring
                     );
                     
                     if let Some(parent) = config_path.parent() {
@@ -343,11 +449,177 @@ sync_interval_minutes = 30
                     
                     output::print_success("PostgreSQL sync configured");
                 }
+                2 => {
+                    let directory = Input::<String>::new()
+                        .with_prompt("Sync directory")
+                        .interact()?;
+                    if !std::path::Path::new(&directory).is_dir() {
+                        output::print_warning(&format!("{} does not exist yet; create or mount it before syncing", directory));
+                    }
+                    
+                    let mode_str = match cloud_mode {
+                        1 => "backup",
+                        2 => "collaborative",
+                        _ => "none",
+                    };
+                    
+                    let config_content = format!(
+                        r#"storage_path = "~/.vault/vault.db"
+
+[cloud]
+mode = "{}"
+backend = "Directory"
+directory = {}
+sync_interval_minutes = 30
+"#,
+                        mode_str, toml::Value::String(directory.clone())
+                    );
+                    
+                    if let Some(parent) = config_path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&config_path, config_content)?;
+                    
+                    output::print_success(&format!("Directory sync configured: {}", directory));
+                }
 
                 _ => {}
             }
         }
-        _ => {}
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// `minutes` as the period between auto-sync cycles, or `None` when it is
+/// zero or too long to count in seconds.
+fn auto_sync_interval(minutes: u64) -> Option<std::time::Duration> {
+    minutes.checked_mul(60)
+        .filter(|seconds| *seconds > 0)
+        .map(std::time::Duration::from_secs)
+}
+
+/// Pull then push every `period` until Ctrl+C. Each cycle runs as its own
+/// task behind an in-process lock, so a slow cycle makes the next one skip
+/// rather than overlap with it.
+async fn auto_sync(config: Config, tenant_id: String, user_id: String, minutes: u64, period: std::time::Duration) -> Result<()> {
+    let config = std::sync::Arc::new(config);
+    let running = std::sync::Arc::new(tokio::sync::Mutex::new(()));
+    
+    println!("{} Syncing every {} minute{} (Ctrl+C to stop)...", "🔄".cyan(), minutes, if minutes == 1 { "" } else { "s" });
+    loop {
+        match running.clone().try_lock_owned() {
+            Ok(guard) => {
+                let config = config.clone();
+                let tenant_id = tenant_id.clone();
+                let user_id = user_id.clone();
+                tokio::spawn(async move {
+                    let _guard = guard;
+                    if let Err(e) = auto_sync_cycle(&config, &tenant_id, &user_id).await {
+                        output::print_error(&format!("Auto-sync failed: {}", e));
+                    }
+                });
+            }
+            Err(_) => output::print_warning("Previous sync still running, skipping this cycle"),
+        }
+        
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = tokio::time::sleep(period) => {}
+        }
+    }
+    
+    // Let an in-flight cycle finish instead of cutting it off mid-write
+    if running.try_lock().is_err() {
+        output::print_info("Waiting for the running sync to finish...");
+    }
+    let _ = running.lock().await;
+    output::print_info("Auto-sync stopped");
+    Ok(())
+}
+
+/// One pull and push. The vault is opened for the cycle and closed when it
+/// ends, so other commands can use it while the daemon sleeps.
+async fn auto_sync_cycle(config: &Config, tenant_id: &str, user_id: &str) -> Result<()> {
+    let cloud_config = config.cloud.as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Cloud sync not configured"))?;
+    let storage = open_storage(config, false, false)?;
+    let sync_manager = SyncManager::from_config(cloud_config, storage)?;
+    
+    let timestamp = output::format_date(&chrono::Utc::now());
+    
+    let pull = match sync_manager.pull(tenant_id, false).await {
+        Ok(record) => record,
+        Err(e) => {
+            output::print_error(&format!("[{}] Pull failed: {}", timestamp, e));
+            return Ok(());
+        }
+    };
+    let audit_entry = AuditEntry::new(
+        tenant_id.to_string(),
+        AuditLogger::EVENT_SYNC_PULL.to_string(),
+        format!("Auto-sync pulled {} secrets from cloud", pull.result.pulled),
+        user_id.to_string(),
+    );
+    AuditLogger::log_event(sync_manager.storage(), &audit_entry)?;
+    
+    let push = match sync_manager.push(tenant_id, false).await {
+        Ok(record) => record,
+        Err(e) => {
+            output::print_error(&format!("[{}] Push failed: {}", timestamp, e));
+            return Ok(());
+        }
+    };
+    let audit_entry = AuditEntry::new(
+        tenant_id.to_string(),
+        AuditLogger::EVENT_SYNC_PUSH.to_string(),
+        format!("Auto-sync pushed {} secrets to cloud", push.result.pushed),
+        user_id.to_string(),
+    );
+    AuditLogger::log_event(sync_manager.storage(), &audit_entry)?;
+    
+    println!("[{}] pull: {}", timestamp, pull.summary().dimmed());
+    println!("[{}] push: {}", timestamp, push.summary().dimmed());
+    for error in pull.result.errors.iter().chain(&push.result.errors) {
+        println!("  - {}", error);
+    }
+    Ok(())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_auto_sync_interval_rejects_zero_and_overflow() {
+        assert_eq!(auto_sync_interval(30), Some(std::time::Duration::from_secs(30 * 60)));
+        assert_eq!(auto_sync_interval(0), None);
+        assert_eq!(auto_sync_interval(u64::MAX / 60 + 1), None);
+        assert!(auto_sync_interval(u64::MAX / 60).is_some());
+    }
+    
+    #[tokio::test]
+    async fn test_auto_sync_cycle_closes_the_vault() {
+        let temp_dir = TempDir::new().unwrap();
+        let sync_dir = TempDir::new().unwrap();
+        let config = Config {
+            storage_path: temp_dir.path().join("test.db").to_string_lossy().to_string(),
+            cloud: Some(crate::config::CloudConfig {
+                mode: crate::config::CloudMode::Backup,
+                backend: Some(crate::config::CloudBackend::Directory),
+                region: None,
+                bucket: None,
+                database_url: None,
+                directory: Some(sync_dir.path().to_string_lossy().to_string()),
+                envelope_encryption: None,
+                sync_interval_minutes: None,
+                credentials_in_keyring: None,
+            }),
+            ..Config::default()
+        };
+        
+        auto_sync_cycle(&config, "test-tenant", "admin@test.com").await.unwrap();
+        // Nothing from the cycle still holds the vault
+        assert!(crate::storage::VaultStorage::new(&config.storage_path).is_ok());
+        auto_sync_cycle(&config, "test-tenant", "admin@test.com").await.unwrap();
+    }
+}
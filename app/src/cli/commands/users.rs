@@ -1,10 +1,10 @@
 use anyhow::Result;
-use dialoguer::{Password, Confirm, Input};
-use owo_colors::OwoColorize;
+use comfy_table::{Cell, Color};
+use dialoguer::{Password, Input};
 
 use crate::{
     storage::VaultStorage,
-    cli::{UserAction, output},
+    cli::{UserAction, output::{self, Colorize}},
     auth::{SessionManager, Role},
     config::{Config, CloudMode},
 };
@@ -40,55 +40,47 @@ pub async fn users_command(action: UserAction, storage: &VaultStorage, config: &
                 // Anyone can accept invitations
             }
         }
-    } else {
+    } else if !matches!(action, UserAction::Accept { .. }) {
+        // Invitees accept before they have ever logged in
         output::print_error("Please login first");
         return Ok(());
     }
     
     match action {
         UserAction::Invite { email, role } => {
-            // Validate role
-            match role.to_lowercase().as_str() {
-                "admin" | "owner" | "writer" | "reader" | "auditor" => {},
+            let role_enum = match role.to_lowercase().as_str() {
+                "admin" => Role::Admin,
+                "owner" => Role::Owner,
+                "writer" => Role::Writer,
+                "reader" => Role::Reader,
+                "auditor" => Role::Auditor,
                 _ => {
                     output::print_error(&format!("Invalid role: {}. Valid roles: admin, owner, writer, reader, auditor", role));
                     return Ok(());
                 }
-            }
+            };
             
             let session = SessionManager::get_current_session()?;
             
             println!("{} Inviting {} to tenant {} with role {}", 
                 "📧".cyan(), email.cyan(), session.tenant_id.cyan(), role.yellow());
             
-            // Generate invitation token (in real implementation, this would be sent via email)
-            use uuid::Uuid;
-            let invitation_token = Uuid::new_v4().to_string();
-            
-            // Store invitation in database (placeholder for real implementation)
-            // let invitation_key = format!("invitation:{}:{}", session.tenant_id, email);
-            // let invitation_data = serde_json::json!({
-            //     "email": email,
-            //     "role": role,
-            //     "token": invitation_token,
-            //     "invited_by": session.user_id,
-            //     "created_at": chrono::Utc::now(),
-            //     "expires_at": chrono::Utc::now() + chrono::Duration::days(7)
-            // });
-            
-            // In a real implementation, you'd store this in the database
-            println!("\n{} Invitation created!", "✓".green());
-            println!("Invitation token: {}", invitation_token.yellow());
-            println!("Share this token with {} to accept the invitation", email.cyan());
-            println!("Token expires in 7 days");
-            
-            output::print_success(&format!("Invitation sent to {}", email));
+            match storage.create_invitation(&session.tenant_id, &email, role_enum, &session.user_id).await {
+                Ok(invitation) => {
+                    println!("\n{} Invitation created!", "✓".green());
+                    println!("Invitation token: {}", invitation.token.yellow());
+                    println!("Share this token with {} to accept the invitation", email.cyan());
+                    println!("Token expires {}", output::format_date(&invitation.expires_at));
+                }
+                Err(e) => {
+                    output::print_error(&format!("Failed to create invitation: {}", e));
+                }
+            }
         }
         
         UserAction::Accept { token } => {
             println!("{} Accepting invitation with token: {}", "🎫".cyan(), token.yellow());
             
-            // In real implementation, validate token and create user account
             let email = Input::<String>::new()
                 .with_prompt("Enter your email")
                 .interact()?;
@@ -98,17 +90,15 @@ pub async fn users_command(action: UserAction, storage: &VaultStorage, config: &
                 .with_confirmation("Confirm password", "Passwords do not match")
                 .interact()?;
             
-            // Hash password (placeholder for real implementation)
-            use sha2::{Sha256, Digest};
-            let mut hasher = Sha256::new();
-            hasher.update(password.as_bytes());
-            let _password_hash: [u8; 32] = hasher.finalize().into();
-            
-            // Add user to tenant (mock implementation)
-            println!("{} Account created successfully!", "✓".green());
-            println!("You can now login with: vault login --tenant <tenant> --email {}", email);
-            
-            output::print_success("Invitation accepted successfully");
+            match storage.accept_invitation(&token, &email, &password).await {
+                Ok(user) => {
+                    output::print_success(&format!("Joined tenant {} as {:?}", user.tenant_id, user.role));
+                    println!("You can now login with: vault login --tenant {} --email {}", user.tenant_id, user.email);
+                }
+                Err(e) => {
+                    output::print_error(&format!("Failed to accept invitation: {}", e));
+                }
+            }
         }
         
         UserAction::List => {
@@ -123,27 +113,36 @@ pub async fn users_command(action: UserAction, storage: &VaultStorage, config: &
                         return Ok(());
                     }
                     
-                    output::print_table_header(&["Email", "Role", "Status", "Last Login"]);
+                    let mut table = output::table(&["Email", "Role", "Status", "Last Login"]);
                     
                     let user_count = users.len();
                     for user in &users {
-                        let role_colored = match user.role {
-                            Role::Admin => "Admin".red().to_string(),
-                            Role::Owner => "Owner".purple().to_string(),
-                            Role::Writer => "Writer".green().to_string(),
-                            Role::Reader => "Reader".blue().to_string(),
-                            Role::Auditor => "Auditor".yellow().to_string(),
+                        let (role_name, role_color) = match user.role {
+                            Role::Admin => ("Admin", Color::Red),
+                            Role::Owner => ("Owner", Color::Magenta),
+                            Role::Writer => ("Writer", Color::Green),
+                            Role::Reader => ("Reader", Color::Blue),
+                            Role::Auditor => ("Auditor", Color::Yellow),
                         };
                         
-                        let status = if user.is_active { "Active".green().to_string() } else { "Inactive".red().to_string() };
+                        let status = if user.is_active {
+                            output::cell("Active", Color::Green)
+                        } else {
+                            output::cell("Inactive", Color::Red)
+                        };
                         let last_login = user.last_login
-                            .map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string())
+                            .map(|dt| output::format_date(&dt))
                             .unwrap_or_else(|| "Never".to_string());
                         
-                        println!("  {} {} | {} | {} | {}", 
-                            "•".green(), user.email.cyan(), role_colored, status, last_login);
+                        table.add_row(vec![
+                            output::cell(&user.email, Color::Cyan),
+                            output::cell(role_name, role_color),
+                            status,
+                            Cell::new(last_login),
+                        ]);
                     }
                     
+                    println!("{}", table);
                     println!("\nTotal: {} users", user_count);
                 }
                 Err(e) => {
@@ -160,10 +159,7 @@ pub async fn users_command(action: UserAction, storage: &VaultStorage, config: &
                 return Ok(());
             }
             
-            if Confirm::new()
-                .with_prompt(format!("Remove user {} from tenant {}?", email, session.tenant_id))
-                .interact()?
-            {
+            if output::confirm(format!("Remove user {} from tenant {}?", email, session.tenant_id), false)? {
                 match storage.remove_user(&session.tenant_id, &email).await {
                     Ok(_) => {
                         output::print_success(&format!("User {} removed from tenant", email));
@@ -194,10 +190,7 @@ pub async fn users_command(action: UserAction, storage: &VaultStorage, config: &
             
             if session.user_id == email && matches!(role_enum, Role::Reader | Role::Auditor) {
                 output::print_warning("Changing your own role to a lower privilege level");
-                if !Confirm::new()
-                    .with_prompt("Are you sure you want to continue?")
-                    .interact()?
-                {
+                if !output::confirm("Are you sure you want to continue?", false)? {
                     output::print_info("Operation cancelled");
                     return Ok(());
                 }
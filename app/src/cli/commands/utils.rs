@@ -1,17 +1,26 @@
 use anyhow::Result;
-use dialoguer::Confirm;
-use owo_colors::OwoColorize;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use dialoguer::Password;
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::{
-    storage::{VaultStorage, AuditLogger, AuditEntry},
+    crypto::{seal_export, is_sealed_export, open_export},
+    storage::{VaultStorage, AuditLogger, AuditEntry, ListOptions, SecretMetadata},
     config::Config,
-    cli::output,
-    auth::SessionManager,
+    error::VaultError,
+    cli::output::{self, Colorize},
+    auth::{estimate_strength, SessionManager},
 };
 
-pub async fn status_command(config: &Config, storage: &VaultStorage) -> Result<()> {
+pub async fn status_command(config: &Config, storage: &VaultStorage, json: bool) -> Result<()> {
+    if json {
+        return status_json(config, storage).await;
+    }
+    
     println!("{} Vault Status", "ℹ".blue());
+    println!("Profile: {}", crate::config::profile_name().cyan());
     println!("Storage: {}", config.storage_path);
     
     if let Some(tenant) = &config.tenant_id {
@@ -45,6 +54,23 @@ pub async fn status_command(config: &Config, storage: &VaultStorage) -> Result<(
     println!("Secrets: {}", stats.secret_count);
     println!("Namespaces: {}", stats.namespace_count);
     println!("Tenants: {}", stats.tenant_count);
+    println!(
+        "Storage: {} bytes of secrets ({} audit log, {} sessions, {} internal)",
+        stats.total_size, stats.audit_size, stats.session_size, stats.internal_size
+    );
+    if let Some((max_secrets, max_namespaces)) = storage.tenant_limits()? {
+        let namespaces = storage.list_namespaces().await?;
+        let used: usize = namespaces.iter().map(|(_, count)| count).sum();
+        let limit = |max: Option<usize>| max.map_or_else(|| "unlimited".to_string(), |max| max.to_string());
+        println!("Secret quota: {} of {}", used, limit(max_secrets));
+        println!("Namespace quota: {} of {}", namespaces.len(), limit(max_namespaces));
+    }
+    if !stats.corrupted_records.is_empty() {
+        output::print_warning(&format!(
+            "{} corrupted secret records. Run 'vault doctor --salvage <dir>' to recover readable secrets",
+            stats.corrupted_records.len()
+        ));
+    }
     
     // Session information
     match SessionManager::get_current_session() {
@@ -52,6 +78,7 @@ pub async fn status_command(config: &Config, storage: &VaultStorage) -> Result<(
             println!("Status: {}", "Logged in".green());
             println!("User: {}", session.user_id.cyan());
             println!("Role: {:?}", session.role);
+            println!("Namespace: {}", session.namespace.as_deref().unwrap_or("default").cyan());
             
             let time_left = session.time_until_expiry();
             let hours = time_left.num_hours();
@@ -73,7 +100,66 @@ pub async fn status_command(config: &Config, storage: &VaultStorage) -> Result<(
     Ok(())
 }
 
-pub async fn doctor_command(config: &Config, storage: &VaultStorage) -> Result<()> {
+async fn status_json(config: &Config, storage: &VaultStorage) -> Result<()> {
+    let stats = storage.get_stats().await?;
+    let cloud_mode = match config.cloud.as_ref().map(|cloud| &cloud.mode) {
+        Some(crate::config::CloudMode::Backup) => "backup",
+        Some(crate::config::CloudMode::Collaborative) => "collaborative",
+        Some(crate::config::CloudMode::None) | None => "none",
+    };
+    let quota = match storage.tenant_limits()? {
+        Some((max_secrets, max_namespaces)) => {
+            let namespaces = storage.list_namespaces().await?;
+            serde_json::json!({
+                "secrets": namespaces.iter().map(|(_, count)| count).sum::<usize>(),
+                "max_secrets": max_secrets,
+                "namespaces": namespaces.len(),
+                "max_namespaces": max_namespaces,
+            })
+        }
+        None => serde_json::Value::Null,
+    };
+    let session = match SessionManager::get_current_session() {
+        Ok(session) => serde_json::json!({
+            "logged_in": session.is_valid(),
+            "user": session.user_id,
+            "role": session.role,
+            "namespace": session.namespace.as_deref().unwrap_or("default"),
+            "expires_at": session.expires_at,
+        }),
+        Err(_) => serde_json::json!({ "logged_in": false }),
+    };
+    
+    let status = serde_json::json!({
+        "profile": crate::config::profile_name(),
+        "storage_path": config.storage_path,
+        "tenant": config.tenant_id,
+        "cloud_mode": cloud_mode,
+        "secrets": stats.secret_count,
+        "namespaces": stats.namespace_count,
+        "tenants": stats.tenant_count,
+        "sizes": {
+            "secrets": stats.total_size,
+            "audit": stats.audit_size,
+            "sessions": stats.session_size,
+            "internal": stats.internal_size,
+        },
+        "quota": quota,
+        "corrupted_records": stats.corrupted_records.len(),
+        "session": session,
+    });
+    output::print_json(&status)?;
+    Ok(())
+}
+
+pub async fn doctor_command(config: &Config, config_path: Option<&str>, storage: &VaultStorage, salvage: Option<&str>, benchmark: bool) -> Result<()> {
+    if let Some(dir) = salvage {
+        return salvage_command(storage, dir).await;
+    }
+    if benchmark {
+        return benchmark_command(config, config_path, storage).await;
+    }
+    
     println!("{} Running diagnostics...", "🔍".cyan());
     
     println!("Checking storage...");
@@ -94,15 +180,307 @@ pub async fn doctor_command(config: &Config, storage: &VaultStorage) -> Result<(
     let test_secrets = storage.list("default").await?;
     println!("  {} Found {} secrets in default namespace", "ℹ".blue(), test_secrets.len());
     
+    println!("Checking records...");
+    let stats = storage.get_stats().await?;
+    if stats.corrupted_records.is_empty() {
+        println!("  {} All secret records are readable", "✓".green());
+    } else {
+        for key in &stats.corrupted_records {
+            println!("  {} Corrupted record: {}", "✗".red(), key);
+        }
+        println!("  {} Run 'vault doctor --salvage <dir>' to export every readable secret", "ℹ".blue());
+    }
+    
+    println!("Purging expired secrets...");
+    match storage.purge_expired().await {
+        Ok(0) => println!("  {} No expired secrets", "✓".green()),
+        Ok(purged) => println!("  {} Deleted {} expired secret(s)", "✓".green(), purged),
+        Err(e) => println!("  {} Could not purge expired secrets: {}", "✗".red(), e),
+    }
+    
     println!("{} Diagnostics complete", "✓".green());
     Ok(())
 }
 
+/// Parse a duration written as a whole number and a unit: `30s`, `15m`,
+/// `12h` or `90d`.
+pub fn parse_duration(value: &str) -> Option<chrono::Duration> {
+    let (amount, unit) = value.split_at(value.len().saturating_sub(1));
+    let amount: i64 = amount.parse().ok()?;
+    match unit {
+        "s" => chrono::Duration::try_seconds(amount),
+        "m" => chrono::Duration::try_minutes(amount),
+        "h" => chrono::Duration::try_hours(amount),
+        "d" => chrono::Duration::try_days(amount),
+        _ => None,
+    }
+}
+
+/// Read a secret value piped to the command, dropping the single trailing
+/// newline `echo` adds.
+pub fn read_piped_value(mut reader: impl std::io::Read) -> Result<String> {
+    let mut value = String::new();
+    reader.read_to_string(&mut value)?;
+    if value.ends_with('\n') {
+        value.pop();
+        if value.ends_with('\r') {
+            value.pop();
+        }
+    }
+    Ok(value)
+}
+
+/// Print the estimated strength of a password. Returns false, after saying
+/// why, when it scores below `min_score`.
+pub fn check_password_strength(password: &str, user_inputs: &[&str], min_score: u8) -> bool {
+    let strength = estimate_strength(password, user_inputs);
+    let summary = format!(
+        "Password strength: {} ({}/4), estimated crack time: {}",
+        strength.label(), strength.score, strength.crack_time_display()
+    );
+    if strength.score < min_score {
+        output::print_error(&format!("{}; at least {}/4 is required", summary, min_score));
+        return false;
+    }
+    
+    if strength.score < 3 {
+        output::print_warning(&summary);
+    } else {
+        output::print_info(&summary);
+    }
+    true
+}
+
+/// Number of secrets read by the bulk-read benchmark.
+const BENCHMARK_READS: usize = 100;
+
+/// Derivation time `doctor --benchmark` calibrates Argon2 towards.
+const BENCHMARK_KDF_TARGET: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Time the pieces that make up a `get`/`list`: key derivation, the ciphers,
+/// reading secrets and flushing sled.
+async fn benchmark_command(config: &Config, config_path: Option<&str>, storage: &VaultStorage) -> Result<()> {
+    use crate::crypto::{measure_derivation, EncryptionAlgorithm, KeyDerivationParams, MasterKey};
+    
+    println!("{} Running benchmarks...", "⏱".cyan());
+    let mut table = output::table(&["Operation", "Time"]);
+    
+    let kdf_params = storage.tenant_kdf_params()?
+        .unwrap_or_else(|| config.get_key_derivation_params());
+    let elapsed = measure_derivation(&kdf_params)?;
+    table.add_row(vec![
+        format!("Argon2id derivation ({} MB, {} passes)", kdf_params.memory_cost / 1024, kdf_params.time_cost),
+        format_elapsed(elapsed),
+    ]);
+    
+    // Averaged over many rounds; a single 1 KiB round trip is too fast to time alone
+    const ROUNDS: u32 = 1000;
+    let payload = [0x5au8; 1024];
+    for (label, algorithm) in [
+        ("AES-256-GCM", EncryptionAlgorithm::Aes256Gcm),
+        ("ChaCha20-Poly1305", EncryptionAlgorithm::ChaCha20Poly1305),
+        ("XChaCha20-Poly1305", EncryptionAlgorithm::XChaCha20Poly1305),
+    ] {
+        let key = MasterKey::generate(algorithm);
+        let start = std::time::Instant::now();
+        for _ in 0..ROUNDS {
+            let encrypted = key.encrypt(&payload)?;
+            key.decrypt(&encrypted)?;
+        }
+        table.add_row(vec![
+            format!("{} encrypt+decrypt 1 KiB", label),
+            format_elapsed(start.elapsed() / ROUNDS),
+        ]);
+    }
+    
+    match storage.benchmark(BENCHMARK_READS).await {
+        Ok(result) => {
+            let per_secret = if result.secrets_read > 0 {
+                format!(" ({} each)", format_elapsed(result.read_time / result.secrets_read as u32))
+            } else {
+                String::new()
+            };
+            table.add_row(vec![
+                format!("Read + decrypt {} secrets", result.secrets_read),
+                format!("{}{}", format_elapsed(result.read_time), per_secret),
+            ]);
+            table.add_row(vec!["sled flush".to_string(), format_elapsed(result.flush_time)]);
+        }
+        Err(e) => output::print_warning(&format!("Skipping storage benchmarks: {}", e)),
+    }
+    
+    println!("{}", table);
+    
+    println!("{} Calibrating Argon2id (target {} ms)...", "⏱".cyan(), BENCHMARK_KDF_TARGET.as_millis());
+    let calibration = KeyDerivationParams::calibrate(BENCHMARK_KDF_TARGET, config.security.key_derivation_parallelism)?;
+    let params = calibration.recommended;
+    print_recommended_kdf_params(&params);
+    
+    let configured = config.get_key_derivation_params();
+    if params == configured {
+        output::print_info("The config already uses these parameters");
+    } else if output::confirm("Write these parameters to the config?", false)? {
+        save_kdf_params(config, config_path, &params)?;
+    }
+    
+    Ok(())
+}
+
+fn format_elapsed(elapsed: std::time::Duration) -> String {
+    if elapsed < std::time::Duration::from_millis(1) {
+        format!("{} µs", elapsed.as_micros())
+    } else {
+        format!("{:.2} ms", elapsed.as_secs_f64() * 1000.0)
+    }
+}
+
+/// Write every recoverable secret to `dir` as one import file per namespace.
+async fn salvage_command(storage: &VaultStorage, dir: &str) -> Result<()> {
+    let report = match storage.salvage().await {
+        Ok(report) => report,
+        Err(e) => {
+            output::print_error(&format!("Salvage failed: {}", e));
+            return Ok(());
+        }
+    };
+    
+    let mut by_namespace: BTreeMap<String, BTreeMap<String, ExportedSecret>> = BTreeMap::new();
+    for salvaged in report.recovered {
+        let metadata = salvaged.metadata;
+        let (value, encoding) = if metadata.binary {
+            (STANDARD.encode(&salvaged.value), ValueEncoding::Base64)
+        } else {
+            match String::from_utf8(salvaged.value) {
+                Ok(text) => (text, ValueEncoding::Utf8),
+                Err(e) => (STANDARD.encode(e.into_bytes()), ValueEncoding::Base64),
+            }
+        };
+        by_namespace.entry(metadata.namespace.clone()).or_default().insert(metadata.key.clone(), ExportedSecret {
+            id: Some(metadata.id),
+            value,
+            encoding,
+            namespace: Some(metadata.namespace),
+            created_at: Some(metadata.created_at),
+            updated_at: Some(metadata.updated_at),
+            created_by: Some(metadata.created_by),
+            version: Some(metadata.version),
+            tags: metadata.tags,
+        });
+    }
+    
+    // Recovered values are plaintext, so only the current user may read them
+    let mut dir_builder = std::fs::DirBuilder::new();
+    dir_builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        dir_builder.mode(0o700);
+    }
+    dir_builder.create(dir)?;
+    let mut recovered = 0;
+    for (namespace, secrets) in by_namespace {
+        recovered += secrets.len();
+        let export = VaultExport {
+            export_info: Some(ExportInfo {
+                exported_at: chrono::Utc::now(),
+                exported_by: SessionManager::get_current_session().map(|s| s.user_id).unwrap_or_default(),
+                namespace: namespace.clone(),
+                format: "json".to_string(),
+                vault_version: env!("CARGO_PKG_VERSION").to_string(),
+                secret_count: secrets.len(),
+            }),
+            secrets,
+        };
+        // Namespaces may hold `/` or `..`; encoded, each stays one file in `dir`
+        let path = std::path::Path::new(dir).join(format!("{}.json", crate::sync::path_segment(&namespace)));
+        write_private_file(&path, serde_json::to_string_pretty(&export)?.as_bytes())?;
+        println!("  {} {} ({} secrets) -> vault import {} --namespace {}", "✓".green(), namespace, export.secrets.len(), path.display(), namespace);
+    }
+    
+    for (key, reason) in &report.skipped {
+        println!("  {} {}: {}", "⚠".yellow(), key, reason);
+    }
+    
+    output::print_success(&format!("Salvaged {} secrets to {} ({} skipped)", recovered, dir, report.skipped.len()));
+    
+    Ok(())
+}
+
+/// Write `contents` to `path` readable by the current user only, including
+/// when the file already existed with wider permissions.
+fn write_private_file(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents)
+}
+
+pub async fn bench_kdf_command(config: &Config, config_path: Option<&str>, target_ms: u64, write: bool) -> Result<()> {
+    use crate::crypto::KeyDerivationParams;
+    
+    let target = std::time::Duration::from_millis(target_ms);
+    println!("{} Calibrating Argon2id (target {} ms)...", "⏱".cyan(), target_ms);
+    
+    let calibration = KeyDerivationParams::calibrate(target, config.security.key_derivation_parallelism)?;
+    let mut table = output::table(&["Memory", "Passes", "Time"]);
+    for (params, elapsed) in &calibration.samples {
+        table.add_row(vec![
+            format!("{} MB", params.memory_cost / 1024),
+            params.time_cost.to_string(),
+            format!("{} ms", elapsed.as_millis()),
+        ]);
+    }
+    println!("{}", table);
+    
+    let params = calibration.recommended;
+    print_recommended_kdf_params(&params);
+    
+    if write {
+        save_kdf_params(config, config_path, &params)?;
+    } else {
+        output::print_info("Run with --write to save these parameters to the config");
+    }
+    
+    Ok(())
+}
+
+fn print_recommended_kdf_params(params: &crate::crypto::KeyDerivationParams) {
+    println!(
+        "Recommended: memory_cost = {} ({} MB), time_cost = {}, parallelism = {}",
+        params.memory_cost, params.memory_cost / 1024, params.time_cost, params.parallelism
+    );
+}
+
+/// Store `params` as the `[security]` key derivation settings used for new tenants.
+fn save_kdf_params(config: &Config, config_path: Option<&str>, params: &crate::crypto::KeyDerivationParams) -> Result<()> {
+    let mut updated = config.clone();
+    updated.security.key_derivation_memory_cost = params.memory_cost;
+    updated.security.key_derivation_time_cost = params.time_cost;
+    updated.security.key_derivation_parallelism = params.parallelism;
+    updated.save(config_path)?;
+    output::print_success("Saved key derivation parameters to config");
+    output::print_info("New tenants use them; existing tenants keep the parameters they were created with");
+    Ok(())
+}
+
 pub async fn export_command(
+    config: &Config,
     storage: &VaultStorage,
     output: &str,
     format: &str,
     namespace: Option<&str>,
+    encrypt: bool,
 ) -> Result<()> {
     if let Ok(session) = SessionManager::get_current_session() {
         if !session.role.can_read() {
@@ -122,66 +500,84 @@ pub async fn export_command(
         "Export all secrets from default namespace?".to_string()
     };
     
-    if !Confirm::new()
-        .with_prompt(confirm_msg)
-        .default(false)
-        .interact()?
-    {
+    if !output::confirm(confirm_msg, false)? {
         output::print_info("Export cancelled");
         return Ok(());
     }
     
+    let export_password = if encrypt {
+        Some(Password::new()
+            .with_prompt("Export passphrase")
+            .with_confirmation("Confirm export passphrase", "Passphrases do not match")
+            .interact()?)
+    } else {
+        output::print_warning("The export file will hold your secrets in plaintext. Use --encrypt to protect it");
+        None
+    };
+    
     let pb = ProgressBar::new_spinner();
     pb.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}")?);
     pb.set_message("Exporting secrets...");
     pb.enable_steady_tick(std::time::Duration::from_millis(100));
     
-    let secrets = storage.list_with_metadata(ns, None).await?;
+    let secrets = storage.list_with_metadata(ns, None, &ListOptions::default()).await?;
     
-    let mut export_data = serde_json::Map::new();
-    let mut exported_count = 0;
+    let mut export_data = BTreeMap::new();
     
     for (key, metadata) in secrets {
-        if let Some((value, _)) = storage.get_with_metadata(&key, ns).await? {
-            let secret_data = serde_json::json!({
-                "id": metadata.id,
-                "value": value,
-                "namespace": metadata.namespace,
-                "created_at": metadata.created_at,
-                "updated_at": metadata.updated_at,
-                "created_by": metadata.created_by,
-                "version": metadata.version,
-                "tags": metadata.tags
+        if metadata.binary && matches!(format, "csv" | "env") {
+            output::print_warning(&format!("Skipping binary secret {}/{}: {} holds text values only", ns, key, format));
+            continue;
+        }
+        // Keys that are not valid variable names are renamed as `vault env` does
+        let name = if format == "env" && !is_env_name(&key) {
+            env_var_name(&key, None)
+        } else {
+            key.clone()
+        };
+        if export_data.contains_key(&name) {
+            output::print_warning(&format!("Skipping {}/{}: {} is already exported", ns, key, name));
+            continue;
+        }
+        let read = storage.get_bytes(&key, ns).await;
+        if let Some(bytes) = skip_dangling_alias(ns, &key, &metadata, read)? {
+            // Binary values are carried as base64 so the export stays valid JSON
+            let (value, encoding) = if metadata.binary {
+                (STANDARD.encode(&bytes), ValueEncoding::Base64)
+            } else {
+                (String::from_utf8(bytes)?, ValueEncoding::Utf8)
+            };
+            export_data.insert(name, ExportedSecret {
+                id: Some(metadata.id),
+                value,
+                encoding,
+                namespace: Some(metadata.namespace),
+                created_at: Some(metadata.created_at),
+                updated_at: Some(metadata.updated_at),
+                created_by: Some(metadata.created_by),
+                version: Some(metadata.version),
+                tags: metadata.tags,
             });
-            export_data.insert(key, secret_data);
-            exported_count += 1;
         }
     }
+    let exported_count = export_data.len();
     
-    let export_metadata = serde_json::json!({
-        "export_info": {
-            "exported_at": chrono::Utc::now(),
-            "exported_by": SessionManager::get_current_session().map(|s| s.user_id).unwrap_or_default(),
-            "namespace": ns,
-            "format": format,
-            "vault_version": env!("CARGO_PKG_VERSION"),
-            "secret_count": exported_count
-        },
-        "secrets": export_data
-    });
+    let export_metadata = VaultExport {
+        export_info: Some(ExportInfo {
+            exported_at: chrono::Utc::now(),
+            exported_by: SessionManager::get_current_session().map(|s| s.user_id).unwrap_or_default(),
+            namespace: ns.to_string(),
+            format: format.to_string(),
+            vault_version: env!("CARGO_PKG_VERSION").to_string(),
+            secret_count: exported_count,
+        }),
+        secrets: export_data,
+    };
     
-    let content = match format {
-        "json" => serde_json::to_string_pretty(&export_metadata)?,
-        "yaml" => {
-            format!(
-                "# Vault Export\n# Exported at: {}\n# Namespace: {}\n# Count: {}\n\n{}",
-                chrono::Utc::now(),
-                ns,
-                exported_count,
-                serde_json::to_string_pretty(&export_metadata)?
-            )
-        }
-        _ => return Err(anyhow::anyhow!("Unsupported format: {}. Use 'json' or 'yaml'", format)),
+    let content = render_export(&export_metadata, format)?;
+    let content = match &export_password {
+        Some(password) => seal_export(content.as_bytes(), format, password, config.get_encryption_algorithm())?,
+        None => content,
     };
     
     std::fs::write(output, content)?;
@@ -193,20 +589,216 @@ pub async fn export_command(
             format!("Exported {} secrets from namespace {} to {}", exported_count, ns, output),
             session.user_id,
         );
-        let _ = AuditLogger::log_event(&audit_entry);
+        AuditLogger::log_event(storage, &audit_entry)?;
     }
     
     pb.finish_with_message(format!("{} Export completed", "✓".green()));
-    output::print_success(&format!("Exported {} secrets to {}", exported_count, output));
+    if encrypt {
+        output::print_success(&format!("Exported {} secrets to {} (encrypted)", exported_count, output));
+    } else {
+        output::print_success(&format!("Exported {} secrets to {}", exported_count, output));
+    }
     
     Ok(())
 }
 
+/// Serialize an export bundle as `json` or `yaml`.
+fn render_export(export: &VaultExport, format: &str) -> Result<String> {
+    match format {
+        "json" => Ok(serde_json::to_string_pretty(export)?),
+        "yaml" => {
+            let header = match &export.export_info {
+                Some(info) => format!(
+                    "# Vault Export\n# Exported at: {}\n# Namespace: {}\n# Count: {}\n",
+                    info.exported_at, info.namespace, info.secret_count
+                ),
+                None => String::new(),
+            };
+            Ok(format!("{}{}", header, serde_yaml::to_string(export)?))
+        }
+        "csv" => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            writer.write_record(CSV_COLUMNS)?;
+            for (key, secret) in &export.secrets {
+                if matches!(secret.encoding, ValueEncoding::Base64) {
+                    return Err(anyhow::anyhow!("Secret {} holds binary data, which CSV cannot carry", key));
+                }
+                writer.write_record([
+                    secret.namespace.as_deref().unwrap_or(""),
+                    key,
+                    &secret.value,
+                    &secret.tags.join("|"),
+                ])?;
+            }
+            Ok(String::from_utf8(writer.into_inner()?)?)
+        }
+        "env" => {
+            let mut content = String::new();
+            for (key, secret) in &export.secrets {
+                if matches!(secret.encoding, ValueEncoding::Base64) || !is_env_name(key) {
+                    return Err(anyhow::anyhow!("Secret {} cannot be written as a .env line", key));
+                }
+                content.push_str(&format!("{}={}\n", key, quote_dotenv_value(&secret.value)));
+            }
+            Ok(content)
+        }
+        _ => Err(anyhow::anyhow!("Unsupported format: {}. Use 'json', 'yaml', 'csv' or 'env'", format)),
+    }
+}
+
+/// Whether `name` is usable as an environment variable name as-is.
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Value as written in a .env file: bare when it is plain, otherwise double
+/// quoted with `\\`, `\"`, `\n` and `\$` escapes.
+fn quote_dotenv_value(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || "_-.,/:@%+".contains(c));
+    if plain {
+        return value.to_string();
+    }
+    
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Value as a POSIX shell word: single quoted, with each embedded quote
+/// written as `'\''`.
+fn quote_shell_value(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Turn a secret key into an environment variable name: uppercase, with
+/// anything outside `[A-Z0-9_]` replaced by `_`.
+fn env_var_name(key: &str, prefix: Option<&str>) -> String {
+    let mut name: String = format!("{}{}", prefix.unwrap_or(""), key)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// A bulk read of `ns/key`, with an alias whose target is gone skipped
+/// with a warning instead of failing the whole command.
+fn skip_dangling_alias<T>(
+    ns: &str,
+    key: &str,
+    meta: &SecretMetadata,
+    read: crate::error::Result<Option<T>>,
+) -> Result<Option<T>> {
+    match (read, &meta.alias_of) {
+        (Err(VaultError::SecretNotFound(_)), Some(target)) => {
+            output::print_warning(&format!("Skipping alias {}/{}: its target {} no longer exists", ns, key, target));
+            Ok(None)
+        }
+        (read, _) => Ok(read?),
+    }
+}
+
+/// `vault env` and `vault exec`: run `command` with the namespace's secrets
+/// in its environment, exiting with its status. The values are only handed
+/// to the child; nothing is written to disk. With no command, print them as
+/// shell exports instead.
+pub async fn env_command(
+    storage: &VaultStorage,
+    namespace: Option<&str>,
+    prefix: Option<&str>,
+    map: Option<&str>,
+    command: &[String],
+) -> Result<()> {
+    let ns = namespace.unwrap_or("default");
+    
+    let mapping: std::collections::HashMap<String, String> = match map {
+        Some(path) => toml::from_str(&std::fs::read_to_string(path)?)?,
+        None => std::collections::HashMap::new(),
+    };
+    
+    let mut vars = Vec::new();
+    for (key, meta) in storage.list_with_metadata(ns, None, &ListOptions::default()).await? {
+        if meta.binary {
+            output::print_warning(&format!("Skipping binary secret: {}/{}", ns, key));
+            continue;
+        }
+        if storage.is_secret_password_protected(&key, ns).await? {
+            output::print_warning(&format!("Skipping password-protected secret: {}/{}", ns, key));
+            continue;
+        }
+        
+        let read = storage.get_with_metadata(&key, ns).await;
+        if let Some((value, _)) = skip_dangling_alias(ns, &key, &meta, read)? {
+            let name = match mapping.get(&key) {
+                Some(name) => name.clone(),
+                None => env_var_name(&key, prefix),
+            };
+            vars.push((name, value));
+        }
+    }
+    
+    let description = match command.first() {
+        Some(program) => format!("Injected {} secrets from namespace {} into '{}'", vars.len(), ns, program),
+        None => format!("Printed {} secrets from namespace {} as shell exports", vars.len(), ns),
+    };
+    if let Ok(session) = SessionManager::get_current_session() {
+        let audit_entry = AuditEntry::new(
+            session.tenant_id,
+            AuditLogger::EVENT_SECRET_ACCESSED.to_string(),
+            description,
+            session.user_id,
+        );
+        AuditLogger::log_event(storage, &audit_entry)?;
+    }
+    
+    // Without a command, print a block for `eval "$(vault env ...)"`
+    let Some((program, args)) = command.split_first() else {
+        for (name, value) in &vars {
+            println!("export {}={}", name, quote_shell_value(value));
+        }
+        return Ok(());
+    };
+    
+    let mut child = std::process::Command::new(program);
+    child.args(args).envs(vars);
+    
+    // Replace this process on Unix so the secrets live only in the child
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let err = child.exec();
+        Err(anyhow::anyhow!("Failed to run '{}': {}", program, err))
+    }
+    
+    #[cfg(not(unix))]
+    {
+        let status = child.status()
+            .map_err(|e| anyhow::anyhow!("Failed to run '{}': {}", program, e))?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
+
 pub async fn import_command(
     storage: &VaultStorage,
     input: &str,
     format: &str,
     namespace: Option<&str>,
+    atomic: bool,
 ) -> Result<()> {
     if let Ok(session) = SessionManager::get_current_session() {
         if !session.role.can_write() {
@@ -223,21 +815,82 @@ pub async fn import_command(
         return Ok(());
     }
     
-    let content = std::fs::read_to_string(input)?;
+    let mut content = zeroize::Zeroizing::new(std::fs::read_to_string(input)?);
+    let mut format = format.to_string();
+    if is_sealed_export(&content) {
+        let password = Password::new()
+            .with_prompt("Export passphrase")
+            .interact()?;
+        match open_export(&content, &password) {
+            Ok((bundle_format, bundle)) => {
+                content = zeroize::Zeroizing::new(String::from_utf8(bundle.to_vec())?);
+                format = bundle_format;
+            }
+            Err(e) => {
+                output::print_error(&format!("Cannot open {}: {}", input, e));
+                return Ok(());
+            }
+        }
+    }
     let ns = namespace.unwrap_or("default");
     
-    let import_data: serde_json::Value = match format {
-        "json" => serde_json::from_str(&content)?,
-        "yaml" => {
-            return Err(anyhow::anyhow!("YAML import not fully supported yet. Please use JSON format."));
+    let secrets_data = match format.as_str() {
+        "json" | "yaml" | "csv" | "env" => {
+            let parsed = match format.as_str() {
+                "json" => parse_import_file(&content),
+                "yaml" => parse_yaml_import_file(&content),
+                "csv" => parse_csv_import_file(&content),
+                _ => parse_env_import_file(&content),
+            };
+            match parsed {
+                Ok(entries) => entries,
+                Err(problems) => {
+                    output::print_error(&format!("Import file {} is invalid ({} problems):", input, problems.len()));
+                    for problem in &problems {
+                        println!("  - {}", problem);
+                    }
+                    return Ok(());
+                }
+            }
         }
-        _ => return Err(anyhow::anyhow!("Unsupported format: {}. Use 'json' or 'yaml'", format)),
-    };
-    
-    let secrets_data = if let Some(secrets) = import_data.get("secrets") {
-        secrets.as_object().unwrap_or(&serde_json::Map::new()).clone()
-    } else {
-        import_data.as_object().unwrap_or(&serde_json::Map::new()).clone()
+        "bitwarden" | "1password" => {
+            let parsed = if format == "bitwarden" {
+                super::password_managers::parse_bitwarden(&content)
+            } else {
+                super::password_managers::parse_1password(&content)
+            };
+            let import = match parsed {
+                Ok(import) => import,
+                Err(e) => {
+                    output::print_error(&format!("Import file {} is invalid: {}", input, e));
+                    return Ok(());
+                }
+            };
+            
+            for warning in &import.warnings {
+                output::print_warning(warning);
+            }
+            let mut table = output::table(&["Category", "Imported", "Skipped"]);
+            let categories: std::collections::BTreeSet<_> = import.imported.keys().chain(import.skipped.keys()).collect();
+            for category in categories {
+                table.add_row(vec![
+                    category.to_string(),
+                    import.imported.get(category).copied().unwrap_or(0).to_string(),
+                    import.skipped.get(category).copied().unwrap_or(0).to_string(),
+                ]);
+            }
+            println!("{}", table);
+            
+            import.items.into_iter()
+                .map(|item| ImportEntry {
+                    key: item.key,
+                    value: ImportValue::Text(item.value),
+                    tags: item.tags,
+                    namespace: item.namespace,
+                })
+                .collect()
+        }
+        _ => return Err(anyhow::anyhow!("Unsupported format: {}. Use 'json', 'yaml', 'csv', 'env', 'bitwarden' or '1password'", format)),
     };
     
     if secrets_data.is_empty() {
@@ -248,13 +901,15 @@ pub async fn import_command(
     println!("{} Import Preview", "📊".cyan());
     println!("File: {}", input);
     println!("Target namespace: {}", ns.cyan());
+    let folder_namespaces: std::collections::BTreeSet<_> = secrets_data.iter()
+        .filter_map(|entry| entry.namespace.as_deref())
+        .collect();
+    if !folder_namespaces.is_empty() {
+        println!("Folder namespaces: {}", folder_namespaces.into_iter().collect::<Vec<_>>().join(", ").cyan());
+    }
     println!("Secrets to import: {}", secrets_data.len());
     
-    if !Confirm::new()
-        .with_prompt("Proceed with import?")
-        .default(false)
-        .interact()?
-    {
+    if !output::confirm("Proceed with import?", false)? {
         output::print_info("Import cancelled");
         return Ok(());
     }
@@ -267,28 +922,25 @@ pub async fn import_command(
             .progress_chars("#>-")
     );
     
+    if atomic {
+        return import_atomic(storage, input, ns, secrets_data, pb).await;
+    }
+    
     let mut imported = 0;
     let mut errors = Vec::new();
     
-    for (key, data) in secrets_data {
-        pb.set_message(format!("Importing {}", key));
+    for entry in secrets_data {
+        pb.set_message(format!("Importing {}", entry.key));
         
-        match data.get("value").and_then(|v| v.as_str()) {
-            Some(value) => {
-                let tags: Vec<String> = data.get("tags")
-                    .and_then(|t| t.as_array())
-                    .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
-                    .unwrap_or_else(Vec::new);
-                
-                match storage.put_with_tags(&key, value, ns, &tags).await {
-                    Ok(_) => imported += 1,
-                    Err(e) => {
-                        errors.push(format!("Failed to import {}: {}", key, e));
-                    }
-                }
-            }
-            None => {
-                errors.push(format!("No value found for secret: {}", key));
+        let entry_ns = entry.namespace.as_deref().unwrap_or(ns);
+        let result = match &entry.value {
+            ImportValue::Text(text) => storage.put_with_tags(&entry.key, text, entry_ns, &entry.tags).await,
+            ImportValue::Binary(bytes) => storage.put_bytes(&entry.key, bytes, entry_ns, &entry.tags).await,
+        };
+        match result {
+            Ok(_) => imported += 1,
+            Err(e) => {
+                errors.push(format!("Failed to import {}: {}", entry.key, e));
             }
         }
         
@@ -304,7 +956,7 @@ pub async fn import_command(
             format!("Imported {} secrets from {} to namespace {}", imported, input, ns),
             session.user_id,
         );
-        let _ = AuditLogger::log_event(&audit_entry);
+        AuditLogger::log_event(storage, &audit_entry)?;
     }
     
     println!("\n{} Import Results", "📊".green());
@@ -320,6 +972,349 @@ pub async fn import_command(
     Ok(())
 }
 
+/// On-disk layout written by `vault export`. `parse_import_file` validates
+/// it piece by piece so errors can name the offending secret.
+#[derive(Serialize)]
+struct VaultExport {
+    export_info: Option<ExportInfo>,
+    secrets: BTreeMap<String, ExportedSecret>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExportInfo {
+    exported_at: chrono::DateTime<chrono::Utc>,
+    exported_by: String,
+    namespace: String,
+    format: String,
+    vault_version: String,
+    secret_count: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExportedSecret {
+    id: Option<uuid::Uuid>,
+    value: String,
+    #[serde(default)]
+    encoding: ValueEncoding,
+    namespace: Option<String>,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_by: Option<String>,
+    version: Option<u64>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum ValueEncoding {
+    #[default]
+    Utf8,
+    Base64,
+}
+
+#[derive(Debug)]
+enum ImportValue {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+#[derive(Debug)]
+struct ImportEntry {
+    key: String,
+    value: ImportValue,
+    tags: Vec<String>,
+    namespace: Option<String>, // Overrides the target namespace (e.g. a Bitwarden folder)
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
+
+/// Validate an import file, accepting either a full export or a bare map of
+/// secrets. Every malformed secret is reported by key instead of failing on
+/// the first one.
+fn parse_import_file(content: &str) -> std::result::Result<Vec<ImportEntry>, Vec<String>> {
+    let document: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| vec![format!("Invalid JSON: {}", e)])?;
+    parse_import_document(&document)
+}
+
+/// Same layout as `parse_import_file`, written as YAML.
+fn parse_yaml_import_file(content: &str) -> std::result::Result<Vec<ImportEntry>, Vec<String>> {
+    let document: serde_json::Value = serde_yaml::from_str(content)
+        .map_err(|e| vec![format!("Invalid YAML: {}", e)])?;
+    parse_import_document(&document)
+}
+
+/// Header row of CSV exports; tags are separated by `|`.
+const CSV_COLUMNS: [&str; 4] = ["namespace", "key", "value", "tags"];
+
+/// Parse a CSV file with the `CSV_COLUMNS` header. A blank namespace leaves
+/// the secret in the import's target namespace.
+fn parse_csv_import_file(content: &str) -> std::result::Result<Vec<ImportEntry>, Vec<String>> {
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    let headers = reader.headers()
+        .map_err(|e| vec![format!("Invalid CSV: {}", e)])?;
+    if !headers.iter().map(str::trim).eq(CSV_COLUMNS) {
+        return Err(vec![format!(
+            "Expected the header row '{}', found '{}'",
+            CSV_COLUMNS.join(","),
+            headers.iter().collect::<Vec<_>>().join(",")
+        )]);
+    }
+    
+    let mut problems = Vec::new();
+    let mut entries = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                problems.push(e.to_string());
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        let (namespace, key, value, tags) = (record[0].trim(), record[1].trim(), &record[2], &record[3]);
+        if key.is_empty() {
+            problems.push(format!("Line {}: key is empty", line));
+            continue;
+        }
+        
+        entries.push(ImportEntry {
+            key: key.to_string(),
+            value: ImportValue::Text(value.to_string()),
+            tags: tags.split('|').map(str::trim).filter(|tag| !tag.is_empty()).map(String::from).collect(),
+            namespace: (!namespace.is_empty()).then(|| namespace.to_string()),
+        });
+    }
+    
+    if problems.is_empty() {
+        Ok(entries)
+    } else {
+        Err(problems)
+    }
+}
+
+/// Parse `KEY=value` lines of a .env file. Blank lines and `#` comments are
+/// skipped, as is an `export ` prefix. Values may be bare (a trailing
+/// ` # comment` is dropped), single quoted (taken literally) or double quoted
+/// (with `\n`, `\"`, `\\` and `\$` escapes, and able to span lines).
+fn parse_env_import_file(content: &str) -> std::result::Result<Vec<ImportEntry>, Vec<String>> {
+    let mut problems = Vec::new();
+    let mut entries = Vec::new();
+    let mut lines = content.lines().enumerate();
+    
+    while let Some((index, line)) = lines.next() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map_or(line, str::trim_start);
+        
+        let Some((key, raw)) = line.split_once('=') else {
+            problems.push(format!("Line {}: expected KEY=value", line_number));
+            continue;
+        };
+        let key = key.trim();
+        if !is_env_name(key) {
+            problems.push(format!("Line {}: '{}' is not a valid environment variable name", line_number, key));
+            continue;
+        }
+        
+        let raw = raw.trim_start();
+        let value = if let Some(rest) = raw.strip_prefix('"') {
+            let mut value = String::new();
+            let mut text = rest.to_string();
+            let closed = loop {
+                let mut chars = text.chars();
+                let mut closed = false;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => {
+                            closed = true;
+                            break;
+                        }
+                        '\\' => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('r') => value.push('\r'),
+                            Some('t') => value.push('\t'),
+                            Some(other) => value.push(other),
+                            None => value.push('\\'),
+                        },
+                        c => value.push(c),
+                    }
+                }
+                if closed {
+                    break true;
+                }
+                match lines.next() {
+                    Some((_, next)) => {
+                        value.push('\n');
+                        text = next.to_string();
+                    }
+                    None => break false,
+                }
+            };
+            if !closed {
+                problems.push(format!("Line {}: unterminated double-quoted value for {}", line_number, key));
+                continue;
+            }
+            value
+        } else if let Some(rest) = raw.strip_prefix('\'') {
+            match rest.split_once('\'') {
+                Some((value, _)) => value.to_string(),
+                None => {
+                    problems.push(format!("Line {}: unterminated single-quoted value for {}", line_number, key));
+                    continue;
+                }
+            }
+        } else {
+            raw.split_once(" #").map_or(raw, |(value, _)| value).trim_end().to_string()
+        };
+        
+        entries.push(ImportEntry { key: key.to_string(), value: ImportValue::Text(value), tags: Vec::new(), namespace: None });
+    }
+    
+    if problems.is_empty() {
+        Ok(entries)
+    } else {
+        Err(problems)
+    }
+}
+
+fn parse_import_document(document: &serde_json::Value) -> std::result::Result<Vec<ImportEntry>, Vec<String>> {
+    let Some(document) = document.as_object() else {
+        return Err(vec!["Expected an object at the top level".to_string()]);
+    };
+    
+    let mut problems = Vec::new();
+    let secrets = if document.contains_key("secrets") {
+        for field in document.keys().filter(|k| *k != "secrets" && *k != "export_info") {
+            problems.push(format!("Unknown top-level field '{}'", field));
+        }
+        
+        if let Some(info) = document.get("export_info") {
+            match serde_json::from_value::<ExportInfo>(info.clone()) {
+                Ok(info) => match (parse_version(&info.vault_version), parse_version(env!("CARGO_PKG_VERSION"))) {
+                    (Some(file), Some(current)) if file > current => problems.push(format!(
+                        "export_info.vault_version {} is newer than this vault ({})",
+                        info.vault_version, env!("CARGO_PKG_VERSION")
+                    )),
+                    (None, _) => problems.push(format!("export_info.vault_version '{}' is not a valid version", info.vault_version)),
+                    _ => {}
+                },
+                Err(e) => problems.push(format!("export_info: {}", e)),
+            }
+        }
+        
+        match document["secrets"].as_object() {
+            Some(secrets) => secrets,
+            None => {
+                problems.push("secrets: expected an object keyed by secret name".to_string());
+                return Err(problems);
+            }
+        }
+    } else {
+        document
+    };
+    
+    let mut entries = Vec::new();
+    for (key, data) in secrets {
+        let secret = match serde_json::from_value::<ExportedSecret>(data.clone()) {
+            Ok(secret) => secret,
+            Err(e) => {
+                problems.push(format!("Secret '{}': {}", key, e));
+                continue;
+            }
+        };
+        
+        let value = match secret.encoding {
+            ValueEncoding::Utf8 => ImportValue::Text(secret.value),
+            ValueEncoding::Base64 => match STANDARD.decode(&secret.value) {
+                Ok(bytes) => ImportValue::Binary(bytes),
+                Err(e) => {
+                    problems.push(format!("Secret '{}': invalid base64 value: {}", key, e));
+                    continue;
+                }
+            },
+        };
+        entries.push(ImportEntry { key: key.clone(), value, tags: secret.tags, namespace: None });
+    }
+    
+    if problems.is_empty() {
+        Ok(entries)
+    } else {
+        Err(problems)
+    }
+}
+
+async fn import_atomic(
+    storage: &VaultStorage,
+    input: &str,
+    ns: &str,
+    secrets_data: Vec<ImportEntry>,
+    pb: ProgressBar,
+) -> Result<()> {
+    let mut tx = storage.transaction()?;
+    let mut errors = Vec::new();
+    
+    for entry in secrets_data {
+        pb.set_message(format!("Staging {}", entry.key));
+        
+        let entry_ns = entry.namespace.as_deref().unwrap_or(ns);
+        let result = match &entry.value {
+            ImportValue::Text(text) => tx.put(&entry.key, text, entry_ns, &entry.tags),
+            ImportValue::Binary(bytes) => tx.put_bytes(&entry.key, bytes, entry_ns, &entry.tags),
+        };
+        if let Err(e) = result {
+            errors.push(format!("Failed to import {}: {}", entry.key, e));
+        }
+        
+        pb.inc(1);
+    }
+    
+    // The commit records the import itself; only a rollback is logged here
+    let staged = tx.len();
+    let outcome = if errors.is_empty() {
+        let description = format!("Atomically imported {} secrets from {} to namespace {}", staged, input, ns);
+        tx.commit_as(AuditLogger::EVENT_IMPORT, &description).await.map_err(|e| vec![format!("Commit failed: {}", e)])
+    } else {
+        Err(errors)
+    };
+    
+    if let (Err(errors), Ok(session)) = (&outcome, SessionManager::get_current_session()) {
+        let audit_entry = AuditEntry::new(
+            session.tenant_id,
+            AuditLogger::EVENT_IMPORT.to_string(),
+            format!("Atomic import from {} to namespace {} rolled back ({} errors, {} secrets discarded)", input, ns, errors.len(), staged),
+            session.user_id,
+        );
+        AuditLogger::log_event(storage, &audit_entry)?;
+    }
+    
+    match outcome {
+        Ok(imported) => {
+            pb.finish_with_message("Import completed");
+            println!("\n{} Import Results", "📊".green());
+            output::print_success(&format!("Successfully imported: {}", imported));
+        }
+        Err(errors) => {
+            pb.abandon_with_message("Import rolled back");
+            output::print_error(&format!("Import aborted, no secrets were written. Errors: {}", errors.len()));
+            for error in &errors {
+                println!("  - {}", error);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
 pub async fn completions_command(shell: &str) -> Result<()> {
     use clap::CommandFactory;
     use clap_complete::{generate, Shell};
@@ -340,4 +1335,236 @@ pub async fn completions_command(shell: &str) -> Result<()> {
     generate(shell, &mut cmd, "vault", &mut io::stdout());
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_bulk_reads_skip_dangling_aliases() {
+        let now = chrono::Utc::now();
+        let mut meta = SecretMetadata {
+            id: uuid::Uuid::new_v4(),
+            tenant_id: "t".to_string(),
+            namespace: "billing".to_string(),
+            key: "db".to_string(),
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by: "alice@test.com".to_string(),
+            updated_by: "alice@test.com".to_string(),
+            tags: Vec::new(),
+            alias_of: Some("shared/db".to_string()),
+            binary: false,
+            expires_at: None,
+        };
+        let gone = || Err::<Option<()>, _>(VaultError::SecretNotFound("shared/db".to_string()));
+        
+        assert!(skip_dangling_alias("billing", "db", &meta, gone()).unwrap().is_none());
+        assert_eq!(skip_dangling_alias("billing", "db", &meta, Ok(Some(5))).unwrap(), Some(5));
+        
+        // Other failures, and missing secrets that are not aliases, still abort
+        assert!(skip_dangling_alias::<()>("billing", "db", &meta, Err(VaultError::VaultLocked)).is_err());
+        meta.alias_of = None;
+        assert!(skip_dangling_alias("billing", "db", &meta, gone()).is_err());
+    }
+    
+    #[test]
+    #[cfg(unix)]
+    fn test_private_files_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("default.json");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        
+        write_private_file(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+    
+    #[test]
+    fn test_parse_import_file_reports_malformed_secrets() {
+        let valid = r#"{"secrets": {"a": {"value": "x", "tags": ["t"]}, "b": {"value": "AAE=", "encoding": "base64"}}}"#;
+        let entries = parse_import_file(valid).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(&entries[1].value, ImportValue::Binary(bytes) if bytes == &[0, 1]));
+        
+        let malformed = r#"{"secrets": {"ok": {"value": "x"}, "bad": {"value": 5}, "typo": {"valeu": "x"}}}"#;
+        let problems = parse_import_file(malformed).unwrap_err();
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().any(|p| p.starts_with("Secret 'bad'")));
+        assert!(problems.iter().any(|p| p.starts_with("Secret 'typo'")));
+        
+        let newer = r#"{"export_info": {"exported_at": "2024-01-01T00:00:00Z", "exported_by": "u", "namespace": "default",
+            "format": "json", "vault_version": "999.0.0", "secret_count": 0}, "secrets": {}}"#;
+        let problems = parse_import_file(newer).unwrap_err();
+        assert!(problems[0].contains("newer than this vault"));
+    }
+    
+    #[test]
+    fn test_yaml_export_round_trip() {
+        let secret = |value: &str, encoding, tags: &[&str]| ExportedSecret {
+            id: Some(uuid::Uuid::new_v4()),
+            value: value.to_string(),
+            encoding,
+            namespace: Some("prod".to_string()),
+            created_at: Some(chrono::Utc::now()),
+            updated_at: Some(chrono::Utc::now()),
+            created_by: Some("alice@test.com".to_string()),
+            version: Some(3),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        };
+        let export = VaultExport {
+            export_info: Some(ExportInfo {
+                exported_at: chrono::Utc::now(),
+                exported_by: "alice@test.com".to_string(),
+                namespace: "prod".to_string(),
+                format: "yaml".to_string(),
+                vault_version: env!("CARGO_PKG_VERSION").to_string(),
+                secret_count: 2,
+            }),
+            secrets: BTreeMap::from([
+                ("db-password".to_string(), secret("p@ss: \"quoted\"\nline two", ValueEncoding::Utf8, &["db", "prod"])),
+                ("keystore".to_string(), secret("AAE=", ValueEncoding::Base64, &[])),
+            ]),
+        };
+        
+        let yaml = render_export(&export, "yaml").unwrap();
+        assert!(yaml.starts_with("# Vault Export"));
+        assert!(serde_json::from_str::<serde_json::Value>(&yaml).is_err());
+        
+        let entries = parse_yaml_import_file(&yaml).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, "db-password");
+        assert!(matches!(&entries[0].value, ImportValue::Text(text) if text == "p@ss: \"quoted\"\nline two"));
+        assert_eq!(entries[0].tags, ["db", "prod"]);
+        assert_eq!(entries[1].key, "keystore");
+        assert!(matches!(&entries[1].value, ImportValue::Binary(bytes) if bytes == &[0, 1]));
+        assert!(entries[1].tags.is_empty());
+    }
+    
+    #[test]
+    fn test_csv_round_trip_and_validation() {
+        let secret = |namespace: Option<&str>, value: &str, tags: &[&str]| ExportedSecret {
+            id: None,
+            value: value.to_string(),
+            encoding: ValueEncoding::Utf8,
+            namespace: namespace.map(String::from),
+            created_at: None,
+            updated_at: None,
+            created_by: None,
+            version: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        };
+        let export = VaultExport {
+            export_info: None,
+            secrets: BTreeMap::from([
+                ("db-password".to_string(), secret(Some("prod"), "a,b \"c\"\nline two", &["db", "prod"])),
+                ("token".to_string(), secret(None, "plain", &[])),
+            ]),
+        };
+        
+        let csv = render_export(&export, "csv").unwrap();
+        assert!(csv.starts_with("namespace,key,value,tags\n"));
+        let entries = parse_csv_import_file(&csv).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].namespace.as_deref(), Some("prod"));
+        assert!(matches!(&entries[0].value, ImportValue::Text(text) if text == "a,b \"c\"\nline two"));
+        assert_eq!(entries[0].tags, ["db", "prod"]);
+        assert_eq!(entries[1].namespace, None);
+        assert!(entries[1].tags.is_empty());
+        
+        let wrong_header = parse_csv_import_file("name,value\nx,y\n").unwrap_err();
+        assert!(wrong_header[0].contains("Expected the header row"));
+        let problems = parse_csv_import_file("namespace,key,value,tags\nprod,,x,\nprod,a,b\n").unwrap_err();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("key is empty"));
+    }
+    
+    #[test]
+    fn test_dotenv_round_trip_and_parsing() {
+        let values = ["plain-value", "two words", "quote \" and $HOME", "line one\nline two", "back\\slash", ""];
+        let content: String = values.iter().enumerate()
+            .map(|(i, value)| format!("KEY_{}={}\n", i, quote_dotenv_value(value)))
+            .collect();
+        let entries = parse_env_import_file(&content).unwrap();
+        assert_eq!(entries.len(), values.len());
+        for (entry, value) in entries.iter().zip(values) {
+            assert!(matches!(&entry.value, ImportValue::Text(text) if text == value), "{} != {:?}", entry.key, value);
+        }
+        
+        let file = "# comment\n\nexport API_KEY=abc123 # inline\nSINGLE='raw $x \\n'\nMULTI=\"first\nsecond\"\n";
+        let entries = parse_env_import_file(file).unwrap();
+        let parsed: Vec<_> = entries.iter()
+            .map(|entry| match &entry.value {
+                ImportValue::Text(text) => (entry.key.as_str(), text.as_str()),
+                ImportValue::Binary(_) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(parsed, [("API_KEY", "abc123"), ("SINGLE", "raw $x \\n"), ("MULTI", "first\nsecond")]);
+        
+        let problems = parse_env_import_file("1BAD=x\nno-equals\nOPEN=\"never closed\n").unwrap_err();
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].contains("not a valid environment variable name"));
+        assert_eq!(quote_shell_value("it's"), "'it'\\''s'");
+    }
+    
+    #[test]
+    fn test_env_var_names_from_keys() {
+        assert_eq!(env_var_name("db-password", None), "DB_PASSWORD");
+        assert_eq!(env_var_name("api.key", Some("app_")), "APP_API_KEY");
+        assert_eq!(env_var_name("2fa-seed", None), "_2FA_SEED");
+    }
+    
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("90d"), Some(chrono::Duration::days(90)));
+        assert_eq!(parse_duration("12h"), Some(chrono::Duration::hours(12)));
+        assert_eq!(parse_duration("30s"), Some(chrono::Duration::seconds(30)));
+        assert_eq!(parse_duration("12"), None);
+        assert_eq!(parse_duration("1w"), None);
+        assert_eq!(parse_duration(&format!("{}d", i64::MAX)), None);
+    }
+    
+    #[test]
+    fn test_json_and_quiet_are_global_flags() {
+        use clap::{CommandFactory, Parser};
+        use crate::cli::VaultCli;
+        
+        VaultCli::command().debug_assert();
+        for args in [["vault", "--json", "list"], ["vault", "list", "--json"]] {
+            let cli = VaultCli::try_parse_from(args).unwrap();
+            assert!(cli.json && !cli.quiet);
+        }
+        assert!(VaultCli::try_parse_from(["vault", "status", "-q"]).unwrap().quiet);
+    }
+    
+    #[test]
+    fn test_binary_secret_hint_names_a_real_flag() {
+        use clap::Parser;
+        use crate::cli::{commands::OUTPUT_FILE_FLAG, Commands, VaultCli, VaultCommands};
+        
+        let cli = VaultCli::try_parse_from(["vault", "get", "logo", OUTPUT_FILE_FLAG, "logo.png"]).unwrap();
+        assert!(matches!(cli.command, Commands::Vault(VaultCommands::Get { output_file: Some(path), .. }) if path == "logo.png"));
+    }
+    
+    #[test]
+    fn test_put_file_is_put_with_a_file() {
+        use clap::Parser;
+        use crate::cli::{Commands, VaultCli, VaultCommands};
+        
+        let cli = VaultCli::try_parse_from(["vault", "put-file", "tls", "--file", "tls.crt"]).unwrap();
+        assert!(matches!(cli.command, Commands::Vault(VaultCommands::Put { file: Some(path), .. }) if path == "tls.crt"));
+    }
+
+    #[test]
+    fn test_piped_value_loses_one_trailing_newline() {
+        assert_eq!(read_piped_value("s3cr3t\n".as_bytes()).unwrap(), "s3cr3t");
+        assert_eq!(read_piped_value("s3cr3t\r\n".as_bytes()).unwrap(), "s3cr3t");
+        assert_eq!(read_piped_value("line1\nline2\n\n".as_bytes()).unwrap(), "line1\nline2\n");
+        assert_eq!(read_piped_value("  spaced  ".as_bytes()).unwrap(), "  spaced  ");
+    }
+}
@@ -1,11 +1,11 @@
 use clap::{Parser, Subcommand};
-// use owo_colors::OwoColorize; // TODO: Use for colored output
 use anyhow::Result;
 
+mod clipboard;
 mod commands;
 mod output;
 
-use crate::{storage::VaultStorage, config::Config};
+use crate::{storage::{VaultStorage, ListOptions, SearchOptions, SortBy}, config::Config};
 use commands::*;
 
 #[derive(Parser)]
@@ -21,10 +21,66 @@ pub struct VaultCli {
     
     #[arg(long, global = true, help = "Configuration file path")]
     pub config: Option<String>,
+    
+    #[arg(short = 'y', long, global = true, help = "Answer yes to every confirmation prompt")]
+    pub yes: bool,
+    
+    #[arg(long, global = true, help = "Print machine-readable JSON (list, get, search, status, whoami, ...)")]
+    pub json: bool,
+    
+    #[arg(short = 'q', long, global = true, help = "Suppress success and informational messages")]
+    pub quiet: bool,
+    
+    #[arg(long, global = true, env = "VAULT_PROFILE", help = "Named profile with its own config, session and default storage path")]
+    pub profile: Option<String>,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
+    #[command(flatten)]
+    Vault(VaultCommands),
+    
+    /// Sync with cloud
+    Sync {
+        #[command(subcommand)]
+        action: SyncAction,
+    },
+    
+    /// Audit operations
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+    
+    /// Generate passwords, keys and credentials without storing them
+    Gen {
+        #[arg(help = "What to generate", value_parser = ["password", "api-key", "uuid", "hex", "ssh", "postgres", "mysql", "redis", "mongodb"])]
+        kind: String,
+        #[arg(long, help = "Length of passwords and hex keys (default 32)")]
+        length: Option<usize>,
+        #[arg(long, help = "Include symbols in passwords")]
+        symbols: bool,
+        #[arg(long, help = "API key prefix (default vk)")]
+        prefix: Option<String>,
+        #[arg(long, help = "Number of values to generate", default_value = "1")]
+        count: usize,
+        #[arg(long, help = "SSH key algorithm", value_parser = ["ed25519", "rsa"], default_value = "ed25519")]
+        key_type: String,
+        #[arg(long, help = "SSH public key comment", default_value = "vault@generated")]
+        comment: String,
+    },
+    
+    /// Background helper spawned by `get --copy` to clear the clipboard
+    #[command(hide = true)]
+    ClearClipboard {
+        #[arg(long)]
+        after: u64,
+    },
+}
+
+/// Commands that run against the vault `VaultCli::run` opens for them
+#[derive(Subcommand)]
+pub enum VaultCommands {
     /// Initialize a new vault
     Init {
         #[arg(long, help = "Tenant identifier")]
@@ -33,6 +89,14 @@ pub enum Commands {
         admin: String,
         #[arg(long, help = "Force initialization even if vault exists")]
         force: bool,
+        #[arg(long, help = "Cipher for the tenant's secrets (defaults to security.encryption_algorithm)", value_parser = ["aes256gcm", "chacha20poly1305", "xchacha20poly1305"])]
+        algorithm: Option<String>,
+        #[arg(long, help = "Argon2 memory cost in KiB (defaults to security.key_derivation_memory_cost)")]
+        kdf_memory: Option<u32>,
+        #[arg(long, help = "Argon2 iterations (defaults to security.key_derivation_time_cost)")]
+        kdf_time: Option<u32>,
+        #[arg(long, help = "Argon2 parallelism (defaults to security.key_derivation_parallelism)")]
+        kdf_parallelism: Option<u32>,
     },
     
     /// Login to a tenant
@@ -48,67 +112,237 @@ pub enum Commands {
     /// Logout from current session
     Logout,
     
+    /// Change the tenant's master passphrase, re-encrypting every secret
+    Passwd,
+    
+    /// Set the namespace commands use when --namespace is omitted
+    Use {
+        #[arg(help = "Namespace to work in (prints the current one if omitted)")]
+        namespace: Option<String>,
+        #[arg(long, help = "Reset to the default namespace", conflicts_with = "namespace")]
+        clear: bool,
+    },
+    
     /// Show current user info
     Whoami,
     
-    /// Store a secret
+    /// Store a secret (`put-file` is an alias kept for `put <key> --file <path>`)
+    #[command(alias = "put-file")]
     Put {
         #[arg(help = "Secret key")]
         key: String,
         #[arg(long, help = "Namespace for the secret")]
         namespace: Option<String>,
+        #[arg(long, help = "Operate on this tenant for this command only")]
+        tenant: Option<String>,
         #[arg(long, help = "Secret value (will prompt if not provided)")]
         value: Option<String>,
+        #[arg(long, conflicts_with = "value", help = "Read the value from stdin (the default when stdin is piped)")]
+        stdin: bool,
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["value", "stdin"], help = "Store the file's exact contents, trailing newline included; non-UTF-8 files become binary secrets")]
+        file: Option<String>,
         #[arg(long, help = "Tags for the secret")]
         tags: Vec<String>,
         #[arg(long, help = "Force overwrite existing secret")]
         force: bool,
+        #[arg(long, value_parser = parse_ttl, help = "Delete the secret after this long, e.g. 12h or 90d")]
+        ttl: Option<chrono::Duration>,
     },
     
     /// Retrieve a secret
     Get {
-        #[arg(help = "Secret key")]
-        key: String,
+        #[arg(help = "Secret key", required_unless_present = "keys", conflicts_with = "keys")]
+        key: Option<String>,
+        #[arg(long, value_delimiter = ',', help = "Fetch several secrets: comma-separated keys or namespace/key paths, or - to read them from stdin")]
+        keys: Vec<String>,
         #[arg(long, help = "Namespace for the secret")]
         namespace: Option<String>,
+        #[arg(long, help = "Operate on this tenant for this command only")]
+        tenant: Option<String>,
         #[arg(long, help = "Copy to clipboard instead of printing")]
         copy: bool,
+        #[arg(long, value_name = "SECS", requires = "copy", help = "Clear the clipboard after SECS seconds, 0 = never (defaults to ui.clipboard_clear_seconds)")]
+        clear_after: Option<u64>,
+        #[arg(long, requires = "copy", conflicts_with = "clear_after", help = "Leave the copied value on the clipboard")]
+        no_clear: bool,
         #[arg(long, help = "Show secret metadata")]
         metadata: bool,
+        #[arg(long, help = "Write the exact stored bytes to stdout")]
+        raw: bool,
+        #[arg(long, alias = "output", help = "Write the value to a file instead of printing it")]
+        output_file: Option<String>,
+        #[arg(long, help = "Print the value even when ui.require_show_flag is set")]
+        show: bool,
+    },
+    
+    /// Encrypt a single secret into a blob that can be shared with a password
+    Share {
+        #[arg(help = "Secret key")]
+        key: String,
+        #[arg(long, help = "Namespace for the secret")]
+        namespace: Option<String>,
+        #[arg(long, help = "Shared password (will prompt if not provided)")]
+        password: Option<String>,
+    },
+    
+    /// Store a secret received as a share blob
+    Receive {
+        #[arg(help = "Share blob")]
+        blob: String,
+        #[arg(long, help = "Namespace to store the secret in")]
+        namespace: Option<String>,
+        #[arg(long, help = "Store under this key instead of the shared name")]
+        key: Option<String>,
+        #[arg(long, help = "Shared password (will prompt if not provided)")]
+        password: Option<String>,
+        #[arg(long, help = "Force overwrite existing secret")]
+        force: bool,
+    },
+    
+    /// Create an alias that points at another secret
+    Link {
+        #[arg(help = "Alias key")]
+        alias: String,
+        #[arg(long, help = "Target secret as <namespace>/<key>")]
+        to: String,
+        #[arg(long, help = "Namespace for the alias")]
+        namespace: Option<String>,
+        #[arg(long, help = "Replace an existing secret at the alias key (it is kept in history)")]
+        force: bool,
     },
     
     /// List secrets
     List {
         #[arg(long, help = "Namespace to list")]
         namespace: Option<String>,
+        #[arg(long, help = "Operate on this tenant for this command only")]
+        tenant: Option<String>,
         #[arg(long, help = "Filter by tag")]
         tag: Option<String>,
         #[arg(long, help = "Show detailed information")]
         detailed: bool,
+        #[arg(long, help = "Print only the number of matching secrets")]
+        count: bool,
+        #[arg(long, value_parser = parse_sort, default_value = "key", help = "Sort by key, created, updated or version")]
+        sort: SortBy,
+        #[arg(long, help = "Sort in descending order")]
+        desc: bool,
+        #[arg(long, help = "Show at most this many secrets")]
+        limit: Option<usize>,
+        #[arg(long, default_value_t = 0, help = "Skip this many secrets before listing")]
+        offset: usize,
+    },
+    
+    /// List namespaces and how many secrets each holds
+    Namespaces {
+        #[arg(long, help = "Operate on this tenant for this command only")]
+        tenant: Option<String>,
     },
     
     /// Delete a secret
     Delete {
+        #[arg(help = "Secret key", required_unless_present = "all", conflicts_with = "all")]
+        key: Option<String>,
+        #[arg(long, help = "Namespace for the secret")]
+        namespace: Option<String>,
+        #[arg(long, requires = "namespace", help = "Delete every secret in --namespace")]
+        all: bool,
+        #[arg(long, help = "Operate on this tenant for this command only")]
+        tenant: Option<String>,
+        #[arg(long, help = "Force deletion without confirmation")]
+        force: bool,
+        #[arg(long, conflicts_with = "all", help = "Delete permanently instead of moving to the trash")]
+        hard: bool,
+    },
+    
+    /// List, restore or empty deleted secrets
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+    
+    /// Show the stored versions of a secret
+    History {
         #[arg(help = "Secret key")]
         key: String,
         #[arg(long, help = "Namespace for the secret")]
         namespace: Option<String>,
-        #[arg(long, help = "Force deletion without confirmation")]
+        #[arg(long, help = "Show the cipher and value type of each version")]
+        detailed: bool,
+        #[arg(long, value_name = "VERSION", help = "Print the value of this version")]
+        reveal: Option<u64>,
+    },
+    
+    /// Rename a secret or move it to another namespace
+    Move {
+        #[arg(help = "Secret key")]
+        key: String,
+        #[arg(long, help = "Namespace the secret is in")]
+        namespace: Option<String>,
+        #[arg(long, required_unless_present = "new_namespace", help = "New key (defaults to the current one)")]
+        new_key: Option<String>,
+        #[arg(long, help = "Namespace to move to (defaults to the current one)")]
+        new_namespace: Option<String>,
+        #[arg(long, help = "Replace an existing secret at the destination")]
         force: bool,
     },
     
+    /// Copy a secret to another namespace or key
+    Copy {
+        #[arg(help = "Secret key")]
+        key: String,
+        #[arg(long, help = "Namespace to copy from")]
+        namespace: Option<String>,
+        #[arg(long, help = "Namespace to copy to (defaults to the source namespace)")]
+        to_namespace: Option<String>,
+        #[arg(long, help = "Key for the copy (defaults to the source key)")]
+        new_key: Option<String>,
+        #[arg(long, help = "Overwrite an existing secret at the destination without asking")]
+        force: bool,
+    },
+    
+    /// Add or remove tags on an existing secret
+    Tag {
+        #[arg(help = "Secret key")]
+        key: String,
+        #[arg(long, help = "Namespace for the secret")]
+        namespace: Option<String>,
+        #[arg(long, help = "Tags to add")]
+        add: Vec<String>,
+        #[arg(long, help = "Tags to remove")]
+        remove: Vec<String>,
+    },
+    
+    /// Manage tags across many secrets
+    Tags {
+        #[command(subcommand)]
+        action: TagsAction,
+    },
+    
+    /// Restore an earlier version of a secret as its new current value
+    Rollback {
+        #[arg(help = "Secret key")]
+        key: String,
+        #[arg(long, help = "Namespace for the secret")]
+        namespace: Option<String>,
+        #[arg(long, value_name = "VERSION", help = "Version to restore (see 'vault history')")]
+        to: u64,
+    },
+    
     /// Search secrets
     Search {
         #[arg(help = "Search query")]
         query: String,
         #[arg(long, help = "Namespace to search in")]
         namespace: Option<String>,
-    },
-    
-    /// Sync with cloud
-    Sync {
-        #[command(subcommand)]
-        action: SyncAction,
+        #[arg(long, help = "Operate on this tenant for this command only")]
+        tenant: Option<String>,
+        #[arg(long, help = "Also match secret values (decrypts every secret it scans)")]
+        in_values: bool,
+        #[arg(long, help = "Treat the query as a regular expression")]
+        regex: bool,
+        #[arg(long, help = "Print only the number of matching secrets")]
+        count: bool,
     },
     
     /// Manage roles and permissions
@@ -117,43 +351,96 @@ pub enum Commands {
         action: RoleAction,
     },
     
-    /// Manage users (collaborative mode only)
-    Users {
+    /// List and revoke login sessions on this machine
+    Sessions {
         #[command(subcommand)]
-        action: UserAction,
+        action: SessionAction,
     },
     
-    /// Audit operations
-    Audit {
+    /// Manage two-factor login for the current user
+    #[command(name = "2fa")]
+    TwoFactor {
         #[command(subcommand)]
-        action: AuditAction,
+        action: TwoFactorAction,
+    },
+    
+    /// Manage users (collaborative mode only)
+    Users {
+        #[command(subcommand)]
+        action: UserAction,
     },
     
     /// Export secrets
     Export {
         #[arg(long, help = "Output file path")]
         output: String,
-        #[arg(long, help = "Export format", default_value = "json")]
+        #[arg(long, help = "Export format (json, yaml, csv, env)", default_value = "json")]
         format: String,
         #[arg(long, help = "Namespace to export")]
         namespace: Option<String>,
+        #[arg(long, help = "Encrypt the file under a passphrase you choose")]
+        encrypt: bool,
+    },
+    
+    /// Run a command with a namespace's secrets as environment variables, or print them as shell exports
+    Env {
+        #[arg(long, help = "Namespace to load secrets from")]
+        namespace: Option<String>,
+        #[arg(long, help = "Prefix added to every variable name")]
+        prefix: Option<String>,
+        #[arg(long, help = "TOML file mapping secret keys to variable names")]
+        map: Option<String>,
+        #[arg(trailing_var_arg = true, help = "Command to run, after --; without one, print export lines to source")]
+        command: Vec<String>,
+    },
+    
+    /// Run a command with a namespace's secrets as environment variables
+    Exec {
+        #[arg(long, help = "Namespace to load secrets from")]
+        namespace: Option<String>,
+        #[arg(trailing_var_arg = true, required = true, help = "Command to run, after --")]
+        command: Vec<String>,
     },
     
     /// Import secrets
     Import {
         #[arg(help = "Input file path")]
         input: String,
-        #[arg(long, help = "Import format", default_value = "json")]
+        #[arg(long, help = "Import format: json, yaml, csv, env, bitwarden or 1password (a .1pux's export.data); encrypted exports are detected", default_value = "json")]
         format: String,
-        #[arg(long, help = "Target namespace")]
+        #[arg(long, help = "Target namespace (Bitwarden folders and 1Password vaults keep their own)")]
         namespace: Option<String>,
+        #[arg(long, help = "Abort the whole import if any secret fails")]
+        atomic: bool,
     },
     
     /// Show vault status
     Status,
     
     /// Run diagnostics
-    Doctor,
+    Doctor {
+        #[arg(long, value_name = "DIR", help = "Export every readable secret to DIR, one import file per namespace")]
+        salvage: Option<String>,
+        #[arg(long, help = "Time key derivation, encryption, reads and flushes, and recommend Argon2 parameters", conflicts_with = "salvage")]
+        benchmark: bool,
+    },
+    
+    /// Calibrate Argon2 key derivation parameters for this machine
+    BenchKdf {
+        #[arg(long, help = "Target derivation time in milliseconds", default_value = "500")]
+        target_ms: u64,
+        #[arg(long, help = "Write the recommended parameters to the config file")]
+        write: bool,
+    },
+    
+    /// Re-encrypt every secret with a different cipher
+    #[command(alias = "rekey")]
+    Reencrypt {
+        #[arg(long, help = "Target algorithm", value_parser = ["aes256gcm", "chacha20poly1305", "xchacha20poly1305"])]
+        to: String,
+        #[arg(long, help = "Skip confirmation")]
+        force: bool,
+    },
     
     /// Generate shell completions
     Completions {
@@ -180,7 +467,17 @@ pub enum SyncAction {
         interval: Option<u64>,
     },
     /// Show sync status
-    Status,
+    Status {
+        #[arg(long, help = "Check the sync backend instead of the cached state")]
+        refresh: bool,
+    },
+    /// Show differences between local secrets and the sync backend
+    Diff,
+    /// Show past push/pull runs
+    History {
+        #[arg(long, default_value = "20", help = "Number of runs to show")]
+        limit: usize,
+    },
     /// Configure sync backend
     Configure,
 }
@@ -223,11 +520,78 @@ pub enum AuditAction {
     Search {
         #[arg(help = "Search query")]
         query: String,
-        #[arg(long, help = "Start date")]
+        #[arg(long, help = "Start time (RFC 3339, YYYY-MM-DD, or relative like 7d)")]
         since: Option<String>,
-        #[arg(long, help = "End date")]
+        #[arg(long, help = "End time, inclusive (a bare YYYY-MM-DD covers the whole day)")]
         until: Option<String>,
     },
+    /// Find secrets that share the same value
+    Duplicates,
+    /// Export audit entries for SIEM ingestion
+    Export {
+        #[arg(long, help = "Only entries at or after this time (RFC 3339, YYYY-MM-DD, or relative like 24h, 7d)")]
+        since: Option<String>,
+        #[arg(long, default_value = "jsonl", help = "Output format (jsonl, csv)")]
+        format: String,
+        #[arg(long, help = "File to write to (stdout if omitted)")]
+        output: Option<String>,
+        #[arg(long, help = "Keep appending new entries as they are logged")]
+        follow: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagsAction {
+    /// Rename a tag on every secret that has it
+    Rename {
+        #[arg(long, help = "Tag to rename")]
+        from: String,
+        #[arg(long, help = "New tag name")]
+        to: String,
+        #[arg(long, help = "Only secrets in this namespace (all namespaces if omitted)")]
+        namespace: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TrashAction {
+    /// List deleted secrets, most recent first
+    List,
+    /// Restore a deleted secret
+    Restore {
+        #[arg(help = "Secret as namespace/key, or a key in --namespace")]
+        path: String,
+        #[arg(long, help = "Namespace for the secret")]
+        namespace: Option<String>,
+    },
+    /// Permanently delete trashed secrets
+    Empty {
+        #[arg(long, value_name = "AGE", value_parser = parse_ttl, help = "Only secrets deleted at least this long ago, e.g. 30d")]
+        older_than: Option<chrono::Duration>,
+        #[arg(long, help = "Empty without confirmation")]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SessionAction {
+    /// List active sessions
+    List,
+    /// Log out one session
+    Revoke {
+        #[arg(help = "Session id, as shown by 'vault sessions list'")]
+        id: uuid::Uuid,
+    },
+    /// Log out every session
+    RevokeAll,
+}
+
+#[derive(Subcommand)]
+pub enum TwoFactorAction {
+    /// Enroll an authenticator app; login will then ask for its code
+    Enroll,
+    /// Turn two-factor login off (asks for a current code)
+    Disable,
 }
 
 #[derive(Subcommand)]
@@ -262,64 +626,303 @@ pub enum UserAction {
 
 impl VaultCli {
     pub async fn run(self) -> Result<()> {
+        // Every path helper resolves against the selected profile
+        crate::config::set_active_profile(self.profile.clone())?;
+        
         let config = Config::load(self.config.as_deref())?;
-        let mut storage = VaultStorage::new(&config.storage_path)?;
+        output::init(&config.ui, self.json, self.quiet, self.yes);
+        let json = self.json;
+        
+        let command = match self.command {
+            Commands::Vault(command) => command,
+            // Never touches the vault; must not contend for its lock while sleeping
+            Commands::ClearClipboard { after } => {
+                return clipboard::clear_if_unchanged(after).await;
+            }
+            Commands::Gen { kind, length, symbols, prefix, count, key_type, comment } => {
+                return gen_command(&kind, length, symbols, prefix.as_deref(), count, &key_type, &comment).await;
+            }
+            // Sync and audit open the vault themselves; audit tail and export
+            // poll through short-lived handles so --follow never holds its lock
+            Commands::Sync { action } => {
+                let result = sync_command(action, &config, json).await;
+                if result.is_ok() {
+                    let _ = crate::auth::SessionManager::touch_session();
+                }
+                return result;
+            }
+            Commands::Audit { action } => {
+                let result = audit_command(action, &config).await;
+                if result.is_ok() {
+                    let _ = crate::auth::SessionManager::touch_session();
+                }
+                return result;
+            }
+        };
         
-        match self.command {
-            Commands::Init { tenant, admin, force } => {
-                init_command(&mut storage, &tenant, &admin, force).await
+        let tenant_override = match &command {
+            VaultCommands::Put { tenant, .. }
+            | VaultCommands::Get { tenant, .. }
+            | VaultCommands::List { tenant, .. }
+            | VaultCommands::Namespaces { tenant, .. }
+            | VaultCommands::Delete { tenant, .. }
+            | VaultCommands::Search { tenant, .. } => tenant.clone(),
+            _ => None,
+        };
+        
+        // Unlocking another tenant may record a used authentication code, and
+        // searching values audits the access
+        let read_only = tenant_override.is_none() && matches!(
+            command,
+            VaultCommands::Status
+                | VaultCommands::List { .. }
+                | VaultCommands::Namespaces { .. }
+                | VaultCommands::Search { in_values: false, .. }
+        );
+        let session_command = !matches!(command, VaultCommands::Init { .. } | VaultCommands::Login { .. } | VaultCommands::Logout);
+        let mut storage = open_storage(&config, read_only, session_command)?;
+        if let Some(tenant) = tenant_override {
+            switch_tenant(&mut storage, &config, &tenant)?;
+        }
+        
+        // Namespace chosen with `vault use`, applied wherever --namespace is omitted
+        let session_namespace = crate::auth::SessionManager::get_current_session()
+            .ok()
+            .and_then(|session| session.namespace);
+        let session_namespace = session_namespace.as_deref();
+        
+        let result = match command {
+            VaultCommands::Init { tenant, admin, force, algorithm, kdf_memory, kdf_time, kdf_parallelism } => {
+                let algorithm = algorithm.as_deref()
+                    .and_then(crate::crypto::EncryptionAlgorithm::from_name)
+                    .unwrap_or_else(|| config.get_encryption_algorithm());
+                let defaults = config.get_key_derivation_params();
+                let kdf_params = crate::crypto::KeyDerivationParams {
+                    memory_cost: kdf_memory.unwrap_or(defaults.memory_cost),
+                    time_cost: kdf_time.unwrap_or(defaults.time_cost),
+                    parallelism: kdf_parallelism.unwrap_or(defaults.parallelism),
+                };
+                init_command(&mut storage, &tenant, &admin, force, algorithm, kdf_params, config.security.min_password_score).await
             }
-            Commands::Login { tenant, email, remember } => {
+            VaultCommands::Login { tenant, email, remember } => {
                 login_command(&mut storage, &config, &tenant, email.as_deref(), remember).await
             }
-            Commands::Logout => {
-                logout_command().await
+            VaultCommands::Logout => {
+                logout_command(&storage).await
             }
-            Commands::Put { key, namespace, value, tags, force } => {
-                put_command(&storage, &key, namespace.as_deref(), value.as_deref(), &tags, force).await
+            VaultCommands::Passwd => {
+                passwd_command(&mut storage, config.security.min_password_score).await
             }
-            Commands::Get { key, namespace, copy, metadata } => {
-                get_command(&storage, &key, namespace.as_deref(), copy, metadata).await
+            VaultCommands::Put { key, namespace, value, stdin, file, tags, force, ttl, .. } => {
+                put_command(&storage, &key, namespace.as_deref().or(session_namespace), value.as_deref(), stdin, file.as_deref(), &tags, force, ttl).await
             }
-            Commands::List { namespace, tag, detailed } => {
-                list_command(&storage, namespace.as_deref(), tag.as_deref(), detailed).await
+            VaultCommands::Get { key: None, keys, namespace, show, .. } => {
+                get_many_command(&storage, &keys, namespace.as_deref().or(session_namespace), json, show, config.ui.require_show_flag).await
             }
-            Commands::Delete { key, namespace, force } => {
-                delete_command(&storage, &key, namespace.as_deref(), force).await
+            VaultCommands::Get { key: Some(key), namespace, copy, clear_after, no_clear, metadata, raw, output_file, show, .. } => {
+                let clear_after = if no_clear { 0 } else { clear_after.unwrap_or(config.ui.clipboard_clear_seconds) };
+                get_command(&storage, &key, namespace.as_deref().or(session_namespace), copy, clear_after, metadata, json, raw, output_file.as_deref(), show, config.ui.require_show_flag).await
             }
-            Commands::Search { query, namespace } => {
-                search_command(&storage, &query, namespace.as_deref()).await
+            VaultCommands::Share { key, namespace, password } => {
+                share_command(&storage, &config, &key, namespace.as_deref().or(session_namespace), password.as_deref()).await
             }
-            Commands::Status => {
-                status_command(&config, &storage).await
+            VaultCommands::Receive { blob, namespace, key, password, force } => {
+                receive_command(&storage, &blob, namespace.as_deref().or(session_namespace), key.as_deref(), password.as_deref(), force).await
             }
-            Commands::Whoami => {
-                whoami_command().await
+            VaultCommands::Link { alias, to, namespace, force } => {
+                link_command(&storage, &alias, &to, namespace.as_deref().or(session_namespace), force).await
             }
-            Commands::Doctor => {
-                doctor_command(&config, &storage).await
+            VaultCommands::List { namespace, tag, detailed, count, sort, desc, limit, offset, .. } => {
+                let page = ListOptions { sort_by: sort, descending: desc, offset, limit };
+                list_command(&storage, namespace.as_deref().or(session_namespace), tag.as_deref(), detailed, count, page, json).await
             }
-            Commands::Sync { action } => {
-                sync_command(action, &config).await
+            VaultCommands::Namespaces { .. } => {
+                namespaces_command(&storage, json).await
             }
-            Commands::Roles { action } => {
-                roles_command(action).await
+            VaultCommands::Delete { key: Some(key), namespace, force, hard, .. } => {
+                delete_command(&storage, &key, namespace.as_deref().or(session_namespace), force, hard).await
             }
-            Commands::Audit { action } => {
-                audit_command(action).await
+            VaultCommands::Delete { key: None, namespace, force, .. } => {
+                delete_namespace_command(&storage, namespace.as_deref().unwrap_or("default"), force).await
+            }
+            VaultCommands::Trash { action } => {
+                trash_command(action, &storage, session_namespace, json).await
+            }
+            VaultCommands::History { key, namespace, detailed, reveal } => {
+                history_command(&storage, &key, namespace.as_deref().or(session_namespace), detailed, reveal).await
+            }
+            VaultCommands::Move { key, namespace, new_key, new_namespace, force } => {
+                move_command(&storage, &key, namespace.as_deref().or(session_namespace), new_key.as_deref(), new_namespace.as_deref(), force).await
+            }
+            VaultCommands::Copy { key, namespace, to_namespace, new_key, force } => {
+                copy_command(&storage, &key, namespace.as_deref().or(session_namespace), to_namespace.as_deref(), new_key.as_deref(), force).await
+            }
+            VaultCommands::Tag { key, namespace, add, remove } => {
+                tag_command(&storage, &key, namespace.as_deref().or(session_namespace), &add, &remove).await
+            }
+            VaultCommands::Tags { action } => {
+                tags_command(action, &storage).await
+            }
+            VaultCommands::Rollback { key, namespace, to } => {
+                rollback_command(&storage, &key, namespace.as_deref().or(session_namespace), to).await
+            }
+            VaultCommands::Search { query, namespace, in_values, regex, count, .. } => {
+                let options = SearchOptions { values: in_values, regex };
+                search_command(&storage, &query, namespace.as_deref(), &options, count, json).await
+            }
+            VaultCommands::Status => {
+                status_command(&config, &storage, json).await
+            }
+            VaultCommands::Use { namespace, clear } => {
+                use_command(namespace.as_deref(), clear).await
+            }
+            VaultCommands::Whoami => {
+                whoami_command(json).await
+            }
+            VaultCommands::Doctor { salvage, benchmark } => {
+                doctor_command(&config, self.config.as_deref(), &storage, salvage.as_deref(), benchmark).await
+            }
+            VaultCommands::BenchKdf { target_ms, write } => {
+                bench_kdf_command(&config, self.config.as_deref(), target_ms, write).await
+            }
+            VaultCommands::Reencrypt { to, force } => {
+                reencrypt_command(&mut storage, &config, self.config.as_deref(), &to, force).await
+            }
+            VaultCommands::Roles { action } => {
+                roles_command(action, &storage).await
+            }
+            VaultCommands::Sessions { action } => {
+                sessions_command(action, &storage).await
+            }
+            VaultCommands::TwoFactor { action } => {
+                two_factor_command(action, &storage).await
             }
-            Commands::Users { action } => {
+            VaultCommands::Users { action } => {
                 users_command(action, &storage, &config).await
             }
-            Commands::Export { output, format, namespace } => {
-                export_command(&storage, &output, &format, namespace.as_deref()).await
+            VaultCommands::Export { output, format, namespace, encrypt } => {
+                export_command(&config, &storage, &output, &format, namespace.as_deref().or(session_namespace), encrypt).await
             }
-            Commands::Import { input, format, namespace } => {
-                import_command(&storage, &input, &format, namespace.as_deref()).await
+            VaultCommands::Env { namespace, prefix, map, command } => {
+                env_command(&storage, namespace.as_deref().or(session_namespace), prefix.as_deref(), map.as_deref(), &command).await
             }
-            Commands::Completions { shell } => {
+            VaultCommands::Exec { namespace, command } => {
+                env_command(&storage, namespace.as_deref().or(session_namespace), None, None, &command).await
+            }
+            VaultCommands::Import { input, format, namespace, atomic } => {
+                import_command(&storage, &input, &format, namespace.as_deref().or(session_namespace), atomic).await
+            }
+            VaultCommands::Completions { shell } => {
                 completions_command(&shell).await
             }
+        };
+        
+        // Keeps the inactivity lock from firing while the vault is in use
+        if session_command && result.is_ok() {
+            let _ = crate::auth::SessionManager::touch_session();
         }
+        result
     }
-}
\ No newline at end of file
+}
+
+/// Open the vault with the configured storage settings. Session commands
+/// also relock it first when the session has been idle too long.
+pub(crate) fn open_storage(config: &Config, read_only: bool, session_command: bool) -> Result<VaultStorage> {
+    let mut storage = if read_only {
+        VaultStorage::open_readonly(&config.storage_path)?
+    } else {
+        VaultStorage::new(&config.storage_path)?
+    };
+    storage.set_envelope_encryption(
+        config.cloud.as_ref().and_then(|c| c.envelope_encryption).unwrap_or(false)
+    );
+    storage.set_namespace_keys(config.security.per_namespace_keys);
+    storage.set_max_secret_bytes(config.security.max_secret_bytes);
+    configure_key_provider(&mut storage, config)?;
+    if session_command && config.security.inactivity_lock_minutes > 0 {
+        lock_if_idle(&mut storage, config.security.inactivity_lock_minutes)?;
+    }
+    Ok(storage)
+}
+
+fn configure_key_provider(storage: &mut VaultStorage, config: &Config) -> Result<()> {
+    match config.security.key_provider.as_str() {
+        "local" => Ok(()),
+        #[cfg(feature = "kms")]
+        "kms" => {
+            let kms = config.security.kms.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("key_provider is 'kms' but [security.kms] is not configured"))?;
+            let token = kms.token.clone()
+                .or_else(|| std::env::var("RVAULT_KMS_TOKEN").ok())
+                .ok_or_else(|| anyhow::anyhow!("No KMS token configured. Set security.kms.token or RVAULT_KMS_TOKEN"))?;
+            // The provider only wraps envelope DEKs; without them it would never be asked
+            if !config.cloud.as_ref().and_then(|c| c.envelope_encryption).unwrap_or(false) {
+                output::print_warning(
+                    "key_provider is 'kms' but cloud.envelope_encryption is off, so secrets are encrypted with the master key and the KMS is unused"
+                );
+            }
+            storage.set_key_provider(Box::new(crate::crypto::KmsKeyEncryptionKey::new(&kms.endpoint, &kms.key_name, &token)));
+            Ok(())
+        }
+        #[cfg(not(feature = "kms"))]
+        "kms" => {
+            let _ = storage;
+            Err(anyhow::anyhow!("This build does not include KMS support. Rebuild with --features kms"))
+        }
+        other => Err(anyhow::anyhow!("Unknown key provider '{}'. Use 'local' or 'kms'", other)),
+    }
+}
+
+/// Once the session has gone unused for `minutes`, drop the cached master key
+/// and ask for the passphrase again. The session itself is kept, so the user,
+/// role and working namespace survive the relock.
+fn lock_if_idle(storage: &mut VaultStorage, minutes: u64) -> Result<()> {
+    let Ok(session) = crate::auth::SessionManager::get_current_session() else {
+        return Ok(());
+    };
+    if !session.is_idle(minutes) || storage.current_tenant_id().is_err() {
+        return Ok(());
+    }
+    storage.lock()?;
+    
+    output::print_warning(&format!("Vault locked after {} minutes of inactivity", minutes));
+    let passphrase = dialoguer::Password::new()
+        .with_prompt(format!("Passphrase for tenant '{}'", session.tenant_id))
+        .interact()?;
+    storage.unlock_transient(&session.tenant_id, &passphrase)?;
+    storage.cache_session_key()?;
+    Ok(())
+}
+
+/// Point the vault at another tenant for this command without touching the
+/// persisted session: use the tenant's cached key, or prompt for its
+/// passphrase and, as `login` would, the user's authentication code.
+fn switch_tenant(storage: &mut VaultStorage, config: &Config, tenant: &str) -> Result<()> {
+    if !storage.tenant_exists(tenant)? {
+        return Err(anyhow::anyhow!("Tenant not found: {}", tenant));
+    }
+    let user_id = crate::auth::SessionManager::get_current_session()
+        .map(|session| session.user_id)
+        .unwrap_or_else(|_| "admin".to_string());
+    // Collaborative sessions belong to a user who must be a member of the tenant
+    let is_collaborative = config.cloud.as_ref()
+        .is_some_and(|cloud| matches!(cloud.mode, crate::config::CloudMode::Collaborative));
+    if storage.unlock_from_cache(tenant, is_collaborative.then_some(user_id.as_str()))? {
+        return Ok(());
+    }
+    
+    let passphrase = dialoguer::Password::new()
+        .with_prompt(format!("Passphrase for tenant '{}'", tenant))
+        .interact()?;
+    storage.unlock_transient(tenant, &passphrase)?;
+    
+    match check_second_factor(storage, &user_id)? {
+        Some(true) => Ok(()),
+        Some(false) => Err(anyhow::anyhow!("Two-factor check failed for tenant {}", tenant)),
+        None if config.security.require_2fa => Err(anyhow::anyhow!(
+            "Tenant {} requires two-factor login; run 'vault login --tenant {}' to enroll", tenant, tenant
+        )),
+        None => Ok(()),
+    }
+}
+
@@ -1,11 +1,209 @@
-// Output formatting utilities - currently unused but ready for implementation
-// TODO: Integrate with CLI commands for consistent output formatting
+// Output formatting utilities shared by the CLI commands. Colors, table style
+// and timestamp format all follow the `[ui]` section of the config.
 
-use owo_colors::OwoColorize;
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use comfy_table::{presets, modifiers, Cell, Color, ContentArrangement, Table};
+
+use crate::config::UiConfig;
 use crate::storage::SecretMetadata;
 
+struct OutputSettings {
+    color: bool,
+    table_format: String,
+    date_format: String,
+    json: bool,
+    quiet: bool,
+    assume_yes: bool,
+}
+
+static SETTINGS: OnceLock<OutputSettings> = OnceLock::new();
+
+/// Apply the UI configuration and the global output flags. Colors are only
+/// emitted when enabled in the config and stdout is a terminal, so piped
+/// output stays plain. Under `json` status messages move to stderr so stdout
+/// carries only the JSON document; `quiet` drops success and info messages
+/// altogether, and `assume_yes` answers every confirmation with yes.
+pub fn init(ui: &UiConfig, json: bool, quiet: bool, assume_yes: bool) {
+    let _ = SETTINGS.set(OutputSettings {
+        color: ui.color_output && std::io::stdout().is_terminal(),
+        table_format: ui.table_format.to_lowercase(),
+        date_format: ui.date_format.clone(),
+        json,
+        quiet,
+        assume_yes,
+    });
+}
+
+fn settings() -> &'static OutputSettings {
+    SETTINGS.get_or_init(|| {
+        let ui = UiConfig::default();
+        OutputSettings {
+            color: std::io::stdout().is_terminal(),
+            table_format: ui.table_format,
+            date_format: ui.date_format,
+            json: false,
+            quiet: false,
+            assume_yes: false,
+        }
+    })
+}
+
+pub fn colors_enabled() -> bool {
+    settings().color
+}
+
+pub fn format_date(timestamp: &DateTime<Utc>) -> String {
+    timestamp.format(&settings().date_format).to_string()
+}
+
+/// Drop-in replacement for `owo_colors::OwoColorize` that honours
+/// `ui.color_output`, so call sites keep the familiar `"text".green()` form.
+pub trait Colorize: Display {
+    fn green(&self) -> String { paint(self, |s| owo_colors::OwoColorize::green(&s).to_string()) }
+    fn red(&self) -> String { paint(self, |s| owo_colors::OwoColorize::red(&s).to_string()) }
+    fn blue(&self) -> String { paint(self, |s| owo_colors::OwoColorize::blue(&s).to_string()) }
+    fn yellow(&self) -> String { paint(self, |s| owo_colors::OwoColorize::yellow(&s).to_string()) }
+    fn cyan(&self) -> String { paint(self, |s| owo_colors::OwoColorize::cyan(&s).to_string()) }
+    fn purple(&self) -> String { paint(self, |s| owo_colors::OwoColorize::purple(&s).to_string()) }
+    fn white(&self) -> String { paint(self, |s| owo_colors::OwoColorize::white(&s).to_string()) }
+    fn bold(&self) -> String { paint(self, |s| owo_colors::OwoColorize::bold(&s).to_string()) }
+    fn dimmed(&self) -> String { paint(self, |s| owo_colors::OwoColorize::dimmed(&s).to_string()) }
+}
+
+impl<T: Display + ?Sized> Colorize for T {}
+
+fn paint<T, F>(value: &T, style: F) -> String
+where
+    T: Display + ?Sized,
+    F: FnOnce(&str) -> String,
+{
+    let text = value.to_string();
+    if colors_enabled() {
+        style(&text)
+    } else {
+        text
+    }
+}
+
+/// Create a table styled according to `ui.table_format`
+/// (`modern`, `ascii`, `plain` or `markdown`).
+pub fn table(headers: &[&str]) -> Table {
+    let mut table = Table::new();
+    match settings().table_format.as_str() {
+        "ascii" | "grid" => {
+            table.load_preset(presets::ASCII_FULL);
+        }
+        "plain" | "simple" => {
+            table.load_preset(presets::NOTHING);
+        }
+        "markdown" => {
+            table.load_preset(presets::ASCII_MARKDOWN);
+        }
+        _ => {
+            table
+                .load_preset(presets::UTF8_FULL)
+                .apply_modifier(modifiers::UTF8_ROUND_CORNERS);
+        }
+    }
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    if !colors_enabled() {
+        table.force_no_tty();
+    }
+    table.set_header(headers.iter().map(Cell::new));
+    table
+}
+
+/// A table cell colored only when colors are enabled.
+pub fn cell<T: Display>(content: T, color: Color) -> Cell {
+    let cell = Cell::new(content);
+    if colors_enabled() {
+        cell.fg(color)
+    } else {
+        cell
+    }
+}
+
+fn print_status(symbol: String, message: &str) {
+    if settings().json {
+        eprintln!("{} {}", symbol, message);
+    } else {
+        println!("{} {}", symbol, message);
+    }
+}
+
+/// Ask the user to confirm an action. `--yes` confirms without prompting;
+/// without it, a non-interactive stdin aborts instead of waiting for input.
+pub fn confirm(prompt: impl Into<String>, default: bool) -> anyhow::Result<bool> {
+    let prompt = prompt.into();
+    if settings().assume_yes {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        print_warning(&format!("{} (stdin is not a terminal; pass --yes to confirm)", prompt));
+        return Ok(false);
+    }
+    Ok(dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .default(default)
+        .interact()?)
+}
+
+/// Ask a yes/no preference question. Under `--yes` or without a terminal the
+/// default answer is used, since there is nothing to confirm.
+pub fn ask(prompt: impl Into<String>, default: bool) -> anyhow::Result<bool> {
+    if settings().assume_yes || !std::io::stdin().is_terminal() {
+        return Ok(default);
+    }
+    Ok(dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .default(default)
+        .interact()?)
+}
+
+/// Ask for a password, with a second prompt that must match when
+/// `confirmation` is given. Without a terminal the password is read as the
+/// first line of stdin instead, so scripts and tests can pipe it in.
+pub fn password(prompt: impl Into<String>, confirmation: Option<&str>) -> anyhow::Result<String> {
+    if !std::io::stdin().is_terminal() {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        let password = line.trim_end_matches(['\r', '\n']).to_string();
+        if password.is_empty() {
+            anyhow::bail!("No password on stdin (stdin is not a terminal)");
+        }
+        return Ok(password);
+    }
+    
+    let mut prompt = dialoguer::Password::new().with_prompt(prompt);
+    if let Some(confirmation) = confirmation {
+        prompt = prompt.with_confirmation(confirmation, "Passwords do not match");
+    }
+    Ok(prompt.interact()?)
+}
+
 pub fn print_success(message: &str) {
-    println!("{} {}", "✓".green(), message);
+    if !settings().quiet {
+        print_status("✓".green(), message);
+    }
+}
+
+/// Success message always written to stderr, for commands that report
+/// where a progress spinner would have, which is hidden without a terminal.
+pub fn print_success_to_stderr(message: &str) {
+    if !settings().quiet {
+        eprintln!("{} {}", "✓".green(), message);
+    }
+}
+
+/// Print a `--json` document to stdout.
+pub fn print_json<T: serde::Serialize + ?Sized>(value: &T) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
 }
 
 pub fn print_error(message: &str) {
@@ -13,26 +211,42 @@ pub fn print_error(message: &str) {
 }
 
 pub fn print_info(message: &str) {
-    println!("{} {}", "ℹ".blue(), message);
+    if !settings().quiet {
+        print_status("ℹ".blue(), message);
+    }
 }
 
 pub fn print_warning(message: &str) {
-    println!("{} {}", "⚠".yellow(), message);
+    print_status("⚠".yellow(), message);
 }
 
 pub fn print_secret_list(secrets: &[(String, SecretMetadata)], detailed: bool) {
     if detailed {
+        let mut table = table(&["Key", "Created", "Updated", "Version", "Tags"]);
         for (key, meta) in secrets {
-            println!("  {} {}", "•".green(), key.cyan());
-            println!("    Created: {}", meta.created_at.format("%Y-%m-%d %H:%M:%S UTC"));
-            println!("    Version: {}", meta.version);
-            if !meta.tags.is_empty() {
-                println!("    Tags: {}", meta.tags.join(", ").yellow());
+            let mut key_label = match &meta.alias_of {
+                Some(target) => format!("{} -> {}", key, target),
+                None => key.clone(),
+            };
+            if meta.binary {
+                key_label.push_str(" [binary]");
             }
+            table.add_row(vec![
+                cell(key_label, Color::Cyan),
+                Cell::new(format_date(&meta.created_at)),
+                Cell::new(format_date(&meta.updated_at)),
+                Cell::new(meta.version),
+                cell(meta.tags.join(", "), Color::Yellow),
+            ]);
         }
+        println!("{}", table);
     } else {
-        for (key, _) in secrets {
-            println!("  {}", key.cyan());
+        for (key, meta) in secrets {
+            let marker = if meta.binary { " [binary]".dimmed() } else { String::new() };
+            match &meta.alias_of {
+                Some(target) => println!("  {} {} {}{}", key.cyan(), "->".dimmed(), target, marker),
+                None => println!("  {}{}", key.cyan(), marker),
+            }
         }
     }
 }
@@ -40,4 +254,4 @@ pub fn print_secret_list(secrets: &[(String, SecretMetadata)], detailed: bool) {
 pub fn print_table_header(columns: &[&str]) {
     println!("{}", columns.join(" | ").bold());
     println!("{}", "-".repeat(columns.join(" | ").len()));
-}
\ No newline at end of file
+}
@@ -7,6 +7,7 @@ use crate::error::{VaultError, Result};
 pub struct Config {
     pub storage_path: String,
     pub tenant_id: Option<String>,
+    #[serde(alias = "cloud_sync")]
     pub cloud: Option<CloudConfig>,
     #[serde(default)]
     pub security: SecurityConfig,
@@ -15,14 +16,54 @@ pub struct Config {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(from = "CloudConfigFile")]
 pub struct CloudConfig {
     pub mode: CloudMode,
     pub backend: Option<CloudBackend>,
     pub region: Option<String>,
     pub bucket: Option<String>,
     pub database_url: Option<String>,
+    pub directory: Option<String>, // Root of the Directory backend, e.g. a Dropbox or NFS folder
     pub envelope_encryption: Option<bool>,
     pub sync_interval_minutes: Option<u64>,
+    pub credentials_in_keyring: Option<bool>, // DB password / S3 keys are held in the OS keyring
+}
+
+/// On-disk shape of `[cloud]`. It also accepts the older `[cloud_sync]`
+/// block, which had an `enabled` flag instead of `mode`.
+#[derive(Deserialize)]
+struct CloudConfigFile {
+    mode: Option<CloudMode>,
+    enabled: Option<bool>,
+    backend: Option<CloudBackend>,
+    region: Option<String>,
+    bucket: Option<String>,
+    database_url: Option<String>,
+    directory: Option<String>,
+    envelope_encryption: Option<bool>,
+    sync_interval_minutes: Option<u64>,
+    credentials_in_keyring: Option<bool>,
+}
+
+impl From<CloudConfigFile> for CloudConfig {
+    fn from(file: CloudConfigFile) -> Self {
+        let mode = match (file.mode, file.enabled) {
+            (Some(mode), _) => mode,
+            (None, Some(true)) => CloudMode::Backup,
+            (None, _) => CloudMode::None,
+        };
+        Self {
+            mode,
+            backend: file.backend,
+            region: file.region,
+            bucket: file.bucket,
+            database_url: file.database_url,
+            directory: file.directory,
+            envelope_encryption: file.envelope_encryption,
+            sync_interval_minutes: file.sync_interval_minutes,
+            credentials_in_keyring: file.credentials_in_keyring,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -39,6 +80,7 @@ pub enum CloudMode {
 pub enum CloudBackend {
     S3,
     Postgres,
+    Directory,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -49,23 +91,146 @@ pub struct SecurityConfig {
     pub key_derivation_parallelism: u32,
     pub session_timeout_hours: i64,
     pub require_2fa: bool,
+    #[serde(default = "default_key_provider")]
+    pub key_provider: String, // "local" or "kms"
+    #[serde(default)]
+    pub kms: Option<KmsConfig>,
+    #[serde(default)]
+    pub per_namespace_keys: bool, // Encrypt each namespace under its own HKDF subkey
+    #[serde(default = "default_max_secret_bytes")]
+    pub max_secret_bytes: usize,
+    #[serde(default)]
+    pub inactivity_lock_minutes: u64, // Ask for the passphrase again after this long idle; 0 disables
+    #[serde(default)]
+    pub min_password_score: u8, // Reject master passphrases scoring below this (0-4); 0 only warns
 }
 
+/// External KMS used to wrap data keys when `key_provider = "kms"`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KmsConfig {
+    pub endpoint: String,
+    pub key_name: String,
+    pub token: Option<String>, // Falls back to RVAULT_KMS_TOKEN
+}
+
+fn default_key_provider() -> String {
+    "local".to_string()
+}
+
+fn default_max_secret_bytes() -> usize {
+    crate::storage::DEFAULT_MAX_SECRET_BYTES
+}
+
+fn default_clipboard_clear_seconds() -> u64 {
+    30
+}
+
+/// Upper bound for the clipboard and reveal timeouts.
+const MAX_TIMEOUT_SECONDS: u64 = 24 * 60 * 60;
+
+/// Upper bound for `security.session_timeout_hours`.
+const MAX_SESSION_TIMEOUT_HOURS: i64 = 365 * 24;
+
+/// How many times longer a `--remember` session lasts than a normal one.
+const REMEMBER_SESSION_FACTOR: i64 = 7;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UiConfig {
     pub color_output: bool,
     pub progress_bars: bool,
     pub table_format: String,
     pub date_format: String,
+    #[serde(default)]
+    pub require_show_flag: bool, // `get` masks values unless --show is passed
+    #[serde(default = "default_clipboard_clear_seconds")]
+    pub clipboard_clear_seconds: u64, // 0 = never clear
+    #[serde(default)]
+    pub reveal_timeout_seconds: u64, // 0 = no timeout
+}
+
+/// Profile chosen by the CLI for this process; see `set_active_profile`.
+static ACTIVE_PROFILE: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+
+/// Select the profile every path helper uses for the rest of the process.
+/// The CLI calls this once with `--profile` (which clap also fills from
+/// `VAULT_PROFILE`) before resolving any path.
+pub fn set_active_profile(profile: Option<String>) -> Result<()> {
+    if let Some(profile) = &profile {
+        validate_profile_name(profile)?;
+    }
+    ACTIVE_PROFILE.set(profile)
+        .map_err(|_| VaultError::Config("The profile has already been selected".to_string()))
+}
+
+/// Named profile selected with `--profile` or `VAULT_PROFILE`.
+/// `None` is the default profile.
+pub fn active_profile() -> Option<String> {
+    profile_from(&|name| std::env::var(name).ok())
+}
+
+/// `active_profile`, reading `VAULT_PROFILE` through `env` when the CLI
+/// hasn't selected one.
+fn profile_from(env: &dyn Fn(&str) -> Option<String>) -> Option<String> {
+    match ACTIVE_PROFILE.get() {
+        Some(profile) => profile.clone(),
+        None => env("VAULT_PROFILE"),
+    }
+    .filter(|profile| !profile.is_empty() && profile != "default")
+}
+
+/// Display name of the active profile.
+pub fn profile_name() -> String {
+    active_profile().unwrap_or_else(|| "default".to_string())
+}
+
+/// Profile names become directory names, so keep them to a safe character set.
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(VaultError::Config(format!(
+            "Invalid profile name '{}': use letters, digits, '-' and '_'", name
+        )));
+    }
+    Ok(())
+}
+
+/// Directory holding config.toml and the session file.
+/// `VAULT_CONFIG_DIR` overrides the platform config directory.
+pub fn config_dir() -> PathBuf {
+    config_dir_from(&|name| std::env::var(name).ok())
+}
+
+/// `config_dir`, reading the environment through `env`.
+fn config_dir_from(env: &dyn Fn(&str) -> Option<String>) -> PathBuf {
+    let base = match env("VAULT_CONFIG_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("vault"),
+    };
+    match profile_from(env) {
+        Some(profile) => base.join("profiles").join(profile),
+        None => base,
+    }
+}
+
+fn default_storage_path_for(profile: Option<&str>) -> PathBuf {
+    let base = dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".vault");
+    match profile {
+        Some(profile) => base.join("profiles").join(profile).join("vault.db"),
+        None => base.join("vault.db"),
+    }
+}
+
+pub fn default_config_path() -> PathBuf {
+    config_dir().join("config.toml")
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            storage_path: dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".vault")
-                .join("vault.db")
+            storage_path: default_storage_path_for(active_profile().as_deref())
                 .to_string_lossy()
                 .to_string(),
             tenant_id: None,
@@ -85,6 +250,12 @@ impl Default for SecurityConfig {
             key_derivation_parallelism: 1,
             session_timeout_hours: 24,
             require_2fa: false,
+            key_provider: default_key_provider(),
+            kms: None,
+            per_namespace_keys: false,
+            max_secret_bytes: default_max_secret_bytes(),
+            inactivity_lock_minutes: 0,
+            min_password_score: 0,
         }
     }
 }
@@ -96,39 +267,80 @@ impl Default for UiConfig {
             progress_bars: true,
             table_format: "modern".to_string(),
             date_format: "%Y-%m-%d %H:%M:%S UTC".to_string(),
+            require_show_flag: false,
+            clipboard_clear_seconds: default_clipboard_clear_seconds(),
+            reveal_timeout_seconds: 0,
         }
     }
 }
 
 impl Config {
+    /// Load the config file, falling back to defaults when it does not exist.
+    /// Precedence: environment (`VAULT_STORAGE_PATH`) > `config_path` (from
+    /// `--config`) or the default config.toml > built-in defaults.
     pub fn load(config_path: Option<&str>) -> Result<Self> {
+        Self::load_with_env(config_path, &|name| std::env::var(name).ok())
+    }
+    
+    /// `load`, reading the environment through `env`.
+    fn load_with_env(config_path: Option<&str>, env: &dyn Fn(&str) -> Option<String>) -> Result<Self> {
         let path = match config_path {
             Some(p) => PathBuf::from(p),
-            None => dirs::config_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join("vault")
-                .join("config.toml"),
+            None => config_dir_from(env).join("config.toml"),
         };
         
-        if path.exists() {
+        let mut config: Self = if path.exists() {
             let content = std::fs::read_to_string(&path)
                 .map_err(|e| VaultError::Config(format!("Failed to read config file: {}", e)))?;
             
             toml::from_str(&content)
-                .map_err(|e| VaultError::Config(format!("Failed to parse config file: {}", e)))
+                .map_err(|e| VaultError::Config(format!("Failed to parse config file: {}", e)))?
         } else {
-            Ok(Self::default())
+            Self::default()
+        };
+        
+        config.apply_env_overrides(env);
+        config.validate()?;
+        Ok(config)
+    }
+    
+    fn validate(&self) -> Result<()> {
+        for (name, value) in [
+            ("ui.clipboard_clear_seconds", self.ui.clipboard_clear_seconds),
+            ("ui.reveal_timeout_seconds", self.ui.reveal_timeout_seconds),
+        ] {
+            if value > MAX_TIMEOUT_SECONDS {
+                return Err(VaultError::Config(format!(
+                    "{} must be at most {} (24 hours), got {}", name, MAX_TIMEOUT_SECONDS, value
+                )));
+            }
+        }
+        if self.security.session_timeout_hours > MAX_SESSION_TIMEOUT_HOURS {
+            return Err(VaultError::Config(format!(
+                "security.session_timeout_hours must be at most {} (a year), got {}",
+                MAX_SESSION_TIMEOUT_HOURS, self.security.session_timeout_hours
+            )));
+        }
+        if self.security.min_password_score > 4 {
+            return Err(VaultError::Config(format!(
+                "security.min_password_score must be between 0 and 4, got {}", self.security.min_password_score
+            )));
+        }
+        Ok(())
+    }
+    
+    fn apply_env_overrides(&mut self, env: &dyn Fn(&str) -> Option<String>) {
+        if let Some(storage_path) = env("VAULT_STORAGE_PATH") {
+            if !storage_path.is_empty() {
+                self.storage_path = storage_path;
+            }
         }
     }
     
-    #[allow(dead_code)]
     pub fn save(&self, config_path: Option<&str>) -> Result<()> {
         let path = match config_path {
             Some(p) => PathBuf::from(p),
-            None => dirs::config_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join("vault")
-                .join("config.toml"),
+            None => default_config_path(),
         };
         
         if let Some(parent) = path.parent() {
@@ -145,15 +357,11 @@ impl Config {
         Ok(())
     }
     
-    #[allow(dead_code)]
     pub fn get_encryption_algorithm(&self) -> crate::crypto::EncryptionAlgorithm {
-        match self.security.encryption_algorithm.as_str() {
-            "chacha20poly1305" => crate::crypto::EncryptionAlgorithm::ChaCha20Poly1305,
-            _ => crate::crypto::EncryptionAlgorithm::Aes256Gcm,
-        }
+        crate::crypto::EncryptionAlgorithm::from_name(&self.security.encryption_algorithm)
+            .unwrap_or(crate::crypto::EncryptionAlgorithm::Aes256Gcm)
     }
     
-    #[allow(dead_code)]
     pub fn get_key_derivation_params(&self) -> crate::crypto::KeyDerivationParams {
         crate::crypto::KeyDerivationParams {
             memory_cost: self.security.key_derivation_memory_cost,
@@ -161,4 +369,133 @@ impl Config {
             parallelism: self.security.key_derivation_parallelism,
         }
     }
-}
\ No newline at end of file
+    
+    /// Lifetime of a new session: `security.session_timeout_hours` (24 when
+    /// not positive), seven times that for `vault login --remember`.
+    pub fn session_duration_hours(&self, remember: bool) -> i64 {
+        let hours = if self.security.session_timeout_hours > 0 {
+            self.security.session_timeout_hours
+        } else {
+            24
+        };
+        if remember {
+            hours.saturating_mul(REMEMBER_SESSION_FACTOR)
+        } else {
+            hours
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_env_overrides_config_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "storage_path = \"/from/file.db\"\n").unwrap();
+        let config_dir = temp_dir.path().to_str().unwrap().to_string();
+        
+        let from_file = Config::load_with_env(None, &|name| {
+            (name == "VAULT_CONFIG_DIR").then(|| config_dir.clone())
+        }).unwrap();
+        let from_env = Config::load_with_env(None, &|name| match name {
+            "VAULT_CONFIG_DIR" => Some(config_dir.clone()),
+            "VAULT_STORAGE_PATH" => Some("/from/env.db".to_string()),
+            _ => None,
+        }).unwrap();
+        
+        assert_eq!(from_file.storage_path, "/from/file.db");
+        assert_eq!(from_env.storage_path, "/from/env.db");
+    }
+    
+    #[test]
+    fn test_ui_timeouts_are_validated() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let path = config_path.to_str().unwrap();
+        
+        let defaults = Config::load(Some(path)).unwrap();
+        assert_eq!(defaults.ui.clipboard_clear_seconds, 30);
+        assert_eq!(defaults.ui.reveal_timeout_seconds, 0);
+        
+        let mut config = Config::default();
+        config.ui.clipboard_clear_seconds = 0;
+        config.save(Some(path)).unwrap();
+        assert_eq!(Config::load(Some(path)).unwrap().ui.clipboard_clear_seconds, 0);
+        
+        config.ui.reveal_timeout_seconds = MAX_TIMEOUT_SECONDS + 1;
+        config.save(Some(path)).unwrap();
+        assert!(matches!(Config::load(Some(path)), Err(VaultError::Config(_))));
+    }
+    
+    #[test]
+    fn test_session_lifetime_follows_session_timeout_hours() {
+        let mut config = Config::default();
+        assert_eq!(config.session_duration_hours(false), 24);
+        assert_eq!(config.session_duration_hours(true), 168);
+        
+        config.security.session_timeout_hours = 2;
+        let session = crate::auth::Session::new("acme".to_string(), "admin".to_string(), crate::auth::Role::Admin, config.session_duration_hours(false));
+        assert_eq!(session.expires_at - session.created_at, chrono::Duration::hours(2));
+        assert_eq!(config.session_duration_hours(true), 14);
+        
+        config.security.session_timeout_hours = 0;
+        assert_eq!(config.session_duration_hours(false), 24);
+        
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let path = config_path.to_str().unwrap();
+        config.security.session_timeout_hours = MAX_SESSION_TIMEOUT_HOURS + 1;
+        config.save(Some(path)).unwrap();
+        assert!(matches!(Config::load(Some(path)), Err(VaultError::Config(_))));
+    }
+    
+    #[test]
+    fn test_cloud_config_reads_current_and_legacy_shapes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let path = config_path.to_str().unwrap();
+        
+        std::fs::write(&config_path, "storage_path = \"/vault.db\"\n\n[cloud]\nmode = \"collaborative\"\nbackend = \"S3\"\nbucket = \"secrets\"\n").unwrap();
+        let current = Config::load(Some(path)).unwrap().cloud.unwrap();
+        assert!(matches!(current.mode, CloudMode::Collaborative));
+        assert_eq!(current.bucket.as_deref(), Some("secrets"));
+        
+        std::fs::write(&config_path, "storage_path = \"/vault.db\"\n\n[cloud_sync]\nenabled = true\nbackend = \"Postgres\"\ndatabase_url = \"postgres://db/vault\"\n").unwrap();
+        let legacy = Config::load(Some(path)).unwrap();
+        let cloud = legacy.cloud.clone().unwrap();
+        assert!(matches!(cloud.mode, CloudMode::Backup));
+        assert!(matches!(cloud.backend, Some(CloudBackend::Postgres)));
+        assert_eq!(cloud.database_url.as_deref(), Some("postgres://db/vault"));
+        
+        // Saving rewrites the legacy block in the current shape
+        legacy.save(Some(path)).unwrap();
+        let saved = std::fs::read_to_string(&config_path).unwrap();
+        assert!(saved.contains("[cloud]") && !saved.contains("cloud_sync") && !saved.contains("enabled"));
+        assert!(matches!(Config::load(Some(path)).unwrap().cloud.unwrap().mode, CloudMode::Backup));
+        
+        std::fs::write(&config_path, "storage_path = \"/vault.db\"\n\n[cloud_sync]\nenabled = false\n").unwrap();
+        assert!(matches!(Config::load(Some(path)).unwrap().cloud.unwrap().mode, CloudMode::None));
+    }
+    
+    #[test]
+    fn test_profiles_are_namespaced() {
+        let env_with_profile = |profile: &'static str| move |name: &str| match name {
+            "VAULT_CONFIG_DIR" => Some("/config".to_string()),
+            "VAULT_PROFILE" => Some(profile.to_string()),
+            _ => None,
+        };
+        let work_dir = config_dir_from(&env_with_profile("work"));
+        assert_eq!(work_dir, PathBuf::from("/config/profiles/work"));
+        assert_ne!(work_dir, config_dir_from(&env_with_profile("personal")));
+        assert_eq!(config_dir_from(&env_with_profile("default")), PathBuf::from("/config"));
+        assert!(default_storage_path_for(Some("work")).ends_with(".vault/profiles/work/vault.db"));
+        assert!(default_storage_path_for(None).ends_with(".vault/vault.db"));
+        
+        assert!(validate_profile_name("work_2").is_ok());
+        assert!(validate_profile_name("../evil").is_err());
+        assert!(validate_profile_name("").is_err());
+    }
+}
@@ -1,9 +1,11 @@
-use aes_gcm::{Aes256Gcm, Key, Nonce, aead::{Aead, KeyInit}};
+use aes_gcm::{Aes256Gcm, Key, Nonce, aead::{Aead, KeyInit, Payload}};
 use rand::{rngs::OsRng, RngCore};
 
 use crate::error::{VaultError, Result};
 
-pub fn encrypt_aes256gcm(key: &[u8; 32], plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+/// Encrypt `plaintext`, authenticating `aad` alongside it. Decryption only
+/// succeeds with the same `aad`; an empty slice means no associated data.
+pub fn encrypt_aes256gcm(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
     let key = Key::<Aes256Gcm>::from_slice(key);
     let cipher = Aes256Gcm::new(key);
     
@@ -11,18 +13,18 @@ pub fn encrypt_aes256gcm(key: &[u8; 32], plaintext: &[u8]) -> Result<(Vec<u8>, V
     OsRng.fill_bytes(&mut nonce_bytes);
     let nonce = Nonce::from_slice(&nonce_bytes);
     
-    let ciphertext = cipher.encrypt(nonce, plaintext)
+    let ciphertext = cipher.encrypt(nonce, Payload { msg: plaintext, aad })
         .map_err(|e| VaultError::Crypto(format!("AES-256-GCM encryption failed: {}", e)))?;
     
     Ok((ciphertext, nonce_bytes.to_vec()))
 }
 
-pub fn decrypt_aes256gcm(key: &[u8; 32], ciphertext: &[u8], nonce: &[u8]) -> Result<Vec<u8>> {
+pub fn decrypt_aes256gcm(key: &[u8; 32], ciphertext: &[u8], nonce: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
     let key = Key::<Aes256Gcm>::from_slice(key);
     let cipher = Aes256Gcm::new(key);
     let nonce = Nonce::from_slice(nonce);
     
-    cipher.decrypt(nonce, ciphertext)
+    cipher.decrypt(nonce, Payload { msg: ciphertext, aad })
         .map_err(|e| VaultError::Crypto(format!("AES-256-GCM decryption failed: {}", e)))
 }
 
@@ -35,8 +37,8 @@ mod tests {
         let key = [0u8; 32];
         let plaintext = b"Hello, World!";
         
-        let (ciphertext, nonce) = encrypt_aes256gcm(&key, plaintext).unwrap();
-        let decrypted = decrypt_aes256gcm(&key, &ciphertext, &nonce).unwrap();
+        let (ciphertext, nonce) = encrypt_aes256gcm(&key, plaintext, &[]).unwrap();
+        let decrypted = decrypt_aes256gcm(&key, &ciphertext, &nonce, &[]).unwrap();
         
         assert_eq!(plaintext, decrypted.as_slice());
     }
@@ -46,16 +48,16 @@ mod tests {
         let key = [0u8; 32];
         let plaintext = b"Hello, World!";
         
-        let (ciphertext1, nonce1) = encrypt_aes256gcm(&key, plaintext).unwrap();
-        let (ciphertext2, nonce2) = encrypt_aes256gcm(&key, plaintext).unwrap();
+        let (ciphertext1, nonce1) = encrypt_aes256gcm(&key, plaintext, &[]).unwrap();
+        let (ciphertext2, nonce2) = encrypt_aes256gcm(&key, plaintext, &[]).unwrap();
         
         // Different nonces should produce different ciphertexts
         assert_ne!(nonce1, nonce2);
         assert_ne!(ciphertext1, ciphertext2);
         
         // But both should decrypt to the same plaintext
-        let decrypted1 = decrypt_aes256gcm(&key, &ciphertext1, &nonce1).unwrap();
-        let decrypted2 = decrypt_aes256gcm(&key, &ciphertext2, &nonce2).unwrap();
+        let decrypted1 = decrypt_aes256gcm(&key, &ciphertext1, &nonce1, &[]).unwrap();
+        let decrypted2 = decrypt_aes256gcm(&key, &ciphertext2, &nonce2, &[]).unwrap();
         
         assert_eq!(decrypted1, decrypted2);
         assert_eq!(plaintext, decrypted1.as_slice());
@@ -1,9 +1,11 @@
-use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce, aead::{Aead, KeyInit}};
+use chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305, XNonce, Key, Nonce, aead::{Aead, KeyInit, Payload}};
 use rand::{rngs::OsRng, RngCore};
 
 use crate::error::{VaultError, Result};
 
-pub fn encrypt_chacha20poly1305(key: &[u8; 32], plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+/// Encrypt `plaintext`, authenticating `aad` alongside it. Decryption only
+/// succeeds with the same `aad`; an empty slice means no associated data.
+pub fn encrypt_chacha20poly1305(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
     let key = Key::from_slice(key);
     let cipher = ChaCha20Poly1305::new(key);
     
@@ -11,32 +13,72 @@ pub fn encrypt_chacha20poly1305(key: &[u8; 32], plaintext: &[u8]) -> Result<(Vec
     OsRng.fill_bytes(&mut nonce_bytes);
     let nonce = Nonce::from_slice(&nonce_bytes);
     
-    let ciphertext = cipher.encrypt(nonce, plaintext)
+    let ciphertext = cipher.encrypt(nonce, Payload { msg: plaintext, aad })
         .map_err(|e| VaultError::Crypto(format!("ChaCha20-Poly1305 encryption failed: {}", e)))?;
     
     Ok((ciphertext, nonce_bytes.to_vec()))
 }
 
-pub fn decrypt_chacha20poly1305(key: &[u8; 32], ciphertext: &[u8], nonce: &[u8]) -> Result<Vec<u8>> {
+pub fn decrypt_chacha20poly1305(key: &[u8; 32], ciphertext: &[u8], nonce: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
     let key = Key::from_slice(key);
     let cipher = ChaCha20Poly1305::new(key);
     let nonce = Nonce::from_slice(nonce);
     
-    cipher.decrypt(nonce, ciphertext)
+    cipher.decrypt(nonce, Payload { msg: ciphertext, aad })
         .map_err(|e| VaultError::Crypto(format!("ChaCha20-Poly1305 decryption failed: {}", e)))
 }
 
+/// XChaCha20-Poly1305 uses a 24-byte nonce, so random nonces are safe for
+/// far more messages under one key than with the 12-byte variant.
+pub fn encrypt_xchacha20poly1305(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let key = Key::from_slice(key);
+    let cipher = XChaCha20Poly1305::new(key);
+    
+    let mut nonce_bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = XNonce::from_slice(&nonce_bytes);
+    
+    let ciphertext = cipher.encrypt(nonce, Payload { msg: plaintext, aad })
+        .map_err(|e| VaultError::Crypto(format!("XChaCha20-Poly1305 encryption failed: {}", e)))?;
+    
+    Ok((ciphertext, nonce_bytes.to_vec()))
+}
+
+pub fn decrypt_xchacha20poly1305(key: &[u8; 32], ciphertext: &[u8], nonce: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if nonce.len() != 24 {
+        return Err(VaultError::Crypto("Invalid XChaCha20-Poly1305 nonce length".to_string()));
+    }
+    let key = Key::from_slice(key);
+    let cipher = XChaCha20Poly1305::new(key);
+    let nonce = XNonce::from_slice(nonce);
+    
+    cipher.decrypt(nonce, Payload { msg: ciphertext, aad })
+        .map_err(|e| VaultError::Crypto(format!("XChaCha20-Poly1305 decryption failed: {}", e)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_xchacha20poly1305_roundtrip() {
+        let key = [0u8; 32];
+        let plaintext = b"Hello, World!";
+        
+        let (ciphertext, nonce) = encrypt_xchacha20poly1305(&key, plaintext, &[]).unwrap();
+        assert_eq!(nonce.len(), 24);
+        let decrypted = decrypt_xchacha20poly1305(&key, &ciphertext, &nonce, &[]).unwrap();
+        
+        assert_eq!(plaintext, decrypted.as_slice());
+    }
+    
     #[test]
     fn test_chacha20poly1305_roundtrip() {
         let key = [0u8; 32];
         let plaintext = b"Hello, World!";
         
-        let (ciphertext, nonce) = encrypt_chacha20poly1305(&key, plaintext).unwrap();
-        let decrypted = decrypt_chacha20poly1305(&key, &ciphertext, &nonce).unwrap();
+        let (ciphertext, nonce) = encrypt_chacha20poly1305(&key, plaintext, &[]).unwrap();
+        let decrypted = decrypt_chacha20poly1305(&key, &ciphertext, &nonce, &[]).unwrap();
         
         assert_eq!(plaintext, decrypted.as_slice());
     }
@@ -46,16 +88,16 @@ mod tests {
         let key = [0u8; 32];
         let plaintext = b"Hello, World!";
         
-        let (ciphertext1, nonce1) = encrypt_chacha20poly1305(&key, plaintext).unwrap();
-        let (ciphertext2, nonce2) = encrypt_chacha20poly1305(&key, plaintext).unwrap();
+        let (ciphertext1, nonce1) = encrypt_chacha20poly1305(&key, plaintext, &[]).unwrap();
+        let (ciphertext2, nonce2) = encrypt_chacha20poly1305(&key, plaintext, &[]).unwrap();
         
         // Different nonces should produce different ciphertexts
         assert_ne!(nonce1, nonce2);
         assert_ne!(ciphertext1, ciphertext2);
         
         // But both should decrypt to the same plaintext
-        let decrypted1 = decrypt_chacha20poly1305(&key, &ciphertext1, &nonce1).unwrap();
-        let decrypted2 = decrypt_chacha20poly1305(&key, &ciphertext2, &nonce2).unwrap();
+        let decrypted1 = decrypt_chacha20poly1305(&key, &ciphertext1, &nonce1, &[]).unwrap();
+        let decrypted2 = decrypt_chacha20poly1305(&key, &ciphertext2, &nonce2, &[]).unwrap();
         
         assert_eq!(decrypted1, decrypted2);
         assert_eq!(plaintext, decrypted1.as_slice());
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use rand::{rngs::OsRng, RngCore};
+use secrecy::Secret;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::error::{VaultError, Result};
 use super::{MasterKey, EncryptionAlgorithm, EncryptedData};
@@ -13,9 +15,11 @@ pub struct EnvelopeEncryption {
 }
 
 #[allow(dead_code)]
+#[derive(Zeroize, ZeroizeOnDrop)]
 pub struct DataEncryptionKey {
     key: [u8; 32],
     #[allow(dead_code)]
+    #[zeroize(skip)]
     algorithm: EncryptionAlgorithm,
 }
 
@@ -39,23 +43,28 @@ impl DataEncryptionKey {
     }
     
     pub fn encrypt(&self, plaintext: &[u8]) -> Result<EncryptedData> {
-        let master_key = MasterKey::derive_from_passphrase(
-            &hex::encode(self.key),
-            &[0u8; 32], // DEK doesn't need salt
-            self.algorithm.clone(),
-        )?;
-        
-        master_key.encrypt(plaintext)
+        self.as_master_key().encrypt(plaintext)
     }
     
     pub fn decrypt(&self, encrypted: &EncryptedData) -> Result<Vec<u8>> {
-        let master_key = MasterKey::derive_from_passphrase(
-            &hex::encode(self.key),
-            &[0u8; 32], // DEK doesn't need salt
-            self.algorithm.clone(),
-        )?;
-        
-        master_key.decrypt(encrypted)
+        self.as_master_key().decrypt(encrypted)
+    }
+    
+    pub fn encrypt_with_aad(&self, plaintext: &[u8], aad: &[u8]) -> Result<EncryptedData> {
+        self.as_master_key().encrypt_with_aad(plaintext, aad)
+    }
+    
+    pub fn decrypt_with_aad(&self, encrypted: &EncryptedData, aad: &[u8]) -> Result<Vec<u8>> {
+        self.as_master_key().decrypt_with_aad(encrypted, aad)
+    }
+    
+    // The DEK is already uniformly random, so it is used as the cipher key
+    // directly instead of being stretched through Argon2
+    fn as_master_key(&self) -> MasterKey {
+        MasterKey {
+            key: Secret::new(self.key),
+            algorithm: self.algorithm.clone(),
+        }
     }
     
     pub fn as_bytes(&self) -> &[u8; 32] {
@@ -70,6 +79,52 @@ pub trait KeyEncryptionKey {
     fn key_id(&self) -> String;
 }
 
+/// Marks an `encrypted_dek` that records the id of the key that wrapped it;
+/// the last byte is the layout version.
+const WRAPPED_DEK_MAGIC: &[u8] = b"RVDEK\x01";
+
+/// Key id of DEKs a KMS wrapped before ids were recorded. Their key name was
+/// not kept, so any configured KMS key is tried.
+pub const LEGACY_KMS_KEY_ID: &str = "kms";
+
+/// A DEK as stored with its secret: the provider's ciphertext together with
+/// the `key_id` of the key that wrapped it, so it is always unwrapped by that
+/// key rather than by whichever provider happens to be configured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WrappedDek {
+    pub key_id: String,
+    pub ciphertext: Vec<u8>,
+}
+
+impl WrappedDek {
+    pub fn wrap(kek: &dyn KeyEncryptionKey, dek: &DataEncryptionKey) -> Result<Self> {
+        Ok(Self {
+            key_id: kek.key_id(),
+            ciphertext: kek.encrypt_dek(dek)?,
+        })
+    }
+    
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = WRAPPED_DEK_MAGIC.to_vec();
+        bytes.extend(bincode::serialize(self)?);
+        Ok(bytes)
+    }
+    
+    /// Read a stored `encrypted_dek`. DEKs wrapped before key ids were kept
+    /// are the bare provider output: a transit ciphertext (`vault:v1:...`)
+    /// came from a KMS, anything else from the vault master key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if let Some(encoded) = bytes.strip_prefix(WRAPPED_DEK_MAGIC) {
+            return Ok(bincode::deserialize(encoded)?);
+        }
+        let key_id = if bytes.starts_with(b"vault:") { LEGACY_KMS_KEY_ID } else { "local" };
+        Ok(Self {
+            key_id: key_id.to_string(),
+            ciphertext: bytes.to_vec(),
+        })
+    }
+}
+
 #[allow(dead_code)]
 pub struct LocalKeyEncryptionKey {
     master_key: MasterKey,
@@ -88,13 +143,28 @@ impl LocalKeyEncryptionKey {
 
 impl KeyEncryptionKey for LocalKeyEncryptionKey {
     fn encrypt_dek(&self, dek: &DataEncryptionKey) -> Result<Vec<u8>> {
-        let encrypted = self.master_key.encrypt(dek.as_bytes())?;
+        self.master_key.encrypt_dek(dek)
+    }
+    
+    fn decrypt_dek(&self, encrypted_dek: &[u8], algorithm: EncryptionAlgorithm) -> Result<DataEncryptionKey> {
+        self.master_key.decrypt_dek(encrypted_dek, algorithm)
+    }
+    
+    fn key_id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+/// The vault master key wraps DEKs directly when secrets are stored in envelope mode.
+impl KeyEncryptionKey for MasterKey {
+    fn encrypt_dek(&self, dek: &DataEncryptionKey) -> Result<Vec<u8>> {
+        let encrypted = self.encrypt(dek.as_bytes())?;
         Ok(bincode::serialize(&encrypted)?)
     }
     
     fn decrypt_dek(&self, encrypted_dek: &[u8], algorithm: EncryptionAlgorithm) -> Result<DataEncryptionKey> {
         let encrypted: EncryptedData = bincode::deserialize(encrypted_dek)?;
-        let dek_bytes = self.master_key.decrypt(&encrypted)?;
+        let dek_bytes = self.decrypt(&encrypted)?;
         
         if dek_bytes.len() != 32 {
             return Err(VaultError::Crypto("Invalid DEK length".to_string()));
@@ -107,7 +177,7 @@ impl KeyEncryptionKey for LocalKeyEncryptionKey {
     }
     
     fn key_id(&self) -> String {
-        self.id.clone()
+        "local".to_string()
     }
 }
 
@@ -169,4 +239,22 @@ mod tests {
         assert_eq!(plaintext, decrypted.as_slice());
         assert_eq!(envelope.kek_id, "test-kek");
     }
+    
+    #[test]
+    fn test_wrapped_dek_records_its_key() {
+        let master_key = MasterKey::derive_from_passphrase("test-passphrase", &generate_salt(), EncryptionAlgorithm::Aes256Gcm).unwrap();
+        let kek = LocalKeyEncryptionKey::new(master_key, "external".to_string());
+        let dek = DataEncryptionKey::generate(EncryptionAlgorithm::Aes256Gcm);
+        
+        let wrapped = WrappedDek::wrap(&kek, &dek).unwrap();
+        let decoded = WrappedDek::from_bytes(&wrapped.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, wrapped);
+        assert_eq!(decoded.key_id, "external");
+        let unwrapped = kek.decrypt_dek(&decoded.ciphertext, EncryptionAlgorithm::Aes256Gcm).unwrap();
+        assert_eq!(unwrapped.as_bytes(), dek.as_bytes());
+        
+        // Bare DEKs from before key ids were recorded
+        assert_eq!(WrappedDek::from_bytes(&wrapped.ciphertext).unwrap().key_id, "local");
+        assert_eq!(WrappedDek::from_bytes(b"vault:v1:abcd").unwrap().key_id, LEGACY_KMS_KEY_ID);
+    }
 }
\ No newline at end of file
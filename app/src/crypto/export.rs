@@ -0,0 +1,97 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::error::{VaultError, Result};
+use super::{EncryptedData, EncryptionAlgorithm, MasterKey, generate_salt};
+
+/// First line of every encrypted export file.
+const EXPORT_HEADER: &str = "RVAULT-ENCRYPTED-EXPORT";
+const EXPORT_FORMAT_VERSION: u8 = 1;
+
+/// Body of an encrypted export file, after the header line.
+#[derive(Serialize, Deserialize)]
+struct SealedExport {
+    format: String, // Layout of the plaintext bundle ("json", "yaml")
+    salt: [u8; 32], // Argon2id salt the key was derived with
+    data: EncryptedData,
+}
+
+/// Associated data binding the header and bundle format to the ciphertext.
+fn export_aad(format: &str) -> Vec<u8> {
+    format!("{} v{} {}", EXPORT_HEADER, EXPORT_FORMAT_VERSION, format).into_bytes()
+}
+
+/// Encrypt an export bundle under a password.
+///
+/// Layout: a `RVAULT-ENCRYPTED-EXPORT v1` line, then base64 of the bincoded
+/// `SealedExport`. The key is derived with Argon2id from the password and a
+/// fresh salt stored in the file.
+pub fn seal_export(bundle: &[u8], format: &str, password: &str, algorithm: EncryptionAlgorithm) -> Result<String> {
+    let salt = generate_salt();
+    let key = MasterKey::derive_from_passphrase(password, &salt, algorithm)?;
+    let sealed = SealedExport {
+        format: format.to_string(),
+        salt,
+        data: key.encrypt_with_aad(bundle, &export_aad(format))?,
+    };
+    
+    Ok(format!(
+        "{} v{}\n{}\n",
+        EXPORT_HEADER,
+        EXPORT_FORMAT_VERSION,
+        STANDARD.encode(bincode::serialize(&sealed)?)
+    ))
+}
+
+/// Whether `content` is a file written by `seal_export`.
+pub fn is_sealed_export(content: &str) -> bool {
+    content.starts_with(EXPORT_HEADER)
+}
+
+/// Decrypt a file produced by `seal_export`, returning the bundle format and
+/// the plaintext bundle.
+pub fn open_export(content: &str, password: &str) -> Result<(String, Zeroizing<Vec<u8>>)> {
+    let malformed = || VaultError::Crypto("Malformed encrypted export".to_string());
+    
+    let (header, body) = content.split_once('\n').ok_or_else(malformed)?;
+    let version = header.strip_prefix(EXPORT_HEADER)
+        .and_then(|rest| rest.trim().strip_prefix('v'))
+        .and_then(|version| version.parse::<u8>().ok())
+        .ok_or_else(malformed)?;
+    if version != EXPORT_FORMAT_VERSION {
+        return Err(VaultError::Crypto(format!("Unsupported export format version {}", version)));
+    }
+    
+    let bytes = STANDARD.decode(body.trim()).map_err(|_| malformed())?;
+    let sealed: SealedExport = bincode::deserialize(&bytes).map_err(|_| malformed())?;
+    // Version 1 ciphertexts would be decrypted without the header as AAD
+    if sealed.data.version < 2 {
+        return Err(malformed());
+    }
+    
+    let key = MasterKey::derive_from_passphrase(password, &sealed.salt, sealed.data.algorithm.clone())?;
+    let bundle = key.decrypt_with_aad(&sealed.data, &export_aad(&sealed.format))
+        .map_err(|_| VaultError::Crypto("Wrong export password or corrupted file".to_string()))?;
+    
+    Ok((sealed.format, Zeroizing::new(bundle)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_export_roundtrip() {
+        let bundle = br#"{"secrets": {"db-password": {"value": "hunter2"}}}"#;
+        
+        let sealed = seal_export(bundle, "json", "export-pass", EncryptionAlgorithm::XChaCha20Poly1305).unwrap();
+        assert!(is_sealed_export(&sealed));
+        assert!(!sealed.contains("hunter2"));
+        
+        let (format, opened) = open_export(&sealed, "export-pass").unwrap();
+        assert_eq!(format, "json");
+        assert_eq!(opened.as_slice(), bundle);
+        assert!(open_export(&sealed, "wrong-pass").is_err());
+    }
+}
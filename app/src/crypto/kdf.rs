@@ -1,9 +1,14 @@
-use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::password_hash::SaltString;
+use rand::{rngs::OsRng, RngCore};
 use hkdf::Hkdf;
 use sha2::Sha256;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
 
 use crate::error::{VaultError, Result};
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct KeyDerivationParams {
     pub memory_cost: u32,
     pub time_cost: u32,
@@ -20,7 +25,59 @@ impl Default for KeyDerivationParams {
     }
 }
 
-pub fn derive_key_argon2id(passphrase: &str, salt: &[u8; 32]) -> Result<[u8; 32]> {
+/// Argon2 `(memory_cost KiB, time_cost)` pairs in increasing cost, tried in
+/// order when calibrating against a target derivation time.
+pub const CALIBRATION_LADDER: &[(u32, u32)] = &[
+    (19456, 2),   // 19 MB, OWASP minimum
+    (32768, 2),
+    (65536, 2),
+    (65536, 3),   // 64 MB, current default
+    (131072, 3),
+    (262144, 3),
+    (262144, 4),
+    (524288, 4),
+];
+
+/// Outcome of `KeyDerivationParams::calibrate`: the recommended parameters
+/// and every ladder step measured on the way.
+#[derive(Debug, Clone)]
+pub struct Calibration {
+    pub recommended: KeyDerivationParams,
+    pub samples: Vec<(KeyDerivationParams, Duration)>,
+}
+
+impl KeyDerivationParams {
+    /// Time each step of `CALIBRATION_LADDER` with `parallelism` lanes and
+    /// recommend the one closest to `target`. Stops once a step takes over
+    /// twice the target, since every later step is slower still.
+    pub fn calibrate(target: Duration, parallelism: u32) -> Result<Calibration> {
+        let mut samples = Vec::new();
+        for &(memory_cost, time_cost) in CALIBRATION_LADDER {
+            let params = Self { memory_cost, time_cost, parallelism };
+            let elapsed = measure_derivation(&params)?;
+            samples.push((params, elapsed));
+            if elapsed > target * 2 {
+                break;
+            }
+        }
+        
+        let recommended = samples.iter()
+            .min_by_key(|(_, elapsed)| elapsed.abs_diff(target))
+            .map(|(params, _)| params.clone())
+            .expect("calibration ladder is not empty");
+        Ok(Calibration { recommended, samples })
+    }
+}
+
+/// Time a single derivation with the given parameters.
+pub fn measure_derivation(params: &KeyDerivationParams) -> Result<Duration> {
+    let salt = super::generate_salt();
+    let start = Instant::now();
+    derive_key_argon2id_with_params("calibration-passphrase", &salt, params)?;
+    Ok(start.elapsed())
+}
+
+pub fn derive_key_argon2id(passphrase: &str, salt: &[u8; 32]) -> Result<Zeroizing<[u8; 32]>> {
     derive_key_argon2id_with_params(passphrase, salt, &KeyDerivationParams::default())
 }
 
@@ -28,7 +85,7 @@ pub fn derive_key_argon2id_with_params(
     passphrase: &str,
     salt: &[u8; 32],
     params: &KeyDerivationParams,
-) -> Result<[u8; 32]> {
+) -> Result<Zeroizing<[u8; 32]>> {
     let argon2 = Argon2::new(
         argon2::Algorithm::Argon2id,
         argon2::Version::V0x13,
@@ -40,27 +97,59 @@ pub fn derive_key_argon2id_with_params(
         ).map_err(|e| VaultError::Crypto(format!("Invalid Argon2 parameters: {}", e)))?,
     );
     
-    let salt_string = SaltString::encode_b64(salt)
-        .map_err(|e| VaultError::Crypto(format!("Salt encoding error: {}", e)))?;
-    
-    let hash = argon2.hash_password(passphrase.as_bytes(), &salt_string)
+    // Hash straight into a zeroizing buffer; `hash_password` would leave an
+    // extra copy of the key in its PHC output. Both produce the same bytes.
+    let mut key = Zeroizing::new([0u8; 32]);
+    argon2.hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
         .map_err(|e| VaultError::Crypto(format!("Key derivation error: {}", e)))?;
     
-    let hash_bytes = hash.hash
-        .ok_or_else(|| VaultError::Crypto("No hash output".to_string()))?;
+    Ok(key)
+}
+
+/// Argon2 associated data for passphrase verifiers, so a verifier can never
+/// equal a key derived from the same passphrase and salt.
+const VERIFIER_LABEL: &[u8] = b"rvault-passphrase-verifier";
+
+/// Argon2id PHC string that checks `passphrase` without revealing any key
+/// derived from it. It uses its own random salt and the verifier label.
+pub fn hash_passphrase_verifier(passphrase: &str, params: &KeyDerivationParams) -> Result<String> {
+    let mut salt_bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut salt_bytes);
+    let salt = SaltString::encode_b64(&salt_bytes)
+        .map_err(|e| VaultError::Crypto(format!("Invalid verifier salt: {}", e)))?;
     
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&hash_bytes.as_bytes()[..32]);
+    let argon2_params = argon2::ParamsBuilder::new()
+        .m_cost(params.memory_cost)
+        .t_cost(params.time_cost)
+        .p_cost(params.parallelism)
+        .data(argon2::AssociatedData::new(VERIFIER_LABEL)
+            .map_err(|e| VaultError::Crypto(format!("Invalid verifier label: {}", e)))?)
+        .build()
+        .map_err(|e| VaultError::Crypto(format!("Invalid Argon2 parameters: {}", e)))?;
+    let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, argon2_params);
     
-    Ok(key)
+    argon2.hash_password(passphrase.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| VaultError::Crypto(format!("Key derivation error: {}", e)))
 }
 
-#[allow(dead_code)]
-pub fn derive_key_hkdf(input_key: &[u8], salt: &[u8], info: &[u8]) -> Result<[u8; 32]> {
+/// Check `passphrase` against a verifier from `hash_passphrase_verifier`.
+/// The hash comparison is constant-time.
+pub fn verify_passphrase_verifier(passphrase: &str, verifier: &str) -> Result<bool> {
+    let hash = PasswordHash::new(verifier)
+        .map_err(|e| VaultError::Corruption(format!("Invalid passphrase verifier: {}", e)))?;
+    match Argon2::default().verify_password(passphrase.as_bytes(), &hash) {
+        Ok(()) => Ok(true),
+        Err(argon2::password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(VaultError::Crypto(format!("Passphrase verification failed: {}", e))),
+    }
+}
+
+pub fn derive_key_hkdf(input_key: &[u8], salt: &[u8], info: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
     let hk = Hkdf::<Sha256>::new(Some(salt), input_key);
-    let mut output = [0u8; 32];
+    let mut output = Zeroizing::new([0u8; 32]);
     
-    hk.expand(info, &mut output)
+    hk.expand(info, output.as_mut())
         .map_err(|e| VaultError::Crypto(format!("HKDF expansion failed: {}", e)))?;
     
     Ok(output)
@@ -94,6 +183,29 @@ mod tests {
         assert_ne!(key1, key2);
     }
     
+    #[test]
+    fn test_calibrate_stops_once_past_target() {
+        // Every step overshoots a zero target, so only the cheapest is measured
+        let calibration = KeyDerivationParams::calibrate(Duration::ZERO, 1).unwrap();
+        
+        assert_eq!(calibration.samples.len(), 1);
+        let (memory_cost, time_cost) = CALIBRATION_LADDER[0];
+        assert_eq!(calibration.recommended, KeyDerivationParams { memory_cost, time_cost, parallelism: 1 });
+    }
+    
+    #[test]
+    fn test_passphrase_verifier() {
+        let params = KeyDerivationParams { memory_cost: 8192, time_cost: 1, parallelism: 1 };
+        let verifier = hash_passphrase_verifier("test-passphrase", &params).unwrap();
+        
+        assert!(verifier.starts_with("$argon2id$"));
+        assert!(verify_passphrase_verifier("test-passphrase", &verifier).unwrap());
+        assert!(!verify_passphrase_verifier("wrong-passphrase", &verifier).unwrap());
+        
+        // Same passphrase, fresh salt: the verifier never repeats
+        assert_ne!(verifier, hash_passphrase_verifier("test-passphrase", &params).unwrap());
+    }
+    
     #[test]
     fn test_hkdf_deterministic() {
         let input_key = b"input key material";
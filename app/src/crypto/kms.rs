@@ -0,0 +1,210 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use tokio::runtime::RuntimeFlavor;
+
+use crate::error::{VaultError, Result};
+use super::{DataEncryptionKey, EncryptionAlgorithm, KeyEncryptionKey};
+
+/// Key encryption key held by an external KMS. DEKs are sent to the provider's
+/// transit encrypt/decrypt endpoints, so the wrapping key never leaves the KMS.
+/// Speaks the HashiCorp Vault transit API.
+pub struct KmsKeyEncryptionKey {
+    client: reqwest::Client,
+    endpoint: String,
+    key_name: String,
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct TransitResponse {
+    data: TransitData,
+}
+
+#[derive(Deserialize)]
+struct TransitData {
+    ciphertext: Option<String>,
+    plaintext: Option<String>,
+}
+
+impl KmsKeyEncryptionKey {
+    pub fn new(endpoint: &str, key_name: &str, token: &str) -> Self {
+        Self {
+            // Calls may run on short-lived runtimes (see `call`), so keep no
+            // pooled connections tied to one that is gone
+            client: reqwest::Client::builder()
+                .pool_max_idle_per_host(0)
+                .build()
+                .unwrap_or_default(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            key_name: key_name.to_string(),
+            token: token.to_string(),
+        }
+    }
+    
+    fn call(&self, operation: &str, body: serde_json::Value) -> Result<TransitData> {
+        let url = format!("{}/v1/transit/{}/{}", self.endpoint, operation, self.key_name);
+        let request = self.client
+            .post(&url)
+            .header("X-Vault-Token", &self.token)
+            .json(&body);
+        
+        let fetch = async move {
+            let response = request.send().await
+                .map_err(|e| VaultError::Crypto(format!("KMS request failed: {}", e)))?;
+            if !response.status().is_success() {
+                return Err(VaultError::Crypto(format!("KMS {} returned {}", operation, response.status())));
+            }
+            response.json::<TransitResponse>().await
+                .map(|r| r.data)
+                .map_err(|e| VaultError::Crypto(format!("Invalid KMS response: {}", e)))
+        };
+        
+        // KeyEncryptionKey is synchronous, so bridge onto the runtime when inside
+        // one. block_in_place panics on a current-thread runtime and block_on
+        // cannot nest inside it, so there the request runs on its own thread.
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| handle.block_on(fetch))
+            }
+            Ok(_) => std::thread::scope(|scope| {
+                scope.spawn(|| Self::block_on(fetch)).join()
+                    .unwrap_or_else(|_| Err(VaultError::Crypto("KMS request thread panicked".to_string())))
+            }),
+            Err(_) => Self::block_on(fetch),
+        }
+    }
+    
+    fn block_on<T>(fetch: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(fetch)
+    }
+}
+
+impl KeyEncryptionKey for KmsKeyEncryptionKey {
+    fn encrypt_dek(&self, dek: &DataEncryptionKey) -> Result<Vec<u8>> {
+        let data = self.call("encrypt", serde_json::json!({ "plaintext": STANDARD.encode(dek.as_bytes()) }))?;
+        let ciphertext = data.ciphertext
+            .ok_or_else(|| VaultError::Crypto("KMS response missing ciphertext".to_string()))?;
+        Ok(ciphertext.into_bytes())
+    }
+    
+    fn decrypt_dek(&self, encrypted_dek: &[u8], algorithm: EncryptionAlgorithm) -> Result<DataEncryptionKey> {
+        let ciphertext = String::from_utf8(encrypted_dek.to_vec())?;
+        let data = self.call("decrypt", serde_json::json!({ "ciphertext": ciphertext }))?;
+        let plaintext = data.plaintext
+            .ok_or_else(|| VaultError::Crypto("KMS response missing plaintext".to_string()))?;
+        let dek_bytes = STANDARD.decode(plaintext)
+            .map_err(|e| VaultError::Crypto(format!("Invalid DEK from KMS: {}", e)))?;
+        
+        if dek_bytes.len() != 32 {
+            return Err(VaultError::Crypto("Invalid DEK length".to_string()));
+        }
+        
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&dek_bytes);
+        
+        Ok(DataEncryptionKey::from_bytes(key, algorithm))
+    }
+    
+    fn key_id(&self) -> String {
+        format!("kms:{}", self.key_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    
+    /// Serve `requests` transit calls on a local port, checking the path and
+    /// token. "Encryption" just tags the plaintext, which is enough to see the
+    /// DEK make the round trip.
+    fn mock_transit(requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut content_length = 0;
+                let mut token = String::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    let (name, value) = header.split_once(": ").unwrap();
+                    match name.to_ascii_lowercase().as_str() {
+                        "content-length" => content_length = value.parse().unwrap(),
+                        "x-vault-token" => token = value.to_string(),
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                
+                let (status, data) = match request_line.split_whitespace().nth(1).unwrap() {
+                    _ if token != "test-token" => ("403 Forbidden", serde_json::json!({})),
+                    "/v1/transit/encrypt/vault-key" => ("200 OK", serde_json::json!({
+                        "ciphertext": format!("vault:v1:{}", body["plaintext"].as_str().unwrap()),
+                    })),
+                    "/v1/transit/decrypt/vault-key" => ("200 OK", serde_json::json!({
+                        "plaintext": body["ciphertext"].as_str().unwrap().trim_start_matches("vault:v1:"),
+                    })),
+                    _ => ("404 Not Found", serde_json::json!({})),
+                };
+                let response = serde_json::json!({ "data": data }).to_string();
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, response.len(), response
+                ).unwrap();
+            }
+        });
+        endpoint
+    }
+    
+    fn round_trip(kek: &KmsKeyEncryptionKey) {
+        let dek = DataEncryptionKey::generate(EncryptionAlgorithm::Aes256Gcm);
+        let wrapped = kek.encrypt_dek(&dek).unwrap();
+        assert!(wrapped.starts_with(b"vault:v1:"));
+        let unwrapped = kek.decrypt_dek(&wrapped, EncryptionAlgorithm::Aes256Gcm).unwrap();
+        assert_eq!(unwrapped.as_bytes(), dek.as_bytes());
+    }
+    
+    #[test]
+    fn test_kms_wraps_outside_a_runtime() {
+        let kek = KmsKeyEncryptionKey::new(&mock_transit(2), "vault-key", "test-token");
+        round_trip(&kek);
+        assert_eq!(kek.key_id(), "kms:vault-key");
+    }
+    
+    #[tokio::test]
+    async fn test_kms_wraps_on_a_current_thread_runtime() {
+        round_trip(&KmsKeyEncryptionKey::new(&mock_transit(2), "vault-key", "test-token"));
+    }
+    
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_kms_wraps_on_a_multi_thread_runtime() {
+        round_trip(&KmsKeyEncryptionKey::new(&mock_transit(2), "vault-key", "test-token"));
+    }
+    
+    #[test]
+    fn test_kms_errors_are_reported() {
+        let endpoint = mock_transit(1);
+        let kek = KmsKeyEncryptionKey::new(&endpoint, "vault-key", "wrong-token");
+        let dek = DataEncryptionKey::generate(EncryptionAlgorithm::Aes256Gcm);
+        match kek.encrypt_dek(&dek) {
+            Err(VaultError::Crypto(message)) => assert!(message.contains("403")),
+            other => panic!("expected a KMS error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
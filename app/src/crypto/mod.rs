@@ -2,23 +2,50 @@ use serde::{Deserialize, Serialize};
 
 use secrecy::{Secret, ExposeSecret};
 use rand::{rngs::OsRng, RngCore};
+use zeroize::{Zeroizing, ZeroizeOnDrop};
 
-use crate::error::Result;
+use crate::error::{VaultError, Result};
 
 mod aes;
 mod chacha;
 mod kdf;
 mod envelope;
+mod export;
+mod share;
+#[cfg(feature = "kms")]
+mod kms;
 
 pub use aes::*;
 pub use chacha::*;
 pub use kdf::*;
+pub use envelope::*;
+pub use export::*;
+pub use share::*;
+#[cfg(feature = "kms")]
+pub use kms::*;
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Newest `EncryptedData::version` this build can read: 1 has no associated
+/// data, 2 binds associated data.
+pub const MAX_CIPHERTEXT_VERSION: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EncryptionAlgorithm {
     Aes256Gcm,
     ChaCha20Poly1305,
+    XChaCha20Poly1305,
+}
+
+impl EncryptionAlgorithm {
+    /// Parse the names used in config and on the command line.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "aes256gcm" => Some(Self::Aes256Gcm),
+            "chacha20poly1305" => Some(Self::ChaCha20Poly1305),
+            "xchacha20poly1305" => Some(Self::XChaCha20Poly1305),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,14 +62,39 @@ pub struct MasterKey {
     pub(crate) algorithm: EncryptionAlgorithm,
 }
 
+// `Secret` wipes the key bytes when the `MasterKey` is dropped
+impl ZeroizeOnDrop for MasterKey {}
+
 impl MasterKey {
+    const NAMESPACE_KEY_SALT: &'static [u8] = b"rvault-namespace-key";
+    
+    /// Subkey for one namespace, derived with HKDF using the namespace name
+    /// as `info`, so each namespace's secrets are encrypted under a distinct key.
+    pub fn derive_namespace_key(&self, namespace: &str) -> Result<Self> {
+        let key_bytes = derive_key_hkdf(self.key.expose_secret(), Self::NAMESPACE_KEY_SALT, namespace.as_bytes())?;
+        
+        Ok(Self {
+            key: Secret::new(*key_bytes),
+            algorithm: self.algorithm.clone(),
+        })
+    }
+    
     pub fn derive_from_passphrase(
         passphrase: &str,
         salt: &[u8; 32],
         algorithm: EncryptionAlgorithm,
     ) -> Result<Self> {
-        let key_bytes = derive_key_argon2id(passphrase, salt)?;
-        let key = Secret::new(key_bytes);
+        Self::derive_from_passphrase_with_params(passphrase, salt, algorithm, &KeyDerivationParams::default())
+    }
+    
+    pub fn derive_from_passphrase_with_params(
+        passphrase: &str,
+        salt: &[u8; 32],
+        algorithm: EncryptionAlgorithm,
+        params: &KeyDerivationParams,
+    ) -> Result<Self> {
+        let key_bytes = derive_key_argon2id_with_params(passphrase, salt, params)?;
+        let key = Secret::new(*key_bytes);
         
         Ok(Self {
             key,
@@ -50,11 +102,10 @@ impl MasterKey {
         })
     }
     
-    #[allow(dead_code)]
     pub fn generate(algorithm: EncryptionAlgorithm) -> Self {
-        let mut key_bytes = [0u8; 32];
-        OsRng.fill_bytes(&mut key_bytes);
-        let key = Secret::new(key_bytes);
+        let mut key_bytes = Zeroizing::new([0u8; 32]);
+        OsRng.fill_bytes(key_bytes.as_mut());
+        let key = Secret::new(*key_bytes);
         
         Self {
             key,
@@ -63,44 +114,90 @@ impl MasterKey {
     }
     
     pub fn encrypt(&self, plaintext: &[u8]) -> Result<EncryptedData> {
+        self.encrypt_with_aad(plaintext, &[])
+    }
+    
+    /// Encrypt `plaintext` with `aad` as associated data, so the result only
+    /// decrypts for the same `aad`. Blobs carrying AAD are marked version 2.
+    pub fn encrypt_with_aad(&self, plaintext: &[u8], aad: &[u8]) -> Result<EncryptedData> {
+        let version = if aad.is_empty() { 1 } else { 2 };
         let mut salt = [0u8; 32];
         OsRng.fill_bytes(&mut salt);
         
         match self.algorithm {
             EncryptionAlgorithm::Aes256Gcm => {
-                let (ciphertext, nonce) = encrypt_aes256gcm(self.key.expose_secret(), plaintext)?;
+                let (ciphertext, nonce) = encrypt_aes256gcm(self.key.expose_secret(), plaintext, aad)?;
                 Ok(EncryptedData {
                     algorithm: EncryptionAlgorithm::Aes256Gcm,
                     ciphertext,
                     nonce,
                     salt,
-                    version: 1,
+                    version,
                 })
             }
             EncryptionAlgorithm::ChaCha20Poly1305 => {
-                let (ciphertext, nonce) = encrypt_chacha20poly1305(self.key.expose_secret(), plaintext)?;
+                let (ciphertext, nonce) = encrypt_chacha20poly1305(self.key.expose_secret(), plaintext, aad)?;
                 Ok(EncryptedData {
                     algorithm: EncryptionAlgorithm::ChaCha20Poly1305,
                     ciphertext,
                     nonce,
                     salt,
-                    version: 1,
+                    version,
+                })
+            }
+            EncryptionAlgorithm::XChaCha20Poly1305 => {
+                let (ciphertext, nonce) = encrypt_xchacha20poly1305(self.key.expose_secret(), plaintext, aad)?;
+                Ok(EncryptedData {
+                    algorithm: EncryptionAlgorithm::XChaCha20Poly1305,
+                    ciphertext,
+                    nonce,
+                    salt,
+                    version,
                 })
             }
         }
     }
     
     pub fn decrypt(&self, encrypted: &EncryptedData) -> Result<Vec<u8>> {
+        self.decrypt_with_aad(encrypted, &[])
+    }
+    
+    /// Decrypt a blob from `encrypt_with_aad`. Version 1 blobs predate
+    /// associated data and are decrypted without it. The blob must name this
+    /// key's algorithm; use `for_algorithm` where reading another is intended.
+    pub fn decrypt_with_aad(&self, encrypted: &EncryptedData, aad: &[u8]) -> Result<Vec<u8>> {
+        if encrypted.version > MAX_CIPHERTEXT_VERSION {
+            return Err(VaultError::UnsupportedCiphertextVersion(encrypted.version));
+        }
+        if encrypted.algorithm != self.algorithm {
+            return Err(VaultError::AlgorithmMismatch {
+                expected: self.algorithm.clone(),
+                found: encrypted.algorithm.clone(),
+            });
+        }
+        let aad = if encrypted.version >= 2 { aad } else { &[] };
         match encrypted.algorithm {
             EncryptionAlgorithm::Aes256Gcm => {
-                decrypt_aes256gcm(self.key.expose_secret(), &encrypted.ciphertext, &encrypted.nonce)
+                decrypt_aes256gcm(self.key.expose_secret(), &encrypted.ciphertext, &encrypted.nonce, aad)
             }
             EncryptionAlgorithm::ChaCha20Poly1305 => {
-                decrypt_chacha20poly1305(self.key.expose_secret(), &encrypted.ciphertext, &encrypted.nonce)
+                decrypt_chacha20poly1305(self.key.expose_secret(), &encrypted.ciphertext, &encrypted.nonce, aad)
+            }
+            EncryptionAlgorithm::XChaCha20Poly1305 => {
+                decrypt_xchacha20poly1305(self.key.expose_secret(), &encrypted.ciphertext, &encrypted.nonce, aad)
             }
         }
     }
     
+    /// The same key bytes used with another cipher, for migration paths that
+    /// read blobs written before a `vault reencrypt`.
+    pub fn for_algorithm(&self, algorithm: EncryptionAlgorithm) -> Self {
+        Self {
+            key: Secret::new(*self.key.expose_secret()),
+            algorithm,
+        }
+    }
+    
     #[allow(dead_code)]
     pub fn algorithm(&self) -> &EncryptionAlgorithm {
         &self.algorithm
@@ -124,6 +221,19 @@ pub fn generate_nonce(size: usize) -> Vec<u8> {
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_key_types_zeroize_on_drop() {
+        // Wiping cannot be observed after drop; check the guarantee is in place
+        fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        assert_zeroize_on_drop::<MasterKey>();
+        assert_zeroize_on_drop::<DataEncryptionKey>();
+        assert_zeroize_on_drop::<Zeroizing<[u8; 32]>>();
+        
+        let derived = derive_key_argon2id("passphrase", &[1u8; 32]).unwrap();
+        let master_key = MasterKey::derive_from_passphrase("passphrase", &[1u8; 32], EncryptionAlgorithm::Aes256Gcm).unwrap();
+        assert_eq!(*derived, *master_key.key.expose_secret());
+    }
+    
     #[test]
     fn test_aes256gcm_encryption() {
         let salt = generate_salt();
@@ -140,6 +250,43 @@ mod tests {
         assert_eq!(plaintext, decrypted.as_slice());
     }
     
+    #[test]
+    fn test_associated_data_must_match() {
+        let master_key = MasterKey::generate(EncryptionAlgorithm::XChaCha20Poly1305);
+        
+        let encrypted = master_key.encrypt_with_aad(b"secret data", b"t:prod:db-password").unwrap();
+        assert_eq!(encrypted.version, 2);
+        assert_eq!(master_key.decrypt_with_aad(&encrypted, b"t:prod:db-password").unwrap(), b"secret data");
+        assert!(master_key.decrypt_with_aad(&encrypted, b"t:dev:api-key").is_err());
+        assert!(master_key.decrypt(&encrypted).is_err());
+        
+        // Blobs written before associated data ignore it
+        let legacy = master_key.encrypt(b"secret data").unwrap();
+        assert_eq!(legacy.version, 1);
+        assert_eq!(master_key.decrypt_with_aad(&legacy, b"t:dev:api-key").unwrap(), b"secret data");
+    }
+    
+    #[test]
+    fn test_decrypt_rejects_algorithm_and_version_mismatch() {
+        let master_key = MasterKey::generate(EncryptionAlgorithm::Aes256Gcm);
+        let mut encrypted = master_key.encrypt(b"secret data").unwrap();
+        
+        encrypted.algorithm = EncryptionAlgorithm::ChaCha20Poly1305;
+        assert!(matches!(
+            master_key.decrypt(&encrypted),
+            Err(VaultError::AlgorithmMismatch { expected: EncryptionAlgorithm::Aes256Gcm, found: EncryptionAlgorithm::ChaCha20Poly1305 })
+        ));
+        
+        encrypted.algorithm = EncryptionAlgorithm::Aes256Gcm;
+        encrypted.version = MAX_CIPHERTEXT_VERSION + 1;
+        assert!(matches!(master_key.decrypt(&encrypted), Err(VaultError::UnsupportedCiphertextVersion(3))));
+        
+        let chacha_key = master_key.for_algorithm(EncryptionAlgorithm::ChaCha20Poly1305);
+        let encrypted = chacha_key.encrypt(b"secret data").unwrap();
+        assert!(master_key.decrypt(&encrypted).is_err());
+        assert_eq!(master_key.for_algorithm(encrypted.algorithm.clone()).decrypt(&encrypted).unwrap(), b"secret data");
+    }
+    
     #[test]
     fn test_chacha20poly1305_encryption() {
         let salt = generate_salt();
@@ -0,0 +1,114 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{VaultError, Result};
+use super::{EncryptedData, EncryptionAlgorithm, MasterKey, generate_salt};
+
+/// Version 1 blobs were a hand-packed layout whose header was not
+/// authenticated; they are no longer accepted.
+const SHARE_FORMAT_VERSION: u8 = 2;
+
+/// A single secret as carried inside a share blob. The key name travels
+/// encrypted alongside the value so the recipient can store it under the
+/// same name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedSecret {
+    pub key: String,
+    pub value: Vec<u8>,
+    pub binary: bool,
+}
+
+/// A share blob before base64.
+#[derive(Serialize, Deserialize)]
+struct SealedShare {
+    version: u8,
+    salt: [u8; 32], // Argon2id salt the key was derived with
+    data: EncryptedData,
+}
+
+/// Associated data binding the format version to the ciphertext.
+fn share_aad(version: u8) -> Vec<u8> {
+    format!("RVAULT-SHARE v{}", version).into_bytes()
+}
+
+/// Encrypt a secret under a shared password and encode it as URL-safe base64
+/// of the bincoded `SealedShare`. The key is derived with Argon2id from the
+/// password and a fresh salt carried in the blob.
+pub fn seal_shared_secret(secret: &SharedSecret, password: &str, algorithm: EncryptionAlgorithm) -> Result<String> {
+    let salt = generate_salt();
+    let key = MasterKey::derive_from_passphrase(password, &salt, algorithm)?;
+    let sealed = SealedShare {
+        version: SHARE_FORMAT_VERSION,
+        salt,
+        data: key.encrypt_with_aad(&bincode::serialize(secret)?, &share_aad(SHARE_FORMAT_VERSION))?,
+    };
+    
+    Ok(URL_SAFE_NO_PAD.encode(bincode::serialize(&sealed)?))
+}
+
+/// Decode and decrypt a blob produced by `seal_shared_secret`.
+pub fn open_shared_secret(blob: &str, password: &str) -> Result<SharedSecret> {
+    let malformed = || VaultError::Crypto("Malformed share blob".to_string());
+    
+    let bytes = URL_SAFE_NO_PAD.decode(blob.trim())
+        .map_err(|_| malformed())?;
+    match bytes.first() {
+        Some(&SHARE_FORMAT_VERSION) => {}
+        Some(version) => return Err(VaultError::Crypto(format!("Unsupported share format version {}", version))),
+        None => return Err(malformed()),
+    }
+    let sealed: SealedShare = bincode::deserialize(&bytes).map_err(|_| malformed())?;
+    // Version 1 ciphertexts would be decrypted without the header as AAD
+    if sealed.data.version < 2 {
+        return Err(malformed());
+    }
+    
+    let key = MasterKey::derive_from_passphrase(password, &sealed.salt, sealed.data.algorithm.clone())?;
+    let payload = key.decrypt_with_aad(&sealed.data, &share_aad(sealed.version))
+        .map_err(|_| VaultError::Crypto("Wrong share password or corrupted blob".to_string()))?;
+    
+    Ok(bincode::deserialize(&payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_share_roundtrip() {
+        let secret = SharedSecret {
+            key: "db-password".to_string(),
+            value: b"hunter2".to_vec(),
+            binary: false,
+        };
+        
+        let blob = seal_shared_secret(&secret, "shared-pass", EncryptionAlgorithm::ChaCha20Poly1305).unwrap();
+        assert_eq!(open_shared_secret(&blob, "shared-pass").unwrap(), secret);
+        assert!(open_shared_secret(&blob, "wrong-pass").is_err());
+    }
+    
+    #[test]
+    fn test_share_header_is_authenticated() {
+        let secret = SharedSecret {
+            key: "db-password".to_string(),
+            value: b"hunter2".to_vec(),
+            binary: false,
+        };
+        let blob = seal_shared_secret(&secret, "shared-pass", EncryptionAlgorithm::Aes256Gcm).unwrap();
+        let sealed: SealedShare = bincode::deserialize(&URL_SAFE_NO_PAD.decode(&blob).unwrap()).unwrap();
+        assert_eq!(sealed.version, SHARE_FORMAT_VERSION);
+        
+        // The payload only opens with the header as associated data
+        let key = MasterKey::derive_from_passphrase("shared-pass", &sealed.salt, sealed.data.algorithm.clone()).unwrap();
+        assert!(key.decrypt_with_aad(&sealed.data, b"").is_err());
+        assert!(key.decrypt_with_aad(&sealed.data, &share_aad(SHARE_FORMAT_VERSION)).is_ok());
+        
+        // Blobs from the unauthenticated version 1 layout are refused
+        let mut legacy = URL_SAFE_NO_PAD.decode(&blob).unwrap();
+        legacy[0] = 1;
+        assert!(matches!(
+            open_shared_secret(&URL_SAFE_NO_PAD.encode(legacy), "shared-pass"),
+            Err(VaultError::Crypto(message)) if message.contains("version 1")
+        ));
+    }
+}
@@ -40,14 +40,58 @@ pub enum VaultError {
     #[error("Secret not found: {0}")]
     SecretNotFound(String),
     
+    #[error("Secret already exists: {0}")]
+    SecretExists(String),
+    
+    #[error("Invalid operation: {0}")]
+    InvalidOperation(String),
+    
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+    
+    #[error("Invalid alias: {0}")]
+    InvalidAlias(String),
+    
+    #[error("Invalid search pattern: {0}")]
+    InvalidPattern(String),
+    
+    #[error("Secret {0} holds binary data")]
+    BinarySecret(String),
+    
     #[error("Vault is locked. Please login first")]
     VaultLocked,
     
     #[error("Invalid passphrase")]
     InvalidPassphrase,
     
+    #[error("Invalid access password")]
+    InvalidAccessPassword,
+    
+    #[error("Key does not unlock this tenant")]
+    InvalidKey,
+    
+    #[error("Vault is opened read-only")]
+    ReadOnly,
+    
+    #[error("Vault is busy: {0}")]
+    Locked(String),
+    
+    #[error("Secret is {0} bytes, over the {1} byte limit (security.max_secret_bytes)")]
+    TooLarge(usize, usize),
+    
+    #[error("Ciphertext is {found:?}, but the key is for {expected:?}")]
+    AlgorithmMismatch {
+        expected: crate::crypto::EncryptionAlgorithm,
+        found: crate::crypto::EncryptionAlgorithm,
+    },
+    
+    #[error("Unsupported ciphertext version {0}; it was written by a newer rvault")]
+    UnsupportedCiphertextVersion(u8),
+    
+    #[error("Corrupted vault data: {0}")]
+    Corruption(String),
+    
     #[error("Permission denied: {0}")]
-    #[allow(dead_code)]
     PermissionDenied(String),
 }
 
@@ -14,18 +14,32 @@ pub struct AuditEntry {
     pub user_agent: Option<String>,
     pub resource_type: Option<String>,
     pub resource_id: Option<String>,
+    #[serde(with = "metadata_format")]
     pub metadata: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AuditQuery {
-    pub tenant_id: Option<String>,
-    pub event_type: Option<String>,
-    pub user_id: Option<String>,
-    pub resource_type: Option<String>,
-    pub start_time: Option<DateTime<Utc>>,
-    pub end_time: Option<DateTime<Utc>>,
-    pub limit: Option<usize>,
+/// bincode cannot decode a `serde_json::Value`, so binary formats carry the
+/// metadata as a JSON string while JSON exports keep it as an object.
+mod metadata_format {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<serde_json::Value>, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            value.serialize(serializer)
+        } else {
+            value.as_ref().map(|v| v.to_string()).serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<serde_json::Value>, D::Error> {
+        if deserializer.is_human_readable() {
+            Option::<serde_json::Value>::deserialize(deserializer)
+        } else {
+            Option::<String>::deserialize(deserializer)?
+                .map(|json| serde_json::from_str(&json).map_err(D::Error::custom))
+                .transpose()
+        }
+    }
 }
 
 impl AuditEntry {
@@ -61,10 +75,36 @@ impl AuditEntry {
         self
     }
 
-    pub fn with_context(mut self, ip_address: Option<String>, user_agent: Option<String>) -> Self {
-        self.ip_address = ip_address;
-        self.user_agent = user_agent;
-        self
+    pub const CSV_HEADER: &'static str = "id,timestamp,tenant_id,user_id,event_type,description,resource_type,resource_id,ip_address,user_agent,metadata";
+
+    /// One CSV row matching `CSV_HEADER`; metadata is embedded as JSON.
+    pub fn to_csv_row(&self) -> String {
+        let metadata = self.metadata.as_ref().map(|m| m.to_string());
+        [
+            self.id.to_string(),
+            self.timestamp.to_rfc3339(),
+            self.tenant_id.clone(),
+            self.user_id.clone(),
+            self.event_type.clone(),
+            self.description.clone(),
+            self.resource_type.clone().unwrap_or_default(),
+            self.resource_id.clone().unwrap_or_default(),
+            self.ip_address.clone().unwrap_or_default(),
+            self.user_agent.clone().unwrap_or_default(),
+            metadata.unwrap_or_default(),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
     }
 }
 
@@ -76,7 +116,6 @@ impl AuditLogger {
     pub const EVENT_SECRET_CREATED: &'static str = "secret_created";
     pub const EVENT_SECRET_ACCESSED: &'static str = "secret_accessed";
     pub const EVENT_SECRET_UPDATED: &'static str = "secret_updated";
-    #[allow(dead_code)]
     pub const EVENT_SECRET_DELETED: &'static str = "secret_deleted";
     pub const EVENT_TENANT_CREATED: &'static str = "tenant_created";
     pub const EVENT_USER_ADDED: &'static str = "user_added";
@@ -101,9 +140,28 @@ impl AuditLogger {
         )
     }
 
-    pub fn log_event(_entry: &AuditEntry) -> crate::error::Result<()> {
-        // In a real implementation, write to audit log storage
-        // println!("[AUDIT] {} - {} - {}", entry.timestamp.format("%Y-%m-%d %H:%M:%S"), entry.event_type, entry.description);
-        Ok(())
+    /// Persist `entry` in the vault's audit log.
+    pub fn log_event(storage: &super::VaultStorage, entry: &AuditEntry) -> crate::error::Result<()> {
+        storage.record_audit_entry(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_row_escapes_fields() {
+        let entry = AuditEntry::new(
+            "t".to_string(),
+            AuditLogger::EVENT_EXPORT.to_string(),
+            "Exported \"prod\", 3 secrets".to_string(),
+            "admin@example.com".to_string(),
+        )
+        .with_metadata(serde_json::json!({ "count": 3 }));
+
+        let row = entry.to_csv_row();
+        assert!(row.contains(",\"Exported \"\"prod\"\", 3 secrets\","));
+        assert!(row.ends_with(",\"{\"\"count\"\":3}\""));
     }
 }
@@ -3,21 +3,39 @@ use sled::Db;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use std::path::Path;
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::{
-    crypto::{MasterKey, EncryptedData, generate_salt},
+    crypto::{MasterKey, EncryptedData, DataEncryptionKey, KeyEncryptionKey, WrappedDek, LEGACY_KMS_KEY_ID, generate_salt},
     error::{VaultError, Result},
 };
 
 
 
+const KEY_CANARY: &[u8] = b"rvault-key-canary";
+
+/// Largest ciphertext stored inline in a secret record; bigger values are chunked.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Default for `security.max_secret_bytes`.
+pub const DEFAULT_MAX_SECRET_BYTES: usize = 1024 * 1024;
+
+/// Layout of the `secret:`, `secret_versions:`, `trash:` and `user:`
+/// records, kept under `FORMAT_VERSION_KEY`. Version 1 is the original
+/// `LegacySecret`; 2 added aliases, envelope DEKs, binary values, namespace
+/// keys, chunking, expiry and `updated_by`; 3 added the user's
+/// `password_salt`; 4 let history versions be chunked.
+const RECORD_FORMAT_VERSION: u32 = 4;
+const FORMAT_VERSION_KEY: &str = "format_version";
+
 mod tenant;
 mod secret;
 mod audit;
 mod user;
 
 pub use tenant::*;
-pub use secret::SecretGenerator;
+pub use secret::{SecretGenerator, SecretVersion, SshKeyType};
+use secret::SecretPolicy;
 pub use audit::*;
 pub use user::*;
 
@@ -31,13 +49,104 @@ pub struct SecretMetadata {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_by: String,
+    pub updated_by: String, // Author of the current version; `created_by` survives overwrites
     pub tags: Vec<String>,
+    pub alias_of: Option<String>, // "namespace/key" of the target when this secret is an alias
+    pub binary: bool, // Value is raw bytes rather than UTF-8 text
+    pub expires_at: Option<DateTime<Utc>>, // Treated as deleted from this point on
+}
+
+impl SecretMetadata {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= Utc::now())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Secret {
     pub metadata: SecretMetadata,
     pub encrypted_value: EncryptedData,
+    pub encrypted_dek: Option<Vec<u8>>, // Set in envelope mode: the per-secret DEK as a `WrappedDek`
+    pub namespace_key: bool, // Encrypted under the namespace subkey instead of the master key
+    pub chunks: u32, // Ciphertext lives in this many `secret_chunk:` records (0 = stored inline)
+}
+
+/// `SecretMetadata` as written by format version 1.
+#[derive(Deserialize)]
+struct LegacySecretMetadata {
+    id: Uuid,
+    tenant_id: String,
+    namespace: String,
+    key: String,
+    version: u64,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    created_by: String,
+    tags: Vec<String>,
+}
+
+/// `Secret` as written by format version 1.
+#[derive(Deserialize)]
+struct LegacySecret {
+    metadata: LegacySecretMetadata,
+    encrypted_value: EncryptedData,
+}
+
+impl From<LegacySecret> for Secret {
+    fn from(legacy: LegacySecret) -> Self {
+        let metadata = legacy.metadata;
+        Secret {
+            metadata: SecretMetadata {
+                id: metadata.id,
+                tenant_id: metadata.tenant_id,
+                namespace: metadata.namespace,
+                key: metadata.key,
+                version: metadata.version,
+                created_at: metadata.created_at,
+                updated_at: metadata.updated_at,
+                updated_by: metadata.created_by.clone(),
+                created_by: metadata.created_by,
+                tags: metadata.tags,
+                alias_of: None,
+                binary: false,
+                expires_at: None,
+            },
+            encrypted_value: legacy.encrypted_value,
+            encrypted_dek: None,
+            namespace_key: false,
+            chunks: 0,
+        }
+    }
+}
+
+/// A deleted secret kept under `trash:{tenant}:{ns}:{key}` until it is
+/// restored or the trash is emptied.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashedSecret {
+    pub secret: Secret, // Ciphertext held inline (`chunks` = 0)
+    pub history: Vec<SecretVersion>,
+    pub access_password: Option<Vec<u8>>, // Hash from the secret's `secret_pwd:` record
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// `TrashedSecret` as written before record format 4.
+#[derive(Deserialize)]
+struct LegacyTrashedSecret {
+    secret: Secret,
+    history: Vec<secret::LegacySecretVersion>,
+    access_password: Option<Vec<u8>>,
+    deleted_at: DateTime<Utc>,
+}
+
+impl From<LegacyTrashedSecret> for TrashedSecret {
+    fn from(legacy: LegacyTrashedSecret) -> Self {
+        TrashedSecret {
+            secret: legacy.secret,
+            history: legacy.history.into_iter().map(SecretVersion::from).collect(),
+            access_password: legacy.access_password,
+            deleted_at: legacy.deleted_at,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,13 +154,94 @@ pub struct VaultStats {
     pub secret_count: usize,
     pub namespace_count: usize,
     pub tenant_count: usize,
-    pub total_size: u64,
+    pub total_size: u64, // Bytes of secret records, with their history, chunks, passwords and trash
+    pub audit_size: u64,
+    pub session_size: u64,
+    pub internal_size: u64, // Tenants, users, sync state and other bookkeeping
+    pub corrupted_records: Vec<String>, // Storage keys of secret records that failed to deserialize
+}
+
+/// A secret recovered by `VaultStorage::salvage`.
+pub struct SalvagedSecret {
+    pub metadata: SecretMetadata,
+    pub value: Vec<u8>,
+}
+
+/// Storage timings reported by `vault doctor --benchmark`.
+pub struct StorageBenchmark {
+    pub secrets_read: usize,
+    pub read_time: std::time::Duration, // Load and decrypt all `secrets_read` secrets
+    pub flush_time: std::time::Duration,
+}
+
+/// Field `list_with_metadata` orders its results by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortBy {
+    #[default]
+    Key,
+    CreatedAt,
+    UpdatedAt,
+    Version,
+}
+
+/// Ordering and paging for `list_with_metadata`. `offset` and `limit` apply
+/// after sorting.
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    pub sort_by: SortBy,
+    pub descending: bool,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+/// What `search` matches against and how.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    pub values: bool, // Also decrypt and match secret values
+    pub regex: bool, // Treat the query as a regular expression instead of a substring
+}
+
+/// A compiled search query.
+enum SearchPattern {
+    Substring(String), // Lowercased; matched case-insensitively
+    Regex(regex::Regex),
+}
+
+impl SearchPattern {
+    fn new(query: &str, regex: bool) -> Result<Self> {
+        if regex {
+            regex::Regex::new(query)
+                .map(SearchPattern::Regex)
+                .map_err(|e| VaultError::InvalidPattern(e.to_string()))
+        } else {
+            Ok(SearchPattern::Substring(query.to_lowercase()))
+        }
+    }
+    
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            SearchPattern::Substring(query) => zeroize::Zeroizing::new(text.to_lowercase()).contains(query.as_str()),
+            SearchPattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SalvageReport {
+    pub recovered: Vec<SalvagedSecret>,
+    pub skipped: Vec<(String, String)>, // (storage key, reason)
 }
 
 pub struct VaultStorage {
     db: Db,
     master_key: Option<MasterKey>,
     current_tenant: Option<String>,
+    current_user: Option<String>, // From the session; recorded as the author of writes
+    read_only: bool,
+    envelope_encryption: bool,
+    namespace_keys: bool,
+    max_secret_bytes: usize,
+    key_provider: Option<Box<dyn KeyEncryptionKey + Send + Sync>>,
 }
 
 impl VaultStorage {
@@ -61,20 +251,379 @@ impl VaultStorage {
             std::fs::create_dir_all(parent)?;
         }
         
-        let db = sled::open(path)?;
+        let db = open_db(path)?;
         
         let mut storage = Self {
             db,
             master_key: None,
             current_tenant: None,
+            current_user: None,
+            read_only: false,
+            envelope_encryption: false,
+            namespace_keys: false,
+            max_secret_bytes: DEFAULT_MAX_SECRET_BYTES,
+            key_provider: None,
         };
         
+        storage.purge_plaintext_session_keys()?;
+        storage.migrate_legacy_records()?;
+        storage.migrate_unescaped_keys()?;
+        
         // Try to auto-unlock from session
         storage.try_auto_unlock();
         
         Ok(storage)
     }
     
+    /// Earlier versions cached master keys under `session_key:` in plaintext.
+    /// Drop them; affected users simply log in again.
+    fn purge_plaintext_session_keys(&self) -> Result<()> {
+        let mut batch = sled::Batch::default();
+        let mut purged = 0;
+        for item in self.db.scan_prefix("session_key:") {
+            let (key, _) = item?;
+            batch.remove(key);
+            purged += 1;
+        }
+        if purged > 0 {
+            self.db.apply_batch(batch)?;
+            self.db.flush()?;
+        }
+        Ok(())
+    }
+    
+    /// Rewrite `secret:`, `secret_versions:`, `trash:` and `user:` records
+    /// written before `RECORD_FORMAT_VERSION` in the current layout. Both
+    /// layouts are decoded strictly, so a record is only converted when it
+    /// is exactly a legacy record.
+    fn migrate_legacy_records(&self) -> Result<()> {
+        let stored_version = match self.db.get(FORMAT_VERSION_KEY)? {
+            Some(bytes) => u32::from_be_bytes(bytes.as_ref().try_into()
+                .map_err(|_| VaultError::Corruption(format!("{} is malformed", FORMAT_VERSION_KEY)))?),
+            None => 1,
+        };
+        if stored_version > RECORD_FORMAT_VERSION {
+            return Err(VaultError::Corruption(format!(
+                "vault uses record format {}, newer than this build supports ({}); upgrade rvault",
+                stored_version, RECORD_FORMAT_VERSION
+            )));
+        }
+        if stored_version == RECORD_FORMAT_VERSION {
+            return Ok(());
+        }
+        
+        use bincode::Options;
+        let strict = || bincode::options().with_fixint_encoding().reject_trailing_bytes();
+        
+        let mut batch = sled::Batch::default();
+        for item in self.db.scan_prefix("secret:") {
+            let (key, data) = item?;
+            if strict().deserialize::<Secret>(&data).is_ok() {
+                continue;
+            }
+            // Anything that is neither layout is left for doctor --salvage
+            if let Ok(legacy) = strict().deserialize::<LegacySecret>(&data) {
+                batch.insert(key, bincode::serialize(&Secret::from(legacy))?);
+            }
+        }
+        for item in self.db.scan_prefix("secret_versions:") {
+            let (key, data) = item?;
+            if strict().deserialize::<Vec<SecretVersion>>(&data).is_ok() {
+                continue;
+            }
+            if let Ok(legacy) = strict().deserialize::<Vec<secret::LegacySecretVersion>>(&data) {
+                let history: Vec<SecretVersion> = legacy.into_iter().map(SecretVersion::from).collect();
+                batch.insert(key, bincode::serialize(&history)?);
+            }
+        }
+        for item in self.db.scan_prefix("trash:") {
+            let (key, data) = item?;
+            if strict().deserialize::<TrashedSecret>(&data).is_ok() {
+                continue;
+            }
+            if let Ok(legacy) = strict().deserialize::<LegacyTrashedSecret>(&data) {
+                batch.insert(key, bincode::serialize(&TrashedSecret::from(legacy))?);
+            }
+        }
+        for item in self.db.scan_prefix("user:") {
+            let (key, data) = item?;
+            if strict().deserialize::<User>(&data).is_ok() {
+                continue;
+            }
+            if let Ok(legacy) = strict().deserialize::<user::LegacyUser>(&data) {
+                batch.insert(key, bincode::serialize(&User::from(legacy))?);
+            }
+        }
+        batch.insert(FORMAT_VERSION_KEY, &RECORD_FORMAT_VERSION.to_be_bytes());
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    /// Earlier versions joined tenant, namespace and key with `:` unescaped,
+    /// so a `:` or `%` in any of them left the record under a key that no
+    /// longer matches `secret_key`. Move such records, with their password,
+    /// history and chunks, trashed ones, users and invitations to the escaped
+    /// key. Their values are re-encrypted by `migrate_unescaped_aad` once the
+    /// tenant is unlocked, since that needs its key.
+    fn migrate_unescaped_keys(&self) -> Result<()> {
+        fn needs_check(storage_key: &str, prefix: &str) -> bool {
+            storage_key.contains('%') || storage_key[prefix.len()..].matches(':').count() != 2
+        }
+        
+        let mut batch = sled::Batch::default();
+        let mut moved = 0;
+        for item in self.db.scan_prefix("secret:") {
+            let (key, data) = item?;
+            let old_key = String::from_utf8_lossy(&key).into_owned();
+            if !needs_check(&old_key, "secret:") {
+                continue;
+            }
+            let Ok(secret) = bincode::deserialize::<Secret>(&data) else {
+                continue;
+            };
+            let meta = &secret.metadata;
+            let new_key = secret_key(&meta.tenant_id, &meta.namespace, &meta.key);
+            if new_key == old_key {
+                continue;
+            }
+            
+            batch.remove(key);
+            batch.insert(new_key.as_bytes(), data);
+            let old_pwd_key = format!("secret_pwd:{}", old_key.trim_start_matches("secret:"));
+            if let Some(hash) = self.db.get(&old_pwd_key)? {
+                batch.remove(old_pwd_key.as_bytes());
+                batch.insert(password_key(&meta.tenant_id, &meta.namespace, &meta.key).as_bytes(), hash);
+            }
+            if let Some(history) = self.db.get(versions_key(&old_key))? {
+                batch.remove(versions_key(&old_key).as_bytes());
+                batch.insert(versions_key(&new_key).as_bytes(), history);
+            }
+            for index in 0..secret.chunks {
+                let old_chunk_key = format!("{}{:06}", chunk_prefix(&old_key), index);
+                if let Some(chunk) = self.db.get(&old_chunk_key)? {
+                    batch.remove(old_chunk_key.as_bytes());
+                    batch.insert(format!("{}{:06}", chunk_prefix(&new_key), index).as_bytes(), chunk);
+                }
+            }
+            moved += 1;
+        }
+        
+        for item in self.db.scan_prefix("trash:") {
+            let (key, data) = item?;
+            let old_key = String::from_utf8_lossy(&key).into_owned();
+            if !needs_check(&old_key, "trash:") {
+                continue;
+            }
+            let Ok(trashed) = bincode::deserialize::<TrashedSecret>(&data) else {
+                continue;
+            };
+            let meta = &trashed.secret.metadata;
+            let new_key = trash_key(&secret_key(&meta.tenant_id, &meta.namespace, &meta.key));
+            if new_key != old_key {
+                batch.remove(key);
+                batch.insert(new_key.as_bytes(), data);
+                moved += 1;
+            }
+        }
+        
+        for item in self.db.scan_prefix("user:") {
+            let (key, data) = item?;
+            let Ok(user) = bincode::deserialize::<User>(&data) else {
+                continue;
+            };
+            let new_key = user_key(&user.tenant_id, &user.email);
+            if new_key.as_bytes() != key.as_ref() {
+                batch.remove(key);
+                batch.insert(new_key.as_bytes(), data);
+                moved += 1;
+            }
+        }
+        
+        for item in self.db.scan_prefix("invitation:") {
+            let (key, data) = item?;
+            let Ok(invitation) = bincode::deserialize::<TenantInvitation>(&data) else {
+                continue;
+            };
+            let new_key = invitation_key(&invitation.tenant_id, &invitation.token);
+            if new_key.as_bytes() != key.as_ref() {
+                batch.remove(key);
+                batch.insert(new_key.as_bytes(), data);
+                moved += 1;
+            }
+        }
+        
+        if moved > 0 {
+            self.db.apply_batch(batch)?;
+            self.db.flush()?;
+        }
+        Ok(())
+    }
+    
+    /// Re-encrypt `tenant_id`'s records that still use the unescaped
+    /// `legacy_secret_aad`, which two locations could share when a part held
+    /// a `:`. Secrets with their history, trashed secrets and TOTP secrets
+    /// are covered. Runs once per tenant, the first time a writable handle
+    /// unlocks it.
+    fn migrate_unescaped_aad(&self, tenant_id: &str, master_key: &MasterKey) -> Result<()> {
+        let marker = format!("aad_escaped:{}", escape_key_part(tenant_id));
+        if self.read_only || self.db.contains_key(&marker)? {
+            return Ok(());
+        }
+        
+        let mut batch = sled::Batch::default();
+        for result in self.db.scan_prefix(secret_prefix(tenant_id, None)) {
+            let (key, data) = result?;
+            let storage_key = String::from_utf8_lossy(&key).into_owned();
+            let Ok(mut secret) = bincode::deserialize::<Secret>(&data) else {
+                continue;
+            };
+            
+            self.rekey_history(&mut batch, &storage_key, &secret.metadata, |record| self.readdress_record(master_key, record))?;
+            if self.readdress_record(master_key, &mut secret)? {
+                self.stage_secret(&mut batch, &storage_key, secret)?;
+            }
+        }
+        self.rekey_trash(&mut batch, tenant_id, |record| self.readdress_record(master_key, record))?;
+        
+        // TOTP secrets are their own associated data, so they move as well
+        let legacy_prefix = format!("totp:{}:", tenant_id);
+        for result in self.db.scan_prefix(&legacy_prefix) {
+            let (legacy_key, data) = result?;
+            let user_id = String::from_utf8_lossy(&legacy_key[legacy_prefix.len()..]).into_owned();
+            let new_key = totp_key(tenant_id, &user_id);
+            if new_key.as_bytes() == legacy_key.as_ref() {
+                continue;
+            }
+            let Ok(encrypted) = bincode::deserialize::<EncryptedData>(&data) else {
+                continue;
+            };
+            let key = master_key.for_algorithm(encrypted.algorithm.clone());
+            // Fails for another tenant's secret under the same unescaped prefix
+            let Ok(secret) = key.decrypt_with_aad(&encrypted, &legacy_key) else {
+                continue;
+            };
+            let secret = Zeroizing::new(secret);
+            batch.remove(legacy_key);
+            batch.insert(new_key.as_bytes(), bincode::serialize(&key.encrypt_with_aad(&secret, new_key.as_bytes())?)?);
+        }
+        
+        batch.insert(marker.as_bytes(), &[]);
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    /// Open the vault for inspection only. Every mutating method returns
+    /// `VaultError::ReadOnly`. The database is opened in place, waiting like
+    /// `new` for a process holding it to finish; a vault that was never
+    /// initialized opens as an empty one without being created.
+    pub fn open_readonly(path: &str) -> Result<Self> {
+        let db = if Path::new(path).exists() {
+            open_db(path)?
+        } else {
+            sled::Config::new().temporary(true).open()?
+        };
+        
+        let mut storage = Self {
+            db,
+            master_key: None,
+            current_tenant: None,
+            current_user: None,
+            read_only: true,
+            envelope_encryption: false,
+            namespace_keys: false,
+            max_secret_bytes: DEFAULT_MAX_SECRET_BYTES,
+            key_provider: None,
+        };
+        
+        // Only upgrades the record format, which any writer would do as well
+        storage.migrate_legacy_records()?;
+        storage.try_auto_unlock();
+        
+        Ok(storage)
+    }
+    
+    /// Encrypt newly written secrets with a per-secret data key wrapped by the
+    /// master key, so rotating the master key only has to re-wrap the DEKs.
+    pub fn set_envelope_encryption(&mut self, enabled: bool) {
+        self.envelope_encryption = enabled;
+    }
+    
+    /// Encrypt newly written secrets under a per-namespace subkey derived
+    /// from the master key, so one namespace's key doesn't expose the others.
+    pub fn set_namespace_keys(&mut self, enabled: bool) {
+        self.namespace_keys = enabled;
+    }
+    
+    /// Reject secret values larger than `bytes` with `VaultError::TooLarge`.
+    pub fn set_max_secret_bytes(&mut self, bytes: usize) {
+        self.max_secret_bytes = bytes;
+    }
+    
+    pub fn max_secret_bytes(&self) -> usize {
+        self.max_secret_bytes
+    }
+    
+    /// Wrap envelope DEKs with an external key provider (e.g. a KMS) instead
+    /// of the passphrase-derived master key.
+    #[allow(dead_code)]
+    pub fn set_key_provider(&mut self, provider: Box<dyn KeyEncryptionKey + Send + Sync>) {
+        self.key_provider = Some(provider);
+    }
+    
+    fn key_encryption_key<'a>(&'a self, master_key: &'a MasterKey) -> &'a dyn KeyEncryptionKey {
+        match &self.key_provider {
+            Some(provider) => provider.as_ref(),
+            None => master_key,
+        }
+    }
+    
+    /// Wrap `dek` with the configured provider, or with `master_key` without
+    /// one, recording which key did it.
+    fn wrap_dek(&self, master_key: &MasterKey, dek: &DataEncryptionKey) -> Result<Vec<u8>> {
+        WrappedDek::wrap(self.key_encryption_key(master_key), dek)?.to_bytes()
+    }
+    
+    /// Unwrap a stored DEK with the key that wrapped it. A DEK from an
+    /// external provider needs that provider configured; neither the master
+    /// key nor another provider is tried in its place.
+    fn unwrap_dek(&self, master_key: &MasterKey, encrypted_dek: &[u8], algorithm: crate::crypto::EncryptionAlgorithm) -> Result<DataEncryptionKey> {
+        let wrapped = WrappedDek::from_bytes(encrypted_dek)?;
+        let kek: &dyn KeyEncryptionKey = if wrapped.key_id == master_key.key_id() {
+            master_key
+        } else {
+            match &self.key_provider {
+                Some(provider) if provider.key_id() == wrapped.key_id
+                    || (wrapped.key_id == LEGACY_KMS_KEY_ID && provider.key_id().starts_with("kms:")) => provider.as_ref(),
+                _ => return Err(VaultError::Crypto(format!(
+                    "Data key was wrapped by key provider '{}', which is not configured", wrapped.key_id
+                ))),
+            }
+        };
+        kek.decrypt_dek(&wrapped.ciphertext, algorithm)
+    }
+    
+    /// Attribute later writes and audit events to `user_id`. Set from the
+    /// session on auto-unlock; `login` sets it once the session exists.
+    pub fn set_current_user(&mut self, user_id: &str) {
+        self.current_user = Some(user_id.to_string());
+    }
+    
+    /// User recorded on writes and audit events: the logged-in user, or
+    /// "system" when there is no session (scripts and tests unlocking directly).
+    fn acting_user(&self) -> String {
+        self.current_user.clone().unwrap_or_else(|| "system".to_string())
+    }
+    
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            Err(VaultError::ReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+    
     fn try_auto_unlock(&mut self) {
         use crate::auth::SessionManager;
         
@@ -83,20 +632,26 @@ impl VaultStorage {
                 if self.verbose_debug() {
                     eprintln!("Found valid session for tenant: {}", session.tenant_id);
                 }
+                self.current_user = Some(session.user_id.clone());
                 
                 // Get stored key bytes from session storage
                 match self.get_stored_key_data(&session.tenant_id) {
                     Ok(Some((key_bytes, algorithm))) => {
                         use secrecy::Secret;
                         let master_key = MasterKey {
-                            key: Secret::new(key_bytes),
+                            key: Secret::new(*key_bytes),
                             algorithm,
                         };
-                        self.master_key = Some(master_key);
-                        self.current_tenant = Some(session.tenant_id.clone());
-                        
-                        if self.verbose_debug() {
-                            eprintln!("Auto-unlocked vault for tenant: {}", session.tenant_id);
+                        match self.set_unlocked(&session.tenant_id, master_key) {
+                            Ok(()) if self.verbose_debug() => {
+                                eprintln!("Auto-unlocked vault for tenant: {}", session.tenant_id);
+                            }
+                            Ok(()) => {}
+                            Err(e) => {
+                                if self.verbose_debug() {
+                                    eprintln!("Error unlocking from session: {}", e);
+                                }
+                            }
                         }
                     }
                     Ok(None) => {
@@ -126,11 +681,28 @@ impl VaultStorage {
         std::env::var("VAULT_DEBUG").is_ok()
     }
     
-    fn get_stored_key_data(&self, tenant_id: &str) -> Result<Option<([u8; 32], crate::crypto::EncryptionAlgorithm)>> {
-        let session_key = format!("session_key:{}", tenant_id);
+    /// Wrapping key for cached session keys; see `SessionManager::session_wrapping_key`.
+    fn session_wrapping_key() -> Result<MasterKey> {
+        let key = crate::auth::SessionManager::session_wrapping_key()?;
+        Ok(MasterKey {
+            key: secrecy::Secret::new(*key),
+            algorithm: crate::crypto::EncryptionAlgorithm::Aes256Gcm,
+        })
+    }
+    
+    fn get_stored_key_data(&self, tenant_id: &str) -> Result<Option<(Zeroizing<[u8; 32]>, crate::crypto::EncryptionAlgorithm)>> {
+        let session_key = format!("session_key_wrapped:{}", tenant_id);
         if let Some(data) = self.db.get(&session_key)? {
-            let (key_bytes, algorithm): ([u8; 32], crate::crypto::EncryptionAlgorithm) = bincode::deserialize(&data)?;
-            Ok(Some((key_bytes, algorithm)))
+            let encrypted: EncryptedData = bincode::deserialize(&data)?;
+            // A replaced or missing wrapping key just means logging in again
+            let Ok(plaintext) = Self::session_wrapping_key()?.decrypt(&encrypted) else {
+                return Ok(None);
+            };
+            let plaintext = Zeroizing::new(plaintext);
+            let (mut key_bytes, algorithm): ([u8; 32], crate::crypto::EncryptionAlgorithm) = bincode::deserialize(&plaintext)?;
+            let key = Zeroizing::new(key_bytes);
+            key_bytes.zeroize();
+            Ok(Some((key, algorithm)))
         } else {
             Ok(None)
         }
@@ -141,7 +713,18 @@ impl VaultStorage {
         Ok(self.db.contains_key(key)?)
     }
     
+    /// Encrypted known value stored per tenant so raw keys can be verified
+    /// without going through the passphrase.
+    fn write_key_canary(&self, tenant_id: &str, master_key: &MasterKey) -> Result<()> {
+        let canary = master_key.encrypt(KEY_CANARY)
+            .map_err(|e| VaultError::Crypto(e.to_string()))?;
+        self.db.insert(format!("canary:{}", tenant_id), bincode::serialize(&canary)?)?;
+        Ok(())
+    }
+    
     pub async fn init_tenant(&self, tenant_id: &str, admin: &str) -> Result<()> {
+        self.ensure_writable()?;
+        
         // Fallback method for basic tenant creation without password
         let salt = generate_salt();
         let mut tenant = Tenant::new(
@@ -160,79 +743,172 @@ impl VaultStorage {
         self.db.flush()?;
         
         // Create audit log entry
-        self.log_audit_event(tenant_id, "tenant_created", &format!("Tenant {} created by {}", tenant_id, admin)).await?;
+        self.log_audit_event(tenant_id, AuditLogger::EVENT_TENANT_CREATED, &format!("Tenant {} created by {}", tenant_id, admin)).await?;
         
         Ok(())
     }
     
+    #[allow(dead_code)]
     pub async fn init_tenant_with_password(&self, tenant_id: &str, admin: &str, password: &str) -> Result<()> {
+        self.init_tenant_with_settings(
+            tenant_id,
+            admin,
+            password,
+            crate::crypto::EncryptionAlgorithm::Aes256Gcm,
+            &crate::crypto::KeyDerivationParams::default(),
+        ).await
+    }
+    
+    /// Create a tenant whose secrets use `algorithm` and whose master key is
+    /// derived with `kdf_params`. Both are stored in the tenant settings and
+    /// read back on every unlock.
+    pub async fn init_tenant_with_settings(
+        &self,
+        tenant_id: &str,
+        admin: &str,
+        password: &str,
+        algorithm: crate::crypto::EncryptionAlgorithm,
+        kdf_params: &crate::crypto::KeyDerivationParams,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        
         let salt = generate_salt();
         
         // Derive key from password to test it works
-        let master_key = MasterKey::derive_from_passphrase(
+        let master_key = MasterKey::derive_from_passphrase_with_params(
             password,
             &salt,
-            crate::crypto::EncryptionAlgorithm::Aes256Gcm
+            algorithm.clone(),
+            kdf_params,
         ).map_err(|e| VaultError::Crypto(e.to_string()))?;
         
-        // Create password hash for validation during login
-        use secrecy::ExposeSecret;
-        let password_hash = *master_key.key.expose_secret();
+        // Login checks the passphrase against a separate verifier, never the key
+        let verifier = crate::crypto::hash_passphrase_verifier(password, kdf_params)?;
         
-        let tenant = Tenant::new_with_password(
+        let mut tenant = Tenant::new(
             tenant_id.to_string(),
             tenant_id.to_string(),
             admin.to_string(),
             salt,
-            password_hash,
         );
+        tenant.settings.encryption_algorithm = (&algorithm).into();
+        tenant.settings.key_derivation_params = kdf_params.into();
         
         let key = format!("tenant:{}", tenant_id);
         let value = bincode::serialize(&tenant)?;
         self.db.insert(key, value)?;
+        self.db.insert(format!("verifier:{}", tenant_id), verifier.as_bytes())?;
+        self.write_key_canary(tenant_id, &master_key)?;
         self.db.flush()?;
         
         // Create audit log entry
-        self.log_audit_event(tenant_id, "tenant_created", &format!("Tenant {} created by {}", tenant_id, admin)).await?;
+        self.log_audit_event(tenant_id, AuditLogger::EVENT_TENANT_CREATED, &format!("Tenant {} created by {}", tenant_id, admin)).await?;
         
         Ok(())
     }
     
-    pub fn unlock(&mut self, tenant_id: &str, passphrase: &str) -> Result<()> {
-        let tenant = self.get_tenant(tenant_id)?
+    /// Check `passphrase` against the tenant's verifier, then derive its
+    /// master key. Tenants created before verifiers existed are checked
+    /// against the stored key instead and migrated on success.
+    fn derive_tenant_key(&self, tenant_id: &str, passphrase: &str) -> Result<MasterKey> {
+        let mut tenant = self.get_tenant(tenant_id)?
             .ok_or_else(|| VaultError::TenantNotFound(tenant_id.to_string()))?;
-            
-        let master_key = MasterKey::derive_from_passphrase(
-            passphrase, 
-            &tenant.salt, 
-            crate::crypto::EncryptionAlgorithm::Aes256Gcm
+        let kdf_params = (&tenant.settings.key_derivation_params).into();
+        
+        let verifier = self.db.get(format!("verifier:{}", tenant_id))?;
+        if let Some(verifier) = &verifier {
+            let verifier = std::str::from_utf8(verifier)
+                .map_err(|_| VaultError::Corruption(format!("verifier:{} is not UTF-8", tenant_id)))?;
+            if !crate::crypto::verify_passphrase_verifier(passphrase, verifier)? {
+                return Err(VaultError::InvalidPassphrase);
+            }
+        }
+        
+        let master_key = MasterKey::derive_from_passphrase_with_params(
+            passphrase,
+            &tenant.salt,
+            (&tenant.settings.encryption_algorithm).into(),
+            &kdf_params,
         ).map_err(|e| VaultError::Crypto(e.to_string()))?;
         
-        // Validate password by comparing derived key with stored hash
-        use secrecy::ExposeSecret;
-        let derived_hash = *master_key.key.expose_secret();
-        if derived_hash != tenant.password_hash {
-            return Err(VaultError::InvalidPassphrase);
+        if verifier.is_none() {
+            use secrecy::ExposeSecret;
+            if *master_key.key.expose_secret() != tenant.password_hash {
+                return Err(VaultError::InvalidPassphrase);
+            }
+            if !self.read_only {
+                self.migrate_password_verifier(&mut tenant, passphrase, &master_key, &kdf_params)?;
+            }
         }
         
-        self.master_key = Some(master_key);
-        self.current_tenant = Some(tenant_id.to_string());
+        Ok(master_key)
+    }
+    
+    /// Replace a legacy tenant's stored key with a passphrase verifier. The
+    /// canary is written first if missing, since `unlock_with_key` falls back
+    /// to the stored key only for tenants without one.
+    fn migrate_password_verifier(
+        &self,
+        tenant: &mut Tenant,
+        passphrase: &str,
+        master_key: &MasterKey,
+        kdf_params: &crate::crypto::KeyDerivationParams,
+    ) -> Result<()> {
+        let verifier = crate::crypto::hash_passphrase_verifier(passphrase, kdf_params)?;
+        let canary_key = format!("canary:{}", tenant.id);
         
-        // Store the key data for auto-unlock (in memory only for this session)
-        if let Some(ref mk) = self.master_key {
-            self.store_key_data_for_session(tenant_id, mk)?;
+        let mut batch = sled::Batch::default();
+        if !self.db.contains_key(&canary_key)? {
+            let canary = master_key.encrypt(KEY_CANARY)?;
+            batch.insert(canary_key.as_bytes(), bincode::serialize(&canary)?);
         }
+        batch.insert(format!("verifier:{}", tenant.id).as_bytes(), verifier.as_bytes());
+        tenant.password_hash.zeroize();
+        batch.insert(format!("tenant:{}", tenant.id).as_bytes(), bincode::serialize(&*tenant)?);
         
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    /// Make `master_key` this handle's key for `tenant_id`, after bringing
+    /// the tenant's records up to date with it.
+    fn set_unlocked(&mut self, tenant_id: &str, master_key: MasterKey) -> Result<()> {
+        self.migrate_unescaped_aad(tenant_id, &master_key)?;
+        self.master_key = Some(master_key);
+        self.current_tenant = Some(tenant_id.to_string());
+        Ok(())
+    }
+    
+    #[allow(dead_code)]
+    pub fn unlock(&mut self, tenant_id: &str, passphrase: &str) -> Result<()> {
+        let master_key = self.derive_tenant_key(tenant_id, passphrase)?;
+        self.set_unlocked(tenant_id, master_key)?;
+        self.cache_session_key()
+    }
+    
+    /// Cache the unlocked key, wrapped with the per-install key, so later
+    /// commands auto-unlock. `unlock` does this itself; logins that unlock
+    /// with `unlock_transient` call it once their remaining checks pass.
+    pub fn cache_session_key(&self) -> Result<()> {
+        let (Some(tenant_id), Some(master_key)) = (&self.current_tenant, &self.master_key) else {
+            return Err(VaultError::VaultLocked);
+        };
+        if !self.read_only {
+            self.store_key_data_for_session(tenant_id, master_key)?;
+        }
         Ok(())
     }
     
     fn store_key_data_for_session(&self, tenant_id: &str, master_key: &MasterKey) -> Result<()> {
         use secrecy::ExposeSecret;
         // Store key bytes and algorithm for auto-unlock
-        let session_key = format!("session_key:{}", tenant_id);
-        let key_data = (*master_key.key.expose_secret(), master_key.algorithm.clone());
-        let serialized = bincode::serialize(&key_data)?;
-        self.db.insert(session_key, serialized)?;
+        let session_key = format!("session_key_wrapped:{}", tenant_id);
+        let mut key_data = (*master_key.key.expose_secret(), master_key.algorithm.clone());
+        let serialized = Zeroizing::new(bincode::serialize(&key_data)?);
+        key_data.0.zeroize();
+        let encrypted = Self::session_wrapping_key()?.encrypt(&serialized)?;
+        self.db.insert(session_key, bincode::serialize(&encrypted)?)?;
         self.db.flush()?; // Ensure it's written to disk
         
         if self.verbose_debug() {
@@ -242,396 +918,4430 @@ impl VaultStorage {
         Ok(())
     }
     
+    /// Unlock with key material managed outside the vault (a KMS, a hardware
+    /// token) instead of a passphrase. The key must decrypt the tenant's
+    /// canary, so a wrong key is rejected here rather than producing failed
+    /// decryptions later. Tenants created before canaries existed are checked
+    /// against the stored key hash and get a canary written.
+    pub fn unlock_with_key(&mut self, tenant_id: &str, key: [u8; 32], algorithm: crate::crypto::EncryptionAlgorithm) -> Result<()> {
+        let tenant = self.get_tenant(tenant_id)?
+            .ok_or_else(|| VaultError::TenantNotFound(tenant_id.to_string()))?;
+        let master_key = MasterKey {
+            key: secrecy::Secret::new(key),
+            algorithm,
+        };
+        
+        match self.db.get(format!("canary:{}", tenant_id))? {
+            Some(data) => {
+                let canary: EncryptedData = bincode::deserialize(&data)?;
+                // The canary only proves the key bytes; it may predate a `vault reencrypt`
+                match master_key.for_algorithm(canary.algorithm.clone()).decrypt(&canary) {
+                    Ok(plaintext) if plaintext == KEY_CANARY => {}
+                    _ => return Err(VaultError::InvalidKey),
+                }
+            }
+            None => {
+                // An all-zero hash means no key was ever stored
+                use subtle::ConstantTimeEq;
+                if tenant.password_hash == [0u8; 32] || !bool::from(key.ct_eq(&tenant.password_hash)) {
+                    return Err(VaultError::InvalidKey);
+                }
+                if !self.read_only {
+                    self.write_key_canary(tenant_id, &master_key)?;
+                    self.db.flush()?;
+                }
+            }
+        }
+        
+        self.set_unlocked(tenant_id, master_key)
+    }
+    
+    /// Switch this handle to another tenant using the key cached by an earlier
+    /// login to it. Returns false when no key is cached for the tenant or the
+    /// cached key no longer unlocks it, as after a passphrase change. With
+    /// `user_id` (collaborative mode), the cached key is only used when that
+    /// user is an active member of the tenant.
+    pub fn unlock_from_cache(&mut self, tenant_id: &str, user_id: Option<&str>) -> Result<bool> {
+        if let Some(user_id) = user_id {
+            let is_member = match self.db.get(user_key(tenant_id, user_id))? {
+                Some(data) => bincode::deserialize::<User>(&data)?.is_active,
+                None => false,
+            };
+            if !is_member {
+                return Err(VaultError::PermissionDenied(format!("{} is not a member of tenant {}", user_id, tenant_id)));
+            }
+        }
+        
+        match self.get_stored_key_data(tenant_id)? {
+            Some((key_bytes, algorithm)) => match self.unlock_with_key(tenant_id, *key_bytes, algorithm) {
+                Ok(()) => Ok(true),
+                Err(VaultError::InvalidKey) => Ok(false),
+                Err(e) => Err(e),
+            },
+            None => Ok(false),
+        }
+    }
+    
+    /// Unlock another tenant for the lifetime of this handle only. Unlike
+    /// `unlock`, the derived key is not cached for later commands.
+    pub fn unlock_transient(&mut self, tenant_id: &str, passphrase: &str) -> Result<()> {
+        let master_key = self.derive_tenant_key(tenant_id, passphrase)?;
+        self.set_unlocked(tenant_id, master_key)
+    }
+    
+    /// Forget the master key and, unless read-only, the tenant's cached
+    /// session key, so the passphrase has to be entered again.
+    pub fn lock(&mut self) -> Result<()> {
+        if let Some(tenant_id) = self.current_tenant.take() {
+            if !self.read_only {
+                self.clear_session_key(&tenant_id)?;
+            }
+        }
+        self.master_key = None;
+        Ok(())
+    }
+    
     pub fn clear_session_key(&self, tenant_id: &str) -> Result<()> {
-        let session_key = format!("session_key:{}", tenant_id);
-        self.db.remove(&session_key)?;
+        self.ensure_writable()?;
+        
+        self.db.remove(format!("session_key_wrapped:{}", tenant_id))?;
         Ok(())
     }
     
     #[allow(dead_code)]
-    pub async fn put(&self, key: &str, value: &str, namespace: &str) -> Result<()> {
-        self.put_with_tags(key, value, namespace, &[]).await
+    pub async fn change_passphrase(&mut self, old_passphrase: &str, new_passphrase: &str) -> Result<usize> {
+        self.change_passphrase_with_progress(old_passphrase, new_passphrase, |_, _| {}).await
     }
     
-    pub async fn put_with_protection(&self, key: &str, value: &str, namespace: &str, tags: &[String], access_password: Option<&str>) -> Result<()> {
-        let master_key = self.master_key.as_ref()
-            .ok_or(VaultError::VaultLocked)?;
+    /// Change the tenant passphrase, rotating the master key under a new
+    /// salt. Envelope-encrypted secrets only have their data keys re-wrapped;
+    /// legacy secrets are re-encrypted in full. `progress` gets the number of
+    /// secrets done and the total. All records, the tenant salt and verifier
+    /// are updated in a single batch, so a failure leaves the old passphrase
+    /// working.
+    pub async fn change_passphrase_with_progress(
+        &mut self,
+        old_passphrase: &str,
+        new_passphrase: &str,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<usize> {
+        self.ensure_writable()?;
         
-        let tenant_id = self.current_tenant.as_ref()
+        let tenant_id = self.current_tenant.clone()
             .ok_or(VaultError::VaultLocked)?;
+        let old_key = self.derive_tenant_key(&tenant_id, old_passphrase)?;
+        let mut tenant = self.get_tenant(&tenant_id)?
+            .ok_or_else(|| VaultError::TenantNotFound(tenant_id.clone()))?;
         
-        // Hash access password if provided
-        let access_password_hash: Option<[u8; 32]> = if let Some(pwd) = access_password {
-            use sha2::{Sha256, Digest};
-            let mut hasher = Sha256::new();
-            hasher.update(pwd.as_bytes());
-            Some(hasher.finalize().into())
-        } else {
-            None
-        };
+        let kdf_params = (&tenant.settings.key_derivation_params).into();
+        let new_salt = generate_salt();
+        let new_key = MasterKey::derive_from_passphrase_with_params(
+            new_passphrase,
+            &new_salt,
+            (&tenant.settings.encryption_algorithm).into(),
+            &kdf_params,
+        ).map_err(|e| VaultError::Crypto(e.to_string()))?;
+        
+        let mut batch = sled::Batch::default();
+        let mut rotated = 0;
+        let prefix = secret_prefix(&tenant_id, None);
+        let total = self.db.scan_prefix(&prefix).count();
+        for (done, result) in self.db.scan_prefix(&prefix).enumerate() {
+            let (key, data) = result?;
+            let storage_key = String::from_utf8_lossy(&key).into_owned();
+            let mut secret: Secret = bincode::deserialize(&data)?;
+            
+            self.rekey_history(&mut batch, &storage_key, &secret.metadata, |record| self.rotate_record(&old_key, &new_key, record))?;
+            if self.rotate_record(&old_key, &new_key, &mut secret)? {
+                self.stage_secret(&mut batch, &storage_key, secret)?;
+                rotated += 1;
+            }
+            progress(done + 1, total);
+        }
+        self.rekey_trash(&mut batch, &tenant_id, |record| self.rotate_record(&old_key, &new_key, record))?;
+        self.rekey_totp_secrets(&mut batch, &tenant_id, &old_key, &new_key)?;
         
-        let encrypted_value = master_key.encrypt(value.as_bytes())
+        tenant.salt = new_salt;
+        batch.insert(format!("tenant:{}", tenant_id).as_bytes(), bincode::serialize(&tenant)?);
+        let verifier = crate::crypto::hash_passphrase_verifier(new_passphrase, &kdf_params)?;
+        batch.insert(format!("verifier:{}", tenant_id).as_bytes(), verifier.as_bytes());
+        let canary = new_key.encrypt(KEY_CANARY)
             .map_err(|e| VaultError::Crypto(e.to_string()))?;
+        batch.insert(format!("canary:{}", tenant_id).as_bytes(), bincode::serialize(&canary)?);
+        // Other sessions must not keep unlocking with the old key
+        batch.remove(format!("session_key_wrapped:{}", tenant_id).as_bytes());
         
-        let metadata = SecretMetadata {
-            id: Uuid::new_v4(),
-            tenant_id: tenant_id.clone(),
-            namespace: namespace.to_string(),
-            key: key.to_string(),
-            version: 1,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-            created_by: "user".to_string(),
-            tags: tags.to_vec(),
-        };
-        
-        let secret = Secret {
-            metadata,
-            encrypted_value,
-        };
-        
-        let storage_key = format!("secret:{}:{}:{}", tenant_id, namespace, key);
-        let storage_value = bincode::serialize(&secret)?;
-        self.db.insert(storage_key, storage_value)?;
-        
-        // Store access password hash separately if provided
-        if let Some(hash) = access_password_hash {
-            let pwd_key = format!("secret_pwd:{}:{}:{}", tenant_id, namespace, key);
-            self.db.insert(pwd_key, hash.as_slice())?;
-        }
-        
+        self.db.apply_batch(batch)?;
         self.db.flush()?;
         
-        self.log_audit_event(tenant_id, "secret_created", &format!("Secret {}/{} created", namespace, key)).await?;
+        self.master_key = Some(new_key);
         
-        Ok(())
-    }
-    
-    pub async fn put_with_tags(&self, key: &str, value: &str, namespace: &str, tags: &[String]) -> Result<()> {
-        self.put_with_protection(key, value, namespace, tags, None).await
+        self.log_audit_event(&tenant_id, "passphrase_changed", &format!("Passphrase changed, master key rotated ({} secrets)", rotated)).await?;
+        
+        Ok(rotated)
     }
     
-    pub async fn get(&self, key: &str, namespace: &str) -> Result<Option<String>> {
-        match self.get_with_metadata(key, namespace).await? {
-            Some((value, _)) => Ok(Some(value)),
-            None => Ok(None),
-        }
+    #[allow(dead_code)]
+    pub async fn reencrypt_secrets(&mut self, algorithm: crate::crypto::EncryptionAlgorithm) -> Result<usize> {
+        self.reencrypt_secrets_with_progress(algorithm, |_, _| {}).await
     }
     
-    pub async fn is_secret_password_protected(&self, key: &str, namespace: &str) -> Result<bool> {
-        let tenant_id = self.current_tenant.as_ref()
-            .ok_or(VaultError::VaultLocked)?;
+    /// Re-encrypt every secret of the current tenant with `algorithm` and make
+    /// it the tenant default for new writes. Envelope-encrypted secrets get a
+    /// fresh DEK for the new cipher. `progress` gets the number of secrets
+    /// done and the total. All records and the tenant settings are updated in
+    /// a single batch, and secrets already on the cipher are skipped, so an
+    /// interrupted run changes nothing and can simply be repeated. Returns the
+    /// number of secrets changed.
+    pub async fn reencrypt_secrets_with_progress(
+        &mut self,
+        algorithm: crate::crypto::EncryptionAlgorithm,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<usize> {
+        self.ensure_writable()?;
         
-        let pwd_key = format!("secret_pwd:{}:{}:{}", tenant_id, namespace, key);
-        Ok(self.db.contains_key(&pwd_key)?)
-    }
-    
-    pub async fn get_with_metadata_and_password(&self, key: &str, namespace: &str, access_password: Option<&str>) -> Result<Option<(String, SecretMetadata)>> {
+        let tenant_id = self.current_tenant.clone()
+            .ok_or(VaultError::VaultLocked)?;
         let master_key = self.master_key.as_ref()
             .ok_or(VaultError::VaultLocked)?;
+        let mut tenant = self.get_tenant(&tenant_id)?
+            .ok_or_else(|| VaultError::TenantNotFound(tenant_id.clone()))?;
         
-        let tenant_id = self.current_tenant.as_ref()
-            .ok_or(VaultError::VaultLocked)?;
+        let new_key = master_key.for_algorithm(algorithm.clone());
         
-        // Check if password protected and validate password
-        let pwd_key = format!("secret_pwd:{}:{}:{}", tenant_id, namespace, key);
-        if let Some(stored_hash) = self.db.get(&pwd_key)? {
-            match access_password {
-                Some(pwd) => {
-                    use sha2::{Sha256, Digest};
-                    let mut hasher = Sha256::new();
-                    hasher.update(pwd.as_bytes());
-                    let provided_hash: [u8; 32] = hasher.finalize().into();
-                    
-                    if stored_hash.as_ref() != provided_hash {
-                        return Err(VaultError::Auth("Invalid access password".to_string()));
-                    }
-                }
-                None => {
-                    return Err(VaultError::Auth("Secret is password protected".to_string()));
-                }
+        let mut batch = sled::Batch::default();
+        let mut reencrypted = 0;
+        let prefix = secret_prefix(&tenant_id, None);
+        let total = self.db.scan_prefix(&prefix).count();
+        for (done, result) in self.db.scan_prefix(&prefix).enumerate() {
+            let (key, data) = result?;
+            let storage_key = String::from_utf8_lossy(&key).into_owned();
+            let mut secret: Secret = bincode::deserialize(&data)?;
+            
+            self.rekey_history(&mut batch, &storage_key, &secret.metadata, |record| self.reencrypt_record(master_key, &new_key, record))?;
+            if self.reencrypt_record(master_key, &new_key, &mut secret)? {
+                self.stage_secret(&mut batch, &storage_key, secret)?;
+                reencrypted += 1;
             }
+            progress(done + 1, total);
         }
+        self.rekey_trash(&mut batch, &tenant_id, |record| self.reencrypt_record(master_key, &new_key, record))?;
         
-        let storage_key = format!("secret:{}:{}:{}", tenant_id, namespace, key);
+        tenant.settings.encryption_algorithm = (&algorithm).into();
+        batch.insert(format!("tenant:{}", tenant_id).as_bytes(), bincode::serialize(&tenant)?);
+        let canary = new_key.encrypt(KEY_CANARY)?;
+        batch.insert(format!("canary:{}", tenant_id).as_bytes(), bincode::serialize(&canary)?);
         
-        if let Some(data) = self.db.get(&storage_key)? {
-            let secret: Secret = bincode::deserialize(&data)?;
-            let decrypted = master_key.decrypt(&secret.encrypted_value)
-                .map_err(|e| VaultError::Crypto(e.to_string()))?;
-            let value = String::from_utf8(decrypted)
-                .map_err(|e| VaultError::Crypto(e.to_string()))?;
-            
-            self.log_audit_event(tenant_id, "secret_accessed", &format!("Secret {}/{} accessed", namespace, key)).await?;
-            
-            Ok(Some((value, secret.metadata)))
-        } else {
-            Ok(None)
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        self.master_key = Some(new_key);
+        if let Some(ref mk) = self.master_key {
+            self.store_key_data_for_session(&tenant_id, mk)?;
         }
+        
+        self.log_audit_event(&tenant_id, "secrets_reencrypted", &format!("{} secrets re-encrypted with {:?}", reencrypted, algorithm)).await?;
+        
+        Ok(reencrypted)
     }
     
-    pub async fn get_with_metadata(&self, key: &str, namespace: &str) -> Result<Option<(String, SecretMetadata)>> {
-        self.get_with_metadata_and_password(key, namespace, None).await
+    /// Re-wrap one record's DEK, or re-encrypt its value, from `old_key` to
+    /// `new_key`. Returns false for DEKs wrapped by an external provider,
+    /// which don't depend on the passphrase.
+    fn rotate_record(&self, old_key: &MasterKey, new_key: &MasterKey, secret: &mut Secret) -> Result<bool> {
+        match &secret.encrypted_dek {
+            Some(encrypted_dek) => {
+                let wrapped = WrappedDek::from_bytes(encrypted_dek)?;
+                if wrapped.key_id != old_key.key_id() {
+                    return Ok(false);
+                }
+                let dek = Self::secret_key(old_key, secret)?
+                    .decrypt_dek(&wrapped.ciphertext, secret.encrypted_value.algorithm.clone())?;
+                secret.encrypted_dek = Some(WrappedDek::wrap(&Self::secret_key(new_key, secret)?, &dek)?.to_bytes()?);
+            }
+            None => {
+                let plaintext = self.decrypt_secret(old_key, secret)?;
+                let meta = &secret.metadata;
+                secret.encrypted_value = Self::secret_key(new_key, secret)?
+                    .encrypt_with_aad(&plaintext, secret_aad(&meta.tenant_id, &meta.namespace, &meta.key).as_bytes())?;
+                secret.chunks = 0;
+            }
+        }
+        Ok(true)
     }
     
-    pub async fn list(&self, namespace: &str) -> Result<Vec<String>> {
-        let results = self.list_with_metadata(namespace, None).await?;
-        Ok(results.into_iter().map(|(key, _)| key).collect())
+    /// Re-encrypt one record under `new_key`'s cipher. Envelope-encrypted
+    /// records get a fresh DEK. Returns false when the record already uses
+    /// the cipher and carries associated data.
+    fn reencrypt_record(&self, master_key: &MasterKey, new_key: &MasterKey, secret: &mut Secret) -> Result<bool> {
+        // Records from before associated data are rewritten even when the
+        // cipher already matches
+        if secret.encrypted_value.algorithm == new_key.algorithm && secret.encrypted_value.version >= 2 {
+            return Ok(false);
+        }
+        
+        // Migration path: read each record with the cipher it names
+        let old_key = master_key.for_algorithm(secret.encrypted_value.algorithm.clone());
+        let plaintext = self.decrypt_secret(&old_key, secret)?;
+        let secret_key = Self::secret_key(new_key, secret)?;
+        let aad = secret_aad(&secret.metadata.tenant_id, &secret.metadata.namespace, &secret.metadata.key);
+        if secret.encrypted_dek.is_some() {
+            let dek = DataEncryptionKey::generate(new_key.algorithm.clone());
+            secret.encrypted_value = dek.encrypt_with_aad(&plaintext, aad.as_bytes())?;
+            secret.encrypted_dek = Some(self.wrap_dek(&secret_key, &dek)?);
+        } else {
+            secret.encrypted_value = secret_key.encrypt_with_aad(&plaintext, aad.as_bytes())?;
+        }
+        secret.chunks = 0;
+        Ok(true)
     }
     
-    pub async fn list_with_metadata(&self, namespace: &str, tag_filter: Option<&str>) -> Result<Vec<(String, SecretMetadata)>> {
-        let tenant_id = self.current_tenant.as_ref()
-            .ok_or(VaultError::VaultLocked)?;
-            
-        let prefix = format!("secret:{}:{}:", tenant_id, namespace);
-        let mut results = Vec::new();
+    /// Re-encrypt one record written with `legacy_secret_aad` under
+    /// `secret_aad`, keeping its key or DEK. Returns false for records whose
+    /// associated data is unchanged by escaping or that already use it.
+    fn readdress_record(&self, master_key: &MasterKey, secret: &mut Secret) -> Result<bool> {
+        let meta = &secret.metadata;
+        let aad = secret_aad(&meta.tenant_id, &meta.namespace, &meta.key);
+        let legacy_aad = legacy_secret_aad(&meta.tenant_id, &meta.namespace, &meta.key);
+        if aad == legacy_aad || secret.encrypted_value.version < 2 {
+            return Ok(false);
+        }
         
-        for result in self.db.scan_prefix(&prefix) {
+        let algorithm = secret.encrypted_value.algorithm.clone();
+        let key = master_key.for_algorithm(algorithm.clone());
+        let Ok(plaintext) = self.decrypt_secret_with_aad(&key, secret, legacy_aad.as_bytes()) else {
+            return Ok(false);
+        };
+        let plaintext = Zeroizing::new(plaintext);
+        secret.encrypted_value = match &secret.encrypted_dek {
+            Some(encrypted_dek) => self.unwrap_dek(&Self::secret_key(&key, secret)?, encrypted_dek, algorithm)?
+                .encrypt_with_aad(&plaintext, aad.as_bytes())?,
+            None => Self::secret_key(&key, secret)?.encrypt_with_aad(&plaintext, aad.as_bytes())?,
+        };
+        secret.chunks = 0;
+        Ok(true)
+    }
+    
+    /// Apply `rekey` to every version in the history of the secret at
+    /// `storage_key`, staging the updated history if any version changed.
+    fn rekey_history(
+        &self,
+        batch: &mut sled::Batch,
+        storage_key: &str,
+        current: &SecretMetadata,
+        mut rekey: impl FnMut(&mut Secret) -> Result<bool>,
+    ) -> Result<()> {
+        let mut history = self.load_inline_history(storage_key)?;
+        if rekey_versions(&mut history, current, &mut rekey)? {
+            self.stage_history(batch, storage_key, history)?;
+        }
+        Ok(())
+    }
+    
+    /// Apply `rekey` to every secret in the tenant's trash and its history,
+    /// so deleted secrets stay restorable after a rotation. Unreadable
+    /// entries are skipped rather than failing the rotation.
+    fn rekey_trash(&self, batch: &mut sled::Batch, tenant_id: &str, mut rekey: impl FnMut(&mut Secret) -> Result<bool>) -> Result<()> {
+        for result in self.db.scan_prefix(trash_prefix(tenant_id)) {
             let (key, data) = result?;
-            let key_str = String::from_utf8(key.to_vec())?;
+            let Ok(mut trashed) = bincode::deserialize::<TrashedSecret>(&data) else {
+                continue;
+            };
             
-            if let Some(secret_key) = key_str.split(':').last() {
-                let secret: Secret = bincode::deserialize(&data)?;
-                
-                // Apply tag filter if specified
-                if let Some(tag) = tag_filter {
-                    if !secret.metadata.tags.contains(&tag.to_string()) {
-                        continue;
-                    }
-                }
-                
-                results.push((secret_key.to_string(), secret.metadata));
+            let mut changed = rekey_versions(&mut trashed.history, &trashed.secret.metadata, &mut rekey)?;
+            changed |= rekey(&mut trashed.secret)?;
+            if changed {
+                batch.insert(key, bincode::serialize(&trashed)?);
             }
         }
-        
-        results.sort_by(|a, b| a.0.cmp(&b.0));
-        Ok(results)
+        Ok(())
     }
     
-    pub async fn delete(&self, key: &str, namespace: &str) -> Result<()> {
-        let tenant_id = self.current_tenant.as_ref()
-            .ok_or(VaultError::VaultLocked)?;
-            
-        let storage_key = format!("secret:{}:{}:{}", tenant_id, namespace, key);
-        
-        if self.db.remove(&storage_key)?.is_some() {
-            self.db.flush()?;
-            
-            // Log audit event
-            self.log_audit_event(tenant_id, "secret_deleted", &format!("Secret {}/{} deleted", namespace, key)).await?;
-            
-            Ok(())
-        } else {
-            Err(VaultError::SecretNotFound(format!("{}/{}", namespace, key)))
+    /// Earlier versions of a secret, oldest first.
+    fn load_history(&self, versions_key: &str) -> Result<Vec<SecretVersion>> {
+        match self.db.get(versions_key)? {
+            Some(data) => bincode::deserialize(&data)
+                .map_err(|e| VaultError::Corruption(format!("{}: {}", versions_key, e))),
+            None => Ok(Vec::new()),
         }
     }
     
-    pub async fn search(&self, query: &str, namespace_filter: Option<&str>) -> Result<Vec<(String, String)>> {
-        let tenant_id = self.current_tenant.as_ref()
-            .ok_or(VaultError::VaultLocked)?;
-        
-        let prefix = format!("secret:{}:", tenant_id);
-        let mut results = Vec::new();
-        let query_lower = query.to_lowercase();
-        
-        for result in self.db.scan_prefix(&prefix) {
-            let (key, data) = result?;
-            let key_str = String::from_utf8(key.to_vec())?;
-            let parts: Vec<&str> = key_str.split(':').collect();
-            
-            if parts.len() >= 4 {
-                let namespace = parts[2];
-                let secret_key = parts[3];
-                
-                // Apply namespace filter
-                if let Some(ns_filter) = namespace_filter {
-                    if namespace != ns_filter {
-                        continue;
-                    }
-                }
-                
-                // Check if key matches query
-                if secret_key.to_lowercase().contains(&query_lower) {
-                    results.push((namespace.to_string(), secret_key.to_string()));
-                    continue;
-                }
-                
-                // Check if tags match query
-                let secret: Secret = bincode::deserialize(&data)?;
-                for tag in &secret.metadata.tags {
-                    if tag.to_lowercase().contains(&query_lower) {
-                        results.push((namespace.to_string(), secret_key.to_string()));
-                        break;
-                    }
-                }
+    /// History of the secret at `storage_key` with every version's
+    /// ciphertext joined back inline.
+    fn load_inline_history(&self, storage_key: &str) -> Result<Vec<SecretVersion>> {
+        self.load_history(&versions_key(storage_key))?
+            .into_iter()
+            .map(|version| self.inline_version(storage_key, version))
+            .collect()
+    }
+    
+    /// `version` of the secret at `storage_key` with any chunked ciphertext
+    /// joined back inline.
+    fn inline_version(&self, storage_key: &str, mut version: SecretVersion) -> Result<SecretVersion> {
+        if version.chunks > 0 {
+            let chunk_prefix = version_chunk_prefix(storage_key, version.version);
+            let mut ciphertext = Vec::new();
+            for index in 0..version.chunks {
+                let chunk_key = format!("{}{:06}", chunk_prefix, index);
+                let chunk = self.db.get(&chunk_key)?
+                    .ok_or_else(|| VaultError::Corruption(format!("{} is missing", chunk_key)))?;
+                ciphertext.extend_from_slice(&chunk);
             }
+            version.encrypted_value.ciphertext = ciphertext;
+            version.chunks = 0;
         }
-        
-        results.sort();
-        Ok(results)
+        Ok(version)
     }
     
-    pub async fn get_stats(&self) -> Result<VaultStats> {
-        let mut secret_count = 0;
-        let mut namespaces = std::collections::HashSet::new();
-        let mut tenants = std::collections::HashSet::new();
-        let mut total_size = 0;
+    /// Add the writes that store `history` for the secret at `storage_key`
+    /// to `batch`. Inline ciphertext over `CHUNK_SIZE` is split across
+    /// `secret_chunk:` records under its version, and chunks of versions no
+    /// longer kept chunked are removed.
+    fn stage_history(&self, batch: &mut sled::Batch, storage_key: &str, mut history: Vec<SecretVersion>) -> Result<()> {
+        let kept: Vec<String> = history.iter()
+            .filter(|version| version.chunks > 0)
+            .map(|version| version_chunk_prefix(storage_key, version.version))
+            .collect();
+        let versions_prefix = format!("{}v", chunk_prefix(storage_key));
+        for existing in self.db.scan_prefix(versions_prefix.as_bytes()).keys() {
+            let existing = existing?;
+            if !kept.iter().any(|prefix| existing.starts_with(prefix.as_bytes())) {
+                batch.remove(existing);
+            }
+        }
         
-        for result in self.db.iter() {
-            let (key, value) = result?;
-            let key_str = String::from_utf8(key.to_vec())?;
-            total_size += value.len() as u64;
-            
-            if key_str.starts_with("secret:") {
-                secret_count += 1;
-                let parts: Vec<&str> = key_str.split(':').collect();
-                if parts.len() >= 3 {
-                    tenants.insert(parts[1].to_string());
-                    namespaces.insert(format!("{}:{}", parts[1], parts[2]));
-                }
-            } else if key_str.starts_with("tenant:") {
-                let parts: Vec<&str> = key_str.split(':').collect();
-                if parts.len() >= 2 {
-                    tenants.insert(parts[1].to_string());
+        for version in &mut history {
+            if version.chunks == 0 && version.encrypted_value.ciphertext.len() > CHUNK_SIZE {
+                let chunk_prefix = version_chunk_prefix(storage_key, version.version);
+                let ciphertext = std::mem::take(&mut version.encrypted_value.ciphertext);
+                for (index, chunk) in ciphertext.chunks(CHUNK_SIZE).enumerate() {
+                    batch.insert(format!("{}{:06}", chunk_prefix, index).as_bytes(), chunk);
+                    version.chunks += 1;
                 }
             }
         }
         
-        Ok(VaultStats {
-            secret_count,
-            namespace_count: namespaces.len(),
-            tenant_count: tenants.len(),
-            total_size,
-        })
+        batch.insert(versions_key(storage_key).as_bytes(), bincode::serialize(&history)?);
+        Ok(())
     }
     
-    pub async fn health_check(&self) -> Result<()> {
-        // Check if database is accessible
-        self.db.checksum()?;
+    #[allow(dead_code)]
+    pub async fn put(&self, key: &str, value: &str, namespace: &str) -> Result<()> {
+        self.put_with_tags(key, value, namespace, &[]).await
+    }
+    
+    pub async fn put_with_protection(&self, key: &str, value: &str, namespace: &str, tags: &[String], access_password: Option<&str>) -> Result<()> {
+        self.put_expiring(key, value, namespace, tags, access_password, None).await
+    }
+    
+    /// Store a secret that reads as not found once `ttl` has passed.
+    #[allow(dead_code)]
+    pub async fn put_with_ttl(&self, key: &str, value: &str, namespace: &str, ttl: chrono::Duration) -> Result<()> {
+        let expires_at = Utc::now().checked_add_signed(ttl)
+            .ok_or_else(|| VaultError::InvalidOperation(format!("TTL of {} is out of range", ttl)))?;
+        self.put_expiring(key, value, namespace, &[], None, Some(expires_at)).await
+    }
+    
+    /// `put_with_protection` with an optional expiry. Overwriting a secret
+    /// replaces its expiry, so a later put without one makes it permanent.
+    pub async fn put_expiring(&self, key: &str, value: &str, namespace: &str, tags: &[String], access_password: Option<&str>, expires_at: Option<DateTime<Utc>>) -> Result<()> {
+        self.store_value(key, value.as_bytes(), false, namespace, tags, access_password, expires_at).await
+    }
+    
+    /// Store raw bytes (keystores, certificates, ...) as a binary secret.
+    pub async fn put_bytes(&self, key: &str, value: &[u8], namespace: &str, tags: &[String]) -> Result<()> {
+        self.store_value(key, value, true, namespace, tags, None, None).await
+    }
+    
+    /// Store bytes exactly as given, as a text secret or, with `binary`, one
+    /// only readable through the byte APIs.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn put_value(&self, key: &str, value: &[u8], binary: bool, namespace: &str, tags: &[String], access_password: Option<&str>, expires_at: Option<DateTime<Utc>>) -> Result<()> {
+        self.store_value(key, value, binary, namespace, tags, access_password, expires_at).await
+    }
+    
+    #[allow(clippy::too_many_arguments)]
+    async fn store_value(&self, key: &str, value: &[u8], binary: bool, namespace: &str, tags: &[String], access_password: Option<&str>, expires_at: Option<DateTime<Utc>>) -> Result<()> {
+        self.ensure_writable()?;
         
-        // Check if we can write/read
-        let test_key = "health_check_test";
-        let test_value = b"test";
-        self.db.insert(test_key, test_value)?;
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
         
-        if let Some(retrieved) = self.db.get(test_key)? {
-            if retrieved != test_value {
-                return Err(VaultError::Crypto("Health check failed: data corruption".to_string()));
-            }
-        } else {
-            return Err(VaultError::Crypto("Health check failed: data not found".to_string()));
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        // Hash access password if provided
+        let access_password_hash = access_password
+            .map(hash_access_password)
+            .transpose()?;
+        
+        let mut secret = self.build_secret(master_key, tenant_id, key, value, binary, namespace, tags)?;
+        secret.metadata.expires_at = expires_at;
+        
+        let storage_key = secret_key(tenant_id, namespace, key);
+        self.ensure_within_quota(tenant_id, &[(namespace, storage_key.as_str())], &[])?;
+        let mut batch = sled::Batch::default();
+        self.stage_new_version(&mut batch, &storage_key, secret)?;
+        
+        // Store access password hash separately if provided
+        if let Some(hash) = access_password_hash {
+            let pwd_key = password_key(tenant_id, namespace, key);
+            batch.insert(pwd_key.as_bytes(), hash);
         }
         
-        self.db.remove(test_key)?;
+        self.db.apply_batch(batch)?;
         self.db.flush()?;
         
+        let audit_entry = AuditEntry::new(
+            tenant_id.to_string(),
+            AuditLogger::EVENT_SECRET_CREATED.to_string(),
+            format!("Secret {}/{} created", namespace, key),
+            self.acting_user(),
+        )
+        .with_resource("secret".to_string(), format!("{}/{}", namespace, key))
+        .with_metadata(serde_json::json!({ "tags": tags }));
+        self.record_audit_entry(&audit_entry)?;
+        
         Ok(())
     }
     
-    fn get_tenant(&self, tenant_id: &str) -> Result<Option<Tenant>> {
-        let key = format!("tenant:{}", tenant_id);
-        if let Some(data) = self.db.get(key)? {
-            let tenant: Tenant = bincode::deserialize(&data)?;
-            Ok(Some(tenant))
-        } else {
-            Ok(None)
+    /// Start a group of writes that are committed or discarded as a unit.
+    /// Dropping the transaction without calling `commit` writes nothing.
+    pub fn transaction(&self) -> Result<SecretTransaction<'_>> {
+        self.ensure_writable()?;
+        
+        if self.master_key.is_none() || self.current_tenant.is_none() {
+            return Err(VaultError::VaultLocked);
         }
+        
+        Ok(SecretTransaction {
+            storage: self,
+            batch: sled::Batch::default(),
+            staged: 0,
+            targets: Vec::new(),
+        })
     }
     
-    async fn log_audit_event(&self, tenant_id: &str, event_type: &str, description: &str) -> Result<()> {
-        let audit_entry = AuditEntry {
+    #[allow(clippy::too_many_arguments)]
+    fn build_secret(&self, master_key: &MasterKey, tenant_id: &str, key: &str, value: &[u8], binary: bool, namespace: &str, tags: &[String]) -> Result<Secret> {
+        if value.len() > self.max_secret_bytes {
+            return Err(VaultError::TooLarge(value.len(), self.max_secret_bytes));
+        }
+        
+        let (encrypted_value, encrypted_dek) = self.encrypt_value(master_key, tenant_id, namespace, key, value)?;
+        
+        let metadata = SecretMetadata {
             id: Uuid::new_v4(),
             tenant_id: tenant_id.to_string(),
-            event_type: event_type.to_string(),
-            description: description.to_string(),
-            timestamp: Utc::now(),
-            user_id: "system".to_string(), // TODO: Get from session
-            ip_address: None,
-            user_agent: None,
-            resource_type: None,
-            resource_id: None,
-            metadata: None,
-        };
-        
-        let key = format!("audit:{}:{}", tenant_id, audit_entry.timestamp.timestamp_nanos_opt().unwrap_or(0));
-        let value = bincode::serialize(&audit_entry)?;
-        self.db.insert(key, value)?;
+            namespace: namespace.to_string(),
+            key: key.to_string(),
+            version: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_by: self.acting_user(),
+            updated_by: self.acting_user(),
+            tags: tags.to_vec(),
+            alias_of: None,
+            binary,
+            expires_at: None,
+        };
         
-        Ok(())
+        Ok(Secret {
+            metadata,
+            encrypted_value,
+            encrypted_dek,
+            namespace_key: self.namespace_keys,
+            chunks: 0,
+        })
     }
     
-    pub async fn get_user_role(&self, tenant_id: &str, email: &str) -> Result<Option<crate::auth::Role>> {
-        let user_key = format!("user:{}:{}", tenant_id, email);
-        if let Some(data) = self.db.get(&user_key)? {
-            let user: User = bincode::deserialize(&data)?;
-            if user.is_active {
-                Ok(Some(user.role))
+    /// Stage `secret` as the next version of the one at `storage_key`. The
+    /// previous value joins its `secret_versions:` history, capped at
+    /// `SecretPolicy::max_versions`, and its id and `created_at` carry over.
+    fn stage_new_version(&self, batch: &mut sled::Batch, storage_key: &str, mut secret: Secret) -> Result<()> {
+        // Aliases and unreadable records are replaced outright
+        let previous = self.db.get(storage_key)?
+            .and_then(|data| bincode::deserialize::<Secret>(&data).ok())
+            .filter(|previous| previous.metadata.alias_of.is_none());
+        
+        if let Some(previous) = previous {
+            secret.metadata.id = previous.metadata.id;
+            secret.metadata.created_at = previous.metadata.created_at;
+            secret.metadata.created_by = previous.metadata.created_by.clone();
+            secret.metadata.version = previous.metadata.version + 1;
+            
+            let ciphertext = if previous.chunks > 0 {
+                self.read_chunks(&previous)?
             } else {
-                Ok(None)
+                previous.encrypted_value.ciphertext.clone()
+            };
+            let mut history = self.load_history(&versions_key(storage_key))?;
+            history.push(SecretVersion {
+                version: previous.metadata.version,
+                encrypted_value: EncryptedData { ciphertext, ..previous.encrypted_value },
+                encrypted_dek: previous.encrypted_dek,
+                namespace_key: previous.namespace_key,
+                binary: previous.metadata.binary,
+                created_at: previous.metadata.updated_at,
+                created_by: previous.metadata.updated_by,
+                change_description: None,
+                chunks: 0,
+            });
+            
+            let max_versions = SecretPolicy::default().max_versions.map_or(usize::MAX, |max| max as usize);
+            if history.len() > max_versions {
+                history.drain(..history.len() - max_versions);
             }
-        } else {
-            Ok(None)
+            self.stage_history(batch, storage_key, history)?;
         }
+        
+        self.stage_secret(batch, storage_key, secret)
     }
     
-    #[allow(dead_code)]
-    pub async fn add_user(&self, tenant_id: &str, email: &str, role: crate::auth::Role, password_hash: Option<[u8; 32]>) -> Result<()> {
-        let mut user = User::new(email.to_string(), tenant_id.to_string(), role.clone());
-        if let Some(hash) = password_hash {
-            user = user.with_password(hash);
+    /// Add the removal of the secret at `storage_key` to `batch`: the record,
+    /// its chunks and history, and its access password, so a new secret with
+    /// this name starts unprotected.
+    fn stage_removal(&self, batch: &mut sled::Batch, storage_key: &str) -> Result<()> {
+        batch.remove(storage_key.as_bytes());
+        batch.remove(format!("secret_pwd:{}", storage_key.trim_start_matches("secret:")).as_bytes());
+        batch.remove(versions_key(storage_key).as_bytes());
+        for chunk_key in self.db.scan_prefix(chunk_prefix(storage_key).as_bytes()).keys() {
+            batch.remove(chunk_key?);
         }
-        
-        let user_key = format!("user:{}:{}", tenant_id, email);
-        let user_data = bincode::serialize(&user)?;
-        self.db.insert(user_key, user_data)?;
-        self.db.flush()?;
-        
-        self.log_audit_event(tenant_id, "user_added", &format!("User {} added with role {:?}", email, role)).await?;
-        
         Ok(())
     }
     
-    pub async fn remove_user(&self, tenant_id: &str, email: &str) -> Result<()> {
-        let user_key = format!("user:{}:{}", tenant_id, email);
-        if self.db.remove(&user_key)?.is_some() {
-            self.db.flush()?;
-            self.log_audit_event(tenant_id, "user_removed", &format!("User {} removed", email)).await?;
-            Ok(())
+    /// Fail with `QuotaExceeded` if writing the `(namespace, storage key)`
+    /// pairs in `writes` and removing `removals` would take the tenant past
+    /// its `max_secrets` or `max_namespaces`. Overwrites never count.
+    fn ensure_within_quota(&self, tenant_id: &str, writes: &[(&str, &str)], removals: &[&str]) -> Result<()> {
+        let Some(tenant) = self.get_tenant(tenant_id)? else {
+            return Ok(());
+        };
+        let settings = &tenant.settings;
+        if settings.max_secrets.is_none() && settings.max_namespaces.is_none() {
+            return Ok(());
+        }
+        
+        let mut secrets = std::collections::HashSet::new();
+        let mut namespaces = std::collections::HashSet::new();
+        for result in self.db.scan_prefix(secret_prefix(tenant_id, None)) {
+            let (key, data) = result?;
+            let storage_key = String::from_utf8_lossy(&key).into_owned();
+            if removals.contains(&storage_key.as_str()) {
+                continue;
+            }
+            if let Ok(secret) = bincode::deserialize::<Secret>(&data) {
+                namespaces.insert(secret.metadata.namespace);
+            }
+            secrets.insert(storage_key);
+        }
+        
+        let before = (secrets.len(), namespaces.len());
+        for (namespace, storage_key) in writes {
+            secrets.insert(storage_key.to_string());
+            namespaces.insert(namespace.to_string());
+        }
+        
+        if let Some(max) = settings.max_secrets {
+            if secrets.len() > before.0 && secrets.len() > max {
+                return Err(VaultError::QuotaExceeded(format!("tenant {} is limited to {} secrets", tenant_id, max)));
+            }
+        }
+        if let Some(max) = settings.max_namespaces {
+            if namespaces.len() > before.1 && namespaces.len() > max {
+                return Err(VaultError::QuotaExceeded(format!("tenant {} is limited to {} namespaces", tenant_id, max)));
+            }
+        }
+        Ok(())
+    }
+    
+    /// The current tenant's `(max_secrets, max_namespaces)`, or `None` when
+    /// no tenant is unlocked.
+    pub fn tenant_limits(&self) -> Result<Option<(Option<usize>, Option<usize>)>> {
+        let Some(tenant_id) = &self.current_tenant else {
+            return Ok(None);
+        };
+        Ok(self.get_tenant(tenant_id)?
+            .map(|tenant| (tenant.settings.max_secrets, tenant.settings.max_namespaces)))
+    }
+    
+    /// Add the writes that store `secret` under `storage_key` to `batch`.
+    /// Ciphertext over `CHUNK_SIZE` is split across `secret_chunk:` records
+    /// so sled never holds one huge value. A record that still points at its
+    /// chunks (`chunks > 0`) keeps them; otherwise old chunks are removed.
+    /// Chunks of history versions are left to `stage_history`.
+    fn stage_secret(&self, batch: &mut sled::Batch, storage_key: &str, mut secret: Secret) -> Result<()> {
+        if secret.chunks == 0 {
+            let chunk_prefix = chunk_prefix(storage_key);
+            for existing in self.db.scan_prefix(chunk_prefix.as_bytes()).keys() {
+                let existing = existing?;
+                if existing.get(chunk_prefix.len()).is_some_and(u8::is_ascii_digit) {
+                    batch.remove(existing);
+                }
+            }
+            
+            if secret.encrypted_value.ciphertext.len() > CHUNK_SIZE {
+                let ciphertext = std::mem::take(&mut secret.encrypted_value.ciphertext);
+                for (index, chunk) in ciphertext.chunks(CHUNK_SIZE).enumerate() {
+                    batch.insert(format!("{}{:06}", chunk_prefix, index).as_bytes(), chunk);
+                    secret.chunks += 1;
+                }
+            }
+        }
+        
+        batch.insert(storage_key.as_bytes(), bincode::serialize(&secret)?);
+        Ok(())
+    }
+    
+    /// Reassemble the ciphertext of a chunked secret.
+    fn read_chunks(&self, secret: &Secret) -> Result<Vec<u8>> {
+        let meta = &secret.metadata;
+        let chunk_prefix = chunk_prefix(&secret_key(&meta.tenant_id, &meta.namespace, &meta.key));
+        
+        let mut ciphertext = Vec::new();
+        for index in 0..secret.chunks {
+            let chunk_key = format!("{}{:06}", chunk_prefix, index);
+            let chunk = self.db.get(&chunk_key)?
+                .ok_or_else(|| VaultError::Corruption(format!("{} is missing", chunk_key)))?;
+            ciphertext.extend_from_slice(&chunk);
+        }
+        Ok(ciphertext)
+    }
+    
+    pub async fn put_with_tags(&self, key: &str, value: &str, namespace: &str, tags: &[String]) -> Result<()> {
+        self.put_with_protection(key, value, namespace, tags, None).await
+    }
+    
+    /// Read a text secret; a value that is not UTF-8 is refused with `BinarySecret`.
+    pub async fn get(&self, key: &str, namespace: &str) -> Result<Option<String>> {
+        self.get_bytes(key, namespace).await?
+            .map(|bytes| String::from_utf8(bytes)
+                .map_err(|_| VaultError::BinarySecret(format!("{}/{}", namespace, key))))
+            .transpose()
+    }
+    
+    /// Read a secret's exact bytes, text or binary, with no UTF-8 conversion.
+    pub async fn get_bytes(&self, key: &str, namespace: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.get_bytes_with_metadata_and_password(key, namespace, None).await?
+            .map(|(bytes, _)| bytes))
+    }
+    
+    pub async fn exists(&self, key: &str, namespace: &str) -> Result<bool> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        Ok(self.db.contains_key(secret_key(tenant_id, namespace, key))?)
+    }
+    
+    /// A secret's metadata without decrypting its value. Nothing is released,
+    /// so unlike `get` this is not audited as an access.
+    pub async fn get_metadata(&self, key: &str, namespace: &str) -> Result<Option<SecretMetadata>> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        Ok(self.load_secret(tenant_id, namespace, key)?
+            .filter(|secret| !secret.metadata.is_expired())
+            .map(|secret| secret.metadata))
+    }
+    
+    pub async fn is_secret_password_protected(&self, key: &str, namespace: &str) -> Result<bool> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let pwd_key = password_key(tenant_id, namespace, key);
+        if self.db.contains_key(&pwd_key)? {
+            return Ok(true);
+        }
+        
+        // An alias is protected if the secret it points at is
+        if let Some(secret) = self.load_secret(tenant_id, namespace, key)? {
+            if let Some(target) = &secret.metadata.alias_of {
+                let (target_ns, target_key) = parse_secret_path(target);
+                let target_pwd_key = password_key(tenant_id, target_ns, target_key);
+                return Ok(self.db.contains_key(&target_pwd_key)?);
+            }
+        }
+        
+        Ok(false)
+    }
+    
+    pub async fn get_with_metadata_and_password(&self, key: &str, namespace: &str, access_password: Option<&str>) -> Result<Option<(String, SecretMetadata)>> {
+        match self.get_bytes_with_metadata_and_password(key, namespace, access_password).await? {
+            Some((bytes, metadata)) => {
+                if metadata.binary {
+                    return Err(VaultError::BinarySecret(format!("{}/{}", namespace, key)));
+                }
+                let value = String::from_utf8(bytes)
+                    .map_err(|_| VaultError::BinarySecret(format!("{}/{}", namespace, key)))?;
+                Ok(Some((value, metadata)))
+            }
+            None => Ok(None),
+        }
+    }
+    
+    /// Like `get_with_metadata_and_password` but returns the exact stored bytes.
+    pub async fn get_bytes_with_metadata_and_password(&self, key: &str, namespace: &str, access_password: Option<&str>) -> Result<Option<(Vec<u8>, SecretMetadata)>> {
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let secret = match self.load_secret(tenant_id, namespace, key)? {
+            Some(secret) if secret.metadata.is_expired() => {
+                self.remove_expired(tenant_id, namespace, key).await?;
+                return Ok(None);
+            }
+            Some(secret) => secret,
+            None => return Ok(None),
+        };
+        
+        // Resolve aliases (one level only) to the secret holding the value
+        let resolved;
+        let (value_ns, value_key, value_secret) = match &secret.metadata.alias_of {
+            Some(target) => {
+                let (target_ns, target_key) = parse_secret_path(target);
+                let target_secret = self.load_secret(tenant_id, target_ns, target_key)?
+                    .filter(|target_secret| !target_secret.metadata.is_expired())
+                    .ok_or_else(|| VaultError::SecretNotFound(target.clone()))?;
+                if target_secret.metadata.alias_of.is_some() {
+                    return Err(VaultError::InvalidAlias(format!(
+                        "{}/{} points at another alias ({})", namespace, key, target
+                    )));
+                }
+                resolved = target_secret;
+                (target_ns, target_key, &resolved)
+            }
+            None => (namespace, key, &secret),
+        };
+        
+        // Check if password protected and validate password
+        let pwd_key = password_key(tenant_id, value_ns, value_key);
+        if let Some(stored_hash) = self.db.get(&pwd_key)? {
+            match access_password {
+                Some(pwd) => {
+                    if !verify_access_password(&stored_hash, pwd)? {
+                        return Err(VaultError::InvalidAccessPassword);
+                    }
+                }
+                None => {
+                    return Err(VaultError::Auth("Secret is password protected".to_string()));
+                }
+            }
+        }
+        
+        let value = self.decrypt_secret(master_key, value_secret)?;
+        
+        let description = match &secret.metadata.alias_of {
+            Some(target) => format!("Secret {} accessed via alias {}/{}", target, namespace, key),
+            None => format!("Secret {}/{} accessed", namespace, key),
+        };
+        self.log_secret_event(tenant_id, AuditLogger::EVENT_SECRET_ACCESSED, namespace, key, &description).await?;
+        
+        Ok(Some((value, secret.metadata)))
+    }
+    
+    /// Values of several `(namespace, key)` secrets in one call, as
+    /// `(namespace, key, value)` in the order asked for. A missing secret has
+    /// no value; any other failure, such as a password-protected or binary
+    /// secret, fails the whole call. Each read is audited as with `get`.
+    pub async fn get_many(&self, keys: &[(String, String)]) -> Result<Vec<(String, String, Option<String>)>> {
+        let mut values = Vec::with_capacity(keys.len());
+        for (namespace, key) in keys {
+            let value = self.get(key, namespace).await?;
+            values.push((namespace.clone(), key.clone(), value));
+        }
+        Ok(values)
+    }
+    
+    /// Create `alias` in `namespace` as a pointer to `target` ("namespace/key").
+    /// Aliases resolve a single level, so pointing at another alias, or making
+    /// an existing alias target into an alias itself, is rejected.
+    /// Every version of `namespace/key`, newest (the current value) first.
+    /// Values stay encrypted; use `get_version` to read one.
+    pub async fn secret_history(&self, key: &str, namespace: &str) -> Result<Option<Vec<SecretVersion>>> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let Some(secret) = self.load_secret(tenant_id, namespace, key)? else {
+            return Ok(None);
+        };
+        let storage_key = secret_key(tenant_id, namespace, key);
+        let mut history = self.load_inline_history(&storage_key)?;
+        
+        let ciphertext = if secret.chunks > 0 {
+            self.read_chunks(&secret)?
         } else {
-            Err(VaultError::Auth(format!("User {} not found", email)))
+            secret.encrypted_value.ciphertext.clone()
+        };
+        history.push(SecretVersion {
+            version: secret.metadata.version,
+            encrypted_value: EncryptedData { ciphertext, ..secret.encrypted_value },
+            encrypted_dek: secret.encrypted_dek,
+            namespace_key: secret.namespace_key,
+            binary: secret.metadata.binary,
+            created_at: secret.metadata.updated_at,
+            created_by: secret.metadata.updated_by,
+            change_description: None,
+            chunks: 0,
+        });
+        history.reverse();
+        Ok(Some(history))
+    }
+    
+    /// Value of `namespace/key` as of `version`, whether current or kept in
+    /// its history. Password-protected secrets are refused.
+    pub async fn get_version(&self, key: &str, namespace: &str, version: u64) -> Result<Option<Vec<u8>>> {
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let Some(secret) = self.load_secret(tenant_id, namespace, key)? else {
+            return Ok(None);
+        };
+        // An alias has no value of its own, only what it replaced
+        if let (Some(target), true) = (&secret.metadata.alias_of, secret.metadata.version == version) {
+            return Err(VaultError::InvalidAlias(format!(
+                "{}/{} is an alias; versions are kept for {}", namespace, key, target
+            )));
         }
+        self.ensure_not_password_protected(tenant_id, namespace, key)?;
+        
+        let value = if secret.metadata.version == version {
+            self.decrypt_secret(master_key, &secret)?
+        } else {
+            let storage_key = secret_key(tenant_id, namespace, key);
+            let history = self.load_history(&versions_key(&storage_key))?;
+            let Some(entry) = history.into_iter().find(|entry| entry.version == version) else {
+                return Ok(None);
+            };
+            let entry = self.inline_version(&storage_key, entry)?;
+            self.decrypt_secret(master_key, &version_record(&secret.metadata, &entry))?
+        };
+        
+        self.log_secret_event(tenant_id, AuditLogger::EVENT_SECRET_ACCESSED, namespace, key, &format!("Secret {}/{} version {} accessed", namespace, key, version)).await?;
+        Ok(Some(value))
     }
     
-    pub async fn list_users(&self, tenant_id: &str) -> Result<Vec<User>> {
-        let prefix = format!("user:{}:", tenant_id);
-        let mut users = Vec::new();
+    /// Restore `version` of `namespace/key` as a new current version. History
+    /// is only appended to, never rewritten. Returns the new version number.
+    pub async fn rollback_secret(&self, key: &str, namespace: &str, version: u64) -> Result<u64> {
+        self.ensure_writable()?;
         
-        for result in self.db.scan_prefix(&prefix) {
-            let (_, data) = result?;
-            let user: User = bincode::deserialize(&data)?;
-            users.push(user);
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let current = self.load_secret(tenant_id, namespace, key)?
+            .ok_or_else(|| VaultError::SecretNotFound(format!("{}/{}", namespace, key)))?;
+        if let (Some(target), true) = (&current.metadata.alias_of, current.metadata.version == version) {
+            return Err(VaultError::InvalidAlias(format!(
+                "{}/{} is an alias; versions are kept for {}", namespace, key, target
+            )));
         }
+        self.ensure_not_password_protected(tenant_id, namespace, key)?;
         
-        users.sort_by(|a, b| a.email.cmp(&b.email));
-        Ok(users)
+        let storage_key = secret_key(tenant_id, namespace, key);
+        let source = if current.metadata.version == version {
+            current.clone()
+        } else {
+            let history = self.load_history(&versions_key(&storage_key))?;
+            let entry = history.into_iter().find(|entry| entry.version == version)
+                .ok_or_else(|| VaultError::SecretNotFound(format!("{}/{} version {}", namespace, key, version)))?;
+            version_record(&current.metadata, &self.inline_version(&storage_key, entry)?)
+        };
+        let value = Zeroizing::new(self.decrypt_secret(master_key, &source)?);
+        
+        let mut secret = self.build_secret(master_key, tenant_id, key, &value, source.metadata.binary, namespace, &current.metadata.tags)?;
+        // Aliases are replaced outright, so number past the history here
+        secret.metadata.version = current.metadata.version + 1;
+        let mut batch = sled::Batch::default();
+        self.stage_new_version(&mut batch, &storage_key, secret)?;
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        let new_version = current.metadata.version + 1;
+        self.log_audit_event(tenant_id, "secret_rolled_back", &format!(
+            "Secret {}/{} rolled back to version {} (now version {})", namespace, key, version, new_version
+        )).await?;
+        
+        Ok(new_version)
     }
     
-    pub async fn change_user_role(&self, tenant_id: &str, email: &str, new_role: crate::auth::Role) -> Result<()> {
-        let user_key = format!("user:{}:{}", tenant_id, email);
-        if let Some(data) = self.db.get(&user_key)? {
-            let mut user: User = bincode::deserialize(&data)?;
-            let old_role = user.role.clone();
-            user.change_role(new_role.clone());
+    /// Rename `old_namespace/old_key` to `new_namespace/new_key`, keeping its
+    /// id, `created_at`, tags, history and access password. Values are bound
+    /// to their location, so every version is re-encrypted for the new one.
+    /// An existing destination is only replaced with `force`.
+    pub async fn move_secret(&self, old_key: &str, old_namespace: &str, new_key: &str, new_namespace: &str, force: bool) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let old_path = format!("{}/{}", old_namespace, old_key);
+        let new_path = format!("{}/{}", new_namespace, new_key);
+        let old_storage_key = secret_key(tenant_id, old_namespace, old_key);
+        let new_storage_key = secret_key(tenant_id, new_namespace, new_key);
+        
+        let mut secret = self.load_secret(tenant_id, old_namespace, old_key)?
+            .ok_or_else(|| VaultError::SecretNotFound(old_path.clone()))?;
+        if old_path == new_path || (!force && self.db.contains_key(&new_storage_key)?) {
+            return Err(VaultError::SecretExists(new_path));
+        }
+        self.ensure_within_quota(tenant_id, &[(new_namespace, new_storage_key.as_str())], &[old_storage_key.as_str()])?;
+        
+        // Aliases name their target by path and would be left dangling
+        self.ensure_not_alias_target(tenant_id, &old_path)?;
+        
+        let mut history = self.load_inline_history(&old_storage_key)?;
+        for entry in &mut history {
+            let value = Zeroizing::new(self.decrypt_secret(master_key, &version_record(&secret.metadata, entry))?);
+            let (encrypted_value, encrypted_dek) = self.encrypt_value(master_key, tenant_id, new_namespace, new_key, &value)?;
+            entry.encrypted_value = encrypted_value;
+            entry.encrypted_dek = encrypted_dek;
+            entry.namespace_key = self.namespace_keys;
+        }
+        
+        let value = Zeroizing::new(self.decrypt_secret(master_key, &secret)?);
+        let (encrypted_value, encrypted_dek) = self.encrypt_value(master_key, tenant_id, new_namespace, new_key, &value)?;
+        secret.metadata.namespace = new_namespace.to_string();
+        secret.metadata.key = new_key.to_string();
+        secret.metadata.updated_at = Utc::now();
+        secret.metadata.updated_by = self.acting_user();
+        secret.encrypted_value = encrypted_value;
+        secret.encrypted_dek = encrypted_dek;
+        secret.namespace_key = self.namespace_keys;
+        secret.chunks = 0;
+        
+        let mut batch = sled::Batch::default();
+        let access_password = self.db.get(password_key(tenant_id, old_namespace, old_key))?;
+        self.stage_removal(&mut batch, &new_storage_key)?;
+        self.stage_removal(&mut batch, &old_storage_key)?;
+        self.stage_secret(&mut batch, &new_storage_key, secret)?;
+        if !history.is_empty() {
+            self.stage_history(&mut batch, &new_storage_key, history)?;
+        }
+        if let Some(hash) = access_password {
+            batch.insert(password_key(tenant_id, new_namespace, new_key).as_bytes(), hash);
+        }
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        self.log_audit_event(tenant_id, "secret_moved", &format!("Secret {} moved to {}", old_path, new_path)).await?;
+        
+        Ok(())
+    }
+    
+    /// Copy `from_namespace/key` to `to_namespace`, under `new_key` if given.
+    /// The value is re-encrypted for its new location and checked to decrypt
+    /// back to the source. A new secret gets a fresh id and `created_at` and
+    /// the source's tags; an existing one gets the value as a new version.
+    pub async fn copy_secret(&self, key: &str, from_namespace: &str, to_namespace: &str, new_key: Option<&str>) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let dest_key = new_key.unwrap_or(key);
+        let from_path = format!("{}/{}", from_namespace, key);
+        let to_path = format!("{}/{}", to_namespace, dest_key);
+        if from_path == to_path {
+            return Err(VaultError::InvalidOperation(format!("cannot copy {} onto itself", from_path)));
+        }
+        
+        let source = self.load_secret(tenant_id, from_namespace, key)?
+            .ok_or_else(|| VaultError::SecretNotFound(from_path.clone()))?;
+        if let Some(target) = &source.metadata.alias_of {
+            return Err(VaultError::InvalidAlias(format!(
+                "{} is an alias of {}; copy the target or create another alias", from_path, target
+            )));
+        }
+        // The copy would not carry the access password
+        self.ensure_not_password_protected(tenant_id, from_namespace, key)?;
+        
+        let value = Zeroizing::new(self.decrypt_secret(master_key, &source)?);
+        let mut secret = self.build_secret(master_key, tenant_id, dest_key, &value, source.metadata.binary, to_namespace, &source.metadata.tags)?;
+        secret.metadata.expires_at = source.metadata.expires_at;
+        if *self.decrypt_secret(master_key, &secret)? != *value {
+            return Err(VaultError::Crypto(format!("copy of {} did not decrypt to the original value", from_path)));
+        }
+        
+        let dest_storage_key = secret_key(tenant_id, to_namespace, dest_key);
+        self.ensure_within_quota(tenant_id, &[(to_namespace, dest_storage_key.as_str())], &[])?;
+        let mut batch = sled::Batch::default();
+        self.stage_new_version(&mut batch, &dest_storage_key, secret)?;
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        self.log_audit_event(tenant_id, "secret_copied", &format!("Secret {} copied to {}", from_path, to_path)).await?;
+        
+        Ok(())
+    }
+    
+    #[allow(dead_code)]
+    pub async fn add_tags(&self, key: &str, namespace: &str, tags: &[String]) -> Result<Vec<String>> {
+        self.update_tags(key, namespace, tags, &[]).await
+    }
+    
+    #[allow(dead_code)]
+    pub async fn remove_tags(&self, key: &str, namespace: &str, tags: &[String]) -> Result<Vec<String>> {
+        self.update_tags(key, namespace, &[], tags).await
+    }
+    
+    /// Add and remove tags of an existing secret and return its new tags.
+    /// Only the metadata is rewritten: the value is not re-encrypted and the
+    /// version stays the same, but `updated_at` moves.
+    pub async fn update_tags(&self, key: &str, namespace: &str, add: &[String], remove: &[String]) -> Result<Vec<String>> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let path = format!("{}/{}", namespace, key);
+        let mut secret = self.load_secret(tenant_id, namespace, key)?
+            .filter(|secret| !secret.metadata.is_expired())
+            .ok_or_else(|| VaultError::SecretNotFound(path.clone()))?;
+        
+        let old_tags = secret.metadata.tags.clone();
+        let mut tags: Vec<String> = Vec::new();
+        for tag in old_tags.iter().chain(add) {
+            if !remove.contains(tag) && !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        if tags == old_tags {
+            return Ok(tags);
+        }
+        
+        let added: Vec<_> = tags.iter().filter(|tag| !old_tags.contains(tag)).map(|tag| format!("+{}", tag)).collect();
+        let removed: Vec<_> = old_tags.iter().filter(|tag| !tags.contains(tag)).map(|tag| format!("-{}", tag)).collect();
+        secret.metadata.tags = tags.clone();
+        secret.metadata.updated_at = Utc::now();
+        
+        let mut batch = sled::Batch::default();
+        self.stage_secret(&mut batch, &secret_key(tenant_id, namespace, key), secret)?;
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        self.log_secret_event(tenant_id, AuditLogger::EVENT_SECRET_UPDATED, namespace, key, &format!(
+            "Tags of {} changed: {}", path, [added, removed].concat().join(", ")
+        )).await?;
+        
+        Ok(tags)
+    }
+    
+    /// Replace tag `old` with `new` on every secret of `namespace`, or of the
+    /// whole tenant, in one batch. A secret that already has `new` just loses
+    /// `old`, so no tag is listed twice. Unreadable records are skipped.
+    /// Returns how many secrets changed.
+    pub async fn rename_tag(&self, old: &str, new: &str, namespace: Option<&str>) -> Result<usize> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let prefix = secret_prefix(tenant_id, namespace);
+        let mut batch = sled::Batch::default();
+        let mut renamed = 0;
+        
+        for result in self.db.scan_prefix(&prefix) {
+            let (key, data) = result?;
+            let key_str = String::from_utf8_lossy(&key).to_string();
+            let Some((_, secret_namespace, _)) = parse_secret_key(&key_str) else {
+                continue;
+            };
+            if namespace.is_some_and(|namespace| namespace != secret_namespace) {
+                continue;
+            }
             
-            let updated_data = bincode::serialize(&user)?;
-            self.db.insert(user_key, updated_data)?;
-            self.db.flush()?;
+            let Ok(mut secret) = bincode::deserialize::<Secret>(&data) else {
+                continue;
+            };
+            let meta = &mut secret.metadata;
+            if meta.is_expired() || !meta.tags.iter().any(|tag| tag == old) {
+                continue;
+            }
             
-            self.log_audit_event(tenant_id, "role_changed", &format!("User {} role changed from {:?} to {:?}", email, old_role, new_role)).await?;
+            if meta.tags.iter().any(|tag| tag == new) {
+                meta.tags.retain(|tag| tag != old);
+            } else {
+                for tag in meta.tags.iter_mut().filter(|tag| *tag == old) {
+                    *tag = new.to_string();
+                }
+            }
+            meta.updated_at = Utc::now();
             
-            Ok(())
-        } else {
-            Err(VaultError::Auth(format!("User {} not found", email)))
+            self.stage_secret(&mut batch, &key_str, secret)?;
+            renamed += 1;
+        }
+        
+        if renamed > 0 {
+            self.db.apply_batch(batch)?;
+            self.db.flush()?;
+            
+            let scope = namespace.map_or_else(|| "all namespaces".to_string(), |namespace| format!("namespace {}", namespace));
+            self.log_audit_event(tenant_id, "tag_renamed", &format!(
+                "Tag {} renamed to {} on {} secret(s) in {}", old, new, renamed, scope
+            )).await?;
+        }
+        
+        Ok(renamed)
+    }
+    
+    /// Delete a secret found expired on read. Read-only handles leave it for
+    /// the next writer or `purge_expired`.
+    async fn remove_expired(&self, tenant_id: &str, namespace: &str, key: &str) -> Result<()> {
+        if self.ensure_writable().is_err() {
+            return Ok(());
+        }
+        
+        let mut batch = sled::Batch::default();
+        self.stage_removal(&mut batch, &secret_key(tenant_id, namespace, key))?;
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        self.log_audit_event(tenant_id, "secret_expired", &format!("Secret {}/{} expired", namespace, key)).await
+    }
+    
+    /// Delete every expired secret of the current tenant in one batch and
+    /// return how many were removed.
+    pub async fn purge_expired(&self) -> Result<usize> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let mut batch = sled::Batch::default();
+        let mut purged = 0;
+        for result in self.db.scan_prefix(secret_prefix(tenant_id, None)) {
+            let (key, data) = result?;
+            let Ok(secret) = bincode::deserialize::<Secret>(&data) else {
+                continue;
+            };
+            if secret.metadata.is_expired() {
+                self.stage_removal(&mut batch, &String::from_utf8_lossy(&key))?;
+                purged += 1;
+            }
         }
+        
+        if purged > 0 {
+            self.db.apply_batch(batch)?;
+            self.db.flush()?;
+            self.log_audit_event(tenant_id, "secrets_expired", &format!("Purged {} expired secrets", purged)).await?;
+        }
+        
+        Ok(purged)
+    }
+    
+    /// Version reads and rollbacks take no access password, so they refuse
+    /// protected secrets.
+    fn ensure_not_password_protected(&self, tenant_id: &str, namespace: &str, key: &str) -> Result<()> {
+        if self.db.contains_key(password_key(tenant_id, namespace, key))? {
+            return Err(VaultError::Auth("Secret is password protected".to_string()));
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+    
+    /// Refuse changes that would leave an alias pointing at `path`
+    /// (`<namespace>/<key>`) dangling. Unreadable records are skipped.
+    fn ensure_not_alias_target(&self, tenant_id: &str, path: &str) -> Result<()> {
+        for result in self.db.scan_prefix(secret_prefix(tenant_id, None)) {
+            let (_, data) = result?;
+            let Ok(other) = bincode::deserialize::<Secret>(&data) else {
+                continue;
+            };
+            if other.metadata.alias_of.as_deref() == Some(path) {
+                return Err(VaultError::InvalidAlias(format!(
+                    "{} is the target of alias {}/{}", path, other.metadata.namespace, other.metadata.key
+                )));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Point `namespace/alias` at `target` (`<namespace>/<key>`). An existing
+    /// secret at the alias key is only replaced with `force`.
+    pub async fn link(&self, alias: &str, namespace: &str, target: &str, force: bool) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let (target_ns, target_key) = parse_secret_path(target);
+        let target = format!("{}/{}", target_ns, target_key);
+        
+        if target_ns == namespace && target_key == alias {
+            return Err(VaultError::InvalidAlias(format!("{} cannot point at itself", target)));
+        }
+        
+        let target_secret = self.load_secret(tenant_id, target_ns, target_key)?
+            .ok_or_else(|| VaultError::SecretNotFound(target.clone()))?;
+        if let Some(next) = &target_secret.metadata.alias_of {
+            return Err(VaultError::InvalidAlias(format!(
+                "{} is itself an alias of {}; link to the canonical secret instead", target, next
+            )));
+        }
+        
+        // Turning a secret that other aliases point at into an alias would
+        // create a chain (or a cycle), so refuse it
+        let alias_path = format!("{}/{}", namespace, alias);
+        self.ensure_not_alias_target(tenant_id, &alias_path)?;
+        
+        let storage_key = secret_key(tenant_id, namespace, alias);
+        if !force && self.db.contains_key(&storage_key)? {
+            return Err(VaultError::SecretExists(alias_path));
+        }
+        
+        let (encrypted_value, encrypted_dek) = self.encrypt_value(master_key, tenant_id, namespace, alias, target.as_bytes())?;
+        
+        let metadata = SecretMetadata {
+            id: Uuid::new_v4(),
+            tenant_id: tenant_id.clone(),
+            namespace: namespace.to_string(),
+            key: alias.to_string(),
+            version: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_by: self.acting_user(),
+            updated_by: self.acting_user(),
+            tags: Vec::new(),
+            alias_of: Some(target.clone()),
+            binary: target_secret.metadata.binary,
+            expires_at: None,
+        };
+        
+        let secret = Secret {
+            metadata,
+            encrypted_value,
+            encrypted_dek,
+            namespace_key: self.namespace_keys,
+            chunks: 0,
+        };
+        
+        self.ensure_within_quota(tenant_id, &[(namespace, storage_key.as_str())], &[])?;
+        // A secret replaced with --force stays in history and can be rolled back to
+        let mut batch = sled::Batch::default();
+        self.stage_new_version(&mut batch, &storage_key, secret)?;
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        self.log_secret_event(tenant_id, AuditLogger::EVENT_SECRET_CREATED, namespace, alias, &format!("Alias {} -> {} created", alias_path, target)).await?;
+        
+        Ok(())
+    }
+    
+    pub async fn get_with_metadata(&self, key: &str, namespace: &str) -> Result<Option<(String, SecretMetadata)>> {
+        self.get_with_metadata_and_password(key, namespace, None).await
+    }
+    
+    pub async fn list(&self, namespace: &str) -> Result<Vec<String>> {
+        let results = self.list_with_metadata(namespace, None, &ListOptions::default()).await?;
+        Ok(results.into_iter().map(|(key, _)| key).collect())
+    }
+    
+    pub async fn list_with_metadata(&self, namespace: &str, tag_filter: Option<&str>, options: &ListOptions) -> Result<Vec<(String, SecretMetadata)>> {
+        Ok(self.list_with_metadata_checked(namespace, tag_filter, options).await?.0)
+    }
+    
+    /// Like `list_with_metadata`, but also returns the storage keys of records
+    /// that could not be deserialized. Those are skipped instead of failing
+    /// the whole listing. Keys are compared as written, not in the order of
+    /// their escaped storage keys, so the whole namespace is read.
+    pub async fn list_with_metadata_checked(&self, namespace: &str, tag_filter: Option<&str>, options: &ListOptions) -> Result<(Vec<(String, SecretMetadata)>, Vec<String>)> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+            
+        let prefix = secret_prefix(tenant_id, Some(namespace));
+        let mut results = Vec::new();
+        let mut corrupted = Vec::new();
+        
+        for result in self.db.scan_prefix(&prefix) {
+            let (key, data) = result?;
+            let key_str = String::from_utf8_lossy(&key).to_string();
+            
+            if let Some((_, _, secret_key)) = parse_secret_key(&key_str) {
+                let secret: Secret = match bincode::deserialize(&data) {
+                    Ok(secret) => secret,
+                    Err(_) => {
+                        corrupted.push(key_str.clone());
+                        continue;
+                    }
+                };
+                
+                // Apply tag filter if specified
+                if let Some(tag) = tag_filter {
+                    if !secret.metadata.tags.contains(&tag.to_string()) {
+                        continue;
+                    }
+                }
+                
+                results.push((secret_key, secret.metadata));
+            }
+        }
+        
+        results.sort_by(|(_, a), (_, b)| {
+            let order = match options.sort_by {
+                SortBy::CreatedAt => a.created_at.cmp(&b.created_at),
+                SortBy::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+                SortBy::Version => a.version.cmp(&b.version),
+                SortBy::Key => std::cmp::Ordering::Equal,
+            };
+            order.then_with(|| a.key.cmp(&b.key))
+        });
+        if options.descending {
+            results.reverse();
+        }
+        
+        let results = results.into_iter()
+            .skip(options.offset)
+            .take(options.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok((results, corrupted))
+    }
+    
+    /// Number of secrets `list_with_metadata` would return, without collecting
+    /// them. Like the listing, unreadable records are skipped.
+    pub async fn count_secrets(&self, namespace: &str, tag_filter: Option<&str>) -> Result<usize> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let prefix = secret_prefix(tenant_id, Some(namespace));
+        let mut count = 0;
+        
+        for result in self.db.scan_prefix(&prefix) {
+            let (_, data) = result?;
+            let Ok(secret) = bincode::deserialize::<Secret>(&data) else {
+                continue;
+            };
+            
+            if let Some(tag) = tag_filter {
+                if !secret.metadata.tags.iter().any(|t| t == tag) {
+                    continue;
+                }
+            }
+            
+            count += 1;
+        }
+        
+        Ok(count)
+    }
+    
+    /// Every namespace of the current tenant that holds at least one secret,
+    /// with its secret count, sorted by name. Namespaces are read from each
+    /// record's metadata rather than split out of the storage key, so names
+    /// containing ':' are reported intact. Unreadable records are skipped.
+    pub async fn list_namespaces(&self) -> Result<Vec<(String, usize)>> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let prefix = secret_prefix(tenant_id, None);
+        let mut counts = std::collections::BTreeMap::new();
+        
+        for result in self.db.scan_prefix(&prefix) {
+            let (_, data) = result?;
+            if let Ok(secret) = bincode::deserialize::<Secret>(&data) {
+                *counts.entry(secret.metadata.namespace).or_insert(0) += 1;
+            }
+        }
+        
+        Ok(counts.into_iter().collect())
+    }
+    
+    /// Move a secret, with its history and access password, to the trash.
+    /// It stays encrypted there until `restore` or `purge_trash`; deleting
+    /// the same name again replaces the earlier trashed copy. A secret that
+    /// aliases point at is refused until they are removed.
+    pub async fn delete(&self, key: &str, namespace: &str) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let storage_key = secret_key(tenant_id, namespace, key);
+        let mut secret = self.load_secret(tenant_id, namespace, key)?
+            .ok_or_else(|| VaultError::SecretNotFound(format!("{}/{}", namespace, key)))?;
+        self.ensure_not_alias_target(tenant_id, &format!("{}/{}", namespace, key))?;
+        if secret.chunks > 0 {
+            secret.encrypted_value.ciphertext = self.read_chunks(&secret)?;
+            secret.chunks = 0;
+        }
+        
+        let trashed = TrashedSecret {
+            history: self.load_inline_history(&storage_key)?,
+            access_password: self.db.get(password_key(tenant_id, namespace, key))?
+                .map(|hash| hash.to_vec()),
+            deleted_at: Utc::now(),
+            secret,
+        };
+        
+        let mut batch = sled::Batch::default();
+        self.stage_removal(&mut batch, &storage_key)?;
+        batch.insert(trash_key(&storage_key).as_bytes(), bincode::serialize(&trashed)?);
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        self.log_secret_event(tenant_id, AuditLogger::EVENT_SECRET_DELETED, namespace, key, &format!("Secret {}/{} moved to trash", namespace, key)).await?;
+        
+        Ok(())
+    }
+    
+    /// Delete a secret for good, bypassing the trash. Like `delete`, refuses
+    /// a secret that aliases point at.
+    pub async fn delete_hard(&self, key: &str, namespace: &str) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+            
+        let storage_key = secret_key(tenant_id, namespace, key);
+        
+        if self.db.contains_key(&storage_key)? {
+            self.ensure_not_alias_target(tenant_id, &format!("{}/{}", namespace, key))?;
+            let mut batch = sled::Batch::default();
+            self.stage_removal(&mut batch, &storage_key)?;
+            self.db.apply_batch(batch)?;
+            self.db.flush()?;
+            
+            // Log audit event
+            self.log_secret_event(tenant_id, AuditLogger::EVENT_SECRET_DELETED, namespace, key, &format!("Secret {}/{} deleted", namespace, key)).await?;
+            
+            Ok(())
+        } else {
+            Err(VaultError::SecretNotFound(format!("{}/{}", namespace, key)))
+        }
+    }
+    
+    /// Secrets in the current tenant's trash as (namespace, key, deleted at),
+    /// most recently deleted first. Unreadable entries are skipped.
+    pub async fn list_trash(&self) -> Result<Vec<(String, String, DateTime<Utc>)>> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let mut entries = Vec::new();
+        for result in self.db.scan_prefix(trash_prefix(tenant_id)) {
+            let (_, data) = result?;
+            if let Ok(trashed) = bincode::deserialize::<TrashedSecret>(&data) {
+                let meta = trashed.secret.metadata;
+                entries.push((meta.namespace, meta.key, trashed.deleted_at));
+            }
+        }
+        
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.2));
+        Ok(entries)
+    }
+    
+    /// Bring a deleted secret back from the trash, with its history and
+    /// access password. Fails if a secret of that name exists again.
+    pub async fn restore(&self, key: &str, namespace: &str) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let path = format!("{}/{}", namespace, key);
+        let storage_key = secret_key(tenant_id, namespace, key);
+        let trash_key = trash_key(&storage_key);
+        let data = self.db.get(&trash_key)?
+            .ok_or_else(|| VaultError::SecretNotFound(format!("{} in trash", path)))?;
+        if self.db.contains_key(&storage_key)? {
+            return Err(VaultError::SecretExists(path));
+        }
+        let trashed: TrashedSecret = bincode::deserialize(&data)
+            .map_err(|e| VaultError::Corruption(format!("{}: {}", trash_key, e)))?;
+        self.ensure_within_quota(tenant_id, &[(namespace, storage_key.as_str())], &[])?;
+        
+        let mut batch = sled::Batch::default();
+        self.stage_secret(&mut batch, &storage_key, trashed.secret)?;
+        if !trashed.history.is_empty() {
+            self.stage_history(&mut batch, &storage_key, trashed.history)?;
+        }
+        if let Some(hash) = trashed.access_password {
+            batch.insert(password_key(tenant_id, namespace, key).as_bytes(), hash);
+        }
+        batch.remove(trash_key.as_bytes());
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        self.log_audit_event(tenant_id, "secret_restored", &format!("Secret {} restored from trash", path)).await?;
+        
+        Ok(())
+    }
+    
+    /// Permanently delete trashed secrets deleted at least `older_than` ago
+    /// (`Duration::zero()` empties the trash). Returns how many were removed.
+    pub async fn purge_trash(&self, older_than: chrono::Duration) -> Result<usize> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let cutoff = Utc::now().checked_sub_signed(older_than)
+            .ok_or_else(|| VaultError::InvalidOperation(format!("Age of {} is out of range", older_than)))?;
+        
+        let mut batch = sled::Batch::default();
+        let mut purged = 0;
+        for result in self.db.scan_prefix(trash_prefix(tenant_id)) {
+            let (key, data) = result?;
+            // Unreadable entries could never be restored
+            if let Ok(trashed) = bincode::deserialize::<TrashedSecret>(&data) {
+                if trashed.deleted_at > cutoff {
+                    continue;
+                }
+            }
+            batch.remove(key);
+            purged += 1;
+        }
+        
+        if purged > 0 {
+            self.db.apply_batch(batch)?;
+            self.db.flush()?;
+            self.log_audit_event(tenant_id, "trash_purged", &format!("Purged {} secrets from trash", purged)).await?;
+        }
+        
+        Ok(purged)
+    }
+    
+    /// Delete every secret in `namespace` of the current tenant, with their
+    /// history, chunks and access passwords, in one batch. Returns how many
+    /// secrets were removed. Aliases in other namespaces that point into it
+    /// would be left dangling, so they make this fail unless `force` is set.
+    pub async fn delete_namespace(&self, namespace: &str, force: bool) -> Result<usize> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let target_prefix = format!("{}/", namespace);
+        let mut doomed = Vec::new();
+        let mut dangling = None;
+        for result in self.db.scan_prefix(secret_prefix(tenant_id, None)) {
+            let (key, data) = result?;
+            let key_str = String::from_utf8_lossy(&key).to_string();
+            let in_namespace = key_str.starts_with(&secret_prefix(tenant_id, Some(namespace)));
+            match bincode::deserialize::<Secret>(&data) {
+                Ok(secret) if secret.metadata.namespace == namespace => doomed.push(key_str),
+                Ok(secret) => {
+                    let points_in = secret.metadata.alias_of.as_deref()
+                        .is_some_and(|target| target.starts_with(&target_prefix));
+                    if points_in && dangling.is_none() {
+                        dangling = Some(format!("{}/{}", secret.metadata.namespace, secret.metadata.key));
+                    }
+                }
+                // Unreadable records go with their namespace
+                Err(_) if in_namespace => doomed.push(key_str),
+                Err(_) => {}
+            }
+        }
+        
+        if doomed.is_empty() {
+            return Ok(0);
+        }
+        if let (Some(alias), false) = (dangling, force) {
+            return Err(VaultError::InvalidAlias(format!("{} points into namespace {}", alias, namespace)));
+        }
+        
+        let mut batch = sled::Batch::default();
+        for storage_key in &doomed {
+            self.stage_removal(&mut batch, storage_key)?;
+        }
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        self.log_audit_event(tenant_id, "namespace_deleted", &format!("Namespace {} deleted ({} secrets)", namespace, doomed.len())).await?;
+        
+        Ok(doomed.len())
+    }
+    
+    /// Secrets whose key or tags contain `query`, ignoring case, or match it
+    /// as a regular expression with `options.regex`. With `options.values`
+    /// every other secret in scope is decrypted and its value checked as
+    /// well; that needs an unlocked vault and skips aliases, binary and
+    /// password-protected secrets, and is audited as one access. Only
+    /// `(namespace, key)` is returned, never the value that matched, along
+    /// with the storage keys of records that could not be read or decrypted,
+    /// which are skipped.
+    pub async fn search(&self, query: &str, namespace_filter: Option<&str>, options: &SearchOptions) -> Result<(Vec<(String, String)>, Vec<String>)> {
+        let mut results = Vec::new();
+        let unreadable = self.visit_search_matches(query, namespace_filter, options, |namespace, key| {
+            results.push((namespace.to_string(), key.to_string()));
+        }).await?;
+        
+        results.sort();
+        Ok((results, unreadable))
+    }
+    
+    /// Number of secrets `search` would return, with the records it skipped.
+    pub async fn count_search(&self, query: &str, namespace_filter: Option<&str>, options: &SearchOptions) -> Result<(usize, Vec<String>)> {
+        let mut count = 0;
+        let unreadable = self.visit_search_matches(query, namespace_filter, options, |_, _| count += 1).await?;
+        Ok((count, unreadable))
+    }
+    
+    async fn visit_search_matches<F: FnMut(&str, &str)>(&self, query: &str, namespace_filter: Option<&str>, options: &SearchOptions, mut on_match: F) -> Result<Vec<String>> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let master_key = if options.values {
+            Some(self.master_key.as_ref().ok_or(VaultError::VaultLocked)?)
+        } else {
+            None
+        };
+        
+        let prefix = secret_prefix(tenant_id, None);
+        let pattern = SearchPattern::new(query, options.regex)?;
+        let mut unreadable = Vec::new();
+        let mut decrypted = 0;
+        
+        for result in self.db.scan_prefix(&prefix) {
+            let (key, data) = result?;
+            let key_str = String::from_utf8(key.to_vec())?;
+            
+            if let Some((_, namespace, secret_key)) = parse_secret_key(&key_str) {
+                let (namespace, secret_key) = (namespace.as_str(), secret_key.as_str());
+                
+                // Apply namespace filter
+                if let Some(ns_filter) = namespace_filter {
+                    if namespace != ns_filter {
+                        continue;
+                    }
+                }
+                
+                // Check if key matches query
+                if pattern.matches(secret_key) {
+                    on_match(namespace, secret_key);
+                    continue;
+                }
+                
+                // Check if tags match query
+                let Ok(secret) = bincode::deserialize::<Secret>(&data) else {
+                    unreadable.push(key_str);
+                    continue;
+                };
+                if secret.metadata.tags.iter().any(|tag| pattern.matches(tag)) {
+                    on_match(namespace, secret_key);
+                    continue;
+                }
+                
+                // Check if the decrypted value matches query
+                if let Some(master_key) = master_key {
+                    let meta = &secret.metadata;
+                    if meta.alias_of.is_some() || meta.binary || meta.is_expired() {
+                        continue;
+                    }
+                    if self.db.contains_key(password_key(tenant_id, namespace, secret_key))? {
+                        continue;
+                    }
+                    
+                    let Ok(plaintext) = self.decrypt_secret(master_key, &secret) else {
+                        unreadable.push(key_str);
+                        continue;
+                    };
+                    decrypted += 1;
+                    let plaintext = zeroize::Zeroizing::new(plaintext);
+                    let value = zeroize::Zeroizing::new(String::from_utf8_lossy(&plaintext).into_owned());
+                    if pattern.matches(&value) {
+                        on_match(namespace, secret_key);
+                    }
+                }
+            }
+        }
+        
+        if decrypted > 0 {
+            self.log_audit_event(tenant_id, AuditLogger::EVENT_SECRET_ACCESSED, &format!(
+                "Values of {} secrets in {} searched", decrypted, namespace_filter.unwrap_or("all namespaces")
+            )).await?;
+        }
+        Ok(unreadable)
+    }
+    
+    /// Group secrets of the current tenant that share an identical value.
+    /// Values are compared by a SHA-256 hash salted per call, so nothing
+    /// derived from the plaintext outlives the scan. Aliases and
+    /// password-protected secrets are skipped.
+    pub async fn find_duplicate_values(&self) -> Result<Vec<Vec<(String, String)>>> {
+        use sha2::{Sha256, Digest};
+        
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let salt = generate_salt();
+        let mut groups: std::collections::HashMap<[u8; 32], Vec<(String, String)>> = std::collections::HashMap::new();
+        
+        for (_, secret) in self.secret_records(tenant_id).await? {
+            let meta = &secret.metadata;
+            if meta.alias_of.is_some() {
+                continue;
+            }
+            
+            let pwd_key = password_key(tenant_id, &meta.namespace, &meta.key);
+            if self.db.contains_key(pwd_key)? {
+                continue;
+            }
+            
+            let plaintext = self.decrypt_secret(master_key, &secret)?;
+            let mut hasher = Sha256::new();
+            hasher.update(salt);
+            hasher.update(&plaintext);
+            groups.entry(hasher.finalize().into())
+                .or_default()
+                .push((meta.namespace.clone(), meta.key.clone()));
+        }
+        
+        let mut duplicates: Vec<Vec<(String, String)>> = groups.into_values()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                group.sort();
+                group
+            })
+            .collect();
+        duplicates.sort();
+        Ok(duplicates)
+    }
+    
+    /// Last known sync state for a tenant, recorded after each push/pull.
+    pub fn load_sync_state(&self, tenant_id: &str) -> Result<Option<crate::sync::SyncMetadata>> {
+        let state_key = format!("sync_state:{}", tenant_id);
+        match self.db.get(&state_key)? {
+            Some(data) => Ok(Some(bincode::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+    
+    pub fn save_sync_state(&self, tenant_id: &str, state: &crate::sync::SyncMetadata) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let state_key = format!("sync_state:{}", tenant_id);
+        self.db.insert(state_key, bincode::serialize(state)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    pub fn append_sync_history(&self, tenant_id: &str, record: &crate::sync::SyncRecord) -> Result<()> {
+        self.ensure_writable()?;
+        
+        // Zero-padded so keys sort chronologically
+        let history_key = format!(
+            "sync_history:{}:{:020}",
+            tenant_id,
+            record.started_at.timestamp_micros()
+        );
+        self.db.insert(history_key, bincode::serialize(record)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    /// Past syncs for a tenant, newest first.
+    pub fn sync_history(&self, tenant_id: &str, limit: Option<usize>) -> Result<Vec<crate::sync::SyncRecord>> {
+        let prefix = format!("sync_history:{}:", tenant_id);
+        let mut records = Vec::new();
+        
+        for item in self.db.scan_prefix(prefix.as_bytes()).rev() {
+            if limit.is_some_and(|limit| records.len() >= limit) {
+                break;
+            }
+            let (_, value) = item?;
+            records.push(bincode::deserialize(&value)?);
+        }
+        
+        Ok(records)
+    }
+    
+    /// Audit entries recorded for a tenant at or after `since`, oldest first.
+    pub fn audit_entries_since(&self, tenant_id: &str, since: Option<DateTime<Utc>>) -> Result<Vec<AuditEntry>> {
+        let prefix = format!("audit:{}:", tenant_id);
+        let mut entries = Vec::new();
+        
+        for item in self.db.scan_prefix(prefix.as_bytes()) {
+            let (_, value) = item?;
+            let entry: AuditEntry = bincode::deserialize(&value)?;
+            if since.is_none_or(|since| entry.timestamp >= since) {
+                entries.push(entry);
+            }
+        }
+        
+        entries.sort_by_key(|entry| entry.timestamp);
+        Ok(entries)
+    }
+    
+    /// Raw secret records for a tenant keyed by storage key, without decrypting.
+    pub async fn secret_records(&self, tenant_id: &str) -> Result<std::collections::HashMap<String, Secret>> {
+        let prefix = secret_prefix(tenant_id, None);
+        let mut records = std::collections::HashMap::new();
+        
+        for result in self.db.scan_prefix(&prefix) {
+            let (key, data) = result?;
+            let key_str = String::from_utf8(key.to_vec())?;
+            let secret: Secret = bincode::deserialize(&data)?;
+            records.insert(key_str, secret);
+        }
+        
+        Ok(records)
+    }
+    
+    /// Every secret record of the unlocked tenant keyed by storage key
+    /// (`secret:{tenant}:{ns}:{key}`). Records are returned as stored, so
+    /// nothing is decrypted.
+    pub async fn export_all_secrets(&self) -> Result<std::collections::HashMap<String, Secret>> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        self.secret_records(tenant_id).await
+    }
+    
+    /// The unlocked tenant's id.
+    pub fn current_tenant_id(&self) -> Result<String> {
+        self.current_tenant.clone().ok_or(VaultError::VaultLocked)
+    }
+    
+    /// The current tenant's secret records with any chunked ciphertext
+    /// joined back inline, ready to be sent to a sync backend.
+    pub async fn sync_records(&self) -> Result<Vec<Secret>> {
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let mut records = Vec::new();
+        for mut secret in self.secret_records(tenant_id).await?.into_values() {
+            if secret.chunks > 0 {
+                secret.encrypted_value.ciphertext = self.read_chunks(&secret)?;
+                secret.chunks = 0;
+            }
+            records.push(secret);
+        }
+        Ok(records)
+    }
+    
+    /// Store a record received from a sync backend as-is, replacing the
+    /// local one. It must belong to the current tenant; its value stays
+    /// bound to the location named in its metadata.
+    pub async fn apply_synced_record(&self, secret: Secret) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let meta = &secret.metadata;
+        if &meta.tenant_id != tenant_id || secret.chunks > 0 {
+            return Err(VaultError::Sync(format!("Refusing record for {}/{} of tenant {}", meta.namespace, meta.key, meta.tenant_id)));
+        }
+        
+        let storage_key = secret_key(tenant_id, &meta.namespace, &meta.key);
+        self.ensure_within_quota(tenant_id, &[(meta.namespace.as_str(), storage_key.as_str())], &[])?;
+        let mut batch = sled::Batch::default();
+        self.stage_secret(&mut batch, &storage_key, secret)?;
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    pub async fn get_stats(&self) -> Result<VaultStats> {
+        let mut secret_count = 0;
+        let mut namespaces = std::collections::HashSet::new();
+        let mut tenants = std::collections::HashSet::new();
+        let mut total_size = 0;
+        let mut audit_size = 0;
+        let mut session_size = 0;
+        let mut internal_size = 0;
+        let mut corrupted_records = Vec::new();
+        
+        for result in self.db.iter() {
+            let (key, value) = result?;
+            let key_str = String::from_utf8_lossy(&key).to_string();
+            let size = value.len() as u64;
+            match key_str.split_once(':').map_or("", |(family, _)| family) {
+                "secret" | "secret_pwd" | "secret_versions" | "secret_chunk" | "trash" => total_size += size,
+                "audit" => audit_size += size,
+                "session_key" | "session_key_wrapped" => session_size += size,
+                _ => internal_size += size,
+            }
+            
+            if key_str.starts_with("secret:") {
+                if bincode::deserialize::<Secret>(&value).is_err() {
+                    corrupted_records.push(key_str);
+                    continue;
+                }
+                secret_count += 1;
+                if let Some((tenant_id, namespace, _)) = parse_secret_key(&key_str) {
+                    namespaces.insert((tenant_id.clone(), namespace));
+                    tenants.insert(tenant_id);
+                }
+            } else if key_str.starts_with("tenant:") {
+                let parts: Vec<&str> = key_str.split(':').collect();
+                if parts.len() >= 2 {
+                    tenants.insert(parts[1].to_string());
+                }
+            }
+        }
+        
+        Ok(VaultStats {
+            secret_count,
+            namespace_count: namespaces.len(),
+            tenant_count: tenants.len(),
+            total_size,
+            audit_size,
+            session_size,
+            internal_size,
+            corrupted_records,
+        })
+    }
+    
+    /// Decrypt every readable secret of the current tenant, collecting what
+    /// could not be recovered instead of stopping at the first bad record.
+    /// Aliases and password-protected secrets are reported as skipped.
+    pub async fn salvage(&self) -> Result<SalvageReport> {
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let mut report = SalvageReport::default();
+        let prefix = secret_prefix(tenant_id, None);
+        for result in self.db.scan_prefix(&prefix) {
+            let (key, data) = match result {
+                Ok(entry) => entry,
+                Err(e) => {
+                    // The tree itself is damaged past this point
+                    report.skipped.push(("<scan>".to_string(), e.to_string()));
+                    break;
+                }
+            };
+            let key_str = String::from_utf8_lossy(&key).to_string();
+            
+            let secret: Secret = match bincode::deserialize(&data) {
+                Ok(secret) => secret,
+                Err(e) => {
+                    report.skipped.push((key_str, format!("unreadable record: {}", e)));
+                    continue;
+                }
+            };
+            if secret.metadata.alias_of.is_some() {
+                report.skipped.push((key_str, "alias".to_string()));
+                continue;
+            }
+            let pwd_key = password_key(tenant_id, &secret.metadata.namespace, &secret.metadata.key);
+            if self.db.contains_key(pwd_key)? {
+                report.skipped.push((key_str, "password protected".to_string()));
+                continue;
+            }
+            
+            match self.decrypt_secret(master_key, &secret) {
+                Ok(value) => report.recovered.push(SalvagedSecret { metadata: secret.metadata, value }),
+                Err(e) => report.skipped.push((key_str, format!("decryption failed: {}", e))),
+            }
+        }
+        
+        Ok(report)
+    }
+    
+    /// Argon2 parameters the current tenant's master key is derived with.
+    pub fn tenant_kdf_params(&self) -> Result<Option<crate::crypto::KeyDerivationParams>> {
+        let Some(tenant_id) = &self.current_tenant else {
+            return Ok(None);
+        };
+        Ok(self.get_tenant(tenant_id)?
+            .map(|tenant| (&tenant.settings.key_derivation_params).into()))
+    }
+    
+    /// Time reading and decrypting up to `limit` secrets of the current
+    /// tenant, and a sled flush.
+    pub async fn benchmark(&self, limit: usize) -> Result<StorageBenchmark> {
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let start = std::time::Instant::now();
+        let mut secrets_read = 0;
+        let prefix = secret_prefix(tenant_id, None);
+        for result in self.db.scan_prefix(&prefix).take(limit) {
+            let (_, data) = result?;
+            let secret: Secret = bincode::deserialize(&data)?;
+            self.decrypt_secret(master_key, &secret)?;
+            secrets_read += 1;
+        }
+        let read_time = start.elapsed();
+        
+        let start = std::time::Instant::now();
+        self.db.flush()?;
+        let flush_time = start.elapsed();
+        
+        Ok(StorageBenchmark { secrets_read, read_time, flush_time })
+    }
+    
+    pub async fn health_check(&self) -> Result<()> {
+        self.ensure_writable()?;
+        
+        // Check if database is accessible
+        self.db.checksum()?;
+        
+        // Check if we can write/read, under a key no scan of user data visits
+        let test_key = "_internal:health";
+        let test_value = b"test";
+        self.db.insert(test_key, test_value)?;
+        
+        if let Some(retrieved) = self.db.get(test_key)? {
+            if retrieved != test_value {
+                return Err(VaultError::Crypto("Health check failed: data corruption".to_string()));
+            }
+        } else {
+            return Err(VaultError::Crypto("Health check failed: data not found".to_string()));
+        }
+        
+        self.db.remove(test_key)?;
+        // Left behind by earlier versions interrupted mid-check
+        self.db.remove("health_check_test")?;
+        self.db.flush()?;
+        
+        Ok(())
+    }
+    
+    /// Key a secret's value (or DEK) is encrypted under: the master key, or
+    /// the namespace subkey for secrets written in per-namespace mode.
+    fn secret_key(master_key: &MasterKey, secret: &Secret) -> Result<MasterKey> {
+        use secrecy::ExposeSecret;
+        if secret.namespace_key {
+            master_key.derive_namespace_key(&secret.metadata.namespace)
+        } else {
+            Ok(MasterKey {
+                key: secrecy::Secret::new(*master_key.key.expose_secret()),
+                algorithm: master_key.algorithm.clone(),
+            })
+        }
+    }
+    
+    /// Encrypt the value of `tenant_id`'s `namespace/key`, bound to that
+    /// location as associated data.
+    fn encrypt_value(&self, master_key: &MasterKey, tenant_id: &str, namespace: &str, key: &str, plaintext: &[u8]) -> Result<(EncryptedData, Option<Vec<u8>>)> {
+        let aad = secret_aad(tenant_id, namespace, key);
+        let scoped_key;
+        let master_key = if self.namespace_keys {
+            scoped_key = master_key.derive_namespace_key(namespace)?;
+            &scoped_key
+        } else {
+            master_key
+        };
+        
+        if self.envelope_encryption {
+            let dek = DataEncryptionKey::generate(master_key.algorithm.clone());
+            let encrypted_value = dek.encrypt_with_aad(plaintext, aad.as_bytes())?;
+            let encrypted_dek = self.wrap_dek(master_key, &dek)?;
+            Ok((encrypted_value, Some(encrypted_dek)))
+        } else {
+            let encrypted_value = master_key.encrypt_with_aad(plaintext, aad.as_bytes())
+                .map_err(|e| VaultError::Crypto(e.to_string()))?;
+            Ok((encrypted_value, None))
+        }
+    }
+    
+    fn decrypt_secret(&self, master_key: &MasterKey, secret: &Secret) -> Result<Vec<u8>> {
+        let meta = &secret.metadata;
+        self.decrypt_secret_with_aad(master_key, secret, secret_aad(&meta.tenant_id, &meta.namespace, &meta.key).as_bytes())
+    }
+    
+    fn decrypt_secret_with_aad(&self, master_key: &MasterKey, secret: &Secret, aad: &[u8]) -> Result<Vec<u8>> {
+        let master_key = &Self::secret_key(master_key, secret)?;
+        
+        let assembled;
+        let encrypted_value = if secret.chunks > 0 {
+            assembled = EncryptedData {
+                ciphertext: self.read_chunks(secret)?,
+                ..secret.encrypted_value.clone()
+            };
+            &assembled
+        } else {
+            &secret.encrypted_value
+        };
+        
+        match &secret.encrypted_dek {
+            Some(encrypted_dek) => {
+                let dek = self.unwrap_dek(master_key, encrypted_dek, encrypted_value.algorithm.clone())?;
+                dek.decrypt_with_aad(encrypted_value, aad)
+            }
+            None => master_key.decrypt_with_aad(encrypted_value, aad),
+        }
+    }
+    
+    fn load_secret(&self, tenant_id: &str, namespace: &str, key: &str) -> Result<Option<Secret>> {
+        let storage_key = secret_key(tenant_id, namespace, key);
+        let Some(data) = self.db.get(&storage_key)? else {
+            return Ok(None);
+        };
+        let secret: Secret = bincode::deserialize(&data)
+            .map_err(|e| VaultError::Corruption(format!("{}: {}", storage_key, e)))?;
+        
+        // A record copied here from another key still names its origin
+        let meta = &secret.metadata;
+        if meta.tenant_id != tenant_id || meta.namespace != namespace || meta.key != key {
+            return Err(VaultError::Corruption(format!(
+                "{} holds the record for {}/{}", storage_key, meta.namespace, meta.key
+            )));
+        }
+        Ok(Some(secret))
+    }
+    
+    fn get_tenant(&self, tenant_id: &str) -> Result<Option<Tenant>> {
+        let key = format!("tenant:{}", tenant_id);
+        if let Some(data) = self.db.get(key)? {
+            let tenant: Tenant = bincode::deserialize(&data)?;
+            Ok(Some(tenant))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    async fn log_audit_event(&self, tenant_id: &str, event_type: &str, description: &str) -> Result<()> {
+        let audit_entry = AuditEntry::new(tenant_id.to_string(), event_type.to_string(), description.to_string(), self.acting_user());
+        self.record_audit_entry(&audit_entry)
+    }
+    
+    /// `log_audit_event` for an event about the secret at `namespace/key`,
+    /// which is recorded as the entry's resource.
+    async fn log_secret_event(&self, tenant_id: &str, event_type: &str, namespace: &str, key: &str, description: &str) -> Result<()> {
+        let audit_entry = AuditEntry::new(tenant_id.to_string(), event_type.to_string(), description.to_string(), self.acting_user())
+            .with_resource("secret".to_string(), format!("{}/{}", namespace, key));
+        self.record_audit_entry(&audit_entry)
+    }
+    
+    /// Write an audit entry under `audit:{tenant}:{nanos}:{id}`; the id keeps
+    /// entries from concurrent commands in the same nanosecond apart.
+    pub fn record_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let key = format!(
+            "audit:{}:{}:{}",
+            entry.tenant_id,
+            entry.timestamp.timestamp_nanos_opt().unwrap_or(0),
+            entry.id
+        );
+        self.db.insert(key, bincode::serialize(entry)?)?;
+        Ok(())
+    }
+    
+    pub async fn get_user_role(&self, tenant_id: &str, email: &str) -> Result<Option<crate::auth::Role>> {
+        let user_key = user_key(tenant_id, email);
+        if let Some(data) = self.db.get(&user_key)? {
+            let user: User = bincode::deserialize(&data)?;
+            if user.is_active {
+                Ok(Some(user.role))
+            } else {
+                Ok(None)
+            }
+        } else {
+            Ok(None)
+        }
+    }
+    
+    #[allow(dead_code)]
+    pub async fn add_user(&self, tenant_id: &str, email: &str, role: crate::auth::Role, password: Option<&str>) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let mut user = User::new(email.to_string(), tenant_id.to_string(), role.clone());
+        if let Some(password) = password {
+            let (salt, hash) = hash_user_password(password)?;
+            user = user.with_password(salt, hash);
+        }
+        
+        let user_key = user_key(tenant_id, email);
+        let user_data = bincode::serialize(&user)?;
+        self.db.insert(user_key, user_data)?;
+        self.db.flush()?;
+        
+        self.log_audit_event(tenant_id, "user_added", &format!("User {} added with role {:?}", email, role)).await?;
+        
+        Ok(())
+    }
+    
+    /// Remove a user from the tenant. The last active admin can't be
+    /// removed, so the tenant always keeps someone who can manage it.
+    pub async fn remove_user(&self, tenant_id: &str, email: &str) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let user_key = user_key(tenant_id, email);
+        if let Some(data) = self.db.get(&user_key)? {
+            self.ensure_not_last_admin(tenant_id, &bincode::deserialize(&data)?).await?;
+        }
+        if self.db.remove(&user_key)?.is_some() {
+            self.db.flush()?;
+            self.log_audit_event(tenant_id, "user_removed", &format!("User {} removed", email)).await?;
+            Ok(())
+        } else {
+            Err(VaultError::Auth(format!("User {} not found", email)))
+        }
+    }
+    
+    pub async fn list_users(&self, tenant_id: &str) -> Result<Vec<User>> {
+        let prefix = user_prefix(tenant_id);
+        let mut users = Vec::new();
+        
+        for result in self.db.scan_prefix(&prefix) {
+            let (_, data) = result?;
+            let user: User = bincode::deserialize(&data)?;
+            users.push(user);
+        }
+        
+        users.sort_by(|a, b| a.email.cmp(&b.email));
+        Ok(users)
+    }
+    
+    /// Refuse to remove or demote `user` when no other active admin would
+    /// be left to manage the tenant.
+    async fn ensure_not_last_admin(&self, tenant_id: &str, user: &User) -> Result<()> {
+        let is_admin = |user: &User| user.is_active && matches!(user.role, crate::auth::Role::Admin);
+        let other_admin = self.list_users(tenant_id).await?
+            .iter()
+            .any(|other| other.email != user.email && is_admin(other));
+        if is_admin(user) && !other_admin {
+            return Err(VaultError::InvalidOperation(format!("{} is the last admin of tenant {}", user.email, tenant_id)));
+        }
+        Ok(())
+    }
+    
+    pub async fn change_user_role(&self, tenant_id: &str, email: &str, new_role: crate::auth::Role) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let user_key = user_key(tenant_id, email);
+        if let Some(data) = self.db.get(&user_key)? {
+            let mut user: User = bincode::deserialize(&data)?;
+            if !matches!(new_role, crate::auth::Role::Admin) {
+                self.ensure_not_last_admin(tenant_id, &user).await?;
+            }
+            let old_role = user.role.clone();
+            user.change_role(new_role.clone());
+            
+            let updated_data = bincode::serialize(&user)?;
+            self.db.insert(user_key, updated_data)?;
+            self.db.flush()?;
+            
+            self.log_audit_event(tenant_id, "role_changed", &format!("User {} role changed from {:?} to {:?}", email, old_role, new_role)).await?;
+            
+            Ok(())
+        } else {
+            Err(VaultError::Auth(format!("User {} not found", email)))
+        }
+    }
+    
+    /// Invite `email` to `tenant_id` with `role`. The invitation is valid for
+    /// seven days; its token is what the invitee passes to `accept_invitation`.
+    pub async fn create_invitation(&self, tenant_id: &str, email: &str, role: crate::auth::Role, invited_by: &str) -> Result<TenantInvitation> {
+        self.ensure_writable()?;
+        
+        if self.db.contains_key(user_key(tenant_id, email))? {
+            return Err(VaultError::InvalidOperation(format!("{} is already a member of tenant {}", email, tenant_id)));
+        }
+        
+        let invitation = TenantInvitation::new(tenant_id.to_string(), email.to_string(), role.clone(), invited_by.to_string());
+        self.db.insert(invitation_key(tenant_id, &invitation.token), bincode::serialize(&invitation)?)?;
+        self.db.flush()?;
+        
+        self.log_audit_event(tenant_id, "user_invited", &format!("User {} invited with role {:?} by {}", email, role, invited_by)).await?;
+        
+        Ok(invitation)
+    }
+    
+    /// Redeem the invitation `token` for `email`: create the user with the
+    /// invited role and mark the invitation accepted, in one batch. Unknown,
+    /// expired and already accepted tokens are rejected.
+    pub async fn accept_invitation(&self, token: &str, email: &str, password: &str) -> Result<User> {
+        self.ensure_writable()?;
+        
+        let (key, mut invitation) = self.find_invitation(token)?
+            .ok_or_else(|| VaultError::Auth("Invalid invitation token".to_string()))?;
+        if invitation.accepted {
+            return Err(VaultError::Auth("Invitation has already been accepted".to_string()));
+        }
+        if !invitation.is_valid() {
+            return Err(VaultError::Auth("Invitation has expired".to_string()));
+        }
+        if !invitation.email.eq_ignore_ascii_case(email) {
+            return Err(VaultError::Auth("Invitation was issued to a different email".to_string()));
+        }
+        
+        let tenant_id = invitation.tenant_id.clone();
+        let (salt, hash) = hash_user_password(password)?;
+        let user = User::new(invitation.email.clone(), tenant_id.clone(), invitation.role.clone())
+            .with_password(salt, hash);
+        invitation.accept();
+        
+        let mut batch = sled::Batch::default();
+        batch.insert(user_key(&tenant_id, &user.email).as_bytes(), bincode::serialize(&user)?);
+        batch.insert(key, bincode::serialize(&invitation)?);
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        
+        self.log_audit_event(&tenant_id, "user_added", &format!("User {} joined with role {:?} (invited by {})", user.email, user.role, invitation.invited_by)).await?;
+        
+        Ok(user)
+    }
+    
+    /// The invitation with `token` in any tenant, with its storage key.
+    fn find_invitation(&self, token: &str) -> Result<Option<(sled::IVec, TenantInvitation)>> {
+        if token.is_empty() || !token.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Ok(None);
+        }
+        let suffix = format!(":{}", token);
+        for result in self.db.scan_prefix("invitation:") {
+            let (key, data) = result?;
+            if key.ends_with(suffix.as_bytes()) {
+                let invitation: TenantInvitation = bincode::deserialize(&data)?;
+                return Ok(Some((key, invitation)));
+            }
+        }
+        Ok(None)
+    }
+    
+    /// Enroll `user_id` of the current tenant in two-factor login. The TOTP
+    /// secret is stored encrypted under the tenant master key.
+    pub async fn set_totp_secret(&self, user_id: &str, secret: &[u8]) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let totp_key = totp_key(tenant_id, user_id);
+        let encrypted = master_key.encrypt_with_aad(secret, totp_key.as_bytes())?;
+        self.db.insert(totp_key.as_bytes(), bincode::serialize(&encrypted)?)?;
+        self.db.flush()?;
+        
+        self.log_audit_event(tenant_id, "2fa_enrolled", &format!("User {} enrolled in two-factor login", user_id)).await?;
+        Ok(())
+    }
+    
+    /// The TOTP secret `user_id` of the current tenant enrolled with, if any.
+    pub fn totp_secret(&self, user_id: &str) -> Result<Option<Zeroizing<Vec<u8>>>> {
+        let master_key = self.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let totp_key = totp_key(tenant_id, user_id);
+        let Some(data) = self.db.get(&totp_key)? else {
+            return Ok(None);
+        };
+        let encrypted: EncryptedData = bincode::deserialize(&data)?;
+        // Still readable after a `vault reencrypt` changed the tenant cipher
+        let secret = master_key.for_algorithm(encrypted.algorithm.clone())
+            .decrypt_with_aad(&encrypted, totp_key.as_bytes())?;
+        Ok(Some(Zeroizing::new(secret)))
+    }
+    
+    /// Check `code` against the TOTP secret of `user_id` of the current
+    /// tenant. A valid code's time step is recorded, and codes from that step
+    /// or an earlier one are refused after it, so a code works only once.
+    pub fn accept_totp_code(&self, user_id: &str, code: &str, unix_time: u64) -> Result<bool> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let Some(secret) = self.totp_secret(user_id)? else {
+            return Ok(false);
+        };
+        let Some(step) = crate::auth::totp_step(&secret, code, unix_time) else {
+            return Ok(false);
+        };
+        
+        let step_key = totp_step_key(tenant_id, user_id);
+        let last_step = match self.db.get(&step_key)? {
+            Some(bytes) => Some(u64::from_be_bytes(bytes.as_ref().try_into()
+                .map_err(|_| VaultError::Corruption(format!("{} is malformed", step_key)))?)),
+            None => None,
+        };
+        if last_step.is_some_and(|last| step <= last) {
+            return Ok(false);
+        }
+        self.db.insert(step_key.as_bytes(), &step.to_be_bytes())?;
+        self.db.flush()?;
+        Ok(true)
+    }
+    
+    /// Remove `user_id`'s TOTP secret, turning two-factor login off for them.
+    pub async fn remove_totp_secret(&self, user_id: &str) -> Result<()> {
+        self.ensure_writable()?;
+        
+        let tenant_id = self.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        self.db.remove(totp_key(tenant_id, user_id))?;
+        self.db.remove(totp_step_key(tenant_id, user_id))?;
+        self.db.flush()?;
+        
+        self.log_audit_event(tenant_id, "2fa_disabled", &format!("Two-factor login disabled for {}", user_id)).await?;
+        Ok(())
+    }
+    
+    /// Re-encrypt the tenant's TOTP secrets from `old_key` to `new_key`.
+    fn rekey_totp_secrets(&self, batch: &mut sled::Batch, tenant_id: &str, old_key: &MasterKey, new_key: &MasterKey) -> Result<()> {
+        for result in self.db.scan_prefix(totp_prefix(tenant_id)) {
+            let (key, data) = result?;
+            let encrypted: EncryptedData = bincode::deserialize(&data)?;
+            let secret = Zeroizing::new(old_key.for_algorithm(encrypted.algorithm.clone()).decrypt_with_aad(&encrypted, &key)?);
+            batch.insert(key.clone(), bincode::serialize(&new_key.encrypt_with_aad(&secret, &key)?)?);
+        }
+        Ok(())
+    }
+}
+
+/// Associated data a secret's value is encrypted with, so the ciphertext
+/// only decrypts under the tenant, namespace and key it was written for.
+/// The parts are escaped, so no two locations share it.
+fn secret_aad(tenant_id: &str, namespace: &str, key: &str) -> String {
+    key_path(tenant_id, namespace, key)
+}
+
+/// Associated data of records written before it was escaped. Only
+/// `migrate_unescaped_aad` uses it, to re-encrypt them.
+fn legacy_secret_aad(tenant_id: &str, namespace: &str, key: &str) -> String {
+    format!("{}:{}:{}", tenant_id, namespace, key)
+}
+
+/// Storage key of a user of a tenant.
+fn user_key(tenant_id: &str, email: &str) -> String {
+    format!("user:{}:{}", escape_key_part(tenant_id), escape_key_part(email))
+}
+
+/// Prefix of every user of a tenant.
+fn user_prefix(tenant_id: &str) -> String {
+    format!("user:{}:", escape_key_part(tenant_id))
+}
+
+/// Key of a user's encrypted TOTP secret; also its associated data.
+fn totp_key(tenant_id: &str, user_id: &str) -> String {
+    format!("totp:{}:{}", escape_key_part(tenant_id), escape_key_part(user_id))
+}
+
+/// Key of the time step of the last TOTP code a user logged in with.
+fn totp_step_key(tenant_id: &str, user_id: &str) -> String {
+    format!("totp_step:{}:{}", escape_key_part(tenant_id), escape_key_part(user_id))
+}
+
+/// Prefix of every TOTP secret of a tenant.
+fn totp_prefix(tenant_id: &str) -> String {
+    format!("totp:{}:", escape_key_part(tenant_id))
+}
+
+fn invitation_key(tenant_id: &str, token: &str) -> String {
+    format!("invitation:{}:{}", escape_key_part(tenant_id), escape_key_part(token))
+}
+
+/// Escape a tenant, namespace or key so it can sit between the `:`
+/// separators of a storage key without being mistaken for one.
+fn escape_key_part(part: &str) -> String {
+    part.replace('%', "%25").replace(':', "%3A")
+}
+
+/// Reverse of `escape_key_part`.
+fn unescape_key_part(part: &str) -> String {
+    part.replace("%3A", ":").replace("%25", "%")
+}
+
+/// `tenant:namespace:key` with each part escaped.
+fn key_path(tenant_id: &str, namespace: &str, key: &str) -> String {
+    format!(
+        "{}:{}:{}",
+        escape_key_part(tenant_id),
+        escape_key_part(namespace),
+        escape_key_part(key)
+    )
+}
+
+/// Storage key of a secret record.
+fn secret_key(tenant_id: &str, namespace: &str, key: &str) -> String {
+    format!("secret:{}", key_path(tenant_id, namespace, key))
+}
+
+/// Storage key of the per-secret password record.
+fn password_key(tenant_id: &str, namespace: &str, key: &str) -> String {
+    format!("secret_pwd:{}", key_path(tenant_id, namespace, key))
+}
+
+/// Prefix of every secret of a tenant, or of one of its namespaces.
+fn secret_prefix(tenant_id: &str, namespace: Option<&str>) -> String {
+    match namespace {
+        Some(namespace) => format!(
+            "secret:{}:{}:",
+            escape_key_part(tenant_id),
+            escape_key_part(namespace)
+        ),
+        None => format!("secret:{}:", escape_key_part(tenant_id)),
+    }
+}
+
+/// Prefix of every trashed secret of a tenant.
+fn trash_prefix(tenant_id: &str) -> String {
+    format!("trash:{}:", escape_key_part(tenant_id))
+}
+
+/// Split a `secret:` storage key back into tenant, namespace and key.
+fn parse_secret_key(storage_key: &str) -> Option<(String, String, String)> {
+    let mut parts = storage_key.strip_prefix("secret:")?.split(':');
+    let (tenant_id, namespace, key) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((
+        unescape_key_part(tenant_id),
+        unescape_key_part(namespace),
+        unescape_key_part(key),
+    ))
+}
+
+/// Key of the version history belonging to a `secret:` record.
+fn versions_key(storage_key: &str) -> String {
+    format!("secret_versions:{}", storage_key.trim_start_matches("secret:"))
+}
+
+/// Key a deleted `secret:` record is kept under in the trash.
+fn trash_key(storage_key: &str) -> String {
+    format!("trash:{}", storage_key.trim_start_matches("secret:"))
+}
+
+/// Apply `rekey` to each version in `history`; returns whether any changed.
+fn rekey_versions(
+    history: &mut [SecretVersion],
+    current: &SecretMetadata,
+    rekey: &mut impl FnMut(&mut Secret) -> Result<bool>,
+) -> Result<bool> {
+    let mut changed = false;
+    for version in history {
+        let mut record = version_record(current, version);
+        if rekey(&mut record)? {
+            version.encrypted_value = record.encrypted_value;
+            version.encrypted_dek = record.encrypted_dek;
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+/// A historical version viewed as a `Secret` at the current one's location,
+/// so it decrypts and re-keys like any stored record.
+fn version_record(current: &SecretMetadata, version: &SecretVersion) -> Secret {
+    Secret {
+        metadata: SecretMetadata {
+            version: version.version,
+            binary: version.binary,
+            alias_of: None,
+            updated_at: version.created_at,
+            ..current.clone()
+        },
+        encrypted_value: version.encrypted_value.clone(),
+        encrypted_dek: version.encrypted_dek.clone(),
+        namespace_key: version.namespace_key,
+        chunks: 0,
+    }
+}
+
+/// Key prefix of the chunk records belonging to a `secret:` record.
+fn chunk_prefix(storage_key: &str) -> String {
+    format!("secret_chunk:{}:", storage_key.trim_start_matches("secret:"))
+}
+
+/// Key prefix of the chunk records belonging to `version` in the history
+/// of a `secret:` record.
+fn version_chunk_prefix(storage_key: &str, version: u64) -> String {
+    format!("{}v{}:", chunk_prefix(storage_key), version)
+}
+
+/// How long opening the vault waits for another process to close it.
+const LOCK_WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Open the sled database at `path`. sled takes an exclusive file lock as
+/// part of opening, so a process holding it is waited on for up to
+/// `LOCK_WAIT` by retrying the open itself rather than probing the lock
+/// first.
+fn open_db(path: &str) -> Result<Db> {
+    let deadline = std::time::Instant::now() + LOCK_WAIT;
+    loop {
+        match sled::open(path) {
+            Ok(db) => return Ok(db),
+            Err(e) if is_lock_contention(&e) => {
+                if std::time::Instant::now() >= deadline {
+                    return Err(VaultError::Locked("another vault process is running".to_string()));
+                }
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+            Err(e @ sled::Error::Corruption { .. }) => {
+                return Err(VaultError::Corruption(format!("{} cannot be opened ({}); restore it from a backup", path, e)));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// sled reports a lock held elsewhere only as a generic I/O error, told
+/// apart by the message it builds for it.
+fn is_lock_contention(error: &sled::Error) -> bool {
+    matches!(error, sled::Error::Io(e)
+        if e.kind() == std::io::ErrorKind::Other && e.to_string().starts_with("could not acquire lock"))
+}
+
+/// Secret writes staged in memory and applied in a single sled transaction.
+pub struct SecretTransaction<'a> {
+    storage: &'a VaultStorage,
+    batch: sled::Batch,
+    staged: usize,
+    targets: Vec<(String, String)>, // (namespace, storage key) of each staged write, for the quota check
+}
+
+impl SecretTransaction<'_> {
+    pub fn put(&mut self, key: &str, value: &str, namespace: &str, tags: &[String]) -> Result<()> {
+        self.stage(key, value.as_bytes(), false, namespace, tags)
+    }
+    
+    pub fn put_bytes(&mut self, key: &str, value: &[u8], namespace: &str, tags: &[String]) -> Result<()> {
+        self.stage(key, value, true, namespace, tags)
+    }
+    
+    fn stage(&mut self, key: &str, value: &[u8], binary: bool, namespace: &str, tags: &[String]) -> Result<()> {
+        let master_key = self.storage.master_key.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let tenant_id = self.storage.current_tenant.as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        
+        let secret = self.storage.build_secret(master_key, tenant_id, key, value, binary, namespace, tags)?;
+        let storage_key = secret_key(tenant_id, namespace, key);
+        self.storage.stage_new_version(&mut self.batch, &storage_key, secret)?;
+        self.targets.push((namespace.to_string(), storage_key));
+        self.staged += 1;
+        Ok(())
+    }
+    
+    pub fn len(&self) -> usize {
+        self.staged
+    }
+    
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.staged == 0
+    }
+    
+    #[allow(dead_code)]
+    pub async fn commit(self) -> Result<usize> {
+        let description = format!("{} secrets written atomically", self.staged);
+        self.commit_as("secrets_committed", &description).await
+    }
+    
+    /// Commit, recording `event_type` and `description` as the single audit
+    /// entry for the write, for callers whose own event describes it better
+    /// than the generic `secrets_committed`.
+    pub async fn commit_as(self, event_type: &str, description: &str) -> Result<usize> {
+        use sled::transaction::TransactionError;
+        
+        if let Some(tenant_id) = &self.storage.current_tenant {
+            let writes: Vec<_> = self.targets.iter()
+                .map(|(namespace, storage_key)| (namespace.as_str(), storage_key.as_str()))
+                .collect();
+            self.storage.ensure_within_quota(tenant_id, &writes, &[])?;
+        }
+        
+        let written = self.staged;
+        self.storage.db.transaction(|tx| {
+            tx.apply_batch(&self.batch)?;
+            Ok::<(), sled::transaction::ConflictableTransactionError<VaultError>>(())
+        }).map_err(|e| match e {
+            TransactionError::Abort(e) => e,
+            TransactionError::Storage(e) => VaultError::Storage(e),
+        })?;
+        self.storage.db.flush()?;
+        
+        if let Some(tenant_id) = &self.storage.current_tenant {
+            self.storage.log_audit_event(tenant_id, event_type, description).await?;
+        }
+        
+        Ok(written)
+    }
+}
+
+/// Hash a per-secret access password as `salt || argon2id(password, salt)`.
+fn hash_access_password(password: &str) -> Result<Vec<u8>> {
+    let salt = generate_salt();
+    let hash = crate::crypto::derive_key_argon2id(password, &salt)?;
+    Ok([salt.as_slice(), hash.as_slice()].concat())
+}
+
+/// Password hash for a collaborative user as `(salt, argon2id(password, salt))`,
+/// stored in the user's `password_salt` and `password_hash`.
+fn hash_user_password(password: &str) -> Result<([u8; 32], [u8; 32])> {
+    let salt = generate_salt();
+    Ok((salt, *crate::crypto::derive_key_argon2id(password, &salt)?))
+}
+
+/// Check an access password against a stored hash. Records written before
+/// hashes were salted hold a bare SHA-256 digest and are still accepted.
+fn verify_access_password(stored: &[u8], password: &str) -> Result<bool> {
+    let expected: Vec<u8> = match stored.len() {
+        64 => {
+            let mut salt = [0u8; 32];
+            salt.copy_from_slice(&stored[..32]);
+            crate::crypto::derive_key_argon2id(password, &salt)?.to_vec()
+        }
+        32 => {
+            use sha2::{Sha256, Digest};
+            Sha256::digest(password.as_bytes()).to_vec()
+        }
+        _ => return Err(VaultError::Crypto("Corrupt access password record".to_string())),
+    };
+    let stored_hash = &stored[stored.len() - 32..];
+    
+    // Compare in constant time
+    Ok(expected.iter().zip(stored_hash).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0)
+}
+
+/// Split a "namespace/key" path; a bare key refers to the default namespace.
+pub fn parse_secret_path(path: &str) -> (&str, &str) {
+    match path.split_once('/') {
+        Some((namespace, key)) => (namespace, key),
+        None => ("default", path),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    async fn unlocked_storage(temp_dir: &TempDir) -> VaultStorage {
+        let db_path = temp_dir.path().join("test.db");
+        let mut storage = VaultStorage::new(db_path.to_str().unwrap()).unwrap();
+        storage.init_tenant_with_password("test-tenant", "admin@test.com", "test-passphrase").await.unwrap();
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        storage
+    }
+    
+    #[tokio::test]
+    async fn test_alias_resolves_to_target() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("db-password", "hunter2", "shared").await.unwrap();
+        storage.link("db", "billing", "shared/db-password", false).await.unwrap();
+        
+        let (value, metadata) = storage.get_with_metadata("db", "billing").await.unwrap().unwrap();
+        assert_eq!(value, "hunter2");
+        assert_eq!(metadata.alias_of.as_deref(), Some("shared/db-password"));
+        
+        // Rotating the canonical secret is visible through the alias
+        storage.put("db-password", "correct-horse", "shared").await.unwrap();
+        assert_eq!(storage.get("db", "billing").await.unwrap(), Some("correct-horse".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_change_passphrase_rewraps_deks() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        storage.set_envelope_encryption(true);
+        
+        let large_value = "x".repeat(512 * 1024);
+        storage.put("tls-bundle", &large_value, "certs").await.unwrap();
+        let before = storage.load_secret("test-tenant", "certs", "tls-bundle").unwrap().unwrap();
+        
+        storage.change_passphrase("test-passphrase", "new-passphrase").await.unwrap();
+        
+        // Only the wrapped DEK changes; the value ciphertext is left as-is
+        let after = storage.load_secret("test-tenant", "certs", "tls-bundle").unwrap().unwrap();
+        assert_eq!(before.encrypted_value.ciphertext, after.encrypted_value.ciphertext);
+        assert_ne!(before.encrypted_dek, after.encrypted_dek);
+        
+        assert!(matches!(storage.unlock("test-tenant", "test-passphrase"), Err(VaultError::InvalidPassphrase)));
+        storage.unlock("test-tenant", "new-passphrase").unwrap();
+        assert_eq!(storage.get("tls-bundle", "certs").await.unwrap(), Some(large_value));
+    }
+    
+    #[tokio::test]
+    async fn test_change_passphrase_reports_progress_and_drops_cached_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        for key in ["a", "b", "c"] {
+            storage.put(key, "value", "default").await.unwrap();
+        }
+        
+        // A wrong current passphrase changes nothing
+        assert!(matches!(
+            storage.change_passphrase("wrong-passphrase", "new-passphrase").await,
+            Err(VaultError::InvalidPassphrase)
+        ));
+        assert!(storage.unlock_from_cache("test-tenant", None).unwrap());
+        
+        let mut reports = Vec::new();
+        let rotated = storage.change_passphrase_with_progress("test-passphrase", "new-passphrase", |done, total| reports.push((done, total))).await.unwrap();
+        assert_eq!(rotated, 3);
+        assert_eq!(reports, vec![(1, 3), (2, 3), (3, 3)]);
+        
+        // Sessions holding the old key have to unlock again
+        assert!(!storage.unlock_from_cache("test-tenant", None).unwrap());
+        assert_eq!(storage.get("b", "default").await.unwrap().as_deref(), Some("value"));
+        storage.cache_session_key().unwrap();
+        assert!(storage.unlock_from_cache("test-tenant", None).unwrap());
+        assert_eq!(storage.get("c", "default").await.unwrap().as_deref(), Some("value"));
+    }
+    
+    #[tokio::test]
+    async fn test_per_namespace_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        storage.put("legacy", "master-keyed", "prod").await.unwrap();
+        
+        storage.set_namespace_keys(true);
+        storage.put("api-key", "scoped", "prod").await.unwrap();
+        
+        let legacy = storage.load_secret("test-tenant", "prod", "legacy").unwrap().unwrap();
+        let scoped = storage.load_secret("test-tenant", "prod", "api-key").unwrap().unwrap();
+        assert!(!legacy.namespace_key);
+        assert!(scoped.namespace_key);
+        
+        // The master key alone no longer opens the value
+        let master_key = storage.master_key.as_ref().unwrap();
+        assert!(master_key.decrypt(&scoped.encrypted_value).is_err());
+        
+        // Both modes stay readable, including after a rotation
+        storage.change_passphrase("test-passphrase", "new-passphrase").await.unwrap();
+        assert_eq!(storage.get("legacy", "prod").await.unwrap(), Some("master-keyed".to_string()));
+        assert_eq!(storage.get("api-key", "prod").await.unwrap(), Some("scoped".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_large_secrets_are_chunked_and_bounded() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        let chunk_count = |storage: &VaultStorage| {
+            storage.db.scan_prefix("secret_chunk:test-tenant:files:blob:").count()
+        };
+        
+        let large = vec![7u8; 300 * 1024];
+        storage.put_bytes("blob", &large, "files", &[]).await.unwrap();
+        let record = storage.load_secret("test-tenant", "files", "blob").unwrap().unwrap();
+        assert!(record.chunks > 1);
+        assert!(record.encrypted_value.ciphertext.is_empty());
+        assert_eq!(chunk_count(&storage), record.chunks as usize);
+        
+        let (value, _) = storage.get_bytes_with_metadata_and_password("blob", "files", None).await.unwrap().unwrap();
+        assert_eq!(value, large);
+        
+        // Overwriting with a small value hands the old chunks to its history
+        storage.put_bytes("blob", b"small", "files", &[]).await.unwrap();
+        assert_eq!(chunk_count(&storage), record.chunks as usize);
+        
+        storage.put_bytes("blob", &large, "files", &[]).await.unwrap();
+        storage.delete("blob", "files").await.unwrap();
+        assert_eq!(chunk_count(&storage), 0);
+        
+        storage.set_max_secret_bytes(1024);
+        assert!(matches!(
+            storage.put_bytes("blob", &large, "files", &[]).await,
+            Err(VaultError::TooLarge(_, 1024))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_chunked_versions_stay_out_of_history_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        let storage_key = secret_key("test-tenant", "files", "blob");
+        
+        let large = vec![7u8; 300 * 1024];
+        storage.put_bytes("blob", &large, "files", &[]).await.unwrap();
+        storage.put_bytes("blob", b"small", "files", &[]).await.unwrap();
+        
+        let history = storage.load_history(&versions_key(&storage_key)).unwrap();
+        assert!(history[0].chunks > 1);
+        assert!(history[0].encrypted_value.ciphertext.is_empty());
+        assert!(storage.db.get(versions_key(&storage_key)).unwrap().unwrap().len() < CHUNK_SIZE);
+        assert_eq!(storage.get_version("blob", "files", 1).await.unwrap().unwrap(), large);
+        
+        // Still readable once restored from the trash and after a rename
+        storage.delete("blob", "files").await.unwrap();
+        storage.restore("blob", "files").await.unwrap();
+        storage.move_secret("blob", "files", "archive", "files", false).await.unwrap();
+        assert_eq!(storage.get_version("archive", "files", 1).await.unwrap().unwrap(), large);
+        assert!(storage.db.scan_prefix(chunk_prefix(&storage_key).as_bytes()).next().is_none());
+        
+        // Versions dropped past `max_versions` take their chunks with them
+        let max_versions = SecretPolicy::default().max_versions.unwrap() as usize;
+        for _ in 0..max_versions {
+            storage.put("archive", "small", "files").await.unwrap();
+        }
+        let archive_key = secret_key("test-tenant", "files", "archive");
+        assert!(storage.db.scan_prefix(chunk_prefix(&archive_key).as_bytes()).next().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_audit_entries_since() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        storage.put("first", "value", "default").await.unwrap();
+        let cutoff = Utc::now();
+        storage.put("second", "value", "default").await.unwrap();
+        
+        let all = storage.audit_entries_since("test-tenant", None).unwrap();
+        assert!(all.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        
+        let recent = storage.audit_entries_since("test-tenant", Some(cutoff)).unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].description, "Secret default/second created");
+    }
+    
+    #[tokio::test]
+    async fn test_tenant_provisioned_with_chosen_crypto() {
+        use crate::crypto::EncryptionAlgorithm;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let mut storage = VaultStorage::new(db_path.to_str().unwrap()).unwrap();
+        let kdf_params = crate::crypto::KeyDerivationParams { memory_cost: 8192, time_cost: 1, parallelism: 1 };
+        storage.init_tenant_with_settings(
+            "test-tenant",
+            "admin@test.com",
+            "test-passphrase",
+            EncryptionAlgorithm::XChaCha20Poly1305,
+            &kdf_params,
+        ).await.unwrap();
+        
+        let tenant = storage.get_tenant("test-tenant").unwrap().unwrap();
+        assert_eq!(tenant.settings.key_derivation_params.memory_cost, 8192);
+        
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        storage.put("api-key", "value", "default").await.unwrap();
+        let record = storage.load_secret("test-tenant", "default", "api-key").unwrap().unwrap();
+        assert_eq!(record.encrypted_value.algorithm, EncryptionAlgorithm::XChaCha20Poly1305);
+        
+        storage.change_passphrase("test-passphrase", "new-passphrase").await.unwrap();
+        storage.unlock("test-tenant", "new-passphrase").unwrap();
+        assert_eq!(storage.get("api-key", "default").await.unwrap(), Some("value".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_benchmark_reads_up_to_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        for i in 0..3 {
+            storage.put(&format!("key-{}", i), "value", "default").await.unwrap();
+        }
+        
+        assert_eq!(storage.benchmark(2).await.unwrap().secrets_read, 2);
+        assert_eq!(storage.benchmark(10).await.unwrap().secrets_read, 3);
+        assert!(storage.tenant_kdf_params().unwrap().is_some());
+    }
+    
+    #[tokio::test]
+    async fn test_audit_entries_are_persisted() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        let entry = AuditEntry::new(
+            "test-tenant".to_string(),
+            AuditLogger::EVENT_SECRET_CREATED.to_string(),
+            "Created secret".to_string(),
+            "admin@test.com".to_string(),
+        )
+        .with_metadata(serde_json::json!({ "key": "api-key" }));
+        let mut twin = entry.clone();
+        twin.id = Uuid::new_v4();
+        AuditLogger::log_event(&storage, &entry).unwrap();
+        AuditLogger::log_event(&storage, &twin).unwrap();
+        
+        let entries = storage.audit_entries_since("test-tenant", Some(entry.timestamp)).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].metadata, Some(serde_json::json!({ "key": "api-key" })));
+        assert_eq!(entries[0].user_id, "admin@test.com");
+    }
+    
+    #[tokio::test]
+    async fn test_put_and_get_are_audited_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("api-key", "value", "default").await.unwrap();
+        storage.get("api-key", "default").await.unwrap();
+        
+        let entries = storage.audit_entries_since("test-tenant", None).unwrap();
+        let count = |event_type: &str| entries.iter()
+            .filter(|entry| entry.event_type == event_type && entry.resource_id.as_deref() == Some("default/api-key"))
+            .count();
+        assert_eq!(count(AuditLogger::EVENT_SECRET_CREATED), 1);
+        assert_eq!(count(AuditLogger::EVENT_SECRET_ACCESSED), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_reading_metadata_is_not_audited_as_an_access() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("api-key", "value", "default").await.unwrap();
+        let metadata = storage.get_metadata("api-key", "default").await.unwrap().unwrap();
+        assert_eq!(metadata.key, "api-key");
+        assert!(storage.get_metadata("missing", "default").await.unwrap().is_none());
+        
+        let entries = storage.audit_entries_since("test-tenant", None).unwrap();
+        assert!(!entries.iter().any(|entry| entry.event_type == AuditLogger::EVENT_SECRET_ACCESSED));
+    }
+    
+    #[tokio::test]
+    async fn test_export_all_secrets_covers_the_unlocked_tenant() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        storage.put("api-key", "value", "default").await.unwrap();
+        storage.put("db:url", "value", "prod").await.unwrap();
+        storage.init_tenant_with_password("other-tenant", "admin@test.com", "other-passphrase").await.unwrap();
+        
+        let records = storage.export_all_secrets().await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[&secret_key("test-tenant", "prod", "db:url")].metadata.key, "db:url");
+        
+        storage.lock().unwrap();
+        assert!(matches!(storage.export_all_secrets().await, Err(VaultError::VaultLocked)));
+    }
+    
+    #[tokio::test]
+    async fn test_cached_session_key_is_wrapped() {
+        use secrecy::ExposeSecret;
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        let key = *storage.master_key.as_ref().unwrap().key.expose_secret();
+        
+        let cached = storage.db.get("session_key_wrapped:test-tenant").unwrap().unwrap();
+        assert!(!cached.windows(32).any(|window| window == key));
+        assert!(storage.unlock_from_cache("test-tenant", None).unwrap());
+        assert_eq!(*storage.master_key.as_ref().unwrap().key.expose_secret(), key);
+        
+        // Plaintext entries left by older versions are dropped on open
+        storage.db.insert("session_key:test-tenant", bincode::serialize(&(key, crate::crypto::EncryptionAlgorithm::Aes256Gcm)).unwrap()).unwrap();
+        storage.purge_plaintext_session_keys().unwrap();
+        assert!(storage.db.get("session_key:test-tenant").unwrap().is_none());
+        
+        storage.clear_session_key("test-tenant").unwrap();
+        assert!(storage.db.get("session_key_wrapped:test-tenant").unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_relocated_ciphertext_does_not_decrypt() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("db-password", "prod-value", "prod").await.unwrap();
+        storage.put("api-key", "dev-value", "dev").await.unwrap();
+        
+        // Copy prod/db-password's record over dev/api-key
+        let prod = storage.db.get("secret:test-tenant:prod:db-password").unwrap().unwrap();
+        storage.db.insert("secret:test-tenant:dev:api-key", prod.clone()).unwrap();
+        assert!(storage.get("api-key", "dev").await.is_err());
+        
+        // Rewriting the metadata to match doesn't help: the AAD no longer matches
+        let mut secret: Secret = bincode::deserialize(&prod).unwrap();
+        secret.metadata.namespace = "dev".to_string();
+        secret.metadata.key = "api-key".to_string();
+        storage.db.insert("secret:test-tenant:dev:api-key", bincode::serialize(&secret).unwrap()).unwrap();
+        assert!(matches!(storage.get("api-key", "dev").await, Err(VaultError::Crypto(_))));
+        
+        assert_eq!(storage.get("db-password", "prod").await.unwrap(), Some("prod-value".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_tenant_stores_verifier_not_key() {
+        use secrecy::ExposeSecret;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        let key = *storage.master_key.as_ref().unwrap().key.expose_secret();
+        
+        let tenant = storage.get_tenant("test-tenant").unwrap().unwrap();
+        assert_eq!(tenant.password_hash, [0u8; 32]);
+        let verifier = storage.db.get("verifier:test-tenant").unwrap().unwrap();
+        assert!(!verifier.windows(32).any(|w| w == key));
+        assert!(matches!(storage.unlock("test-tenant", "wrong"), Err(VaultError::InvalidPassphrase)));
+        
+        // A tenant from before verifiers: the key itself is stored as the hash
+        let mut tenant = tenant;
+        tenant.password_hash = key;
+        storage.db.insert("tenant:test-tenant", bincode::serialize(&tenant).unwrap()).unwrap();
+        storage.db.remove("verifier:test-tenant").unwrap();
+        
+        assert!(matches!(storage.unlock("test-tenant", "wrong"), Err(VaultError::InvalidPassphrase)));
+        assert!(storage.db.get("verifier:test-tenant").unwrap().is_none());
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        
+        // The first successful unlock migrates it
+        assert!(storage.db.get("verifier:test-tenant").unwrap().is_some());
+        assert_eq!(storage.get_tenant("test-tenant").unwrap().unwrap().password_hash, [0u8; 32]);
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        assert_eq!(*storage.master_key.as_ref().unwrap().key.expose_secret(), key);
+    }
+    
+    #[tokio::test]
+    async fn test_overwrites_keep_version_history() {
+        use crate::crypto::EncryptionAlgorithm;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("api-key", "first", "prod").await.unwrap();
+        let (_, original) = storage.get_with_metadata("api-key", "prod").await.unwrap().unwrap();
+        storage.put("api-key", "second", "prod").await.unwrap();
+        storage.put("api-key", "third", "prod").await.unwrap();
+        
+        let (value, metadata) = storage.get_with_metadata("api-key", "prod").await.unwrap().unwrap();
+        assert_eq!(value, "third");
+        assert_eq!(metadata.version, 3);
+        assert_eq!(metadata.id, original.id);
+        assert_eq!(metadata.created_at, original.created_at);
+        assert_eq!(storage.get_version("api-key", "prod", 1).await.unwrap(), Some(b"first".to_vec()));
+        assert_eq!(storage.get_version("api-key", "prod", 3).await.unwrap(), Some(b"third".to_vec()));
+        assert_eq!(storage.get_version("api-key", "prod", 4).await.unwrap(), None);
+        
+        // History follows the key through rotation and re-encryption
+        storage.change_passphrase("test-passphrase", "new-passphrase").await.unwrap();
+        storage.reencrypt_secrets(EncryptionAlgorithm::XChaCha20Poly1305).await.unwrap();
+        assert_eq!(storage.get_version("api-key", "prod", 2).await.unwrap(), Some(b"second".to_vec()));
+        
+        // Only the newest `max_versions` earlier values are kept
+        let max_versions = SecretPolicy::default().max_versions.unwrap() as u64;
+        for i in 4..=max_versions + 4 {
+            storage.put("api-key", &format!("value-{}", i), "prod").await.unwrap();
+        }
+        let history = storage.load_history("secret_versions:test-tenant:prod:api-key").unwrap();
+        assert_eq!(history.len() as u64, max_versions);
+        assert_eq!(history.first().unwrap().version, 4);
+        assert_eq!(storage.get_version("api-key", "prod", 3).await.unwrap(), None);
+        
+        storage.delete("api-key", "prod").await.unwrap();
+        assert!(storage.db.get("secret_versions:test-tenant:prod:api-key").unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_move_secret_keeps_identity_and_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put_with_tags("db-password", "first", "dev", &["db".to_string()]).await.unwrap();
+        storage.put_with_tags("db-password", "second", "dev", &["db".to_string()]).await.unwrap();
+        storage.put("db-password", "taken", "prod").await.unwrap();
+        let (_, before) = storage.get_with_metadata("db-password", "dev").await.unwrap().unwrap();
+        
+        assert!(matches!(
+            storage.move_secret("db-password", "dev", "db-password", "prod", false).await,
+            Err(VaultError::SecretExists(_))
+        ));
+        storage.move_secret("db-password", "dev", "db-password", "prod", true).await.unwrap();
+        
+        assert_eq!(storage.get("db-password", "dev").await.unwrap(), None);
+        let (value, after) = storage.get_with_metadata("db-password", "prod").await.unwrap().unwrap();
+        assert_eq!(value, "second");
+        assert_eq!(after.id, before.id);
+        assert_eq!(after.created_at, before.created_at);
+        assert_eq!(after.tags, before.tags);
+        assert_eq!(after.version, 2);
+        // The replaced destination's history is gone; the moved one re-encrypted
+        assert_eq!(storage.get_version("db-password", "prod", 1).await.unwrap(), Some(b"first".to_vec()));
+        assert!(storage.db.get("secret_versions:test-tenant:dev:db-password").unwrap().is_none());
+        
+        // A secret an alias points at stays put
+        storage.link("db-alias", "default", "prod/db-password", false).await.unwrap();
+        assert!(matches!(
+            storage.move_secret("db-password", "prod", "renamed", "prod", false).await,
+            Err(VaultError::InvalidAlias(_))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_copy_secret_to_another_namespace() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put_with_tags("api-key", "staging-value", "staging", &["api".to_string()]).await.unwrap();
+        let (_, source) = storage.get_with_metadata("api-key", "staging").await.unwrap().unwrap();
+        
+        storage.copy_secret("api-key", "staging", "prod", None).await.unwrap();
+        storage.copy_secret("api-key", "staging", "prod", Some("api-key-v2")).await.unwrap();
+        
+        let (value, copy) = storage.get_with_metadata("api-key", "prod").await.unwrap().unwrap();
+        assert_eq!(value, "staging-value");
+        assert_ne!(copy.id, source.id);
+        assert_eq!(copy.tags, source.tags);
+        assert_eq!(storage.get("api-key-v2", "prod").await.unwrap(), Some("staging-value".to_string()));
+        assert_eq!(storage.get("api-key", "staging").await.unwrap(), Some("staging-value".to_string()));
+        
+        assert!(matches!(
+            storage.copy_secret("api-key", "staging", "staging", None).await,
+            Err(VaultError::InvalidOperation(_))
+        ));
+        assert!(matches!(
+            storage.copy_secret("missing", "staging", "prod", None).await,
+            Err(VaultError::SecretNotFound(_))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_rollback_appends_a_new_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put_with_tags("api-key", "first", "prod", &["ci".to_string()]).await.unwrap();
+        storage.put_with_tags("api-key", "second", "prod", &["ci".to_string()]).await.unwrap();
+        
+        assert_eq!(storage.rollback_secret("api-key", "prod", 1).await.unwrap(), 3);
+        let (value, metadata) = storage.get_with_metadata("api-key", "prod").await.unwrap().unwrap();
+        assert_eq!(value, "first");
+        assert_eq!(metadata.version, 3);
+        assert_eq!(metadata.tags, vec!["ci".to_string()]);
+        
+        let history = storage.secret_history("api-key", "prod").await.unwrap().unwrap();
+        assert_eq!(history.iter().map(|v| v.version).collect::<Vec<_>>(), vec![3, 2, 1]);
+        assert_eq!(storage.get_version("api-key", "prod", 2).await.unwrap(), Some(b"second".to_vec()));
+        
+        assert!(matches!(
+            storage.rollback_secret("api-key", "prod", 7).await,
+            Err(VaultError::SecretNotFound(path)) if path == "prod/api-key version 7"
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_list_namespaces_counts_secrets() {
+        let dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&dir).await;
+        
+        storage.put("a", "1", "default").await.unwrap();
+        storage.put("b", "2", "default").await.unwrap();
+        storage.put("url", "3", "team:prod").await.unwrap();
+        
+        assert_eq!(
+            storage.list_namespaces().await.unwrap(),
+            vec![("default".to_string(), 2), ("team:prod".to_string(), 1)]
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_delete_namespace_removes_only_that_namespace() {
+        let dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&dir).await;
+        
+        storage.put("a", "1", "staging").await.unwrap();
+        storage.put("a", "2", "staging").await.unwrap();
+        storage.put("b", "3", "staging").await.unwrap();
+        storage.put("a", "4", "staging:old").await.unwrap();
+        storage.put("a", "5", "prod").await.unwrap();
+        storage.link("link", "prod", "staging/b", false).await.unwrap();
+        
+        assert!(matches!(
+            storage.delete_namespace("staging", false).await,
+            Err(VaultError::InvalidAlias(_))
+        ));
+        assert_eq!(storage.delete_namespace("staging", true).await.unwrap(), 2);
+        
+        let tenant_id = storage.current_tenant.clone().unwrap();
+        assert!(storage.get("a", "staging").await.unwrap().is_none());
+        assert!(storage.get("b", "staging").await.unwrap().is_none());
+        assert!(storage.db.scan_prefix(format!("secret_versions:{}:staging:", tenant_id)).next().is_none());
+        assert_eq!(storage.get("a", "staging:old").await.unwrap().as_deref(), Some("4"));
+        assert_eq!(storage.get("a", "prod").await.unwrap().as_deref(), Some("5"));
+        assert_eq!(storage.delete_namespace("staging", false).await.unwrap(), 0);
+    }
+    
+    #[tokio::test]
+    async fn test_expired_secrets_read_as_missing_and_are_purged() {
+        let dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&dir).await;
+        let tenant_id = storage.current_tenant.clone().unwrap();
+        
+        storage.put_with_ttl("token", "short-lived", "ci", chrono::Duration::hours(1)).await.unwrap();
+        assert_eq!(storage.get("token", "ci").await.unwrap().as_deref(), Some("short-lived"));
+        
+        storage.put_with_ttl("token", "gone", "ci", chrono::Duration::seconds(-1)).await.unwrap();
+        assert!(storage.get("token", "ci").await.unwrap().is_none());
+        assert!(!storage.db.contains_key(format!("secret:{}:ci:token", tenant_id)).unwrap());
+        
+        storage.put_with_ttl("a", "1", "ci", chrono::Duration::seconds(-1)).await.unwrap();
+        storage.put_with_ttl("b", "2", "ci", chrono::Duration::seconds(-1)).await.unwrap();
+        storage.put("c", "3", "ci").await.unwrap();
+        assert_eq!(storage.purge_expired().await.unwrap(), 2);
+        assert_eq!(storage.list("ci").await.unwrap(), vec!["c".to_string()]);
+        
+        // A put without a TTL makes the secret permanent again
+        storage.put_with_ttl("c", "4", "ci", chrono::Duration::seconds(-1)).await.unwrap();
+        storage.put("c", "5", "ci").await.unwrap();
+        assert_eq!(storage.purge_expired().await.unwrap(), 0);
+    }
+    
+    #[tokio::test]
+    async fn test_deleted_secrets_can_be_restored_from_trash() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&dir).await;
+        
+        storage.put("db-url", "postgres://old", "prod").await.unwrap();
+        storage.put("db-url", "postgres://new", "prod").await.unwrap();
+        storage.put_with_protection("pin", "1234", "prod", &[], Some("access")).await.unwrap();
+        storage.delete("db-url", "prod").await.unwrap();
+        storage.delete("pin", "prod").await.unwrap();
+        
+        assert!(storage.get("db-url", "prod").await.unwrap().is_none());
+        let trash: Vec<_> = storage.list_trash().await.unwrap().into_iter()
+            .map(|(ns, key, _)| format!("{}/{}", ns, key))
+            .collect();
+        assert_eq!(trash, vec!["prod/pin", "prod/db-url"]);
+        
+        // Trashed secrets follow the master key through a rotation, which an
+        // unreadable trash entry must not abort
+        storage.db.insert("trash:test-tenant:prod:broken", &b"garbage"[..]).unwrap();
+        storage.change_passphrase("test-passphrase", "rotated-passphrase").await.unwrap();
+        storage.db.remove("trash:test-tenant:prod:broken").unwrap();
+        
+        storage.restore("db-url", "prod").await.unwrap();
+        assert_eq!(storage.get("db-url", "prod").await.unwrap().as_deref(), Some("postgres://new"));
+        assert_eq!(storage.get_version("db-url", "prod", 1).await.unwrap().as_deref(), Some(&b"postgres://old"[..]));
+        assert!(matches!(storage.restore("db-url", "prod").await, Err(VaultError::SecretNotFound(_))));
+        
+        storage.restore("pin", "prod").await.unwrap();
+        assert!(storage.is_secret_password_protected("pin", "prod").await.unwrap());
+        
+        storage.delete("pin", "prod").await.unwrap();
+        storage.put("pin", "5678", "prod").await.unwrap();
+        assert!(matches!(storage.restore("pin", "prod").await, Err(VaultError::SecretExists(_))));
+        
+        assert_eq!(storage.purge_trash(chrono::Duration::days(1)).await.unwrap(), 0);
+        assert_eq!(storage.purge_trash(chrono::Duration::zero()).await.unwrap(), 1);
+        assert!(storage.list_trash().await.unwrap().is_empty());
+        
+        storage.delete_hard("pin", "prod").await.unwrap();
+        assert!(storage.list_trash().await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_tenant_quotas_limit_new_secrets_and_namespaces() {
+        let dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&dir).await;
+        
+        let mut tenant = storage.get_tenant("test-tenant").unwrap().unwrap();
+        tenant.settings.max_secrets = Some(2);
+        tenant.settings.max_namespaces = Some(1);
+        storage.db.insert("tenant:test-tenant", bincode::serialize(&tenant).unwrap()).unwrap();
+        
+        storage.put("a", "1", "default").await.unwrap();
+        assert!(matches!(storage.put("b", "2", "prod").await, Err(VaultError::QuotaExceeded(_))));
+        assert!(matches!(storage.copy_secret("a", "default", "prod", None).await, Err(VaultError::QuotaExceeded(_))));
+        // Moving the only secret out of a namespace doesn't add one
+        storage.move_secret("a", "default", "a", "prod", false).await.unwrap();
+        
+        storage.put("b", "2", "prod").await.unwrap();
+        assert!(matches!(storage.put("c", "3", "prod").await, Err(VaultError::QuotaExceeded(_))));
+        // Overwrites are always allowed
+        storage.put("b", "updated", "prod").await.unwrap();
+        
+        let mut tx = storage.transaction().unwrap();
+        tx.put("b", "again", "prod", &[]).unwrap();
+        tx.put("c", "3", "prod", &[]).unwrap();
+        assert!(matches!(tx.commit().await, Err(VaultError::QuotaExceeded(_))));
+        assert_eq!(storage.get("b", "prod").await.unwrap().as_deref(), Some("updated"));
+        
+        assert_eq!(storage.tenant_limits().unwrap(), Some((Some(2), Some(1))));
+    }
+    
+    #[tokio::test]
+    async fn test_overwrites_keep_creator_and_record_updater() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        
+        storage.set_current_user("alice@test.com");
+        storage.put_with_tags("api-key", "first", "prod", &[]).await.unwrap();
+        let (_, created) = storage.get_with_metadata("api-key", "prod").await.unwrap().unwrap();
+        assert_eq!(created.created_by, "alice@test.com");
+        assert_eq!(created.updated_by, "alice@test.com");
+        
+        storage.set_current_user("bob@test.com");
+        storage.put_with_tags("api-key", "second", "prod", &[]).await.unwrap();
+        let (_, updated) = storage.get_with_metadata("api-key", "prod").await.unwrap().unwrap();
+        assert_eq!(updated.id, created.id);
+        assert_eq!(updated.created_at, created.created_at);
+        assert_eq!(updated.created_by, "alice@test.com");
+        assert_eq!(updated.updated_by, "bob@test.com");
+        assert_eq!(updated.version, 2);
+        assert!(updated.updated_at >= created.updated_at);
+        
+        // Each kept version is credited to whoever wrote it
+        let history = storage.secret_history("api-key", "prod").await.unwrap().unwrap();
+        assert_eq!(history.iter().map(|v| v.created_by.as_str()).collect::<Vec<_>>(), ["bob@test.com", "alice@test.com"]);
+    }
+    
+    #[tokio::test]
+    async fn test_audit_events_name_the_acting_user() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("before-login", "value", "default").await.unwrap();
+        storage.set_current_user("alice@test.com");
+        storage.put("api-key", "value", "default").await.unwrap();
+        storage.delete("api-key", "default").await.unwrap();
+        
+        let entries = storage.audit_entries_since("test-tenant", None).unwrap();
+        let by_user = |description: &str| entries.iter()
+            .find(|entry| entry.description.contains(description))
+            .map(|entry| entry.user_id.clone());
+        assert_eq!(by_user("default/before-login").as_deref(), Some("system"));
+        assert_eq!(by_user("default/api-key created").as_deref(), Some("alice@test.com"));
+        assert!(entries.iter()
+            .filter(|entry| entry.description.contains("default/api-key"))
+            .all(|entry| entry.user_id == "alice@test.com"));
+    }
+    
+    #[tokio::test]
+    async fn test_colons_and_unicode_in_names_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("url:primary", "a", "staging").await.unwrap();
+        storage.put("primary", "b", "staging:url").await.unwrap();
+        storage.put("clé:ключ", "c", "日本:ns").await.unwrap();
+        storage.put("50%3A", "d", "staging").await.unwrap();
+        
+        assert_eq!(storage.get("url:primary", "staging").await.unwrap().as_deref(), Some("a"));
+        assert_eq!(storage.get("primary", "staging:url").await.unwrap().as_deref(), Some("b"));
+        assert_eq!(storage.get("clé:ключ", "日本:ns").await.unwrap().as_deref(), Some("c"));
+        assert_eq!(storage.get("50%3A", "staging").await.unwrap().as_deref(), Some("d"));
+        
+        // `staging` does not pick up secrets of `staging:url`, or vice versa
+        let mut keys = storage.list("staging").await.unwrap();
+        keys.sort();
+        assert_eq!(keys, ["50%3A", "url:primary"]);
+        assert_eq!(storage.list("staging:url").await.unwrap(), ["primary"]);
+        assert_eq!(
+            storage.list_namespaces().await.unwrap(),
+            vec![("staging".to_string(), 2), ("staging:url".to_string(), 1), ("日本:ns".to_string(), 1)]
+        );
+        
+        let mut matches = storage.search("primary", None, &SearchOptions::default()).await.unwrap().0;
+        matches.sort();
+        assert_eq!(matches, [
+            ("staging".to_string(), "url:primary".to_string()),
+            ("staging:url".to_string(), "primary".to_string()),
+        ]);
+        
+        storage.delete("url:primary", "staging").await.unwrap();
+        assert_eq!(storage.get("primary", "staging:url").await.unwrap().as_deref(), Some("b"));
+        storage.restore("url:primary", "staging").await.unwrap();
+        assert_eq!(storage.get("url:primary", "staging").await.unwrap().as_deref(), Some("a"));
+        
+        storage.delete_namespace("staging", false).await.unwrap();
+        assert_eq!(storage.get("primary", "staging:url").await.unwrap().as_deref(), Some("b"));
+    }
+    
+    #[tokio::test]
+    async fn test_unescaped_keys_are_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        storage.put("url:primary", "v1", "staging").await.unwrap();
+        storage.put("url:primary", "v2", "staging").await.unwrap();
+        storage.add_user("test-tenant", "ops%team@test.com", crate::auth::Role::Reader, None).await.unwrap();
+        
+        // Rewrite the records the way earlier versions keyed them
+        for (escaped, legacy) in [
+            ("secret:test-tenant:staging:url%3Aprimary", "secret:test-tenant:staging:url:primary"),
+            ("secret_versions:test-tenant:staging:url%3Aprimary", "secret_versions:test-tenant:staging:url:primary"),
+            ("user:test-tenant:ops%25team@test.com", "user:test-tenant:ops%team@test.com"),
+        ] {
+            let data = storage.db.remove(escaped).unwrap().unwrap();
+            storage.db.insert(legacy, data).unwrap();
+        }
+        
+        // Runs on every open; called directly so the test needn't race sled's lock release
+        storage.migrate_unescaped_keys().unwrap();
+        assert!(!storage.db.contains_key("secret:test-tenant:staging:url:primary").unwrap());
+        assert_eq!(storage.get("url:primary", "staging").await.unwrap().as_deref(), Some("v2"));
+        assert_eq!(storage.secret_history("url:primary", "staging").await.unwrap().unwrap().len(), 2);
+        assert!(storage.get_user_role("test-tenant", "ops%team@test.com").await.unwrap().is_some());
+    }
+    
+    #[tokio::test]
+    async fn test_ciphertexts_cannot_move_between_colliding_locations() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        storage.put("b:c", "first", "a").await.unwrap();
+        storage.put("c", "second", "a:b").await.unwrap();
+        
+        // Unescaped, both locations would read "test-tenant:a:b:c"
+        let mut first = storage.load_secret("test-tenant", "a", "b:c").unwrap().unwrap();
+        let second = storage.load_secret("test-tenant", "a:b", "c").unwrap().unwrap();
+        first.encrypted_value = second.encrypted_value;
+        storage.db.insert(secret_key("test-tenant", "a", "b:c"), bincode::serialize(&first).unwrap()).unwrap();
+        assert!(storage.get("b:c", "a").await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_unescaped_aad_is_reencrypted_on_unlock() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        storage.put("url:primary", "v1", "staging").await.unwrap();
+        storage.put("url:primary", "v2", "staging").await.unwrap();
+        storage.set_totp_secret("ops:oncall", b"12345678901234567890").await.unwrap();
+        
+        // Rewrite the value, its history and the TOTP secret the way earlier versions bound them
+        let master_key = storage.master_key.as_ref().unwrap();
+        let legacy_aad = legacy_secret_aad("test-tenant", "staging", "url:primary");
+        let storage_key = secret_key("test-tenant", "staging", "url:primary");
+        let mut secret = storage.load_secret("test-tenant", "staging", "url:primary").unwrap().unwrap();
+        secret.encrypted_value = master_key.encrypt_with_aad(b"v2", legacy_aad.as_bytes()).unwrap();
+        storage.db.insert(&storage_key, bincode::serialize(&secret).unwrap()).unwrap();
+        let mut history = storage.load_history(&versions_key(&storage_key)).unwrap();
+        history[0].encrypted_value = master_key.encrypt_with_aad(b"v1", legacy_aad.as_bytes()).unwrap();
+        storage.db.insert(versions_key(&storage_key), bincode::serialize(&history).unwrap()).unwrap();
+        let legacy_totp_key = "totp:test-tenant:ops:oncall";
+        let totp = master_key.encrypt_with_aad(b"12345678901234567890", legacy_totp_key.as_bytes()).unwrap();
+        storage.db.remove(totp_key("test-tenant", "ops:oncall")).unwrap();
+        storage.db.insert(legacy_totp_key, bincode::serialize(&totp).unwrap()).unwrap();
+        storage.db.remove("aad_escaped:test-tenant").unwrap();
+        
+        storage.lock().unwrap();
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        assert_eq!(storage.get("url:primary", "staging").await.unwrap().as_deref(), Some("v2"));
+        assert_eq!(storage.get_version("url:primary", "staging", 1).await.unwrap().as_deref(), Some(&b"v1"[..]));
+        assert_eq!(storage.totp_secret("ops:oncall").unwrap().unwrap().as_slice(), b"12345678901234567890");
+        assert!(!storage.db.contains_key(legacy_totp_key).unwrap());
+    }
+    
+    #[tokio::test]
+    async fn test_format_1_records_are_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        // A record as the first release wrote it: the original metadata
+        // fields and a version 1 ciphertext without associated data
+        let mut encrypted = storage.master_key.as_ref().unwrap().encrypt_with_aad(b"legacy-value", b"").unwrap();
+        encrypted.version = 1;
+        let now = Utc::now();
+        let legacy = (
+            (Uuid::new_v4(), "test-tenant", "default", "api-key", 3u64, now, now, "admin@test.com", vec!["prod".to_string()]),
+            encrypted,
+        );
+        let storage_key = secret_key("test-tenant", "default", "api-key");
+        storage.db.insert(storage_key.as_bytes(), bincode::serialize(&legacy).unwrap()).unwrap();
+        storage.db.remove(FORMAT_VERSION_KEY).unwrap();
+        
+        // Runs on every open; called directly so the test needn't reopen the database
+        storage.migrate_legacy_records().unwrap();
+        let (value, metadata) = storage.get_with_metadata("api-key", "default").await.unwrap().unwrap();
+        assert_eq!(value, "legacy-value");
+        assert_eq!(metadata.version, 3);
+        assert_eq!(metadata.updated_by, "admin@test.com");
+        assert_eq!(metadata.tags, ["prod"]);
+        assert!(metadata.alias_of.is_none() && !metadata.binary);
+        assert_eq!(
+            storage.db.get(FORMAT_VERSION_KEY).unwrap().unwrap().as_ref(),
+            RECORD_FORMAT_VERSION.to_be_bytes()
+        );
+        
+        // Records already in the current layout are left alone
+        let rewritten = storage.db.get(&storage_key).unwrap().unwrap();
+        storage.db.remove(FORMAT_VERSION_KEY).unwrap();
+        storage.migrate_legacy_records().unwrap();
+        assert_eq!(storage.db.get(&storage_key).unwrap().unwrap(), rewritten);
+    }
+    
+    #[tokio::test]
+    async fn test_legacy_user_records_are_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        // A user as written before passwords had a stored salt
+        let legacy = (
+            Uuid::new_v4(), "bob@test.com", "test-tenant", crate::auth::Role::Writer,
+            Utc::now(), None::<DateTime<Utc>>, true, Some([7u8; 32]), None::<String>,
+        );
+        storage.db.insert(user_key("test-tenant", "bob@test.com").as_bytes(), bincode::serialize(&legacy).unwrap()).unwrap();
+        storage.db.remove(FORMAT_VERSION_KEY).unwrap();
+        
+        storage.migrate_legacy_records().unwrap();
+        let users = storage.list_users("test-tenant").await.unwrap();
+        let bob = users.iter().find(|user| user.email == "bob@test.com").unwrap();
+        assert_eq!(bob.password_hash, Some([7u8; 32]));
+        assert!(bob.password_salt.is_none());
+        
+        // Each user's salt is drawn at random
+        storage.add_user("test-tenant", "carol@test.com", crate::auth::Role::Reader, Some("password")).await.unwrap();
+        storage.add_user("test-tenant", "dave@test.com", crate::auth::Role::Reader, Some("password")).await.unwrap();
+        let users = storage.list_users("test-tenant").await.unwrap();
+        let hash_of = |email: &str| users.iter().find(|user| user.email == email).unwrap().password_hash;
+        assert_ne!(hash_of("carol@test.com"), hash_of("dave@test.com"));
+    }
+    
+    #[tokio::test]
+    async fn test_legacy_history_records_are_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        storage.put("api-key", "first", "prod").await.unwrap();
+        storage.put("api-key", "second", "prod").await.unwrap();
+        
+        // History as written before versions could be chunked
+        let versions_key = versions_key(&secret_key("test-tenant", "prod", "api-key"));
+        let legacy: Vec<_> = storage.load_history(&versions_key).unwrap().into_iter()
+            .map(|v| (v.version, v.encrypted_value, v.encrypted_dek, v.namespace_key, v.binary, v.created_at, v.created_by, v.change_description))
+            .collect();
+        storage.db.insert(versions_key.as_bytes(), bincode::serialize(&legacy).unwrap()).unwrap();
+        storage.db.remove(FORMAT_VERSION_KEY).unwrap();
+        
+        storage.migrate_legacy_records().unwrap();
+        assert_eq!(storage.get_version("api-key", "prod", 1).await.unwrap().unwrap(), b"first");
+    }
+    
+    #[tokio::test]
+    async fn test_stats_report_secret_data_apart_from_bookkeeping() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("api-key", "value", "default").await.unwrap();
+        let record_size = storage.db.get(secret_key("test-tenant", "default", "api-key")).unwrap().unwrap().len() as u64;
+        
+        // A check interrupted by an earlier version left its probe behind
+        storage.db.insert("health_check_test", b"test".as_slice()).unwrap();
+        storage.health_check().await.unwrap();
+        assert!(!storage.db.contains_key("health_check_test").unwrap());
+        assert!(!storage.db.contains_key("_internal:health").unwrap());
+        
+        let stats = storage.get_stats().await.unwrap();
+        assert_eq!(stats.secret_count, 1);
+        assert_eq!(stats.total_size, record_size);
+        assert!(stats.audit_size > 0);
+        assert!(stats.internal_size > 0);
+    }
+    
+    #[tokio::test]
+    async fn test_totp_secret_is_encrypted_and_survives_rotation() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        
+        assert!(storage.totp_secret("alice@test.com").unwrap().is_none());
+        storage.set_totp_secret("alice@test.com", b"12345678901234567890").await.unwrap();
+        let stored = storage.db.get("totp:test-tenant:alice@test.com").unwrap().unwrap();
+        assert!(!stored.windows(20).any(|window| window == b"12345678901234567890"));
+        
+        storage.change_passphrase("test-passphrase", "new-passphrase").await.unwrap();
+        assert_eq!(storage.totp_secret("alice@test.com").unwrap().unwrap().as_slice(), b"12345678901234567890");
+        
+        storage.remove_totp_secret("alice@test.com").await.unwrap();
+        assert!(storage.totp_secret("alice@test.com").unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_totp_codes_are_accepted_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        let secret = b"12345678901234567890";
+        storage.set_totp_secret("alice@test.com", secret).await.unwrap();
+        
+        let now = 1111111109;
+        let code = crate::auth::totp_code(secret, now);
+        assert!(storage.accept_totp_code("alice@test.com", &code, now).unwrap());
+        // Still inside the drift window, but already used
+        assert!(!storage.accept_totp_code("alice@test.com", &code, now + 10).unwrap());
+        assert!(!storage.accept_totp_code("alice@test.com", &crate::auth::totp_code(secret, now - 30), now).unwrap());
+        assert!(storage.accept_totp_code("alice@test.com", &crate::auth::totp_code(secret, now + 30), now + 30).unwrap());
+        assert!(!storage.accept_totp_code("bob@test.com", &code, now).unwrap());
+    }
+    
+    #[tokio::test]
+    async fn test_lock_forgets_cached_key_until_passphrase_is_reentered() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        storage.put("api-key", "secret", "default").await.unwrap();
+        
+        storage.lock().unwrap();
+        assert!(matches!(storage.get("api-key", "default").await, Err(VaultError::VaultLocked)));
+        assert!(!storage.unlock_from_cache("test-tenant", None).unwrap());
+        
+        storage.unlock_transient("test-tenant", "test-passphrase").unwrap();
+        storage.cache_session_key().unwrap();
+        assert!(storage.unlock_from_cache("test-tenant", None).unwrap());
+        assert_eq!(storage.get("api-key", "default").await.unwrap().as_deref(), Some("secret"));
+    }
+    
+    #[tokio::test]
+    async fn test_invitations_are_stored_and_accepted_once() {
+        use crate::auth::Role;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        let invitation = storage.create_invitation("test-tenant", "bob@test.com", Role::Writer, "admin@test.com").await.unwrap();
+        assert!(storage.db.contains_key(format!("invitation:test-tenant:{}", invitation.token)).unwrap());
+        
+        assert!(matches!(storage.accept_invitation("no-such-token", "bob@test.com", "bob-password").await, Err(VaultError::Auth(_))));
+        assert!(matches!(storage.accept_invitation(&invitation.token, "eve@test.com", "eve-password").await, Err(VaultError::Auth(_))));
+        
+        let user = storage.accept_invitation(&invitation.token, "bob@test.com", "bob-password").await.unwrap();
+        assert!(matches!(user.role, Role::Writer));
+        let salt = user.password_salt.unwrap();
+        assert_eq!(user.password_hash.unwrap(), *crate::crypto::derive_key_argon2id("bob-password", &salt).unwrap());
+        assert!(matches!(storage.get_user_role("test-tenant", "bob@test.com").await.unwrap(), Some(Role::Writer)));
+        
+        // A token works once, and members are not invited again
+        assert!(matches!(storage.accept_invitation(&invitation.token, "bob@test.com", "bob-password").await, Err(VaultError::Auth(_))));
+        assert!(matches!(
+            storage.create_invitation("test-tenant", "bob@test.com", Role::Reader, "admin@test.com").await,
+            Err(VaultError::InvalidOperation(_))
+        ));
+        
+        // Expired invitations are refused
+        let mut expired = TenantInvitation::new("test-tenant".to_string(), "carol@test.com".to_string(), Role::Reader, "admin@test.com".to_string());
+        expired.expires_at = Utc::now() - chrono::Duration::minutes(1);
+        storage.db.insert(invitation_key("test-tenant", &expired.token), bincode::serialize(&expired).unwrap()).unwrap();
+        assert!(matches!(storage.accept_invitation(&expired.token, "carol@test.com", "carol-password").await, Err(VaultError::Auth(_))));
+        assert!(storage.get_user_role("test-tenant", "carol@test.com").await.unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_last_admin_cannot_be_removed() {
+        use crate::auth::Role;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        storage.add_user("test-tenant", "alice@test.com", Role::Admin, None).await.unwrap();
+        storage.add_user("test-tenant", "bob@test.com", Role::Writer, None).await.unwrap();
+        
+        assert!(matches!(storage.remove_user("test-tenant", "alice@test.com").await, Err(VaultError::InvalidOperation(_))));
+        storage.remove_user("test-tenant", "bob@test.com").await.unwrap();
+        assert!(matches!(storage.remove_user("test-tenant", "bob@test.com").await, Err(VaultError::Auth(_))));
+        
+        // With a second admin either one can go
+        storage.add_user("test-tenant", "carol@test.com", Role::Admin, None).await.unwrap();
+        storage.remove_user("test-tenant", "alice@test.com").await.unwrap();
+        let users = storage.list_users("test-tenant").await.unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].email, "carol@test.com");
+        assert!(matches!(storage.get_user_role("test-tenant", "carol@test.com").await.unwrap(), Some(Role::Admin)));
+    }
+    
+    #[tokio::test]
+    async fn test_last_admin_cannot_be_demoted() {
+        use crate::auth::Role;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        storage.add_user("test-tenant", "alice@test.com", Role::Admin, None).await.unwrap();
+        
+        assert!(matches!(
+            storage.change_user_role("test-tenant", "alice@test.com", Role::Reader).await,
+            Err(VaultError::InvalidOperation(_))
+        ));
+        storage.change_user_role("test-tenant", "alice@test.com", Role::Admin).await.unwrap();
+        
+        // With a second admin the first can step down
+        storage.add_user("test-tenant", "carol@test.com", Role::Admin, None).await.unwrap();
+        storage.change_user_role("test-tenant", "alice@test.com", Role::Writer).await.unwrap();
+        assert!(matches!(storage.get_user_role("test-tenant", "alice@test.com").await.unwrap(), Some(Role::Writer)));
+        assert!(matches!(
+            storage.change_user_role("test-tenant", "carol@test.com", Role::Reader).await,
+            Err(VaultError::InvalidOperation(_))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_external_key_provider_wraps_deks() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        storage.set_envelope_encryption(true);
+        
+        let provider_key = MasterKey {
+            key: secrecy::Secret::new([7u8; 32]),
+            algorithm: crate::crypto::EncryptionAlgorithm::Aes256Gcm,
+        };
+        storage.set_key_provider(Box::new(crate::crypto::LocalKeyEncryptionKey::new(provider_key, "external".to_string())));
+        
+        storage.put("api-key", "abc123", "default").await.unwrap();
+        let before = storage.load_secret("test-tenant", "default", "api-key").unwrap().unwrap();
+        assert_eq!(storage.get("api-key", "default").await.unwrap(), Some("abc123".to_string()));
+        
+        // Passphrase rotation leaves provider-wrapped DEKs alone
+        storage.change_passphrase("test-passphrase", "new-passphrase").await.unwrap();
+        let after = storage.load_secret("test-tenant", "default", "api-key").unwrap().unwrap();
+        assert_eq!(before.encrypted_dek, after.encrypted_dek);
+        assert_eq!(storage.get("api-key", "default").await.unwrap(), Some("abc123".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_deks_are_unwrapped_by_the_key_that_wrapped_them() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        storage.set_envelope_encryption(true);
+        storage.put("local-key", "abc", "default").await.unwrap();
+        
+        let provider = |byte: u8, id: &str| Box::new(crate::crypto::LocalKeyEncryptionKey::new(MasterKey {
+            key: secrecy::Secret::new([byte; 32]),
+            algorithm: crate::crypto::EncryptionAlgorithm::Aes256Gcm,
+        }, id.to_string()));
+        storage.set_key_provider(provider(7, "external"));
+        storage.put("external-key", "def", "default").await.unwrap();
+        
+        // Switching providers leaves master-key DEKs readable, and refuses
+        // the old provider's DEKs instead of trying the new one on them
+        storage.set_key_provider(provider(9, "other"));
+        assert_eq!(storage.get("local-key", "default").await.unwrap(), Some("abc".to_string()));
+        match storage.get("external-key", "default").await {
+            Err(VaultError::Crypto(message)) => assert!(message.contains("'external'")),
+            other => panic!("expected a missing provider error, got {:?}", other),
+        }
+        
+        storage.set_key_provider(provider(7, "external"));
+        assert_eq!(storage.get("external-key", "default").await.unwrap(), Some("def".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_transaction_is_all_or_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        let mut tx = storage.transaction().unwrap();
+        tx.put("a", "1", "default", &[]).unwrap();
+        tx.put("b", "2", "default", &[]).unwrap();
+        drop(tx);
+        assert!(storage.list("default").await.unwrap().is_empty());
+        
+        let mut tx = storage.transaction().unwrap();
+        tx.put("a", "1", "default", &[]).unwrap();
+        tx.put("b", "2", "default", &[]).unwrap();
+        assert_eq!(tx.commit().await.unwrap(), 2);
+        assert_eq!(storage.get("b", "default").await.unwrap(), Some("2".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_transaction_commit_is_audited_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        let since = Utc::now();
+        
+        let mut tx = storage.transaction().unwrap();
+        tx.put("a", "1", "default", &[]).unwrap();
+        tx.put("b", "2", "default", &[]).unwrap();
+        assert_eq!(tx.commit_as("import", "Atomically imported 2 secrets").await.unwrap(), 2);
+        
+        let entries = storage.audit_entries_since("test-tenant", Some(since)).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].event_type, "import");
+        assert_eq!(entries[0].description, "Atomically imported 2 secrets");
+    }
+    
+    #[tokio::test]
+    async fn test_find_duplicate_values() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("github", "hunter2", "personal").await.unwrap();
+        storage.put("gitlab", "hunter2", "work").await.unwrap();
+        storage.put("email", "unique", "personal").await.unwrap();
+        storage.link("gh", "personal", "personal/github", false).await.unwrap();
+        
+        let duplicates = storage.find_duplicate_values().await.unwrap();
+        assert_eq!(duplicates, vec![vec![
+            ("personal".to_string(), "github".to_string()),
+            ("work".to_string(), "gitlab".to_string()),
+        ]]);
+    }
+    
+    #[tokio::test]
+    async fn test_counts_match_listing() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put_with_tags("db-password", "a", "default", &["db".to_string()]).await.unwrap();
+        storage.put_with_tags("db-user", "b", "default", &[]).await.unwrap();
+        storage.put_with_tags("token", "c", "default", &["api".to_string()]).await.unwrap();
+        
+        assert_eq!(storage.count_secrets("default", None).await.unwrap(), 3);
+        assert_eq!(storage.count_secrets("default", Some("db")).await.unwrap(), 1);
+        assert_eq!(storage.count_search("db", None, &SearchOptions::default()).await.unwrap().0, storage.search("db", None, &SearchOptions::default()).await.unwrap().0.len());
+        assert_eq!(storage.count_search("api", Some("default"), &SearchOptions::default()).await.unwrap().0, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_list_pages_through_secrets() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        for key in ["a", "b", "c", "d", "e"] {
+            storage.put(key, "value", "default").await.unwrap();
+        }
+        
+        let page = ListOptions { offset: 1, limit: Some(2), ..Default::default() };
+        let keys: Vec<_> = storage.list_with_metadata("default", None, &page).await.unwrap()
+            .into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["b", "c"]);
+        
+        let tail = ListOptions { offset: 4, limit: Some(10), ..Default::default() };
+        assert_eq!(storage.list_with_metadata("default", None, &tail).await.unwrap().len(), 1);
+        let past_end = ListOptions { offset: 9, ..Default::default() };
+        assert!(storage.list_with_metadata("default", None, &past_end).await.unwrap().is_empty());
+        
+        // Keys sort as written, though ':' is escaped below '-' in storage keys
+        for key in ["a:b", "a-b"] {
+            storage.put(key, "value", "escaped").await.unwrap();
+        }
+        let first = ListOptions { limit: Some(1), ..Default::default() };
+        assert_eq!(storage.list_with_metadata("escaped", None, &first).await.unwrap()[0].0, "a-b");
+    }
+    
+    #[tokio::test]
+    async fn test_list_sorts_by_created_at_descending() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        for key in ["b", "c", "a"] {
+            storage.put(key, "value", "default").await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        // Overwriting bumps updated_at but keeps created_at
+        storage.put("b", "new value", "default").await.unwrap();
+        
+        let newest_first = ListOptions { sort_by: SortBy::CreatedAt, descending: true, ..Default::default() };
+        let keys: Vec<_> = storage.list_with_metadata("default", None, &newest_first).await.unwrap()
+            .into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["a", "c", "b"]);
+        
+        let recently_changed = ListOptions { sort_by: SortBy::UpdatedAt, descending: true, limit: Some(1), ..Default::default() };
+        assert_eq!(storage.list_with_metadata("default", None, &recently_changed).await.unwrap()[0].0, "b");
+        
+        let by_key_desc = ListOptions { descending: true, limit: Some(2), ..Default::default() };
+        let keys: Vec<_> = storage.list_with_metadata("default", None, &by_key_desc).await.unwrap()
+            .into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["c", "b"]);
+    }
+    
+    #[tokio::test]
+    async fn test_search_in_values_is_opt_in() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("db-url", "postgres://db.internal:5432/app", "prod").await.unwrap();
+        storage.put("cache-url", "redis://cache.internal:6379", "prod").await.unwrap();
+        storage.link("db-alias", "prod", "prod/db-url", false).await.unwrap();
+        
+        let in_values = SearchOptions { values: true, ..Default::default() };
+        assert!(storage.search("postgres", None, &SearchOptions::default()).await.unwrap().0.is_empty());
+        assert_eq!(
+            storage.search("POSTGRES", None, &in_values).await.unwrap().0,
+            [("prod".to_string(), "db-url".to_string())]
+        );
+        assert_eq!(storage.count_search("internal", Some("prod"), &in_values).await.unwrap().0, 2);
+        
+        // Searching values is audited as one access; unreadable records are skipped
+        let audited = |storage: &VaultStorage| storage.audit_entries_since("test-tenant", None).unwrap().iter()
+            .filter(|entry| entry.description.contains("searched"))
+            .count();
+        assert_eq!(audited(&storage), 2);
+        storage.db.insert(secret_key("test-tenant", "prod", "broken"), &b"garbage"[..]).unwrap();
+        let (matches, unreadable) = storage.search("internal", None, &in_values).await.unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(unreadable, vec![secret_key("test-tenant", "prod", "broken")]);
+        assert_eq!(audited(&storage), 3);
+    }
+    
+    #[tokio::test]
+    async fn test_regex_search_over_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        for key in ["aws_access_key", "aws_secret_key", "aws_region", "old_aws_key", "gcp_key"] {
+            storage.put(key, "value", "default").await.unwrap();
+        }
+        let regex = SearchOptions { regex: true, ..Default::default() };
+        let keys = |results: Vec<(String, String)>| results.into_iter().map(|(_, key)| key).collect::<Vec<_>>();
+        
+        let anchored = storage.search("^aws_.*_key$", None, &regex).await.unwrap().0;
+        assert_eq!(keys(anchored), ["aws_access_key", "aws_secret_key"]);
+        
+        let alternation = storage.search("^(gcp|old)_", None, &regex).await.unwrap().0;
+        assert_eq!(keys(alternation), ["gcp_key", "old_aws_key"]);
+        
+        // Without --regex the pattern is a plain substring
+        assert!(storage.search("^aws_.*_key$", None, &SearchOptions::default()).await.unwrap().0.is_empty());
+        assert!(matches!(storage.search("(unclosed", None, &regex).await, Err(VaultError::InvalidPattern(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_tags_change_without_touching_the_value() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put_with_tags("api-key", "sk-123", "prod", &["api".to_string()]).await.unwrap();
+        let (_, before) = storage.get_with_metadata("api-key", "prod").await.unwrap().unwrap();
+        
+        let tags = storage.add_tags("api-key", "prod", &["billing".to_string(), "api".to_string(), "billing".to_string()]).await.unwrap();
+        assert_eq!(tags, ["api", "billing"]);
+        let tags = storage.remove_tags("api-key", "prod", &["api".to_string()]).await.unwrap();
+        assert_eq!(tags, ["billing"]);
+        
+        let (value, after) = storage.get_with_metadata("api-key", "prod").await.unwrap().unwrap();
+        assert_eq!(value, "sk-123");
+        assert_eq!(after.tags, ["billing"]);
+        assert_eq!(after.version, before.version);
+        assert!(after.updated_at > before.updated_at);
+        assert_eq!(storage.secret_history("api-key", "prod").await.unwrap().unwrap().len(), 1);
+        
+        assert!(matches!(storage.add_tags("missing", "prod", &["x".to_string()]).await, Err(VaultError::SecretNotFound(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_rename_tag_within_a_namespace() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        let tags = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        storage.put_with_tags("db", "a", "app", &tags(&["prod", "db"])).await.unwrap();
+        storage.put_with_tags("api", "b", "app", &tags(&["production", "prod"])).await.unwrap();
+        storage.put_with_tags("cache", "c", "app", &tags(&["staging"])).await.unwrap();
+        storage.put_with_tags("db", "d", "other", &tags(&["prod"])).await.unwrap();
+        
+        assert_eq!(storage.rename_tag("prod", "production", Some("app")).await.unwrap(), 2);
+        async fn tags_of(storage: &VaultStorage, key: &str, ns: &str) -> Vec<String> {
+            storage.get_with_metadata(key, ns).await.unwrap().unwrap().1.tags
+        }
+        assert_eq!(tags_of(&storage, "db", "app").await, ["production", "db"]);
+        assert_eq!(tags_of(&storage, "api", "app").await, ["production"]);
+        assert_eq!(tags_of(&storage, "cache", "app").await, ["staging"]);
+        assert_eq!(tags_of(&storage, "db", "other").await, ["prod"]);
+        
+        storage.db.insert(secret_key("test-tenant", "other", "broken"), &b"garbage"[..]).unwrap();
+        assert_eq!(storage.rename_tag("prod", "production", None).await.unwrap(), 1);
+        assert_eq!(tags_of(&storage, "db", "other").await, ["production"]);
+    }
+    
+    #[tokio::test]
+    async fn test_get_many_keeps_request_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("db-password", "hunter2", "prod").await.unwrap();
+        storage.put("api-key", "sk-123", "default").await.unwrap();
+        
+        let keys = [
+            ("prod".to_string(), "db-password".to_string()),
+            ("default".to_string(), "missing".to_string()),
+            ("default".to_string(), "api-key".to_string()),
+        ];
+        let values = storage.get_many(&keys).await.unwrap();
+        assert_eq!(values, [
+            ("prod".to_string(), "db-password".to_string(), Some("hunter2".to_string())),
+            ("default".to_string(), "missing".to_string(), None),
+            ("default".to_string(), "api-key".to_string(), Some("sk-123".to_string())),
+        ]);
+    }
+    
+    #[tokio::test]
+    async fn test_binary_secret_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        let keystore: Vec<u8> = vec![0xfe, 0xed, 0xfe, 0xed, 0x00, 0x00, 0x00, 0x02, 0xff];
+        storage.put_bytes("keystore.jks", &keystore, "certs", &[]).await.unwrap();
+        
+        let (bytes, metadata) = storage.get_bytes_with_metadata_and_password("keystore.jks", "certs", None).await.unwrap().unwrap();
+        assert_eq!(bytes, keystore);
+        assert!(metadata.binary);
+        
+        // The text API refuses rather than mangling the bytes
+        assert!(matches!(storage.get("keystore.jks", "certs").await, Err(VaultError::BinarySecret(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_png_round_trips_through_the_byte_api() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        // A 1x1 PNG: the signature alone (0x89, 0x0d 0x0a 0x1a 0x0a) is not UTF-8
+        let png: Vec<u8> = [
+            &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a][..],
+            &[0x00, 0x00, 0x00, 0x0d, b'I', b'H', b'D', b'R', 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01],
+            &[0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4, 0x89],
+            &[0x00, 0x00, 0x00, 0x00, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82],
+        ].concat();
+        assert!(std::str::from_utf8(&png).is_err());
+        
+        storage.put_bytes("logo.png", &png, "assets", &[]).await.unwrap();
+        assert_eq!(storage.get_bytes("logo.png", "assets").await.unwrap(), Some(png));
+        assert!(matches!(storage.get("logo.png", "assets").await, Err(VaultError::BinarySecret(_))));
+        
+        // Text secrets come back through the byte API unchanged too
+        storage.put("greeting", "héllo\n", "assets").await.unwrap();
+        assert_eq!(storage.get_bytes("greeting", "assets").await.unwrap().as_deref(), Some("héllo\n".as_bytes()));
+        assert_eq!(storage.get_bytes("missing", "assets").await.unwrap(), None);
+    }
+    
+    #[tokio::test]
+    async fn test_put_value_keeps_bytes_exactly() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        let pem = b"-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n";
+        storage.put_value("tls.crt", pem, false, "certs", &[], None, None).await.unwrap();
+        assert_eq!(storage.get("tls.crt", "certs").await.unwrap().unwrap().as_bytes(), pem);
+        
+        storage.put_value("token.bin", &[0x00, 0x9f, 0x0a], true, "certs", &[], Some("pin"), None).await.unwrap();
+        assert!(storage.get_bytes_with_metadata_and_password("token.bin", "certs", None).await.is_err());
+        let (bytes, metadata) = storage.get_bytes_with_metadata_and_password("token.bin", "certs", Some("pin")).await.unwrap().unwrap();
+        assert_eq!(bytes, [0x00, 0x9f, 0x0a]);
+        assert!(metadata.binary);
+    }
+    
+    #[tokio::test]
+    async fn test_transient_tenant_switch() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        storage.init_tenant_with_password("other-tenant", "admin@test.com", "other-passphrase").await.unwrap();
+        storage.put("shared-name", "first", "default").await.unwrap();
+        
+        assert!(!storage.unlock_from_cache("other-tenant", None).unwrap());
+        assert!(matches!(
+            storage.unlock_transient("other-tenant", "wrong"),
+            Err(VaultError::InvalidPassphrase)
+        ));
+        storage.unlock_transient("other-tenant", "other-passphrase").unwrap();
+        assert!(storage.get("shared-name", "default").await.unwrap().is_none());
+        storage.put("shared-name", "second", "default").await.unwrap();
+        
+        // The transient unlock must not cache a key for later commands
+        assert!(!storage.unlock_from_cache("other-tenant", None).unwrap());
+        assert!(storage.unlock_from_cache("test-tenant", None).unwrap());
+        assert_eq!(storage.get("shared-name", "default").await.unwrap().as_deref(), Some("first"));
+        
+        // A cached key only serves users of its tenant
+        storage.add_user("test-tenant", "bob@test.com", crate::auth::Role::Reader, None).await.unwrap();
+        assert!(storage.unlock_from_cache("test-tenant", Some("bob@test.com")).unwrap());
+        assert!(matches!(
+            storage.unlock_from_cache("test-tenant", Some("mallory@test.com")),
+            Err(VaultError::PermissionDenied(_))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_reencrypt_migrates_mixed_algorithms() {
+        use crate::crypto::EncryptionAlgorithm;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = unlocked_storage(&temp_dir).await;
+        storage.put("aes-text", "aes-value", "default").await.unwrap();
+        storage.set_envelope_encryption(true);
+        storage.put_bytes("aes-blob", &[0, 159, 146, 150], "default", &[]).await.unwrap();
+        
+        // Write one secret with ChaCha20-Poly1305 so the vault holds both ciphers
+        storage.master_key.as_mut().unwrap().algorithm = EncryptionAlgorithm::ChaCha20Poly1305;
+        storage.put("chacha-text", "chacha-value", "default").await.unwrap();
+        storage.master_key.as_mut().unwrap().algorithm = EncryptionAlgorithm::Aes256Gcm;
+        storage.set_envelope_encryption(false);
+        
+        // Normal reads only accept the key's own cipher; reencrypt reads any
+        assert!(matches!(storage.get("chacha-text", "default").await, Err(VaultError::AlgorithmMismatch { .. })));
+        assert_eq!(storage.reencrypt_secrets(EncryptionAlgorithm::ChaCha20Poly1305).await.unwrap(), 2);
+        
+        // Repeating the run skips what is already converted but still reports every secret
+        let mut reports = Vec::new();
+        let reencrypted = storage.reencrypt_secrets_with_progress(EncryptionAlgorithm::ChaCha20Poly1305, |done, total| reports.push((done, total))).await.unwrap();
+        assert_eq!(reencrypted, 0);
+        assert_eq!(reports, vec![(1, 3), (2, 3), (3, 3)]);
+        
+        // A fresh unlock picks up the new tenant default and still decrypts everything
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        storage.put("after", "new-value", "default").await.unwrap();
+        let records = storage.secret_records("test-tenant").await.unwrap();
+        assert_eq!(records.len(), 4);
+        assert!(records.values().all(|s| s.encrypted_value.algorithm == EncryptionAlgorithm::ChaCha20Poly1305));
+        
+        assert_eq!(storage.get("aes-text", "default").await.unwrap().as_deref(), Some("aes-value"));
+        assert_eq!(storage.get("chacha-text", "default").await.unwrap().as_deref(), Some("chacha-value"));
+        assert_eq!(storage.get("after", "default").await.unwrap().as_deref(), Some("new-value"));
+        let (bytes, _) = storage.get_bytes_with_metadata_and_password("aes-blob", "default", None).await.unwrap().unwrap();
+        assert_eq!(bytes, vec![0, 159, 146, 150]);
+    }
+    
+    #[tokio::test]
+    async fn test_password_protected_secret() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        storage.put_with_protection("locked", "value", "default", &[], Some("open-sesame")).await.unwrap();
+        
+        assert!(storage.is_secret_password_protected("locked", "default").await.unwrap());
+        assert!(matches!(storage.get_with_metadata_and_password("locked", "default", None).await, Err(VaultError::Auth(_))));
+        assert!(matches!(
+            storage.get_with_metadata_and_password("locked", "default", Some("wrong")).await,
+            Err(VaultError::InvalidAccessPassword)
+        ));
+        let (value, _) = storage.get_with_metadata_and_password("locked", "default", Some("open-sesame")).await.unwrap().unwrap();
+        assert_eq!(value, "value");
+        
+        // Deleting the secret removes its access password as well
+        storage.delete("locked", "default").await.unwrap();
+        storage.put("locked", "fresh", "default").await.unwrap();
+        assert!(!storage.is_secret_password_protected("locked", "default").await.unwrap());
+    }
+    
+    #[tokio::test]
+    async fn test_unlock_with_key_checks_canary() {
+        use secrecy::ExposeSecret;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let mut embedded = unlocked_storage(&temp_dir).await;
+        embedded.put("api-key", "value", "default").await.unwrap();
+        let key = *embedded.master_key.as_ref().unwrap().key.expose_secret();
+        embedded.lock().unwrap();
+        
+        assert!(matches!(
+            embedded.unlock_with_key("test-tenant", [7u8; 32], crate::crypto::EncryptionAlgorithm::Aes256Gcm),
+            Err(VaultError::InvalidKey)
+        ));
+        embedded.unlock_with_key("test-tenant", key, crate::crypto::EncryptionAlgorithm::Aes256Gcm).unwrap();
+        assert_eq!(embedded.get("api-key", "default").await.unwrap().as_deref(), Some("value"));
+        
+        // Tenants from before canaries are checked against the stored key
+        embedded.db.remove("canary:test-tenant").unwrap();
+        let mut tenant = embedded.get_tenant("test-tenant").unwrap().unwrap();
+        tenant.password_hash = key;
+        embedded.db.insert("tenant:test-tenant", bincode::serialize(&tenant).unwrap()).unwrap();
+        embedded.lock().unwrap();
+        assert!(matches!(
+            embedded.unlock_with_key("test-tenant", [7u8; 32], crate::crypto::EncryptionAlgorithm::Aes256Gcm),
+            Err(VaultError::InvalidKey)
+        ));
+        embedded.unlock_with_key("test-tenant", key, crate::crypto::EncryptionAlgorithm::Aes256Gcm).unwrap();
+        assert!(embedded.db.contains_key("canary:test-tenant").unwrap());
+    }
+    
+    #[tokio::test]
+    async fn test_corrupted_record_is_skipped_and_salvage_recovers_the_rest() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        storage.put("good", "value", "default").await.unwrap();
+        storage.put("bad", "value", "default").await.unwrap();
+        storage.db.insert("secret:test-tenant:default:bad", &b"garbage"[..]).unwrap();
+        
+        let (secrets, corrupted) = storage.list_with_metadata_checked("default", None, &ListOptions::default()).await.unwrap();
+        assert_eq!(secrets.len(), 1);
+        assert_eq!(corrupted, vec!["secret:test-tenant:default:bad".to_string()]);
+        assert_eq!(storage.count_secrets("default", None).await.unwrap(), 1);
+        assert_eq!(storage.count_secrets("default", Some("missing")).await.unwrap(), 0);
+        assert_eq!(storage.get_stats().await.unwrap().corrupted_records.len(), 1);
+        assert!(matches!(storage.get("bad", "default").await, Err(VaultError::Corruption(_))));
+        
+        let report = storage.salvage().await.unwrap();
+        assert_eq!(report.recovered.len(), 1);
+        assert_eq!(report.recovered[0].value, b"value");
+        assert_eq!(report.skipped.len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_alias_cycles_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("a", "value", "default").await.unwrap();
+        storage.link("b", "default", "default/a", false).await.unwrap();
+        
+        // Self-reference, alias-of-alias and re-pointing a target are all refused
+        assert!(matches!(storage.link("c", "default", "default/c", false).await, Err(VaultError::InvalidAlias(_))));
+        assert!(matches!(storage.link("c", "default", "default/b", false).await, Err(VaultError::InvalidAlias(_))));
+        assert!(matches!(storage.link("a", "default", "default/b", false).await, Err(VaultError::InvalidAlias(_))));
+        assert!(matches!(storage.link("c", "default", "default/missing", false).await, Err(VaultError::SecretNotFound(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_alias_targets_cannot_be_deleted() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("db", "hunter2", "shared").await.unwrap();
+        storage.link("db", "billing", "shared/db", false).await.unwrap();
+        assert!(matches!(storage.delete("db", "shared").await, Err(VaultError::InvalidAlias(_))));
+        assert!(matches!(storage.delete_hard("db", "shared").await, Err(VaultError::InvalidAlias(_))));
+        assert_eq!(storage.get("db", "billing").await.unwrap(), Some("hunter2".to_string()));
+        
+        // Once the alias is gone the target can go too
+        storage.delete("db", "billing").await.unwrap();
+        storage.delete("db", "shared").await.unwrap();
+        
+        // A forced namespace delete still leaves the alias dangling
+        storage.put("db", "hunter2", "shared").await.unwrap();
+        storage.link("db", "billing", "shared/db", false).await.unwrap();
+        storage.delete_namespace("shared", true).await.unwrap();
+        assert!(matches!(storage.get("db", "billing").await, Err(VaultError::SecretNotFound(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_link_over_secret_needs_force() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = unlocked_storage(&temp_dir).await;
+        
+        storage.put("db-password", "hunter2", "shared").await.unwrap();
+        storage.put("db", "keepme", "billing").await.unwrap();
+        assert!(matches!(storage.link("db", "billing", "shared/db-password", false).await, Err(VaultError::SecretExists(_))));
+        assert_eq!(storage.get("db", "billing").await.unwrap(), Some("keepme".to_string()));
+        
+        // A forced link keeps the replaced value in history
+        storage.link("db", "billing", "shared/db-password", true).await.unwrap();
+        assert_eq!(storage.get("db", "billing").await.unwrap(), Some("hunter2".to_string()));
+        assert_eq!(storage.get_version("db", "billing", 1).await.unwrap(), Some(b"keepme".to_vec()));
+        
+        assert_eq!(storage.rollback_secret("db", "billing", 1).await.unwrap(), 3);
+        let (value, metadata) = storage.get_with_metadata("db", "billing").await.unwrap().unwrap();
+        assert_eq!(value, "keepme");
+        assert_eq!(metadata.alias_of, None);
+        assert_eq!(metadata.version, 3);
+    }
+    
+    #[tokio::test]
+    async fn test_read_only_rejects_writes() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        {
+            let storage = unlocked_storage(&temp_dir).await;
+            storage.put("api-key", "value", "default").await.unwrap();
+        }
+        
+        let mut storage = VaultStorage::open_readonly(db_path.to_str().unwrap()).unwrap();
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        
+        assert_eq!(storage.list("default").await.unwrap(), vec!["api-key".to_string()]);
+        assert!(matches!(storage.put("other", "value", "default").await, Err(VaultError::ReadOnly)));
+        // Reading a value is audited, and the audit log can't be written
+        assert!(matches!(storage.get("api-key", "default").await, Err(VaultError::ReadOnly)));
+        assert!(matches!(storage.delete("api-key", "default").await, Err(VaultError::ReadOnly)));
+    }
+    
+    #[tokio::test]
+    async fn test_read_only_waits_for_the_writer() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let writer = unlocked_storage(&temp_dir).await;
+        writer.put("api-key", "value", "default").await.unwrap();
+        
+        // The reader gets the lock once the writer closes the database
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            drop(writer);
+        });
+        let mut reader = VaultStorage::open_readonly(db_path.to_str().unwrap()).unwrap();
+        release.join().unwrap();
+        reader.unlock("test-tenant", "test-passphrase").unwrap();
+        assert_eq!(reader.list("default").await.unwrap(), vec!["api-key".to_string()]);
+        
+        // And a writer gets it back once the reader is done
+        drop(reader);
+        assert!(VaultStorage::new(db_path.to_str().unwrap()).is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_read_only_does_not_create_a_missing_vault() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("missing.db");
+        
+        let storage = VaultStorage::open_readonly(db_path.to_str().unwrap()).unwrap();
+        assert_eq!(storage.get_stats().await.unwrap().tenant_count, 0);
+        assert!(!db_path.exists());
+    }
+    
+    #[tokio::test]
+    async fn test_second_writer_is_locked_out() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let _writer = unlocked_storage(&temp_dir).await;
+        
+        assert!(matches!(VaultStorage::new(db_path.to_str().unwrap()), Err(VaultError::Locked(_))));
+    }
+}
@@ -8,7 +8,7 @@ pub struct Tenant {
     pub admin: String,
     pub created_at: DateTime<Utc>,
     pub salt: [u8; 32],
-    pub password_hash: [u8; 32], // Hash of the master password for validation
+    pub password_hash: [u8; 32], // Legacy: the master key itself; zeroed once a `verifier:` record exists
     pub settings: TenantSettings,
 }
 
@@ -26,6 +26,27 @@ pub struct TenantSettings {
 pub enum EncryptionAlgorithm {
     Aes256Gcm,
     ChaCha20Poly1305,
+    XChaCha20Poly1305,
+}
+
+impl From<&EncryptionAlgorithm> for crate::crypto::EncryptionAlgorithm {
+    fn from(algorithm: &EncryptionAlgorithm) -> Self {
+        match algorithm {
+            EncryptionAlgorithm::Aes256Gcm => Self::Aes256Gcm,
+            EncryptionAlgorithm::ChaCha20Poly1305 => Self::ChaCha20Poly1305,
+            EncryptionAlgorithm::XChaCha20Poly1305 => Self::XChaCha20Poly1305,
+        }
+    }
+}
+
+impl From<&crate::crypto::EncryptionAlgorithm> for EncryptionAlgorithm {
+    fn from(algorithm: &crate::crypto::EncryptionAlgorithm) -> Self {
+        match algorithm {
+            crate::crypto::EncryptionAlgorithm::Aes256Gcm => Self::Aes256Gcm,
+            crate::crypto::EncryptionAlgorithm::ChaCha20Poly1305 => Self::ChaCha20Poly1305,
+            crate::crypto::EncryptionAlgorithm::XChaCha20Poly1305 => Self::XChaCha20Poly1305,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,6 +56,26 @@ pub struct KeyDerivationParams {
     pub parallelism: u32,
 }
 
+impl From<&KeyDerivationParams> for crate::crypto::KeyDerivationParams {
+    fn from(params: &KeyDerivationParams) -> Self {
+        Self {
+            memory_cost: params.memory_cost,
+            time_cost: params.time_cost,
+            parallelism: params.parallelism,
+        }
+    }
+}
+
+impl From<&crate::crypto::KeyDerivationParams> for KeyDerivationParams {
+    fn from(params: &crate::crypto::KeyDerivationParams) -> Self {
+        Self {
+            memory_cost: params.memory_cost,
+            time_cost: params.time_cost,
+            parallelism: params.parallelism,
+        }
+    }
+}
+
 impl Default for TenantSettings {
     fn default() -> Self {
         Self {
@@ -60,19 +101,7 @@ impl Tenant {
             admin,
             created_at: Utc::now(),
             salt,
-            password_hash: [0u8; 32], // Will be set during init_tenant_with_password
-            settings: TenantSettings::default(),
-        }
-    }
-    
-    pub fn new_with_password(id: String, name: String, admin: String, salt: [u8; 32], password_hash: [u8; 32]) -> Self {
-        Self {
-            id,
-            name,
-            admin,
-            created_at: Utc::now(),
-            salt,
-            password_hash,
+            password_hash: [0u8; 32],
             settings: TenantSettings::default(),
         }
     }
@@ -13,9 +13,41 @@ pub struct User {
     pub last_login: Option<DateTime<Utc>>,
     pub is_active: bool,
     pub password_hash: Option<[u8; 32]>, // For collaborative mode
+    pub password_salt: Option<[u8; 32]>, // Random salt of `password_hash`; None for hashes from before record format 3
     pub public_key: Option<String>, // For end-to-end encryption
 }
 
+/// `User` as written before record format 3, without `password_salt`.
+#[derive(Deserialize)]
+pub(super) struct LegacyUser {
+    id: Uuid,
+    email: String,
+    tenant_id: String,
+    role: Role,
+    created_at: DateTime<Utc>,
+    last_login: Option<DateTime<Utc>>,
+    is_active: bool,
+    password_hash: Option<[u8; 32]>,
+    public_key: Option<String>,
+}
+
+impl From<LegacyUser> for User {
+    fn from(legacy: LegacyUser) -> Self {
+        User {
+            id: legacy.id,
+            email: legacy.email,
+            tenant_id: legacy.tenant_id,
+            role: legacy.role,
+            created_at: legacy.created_at,
+            last_login: legacy.last_login,
+            is_active: legacy.is_active,
+            password_hash: legacy.password_hash,
+            password_salt: None,
+            public_key: legacy.public_key,
+        }
+    }
+}
+
 impl User {
     pub fn new(email: String, tenant_id: String, role: Role) -> Self {
         Self {
@@ -27,12 +59,13 @@ impl User {
             last_login: None,
             is_active: true,
             password_hash: None,
+            password_salt: None,
             public_key: None,
         }
     }
     
-    #[allow(dead_code)]
-    pub fn with_password(mut self, password_hash: [u8; 32]) -> Self {
+    pub fn with_password(mut self, salt: [u8; 32], password_hash: [u8; 32]) -> Self {
+        self.password_salt = Some(salt);
         self.password_hash = Some(password_hash);
         self
     }
@@ -72,7 +105,6 @@ pub struct TenantInvitation {
 }
 
 impl TenantInvitation {
-    #[allow(dead_code)]
     pub fn new(tenant_id: String, email: String, role: Role, invited_by: String) -> Self {
         use rand::Rng;
         let token: String = (0..32)
@@ -96,12 +128,10 @@ impl TenantInvitation {
         }
     }
     
-    #[allow(dead_code)]
     pub fn is_valid(&self) -> bool {
         !self.accepted && Utc::now() < self.expires_at
     }
     
-    #[allow(dead_code)]
     pub fn accept(&mut self) {
         self.accepted = true;
     }
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use crate::{
     storage::Secret,
-    sync::{ConflictInfo, SyncMetadata},
+    sync::{ConflictInfo, ConflictType, RemoteSecretInfo, SyncMetadata},
     error::Result,
 };
 
@@ -12,26 +12,72 @@ pub async fn detect_conflicts(
     remote_metadata: &SyncMetadata,
 ) -> Result<Vec<ConflictInfo>> {
     let mut conflicts = Vec::new();
+    let mut remote: HashMap<(&str, &str), &RemoteSecretInfo> = remote_metadata.secrets.iter()
+        .map(|info| ((info.namespace.as_str(), info.key.as_str()), info))
+        .collect();
     
-    // Simple conflict detection based on version comparison
-    for (key, secret) in local_secrets {
-        if secret.metadata.version != remote_metadata.sync_version {
-            let parts: Vec<&str> = key.split(':').collect();
-            if parts.len() >= 4 {
+    // Compare by version and timestamp; anything unmatched exists on one side only
+    for secret in local_secrets.values() {
+        let (namespace, key) = (&secret.metadata.namespace, &secret.metadata.key);
+        match remote.remove(&(namespace.as_str(), key.as_str())) {
+            Some(info) => {
+                if secret.metadata.version != info.version || secret.metadata.updated_at != info.updated_at {
+                    conflicts.push(ConflictInfo {
+                        secret_key: key.clone(),
+                        namespace: namespace.clone(),
+                        local_version: secret.metadata.version,
+                        remote_version: info.version,
+                        conflict_type: format!("{:?}", ConflictType::ModifiedBoth),
+                    });
+                }
+            }
+            None => {
                 conflicts.push(ConflictInfo {
-                    secret_key: parts[3].to_string(),
-                    namespace: parts[2].to_string(),
+                    secret_key: key.clone(),
+                    namespace: namespace.clone(),
                     local_version: secret.metadata.version,
-                    remote_version: remote_metadata.sync_version,
-                    conflict_type: "ModifiedBoth".to_string(),
+                    remote_version: 0,
+                    conflict_type: format!("{:?}", ConflictType::LocalOnly),
                 });
             }
         }
     }
     
+    for info in remote.into_values() {
+        conflicts.push(ConflictInfo {
+            secret_key: info.key.clone(),
+            namespace: info.namespace.clone(),
+            local_version: 0,
+            remote_version: info.version,
+            conflict_type: format!("{:?}", ConflictType::RemoteOnly),
+        });
+    }
+    
+    conflicts.sort_by(|a, b| (&a.namespace, &a.secret_key).cmp(&(&b.namespace, &b.secret_key)));
     Ok(conflicts)
 }
 
+/// `None` when both sides hold the same version, otherwise whether
+/// `theirs` is strictly newer and may replace `ours` without conflict.
+pub fn supersedes(theirs: (u64, chrono::DateTime<chrono::Utc>), ours: (u64, chrono::DateTime<chrono::Utc>)) -> Option<bool> {
+    if theirs == ours {
+        None
+    } else {
+        Some(theirs.0 > ours.0)
+    }
+}
+
+/// A conflict for `secret`, changed on both sides since the last sync.
+pub fn modified_both(secret: &Secret, local_version: u64, remote_version: u64) -> ConflictInfo {
+    ConflictInfo {
+        secret_key: secret.metadata.key.clone(),
+        namespace: secret.metadata.namespace.clone(),
+        local_version,
+        remote_version,
+        conflict_type: format!("{:?}", ConflictType::ModifiedBoth),
+    }
+}
+
 #[derive(Debug)]
 pub enum ConflictResolution {
     UseLocal,
@@ -43,7 +89,6 @@ pub enum ConflictResolution {
 pub struct ConflictResolver;
 
 impl ConflictResolver {
-    #[allow(dead_code)]
     pub fn resolve_conflict(
         local_secret: &Secret,
         remote_secret: &Secret,
@@ -84,10 +129,78 @@ impl ConflictResolver {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug)]
 pub enum AutoResolveStrategy {
     PreferLocal,
     PreferRemote,
     PreferNewer,
     Manual,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{crypto::{EncryptedData, EncryptionAlgorithm}, storage::SecretMetadata};
+    
+    fn local_secret(namespace: &str, key: &str, version: u64, updated_at: chrono::DateTime<chrono::Utc>) -> (String, Secret) {
+        let secret = Secret {
+            metadata: SecretMetadata {
+                id: uuid::Uuid::new_v4(),
+                tenant_id: "t".to_string(),
+                namespace: namespace.to_string(),
+                key: key.to_string(),
+                version,
+                created_at: updated_at,
+                updated_at,
+                created_by: "test".to_string(),
+                updated_by: "test".to_string(),
+                tags: Vec::new(),
+                alias_of: None,
+                binary: false,
+                expires_at: None,
+            },
+            encrypted_value: EncryptedData {
+                algorithm: EncryptionAlgorithm::Aes256Gcm,
+                ciphertext: Vec::new(),
+                nonce: Vec::new(),
+                salt: [0u8; 32],
+                version: 1,
+            },
+            encrypted_dek: None,
+            namespace_key: false,
+            chunks: 0,
+        };
+        (format!("secret:t:{}:{}", namespace, key), secret)
+    }
+    
+    #[tokio::test]
+    async fn test_detect_conflicts_reports_added_removed_and_diverged() {
+        let now = chrono::Utc::now();
+        let local: HashMap<String, Secret> = [
+            local_secret("default", "same", 1, now),
+            local_secret("default", "changed", 2, now),
+            local_secret("default", "new-local", 1, now),
+        ].into_iter().collect();
+        let remote = SyncMetadata {
+            last_sync: now,
+            sync_version: 1,
+            conflicts: Vec::new(),
+            secrets: ["same", "changed", "new-remote"].iter()
+                .map(|key| RemoteSecretInfo {
+                    namespace: "default".to_string(),
+                    key: key.to_string(),
+                    version: 1,
+                    updated_at: now,
+                })
+                .collect(),
+        };
+        
+        let diff = crate::sync::SyncDiff::from_conflicts(detect_conflicts(&local, &remote).await.unwrap());
+        assert_eq!(diff.local_only.len(), 1);
+        assert_eq!(diff.local_only[0].secret_key, "new-local");
+        assert_eq!(diff.remote_only.len(), 1);
+        assert_eq!(diff.remote_only[0].secret_key, "new-remote");
+        assert_eq!(diff.diverged.len(), 1);
+        assert_eq!(diff.diverged[0].secret_key, "changed");
+    }
+}
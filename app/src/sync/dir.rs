@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    storage::{Secret, VaultStorage},
+    sync::{modified_both, supersedes, RemoteSecretInfo, SyncResult, SyncMetadata},
+    error::{VaultError, Result},
+};
+
+const METADATA_FILE: &str = "_metadata.json";
+const EXTENSION: &str = "vault";
+
+fn sync_error(context: &str, error: std::io::Error) -> VaultError {
+    VaultError::Sync(format!("{}: {}", context, error))
+}
+
+/// The sync directory must already exist, so an unmounted share or a typo
+/// is reported instead of silently syncing into a fresh local folder. The
+/// tenant id names a directory under it, so one that could step outside is
+/// refused.
+fn tenant_dir(root: &str, tenant_id: &str) -> Result<PathBuf> {
+    if tenant_id.is_empty() || tenant_id == "." || tenant_id == ".." || tenant_id.contains(['/', '\\']) {
+        return Err(VaultError::Sync(format!("Cannot use tenant {} as a directory name", tenant_id)));
+    }
+    let root = Path::new(root);
+    if !root.is_dir() {
+        return Err(VaultError::Sync(format!("Sync directory {} does not exist", root.display())));
+    }
+    Ok(root.join(tenant_id))
+}
+
+/// Percent-encode a name for use as one path segment, like the storage
+/// layer's key escaping: separators and `%` itself are encoded so distinct
+/// names never share a file, and a leading `.` so no name reads as `.`,
+/// `..` or a hidden file.
+pub(crate) fn path_segment(name: &str) -> String {
+    let mut segment = String::with_capacity(name.len());
+    for (index, byte) in name.bytes().enumerate() {
+        match byte {
+            b'.' if index == 0 => segment.push_str("%2E"),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' => segment.push(byte as char),
+            _ => segment.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    segment
+}
+
+/// `{tenant_dir}/{ns}/{key}.vault`, with the namespace and key each encoded
+/// as a single segment.
+fn record_path(tenant_dir: &Path, namespace: &str, key: &str) -> Result<PathBuf> {
+    if namespace.is_empty() || key.is_empty() {
+        return Err(VaultError::Sync(format!("Cannot store {}/{} as a file", namespace, key)));
+    }
+    Ok(tenant_dir
+        .join(path_segment(namespace))
+        .join(format!("{}.{}", path_segment(key), EXTENSION)))
+}
+
+/// Write through a temporary file so a reader of the synced folder never
+/// sees a half-written record.
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+fn read_metadata(tenant_dir: &Path) -> Result<SyncMetadata> {
+    let path = tenant_dir.join(METADATA_FILE);
+    let body = match std::fs::read(&path) {
+        Ok(body) => body,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            // Nothing pushed yet
+            return Ok(SyncMetadata {
+                last_sync: chrono::DateTime::<chrono::Utc>::MIN_UTC,
+                sync_version: 0,
+                conflicts: Vec::new(),
+                secrets: Vec::new(),
+            });
+        }
+        Err(e) => return Err(sync_error(&format!("Failed to read {}", path.display()), e)),
+    };
+    serde_json::from_slice(&body)
+        .map_err(|e| VaultError::Sync(format!("Invalid {}: {}", path.display(), e)))
+}
+
+fn collect_records(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_records(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == EXTENSION) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+pub async fn dir_push(
+    storage: &VaultStorage,
+    path: &str,
+    force: bool,
+) -> Result<SyncResult> {
+    let tenant_id = storage.current_tenant_id()?;
+    let tenant_dir = tenant_dir(path, &tenant_id)?;
+    
+    let mut metadata = read_metadata(&tenant_dir)?;
+    let mut remote: HashMap<(String, String), RemoteSecretInfo> = metadata.secrets.drain(..)
+        .map(|info| ((info.namespace.clone(), info.key.clone()), info))
+        .collect();
+    
+    let mut pushed = 0;
+    let mut conflicts = Vec::new();
+    let mut errors = Vec::new();
+    for secret in storage.sync_records().await? {
+        let meta = &secret.metadata;
+        let id = (meta.namespace.clone(), meta.key.clone());
+        if let Some(info) = remote.get(&id) {
+            match supersedes((meta.version, meta.updated_at), (info.version, info.updated_at)) {
+                None => continue,
+                Some(false) if !force => {
+                    conflicts.push(modified_both(&secret, meta.version, info.version));
+                    continue;
+                }
+                Some(_) => {}
+            }
+        }
+        
+        let file = match record_path(&tenant_dir, &meta.namespace, &meta.key) {
+            Ok(file) => file,
+            Err(e) => {
+                errors.push(e.to_string());
+                continue;
+            }
+        };
+        let body = bincode::serialize(&secret)?;
+        match write_atomic(&file, &body) {
+            Ok(()) => {
+                remote.insert(id, RemoteSecretInfo {
+                    namespace: meta.namespace.clone(),
+                    key: meta.key.clone(),
+                    version: meta.version,
+                    updated_at: meta.updated_at,
+                });
+                pushed += 1;
+            }
+            Err(e) => errors.push(sync_error(&format!("Failed to write {}", file.display()), e).to_string()),
+        }
+    }
+    
+    let mut secrets: Vec<_> = remote.into_values().collect();
+    secrets.sort_by(|a, b| (&a.namespace, &a.key).cmp(&(&b.namespace, &b.key)));
+    let metadata = SyncMetadata {
+        last_sync: chrono::Utc::now(),
+        sync_version: metadata.sync_version + 1,
+        conflicts: conflicts.clone(),
+        secrets,
+    };
+    let metadata_path = tenant_dir.join(METADATA_FILE);
+    let body = serde_json::to_vec_pretty(&metadata)
+        .map_err(|e| VaultError::Sync(e.to_string()))?;
+    write_atomic(&metadata_path, &body)
+        .map_err(|e| sync_error(&format!("Failed to write {}", metadata_path.display()), e))?;
+    
+    Ok(SyncResult {
+        pushed,
+        pulled: 0,
+        conflicts,
+        errors,
+    })
+}
+
+pub async fn dir_pull(
+    storage: &VaultStorage,
+    path: &str,
+    force: bool,
+) -> Result<SyncResult> {
+    let tenant_id = storage.current_tenant_id()?;
+    let tenant_dir = tenant_dir(path, &tenant_id)?;
+    
+    let mut files = Vec::new();
+    if tenant_dir.is_dir() {
+        collect_records(&tenant_dir, &mut files)
+            .map_err(|e| sync_error(&format!("Failed to list {}", tenant_dir.display()), e))?;
+    }
+    files.sort();
+    
+    let local: HashMap<(String, String), Secret> = storage.secret_records(&tenant_id).await?
+        .into_values()
+        .map(|secret| ((secret.metadata.namespace.clone(), secret.metadata.key.clone()), secret))
+        .collect();
+    
+    let mut pulled = 0;
+    let mut conflicts = Vec::new();
+    let mut errors = Vec::new();
+    for file in files {
+        let remote: Secret = match std::fs::read(&file) {
+            Ok(body) => match bincode::deserialize(&body) {
+                Ok(secret) => secret,
+                Err(e) => {
+                    errors.push(format!("Invalid {}: {}", file.display(), e));
+                    continue;
+                }
+            },
+            Err(e) => {
+                errors.push(sync_error(&format!("Failed to read {}", file.display()), e).to_string());
+                continue;
+            }
+        };
+        
+        let meta = &remote.metadata;
+        if let Some(ours) = local.get(&(meta.namespace.clone(), meta.key.clone())) {
+            let ours = &ours.metadata;
+            match supersedes((meta.version, meta.updated_at), (ours.version, ours.updated_at)) {
+                None => continue,
+                Some(false) if !force => {
+                    conflicts.push(modified_both(&remote, ours.version, meta.version));
+                    continue;
+                }
+                Some(_) => {}
+            }
+        }
+        
+        match storage.apply_synced_record(remote).await {
+            Ok(()) => pulled += 1,
+            Err(e) => errors.push(format!("{}: {}", file.display(), e)),
+        }
+    }
+    
+    Ok(SyncResult {
+        pushed: 0,
+        pulled,
+        conflicts,
+        errors,
+    })
+}
+
+pub async fn dir_get_metadata(tenant_id: &str, path: &str) -> Result<SyncMetadata> {
+    read_metadata(&tenant_dir(path, tenant_id)?)
+}
+
+pub async fn dir_fetch(tenant_id: &str, path: &str, namespace: &str, key: &str) -> Result<Option<Secret>> {
+    let file = record_path(&tenant_dir(path, tenant_id)?, namespace, key)?;
+    match std::fs::read(&file) {
+        Ok(body) => Ok(Some(bincode::deserialize(&body)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(sync_error(&format!("Failed to read {}", file.display()), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_record_paths_do_not_collide() {
+        let tenant_dir = Path::new("/sync/tenant");
+        let nested_key = record_path(tenant_dir, "a", "b/c").unwrap();
+        let nested_ns = record_path(tenant_dir, "a/b", "c").unwrap();
+        assert_ne!(nested_key, nested_ns);
+        assert_eq!(nested_key, tenant_dir.join("a").join("b%2Fc.vault"));
+        assert_eq!(nested_ns, tenant_dir.join("a%2Fb").join("c.vault"));
+        
+        // An encoded name doesn't collide with one spelled like its encoding
+        assert_ne!(record_path(tenant_dir, "a", "b%2Fc").unwrap(), nested_key);
+        
+        // Every record stays directly under the tenant directory
+        for (namespace, key) in [("..", "key"), ("ns", ".."), ("../..", "etc/passwd"), (".", "key")] {
+            let path = record_path(tenant_dir, namespace, key).unwrap();
+            assert_eq!(path.parent().and_then(Path::parent), Some(tenant_dir));
+        }
+    }
+    
+    #[test]
+    fn test_tenant_dir_rejects_traversal() {
+        let root = tempfile::TempDir::new().unwrap();
+        let root = root.path().to_str().unwrap();
+        for tenant_id in ["", ".", "..", "../other", "a/b", "a\\b"] {
+            assert!(tenant_dir(root, tenant_id).is_err(), "{:?} was accepted", tenant_id);
+        }
+        assert!(tenant_dir(root, "test-tenant").is_ok());
+    }
+}
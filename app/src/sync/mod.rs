@@ -9,16 +9,40 @@ use crate::{
 
 mod s3;
 mod postgres;
+mod dir;
 mod conflict;
+mod credentials;
 
 pub use s3::*;
 pub use postgres::*;
+pub use dir::*;
 pub use conflict::*;
+pub use credentials::*;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum SyncBackend {
-    S3 { bucket: String, region: String },
+    S3 {
+        bucket: String,
+        region: String,
+        #[serde(skip)]
+        access_key: Option<S3AccessKey>,
+    },
     Postgres { url: String },
+    Directory { path: String },
+}
+
+impl SyncBackend {
+    /// Human-readable backend description that never includes credentials.
+    pub fn describe(&self) -> String {
+        match self {
+            SyncBackend::S3 { bucket, region, .. } => format!("S3 (s3://{}/{})", bucket, region),
+            SyncBackend::Postgres { url } => match split_database_url(url) {
+                Ok((redacted, _)) => format!("Postgres ({})", redacted),
+                Err(_) => "Postgres".to_string(),
+            },
+            SyncBackend::Directory { path } => format!("Directory ({})", path),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -26,6 +50,17 @@ pub struct SyncMetadata {
     pub last_sync: chrono::DateTime<chrono::Utc>,
     pub sync_version: u64,
     pub conflicts: Vec<ConflictInfo>,
+    #[serde(default)]
+    pub secrets: Vec<RemoteSecretInfo>,
+}
+
+/// Per-secret metadata as reported by the remote backend.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteSecretInfo {
+    pub namespace: String,
+    pub key: String,
+    pub version: u64,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -42,6 +77,34 @@ pub enum ConflictType {
     ModifiedBoth,
     DeletedLocal,
     DeletedRemote,
+    LocalOnly,
+    RemoteOnly,
+}
+
+/// Differences between the local vault and the sync backend, grouped for display.
+#[derive(Debug, Serialize, Default)]
+pub struct SyncDiff {
+    pub local_only: Vec<ConflictInfo>,
+    pub remote_only: Vec<ConflictInfo>,
+    pub diverged: Vec<ConflictInfo>,
+}
+
+impl SyncDiff {
+    pub fn from_conflicts(conflicts: Vec<ConflictInfo>) -> Self {
+        let mut diff = Self::default();
+        for conflict in conflicts {
+            match conflict.conflict_type.as_str() {
+                "LocalOnly" => diff.local_only.push(conflict),
+                "RemoteOnly" => diff.remote_only.push(conflict),
+                _ => diff.diverged.push(conflict),
+            }
+        }
+        diff
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.local_only.is_empty() && self.remote_only.is_empty() && self.diverged.is_empty()
+    }
 }
 
 
@@ -56,31 +119,49 @@ impl SyncManager {
         Self { backend, storage }
     }
     
+    pub fn storage(&self) -> &VaultStorage {
+        &self.storage
+    }
+    
     pub fn from_config(config: &CloudConfig, storage: VaultStorage) -> Result<Self> {
-        match config.mode {
-            crate::config::CloudMode::None => {
-                return Err(VaultError::Config("Cloud sync is disabled".to_string()));
-            }
-            _ => {}
+        if let crate::config::CloudMode::None = config.mode {
+            return Err(VaultError::Config("Cloud sync is disabled".to_string()));
         }
         
+        // Connection secrets live in the OS keyring rather than config.toml
+        let keyring = config.credentials_in_keyring.unwrap_or(false);
+        
         let backend = match &config.backend {
             Some(crate::config::CloudBackend::S3) => {
                 let bucket = config.bucket.as_ref()
                     .ok_or_else(|| VaultError::Config("S3 bucket not configured".to_string()))?;
                 let region = config.region.as_ref()
                     .ok_or_else(|| VaultError::Config("S3 region not configured".to_string()))?;
+                let access_key = if keyring {
+                    Some(load_s3_access_key(bucket)?)
+                } else {
+                    None
+                };
                 SyncBackend::S3 {
                     bucket: bucket.clone(),
                     region: region.clone(),
+                    access_key,
                 }
             }
             Some(crate::config::CloudBackend::Postgres) => {
                 let url = config.database_url.as_ref()
                     .ok_or_else(|| VaultError::Config("Database URL not configured".to_string()))?;
-                SyncBackend::Postgres {
-                    url: url.clone(),
-                }
+                let url = if keyring {
+                    resolve_database_url(url)?
+                } else {
+                    url.clone()
+                };
+                SyncBackend::Postgres { url }
+            }
+            Some(crate::config::CloudBackend::Directory) => {
+                let path = config.directory.as_ref()
+                    .ok_or_else(|| VaultError::Config("Sync directory not configured".to_string()))?;
+                SyncBackend::Directory { path: path.clone() }
             }
             None => {
                 return Err(VaultError::Config("No sync backend configured".to_string()));
@@ -90,63 +171,229 @@ impl SyncManager {
         Ok(Self::new(backend, storage))
     }
     
-    pub async fn push(&self, force: bool) -> Result<SyncResult> {
-        match &self.backend {
-            SyncBackend::S3 { bucket, region } => {
-                s3_push(&self.storage, bucket, region, force).await
+    pub async fn push(&self, tenant_id: &str, force: bool) -> Result<SyncRecord> {
+        let started_at = chrono::Utc::now();
+        let timer = std::time::Instant::now();
+        let result = match &self.backend {
+            SyncBackend::S3 { bucket, region, access_key } => {
+                s3_push(&self.storage, bucket, region, access_key.as_ref(), force).await
             }
             SyncBackend::Postgres { url } => {
                 postgres_push(&self.storage, url, force).await
             }
-        }
+            SyncBackend::Directory { path } => {
+                dir_push(&self.storage, path, force).await
+            }
+        };
+        
+        self.finish_sync(tenant_id, SyncOperation::Push, started_at, timer, result).await
     }
     
-    pub async fn pull(&self, force: bool) -> Result<SyncResult> {
-        match &self.backend {
-            SyncBackend::S3 { bucket, region } => {
-                s3_pull(&self.storage, bucket, region, force).await
+    pub async fn pull(&self, tenant_id: &str, force: bool) -> Result<SyncRecord> {
+        let started_at = chrono::Utc::now();
+        let timer = std::time::Instant::now();
+        let result = match &self.backend {
+            SyncBackend::S3 { bucket, region, access_key } => {
+                s3_pull(&self.storage, bucket, region, access_key.as_ref(), force).await
             }
             SyncBackend::Postgres { url } => {
                 postgres_pull(&self.storage, url, force).await
             }
+            SyncBackend::Directory { path } => {
+                dir_pull(&self.storage, path, force).await
+            }
+        };
+        
+        self.finish_sync(tenant_id, SyncOperation::Pull, started_at, timer, result).await
+    }
+    
+    /// Persist a completed push/pull in the sync history. Failed runs are
+    /// recorded too, with the error, so flaky backends leave a trail.
+    async fn finish_sync(
+        &self,
+        tenant_id: &str,
+        operation: SyncOperation,
+        started_at: chrono::DateTime<chrono::Utc>,
+        timer: std::time::Instant,
+        result: Result<SyncResult>,
+    ) -> Result<SyncRecord> {
+        let (result, failure) = match result {
+            Ok(result) => (result, None),
+            Err(e) => (SyncResult {
+                pushed: 0,
+                pulled: 0,
+                conflicts: Vec::new(),
+                errors: vec![e.to_string()],
+            }, Some(e)),
+        };
+        
+        let record = SyncRecord {
+            operation,
+            started_at,
+            duration_ms: timer.elapsed().as_millis() as u64,
+            result,
+        };
+        self.storage.append_sync_history(tenant_id, &record)?;
+        
+        if let Some(e) = failure {
+            return Err(e);
         }
+        self.record_sync_state(tenant_id, &record.result).await?;
+        Ok(record)
     }
     
-    pub async fn status(&self) -> Result<SyncStatus> {
-        // Get local metadata
-        let local_secrets = self.get_local_secrets().await?;
+    /// Sync status computed against the cached remote state, or against the
+    /// backend when `refresh` is set or nothing is cached yet. If the backend
+    /// cannot be reached the cached state is used and the error is reported.
+    pub async fn status(&self, tenant_id: &str, refresh: bool) -> Result<SyncStatus> {
+        let cached = self.storage.load_sync_state(tenant_id)?;
         
-        // Get remote metadata
-        let remote_metadata = match &self.backend {
-            SyncBackend::S3 { bucket, region } => {
-                s3_get_metadata(bucket, region).await?
-            }
-            SyncBackend::Postgres { url } => {
-                postgres_get_metadata(url).await?
-            }
+        let (remote_metadata, from_cache, remote_error) = match cached {
+            Some(state) if !refresh => (state, true, None),
+            cached => match self.get_remote_metadata(tenant_id).await {
+                Ok(remote) => {
+                    self.storage.save_sync_state(tenant_id, &remote)?;
+                    (remote, false, None)
+                }
+                Err(e) => match cached {
+                    Some(state) => (state, true, Some(e.to_string())),
+                    None => return Err(e),
+                },
+            },
         };
         
-        // Compare and detect conflicts
+        // Compare local secrets against the remote view and detect conflicts
+        let local_secrets = self.get_local_secrets(tenant_id).await?;
         let conflicts = detect_conflicts(&local_secrets, &remote_metadata).await?;
+        let diverged = conflicts.iter()
+            .filter(|c| c.conflict_type == "ModifiedBoth")
+            .count();
         
         Ok(SyncStatus {
-            backend: format!("{:?}", self.backend),
+            backend: self.backend.describe(),
             last_sync: remote_metadata.last_sync,
             local_secrets: local_secrets.len(),
-            remote_secrets: remote_metadata.sync_version as usize,
-            conflicts: conflicts.len(),
+            remote_secrets: remote_metadata.secrets.len(),
+            conflicts: diverged,
             sync_needed: !conflicts.is_empty(),
+            from_cache,
+            remote_error,
         })
     }
     
-    async fn get_local_secrets(&self) -> Result<HashMap<String, Secret>> {
-        // This would need access to storage internals
-        // For now, return empty map as placeholder
-        Ok(HashMap::new())
+    /// Compare local secrets against the remote metadata without changing either side.
+    pub async fn diff(&self, tenant_id: &str) -> Result<SyncDiff> {
+        let local_secrets = self.get_local_secrets(tenant_id).await?;
+        let remote_metadata = self.get_remote_metadata(tenant_id).await?;
+        let conflicts = detect_conflicts(&local_secrets, &remote_metadata).await?;
+        Ok(SyncDiff::from_conflicts(conflicts))
+    }
+    
+    async fn get_remote_metadata(&self, tenant_id: &str) -> Result<SyncMetadata> {
+        match &self.backend {
+            SyncBackend::S3 { bucket, region, access_key } => {
+                s3_get_metadata(tenant_id, bucket, region, access_key.as_ref()).await
+            }
+            SyncBackend::Postgres { url } => {
+                postgres_get_metadata(tenant_id, url).await
+            }
+            SyncBackend::Directory { path } => {
+                dir_get_metadata(tenant_id, path).await
+            }
+        }
+    }
+    
+    async fn fetch_remote(&self, tenant_id: &str, namespace: &str, key: &str) -> Result<Option<Secret>> {
+        match &self.backend {
+            SyncBackend::S3 { bucket, region, access_key } => {
+                s3_fetch(tenant_id, bucket, region, access_key.as_ref(), namespace, key).await
+            }
+            SyncBackend::Postgres { url } => {
+                postgres_fetch(tenant_id, url, namespace, key).await
+            }
+            SyncBackend::Directory { path } => {
+                dir_fetch(tenant_id, path, namespace, key).await
+            }
+        }
+    }
+    
+    /// Write the winner of each resolved conflict into the local vault and
+    /// return how many secrets changed. A remote winner that no longer exists
+    /// deletes the local copy; a local winner is stamped newer than the remote
+    /// so the next pull keeps it and the next push uploads it.
+    pub async fn resolve_and_apply(&self, resolutions: Vec<(ConflictInfo, ConflictResolution)>) -> Result<usize> {
+        let tenant_id = self.storage.current_tenant_id()?;
+        let mut local: HashMap<(String, String), Secret> = self.storage.sync_records().await?
+            .into_iter()
+            .map(|secret| ((secret.metadata.namespace.clone(), secret.metadata.key.clone()), secret))
+            .collect();
+        
+        let mut applied = 0;
+        for (conflict, resolution) in resolutions {
+            let (namespace, key) = (conflict.namespace.as_str(), conflict.secret_key.as_str());
+            let ours = local.remove(&(namespace.to_string(), key.to_string()));
+            let theirs = match resolution {
+                ConflictResolution::Skip => continue,
+                ConflictResolution::UseLocal => None,
+                ConflictResolution::UseRemote | ConflictResolution::Merge => {
+                    self.fetch_remote(&tenant_id, namespace, key).await?
+                }
+            };
+            
+            let winner = match (resolution, ours, theirs) {
+                (ConflictResolution::UseRemote, Some(_), None) => {
+                    // Deleted on the remote side
+                    self.storage.delete(key, namespace).await?;
+                    applied += 1;
+                    continue;
+                }
+                (ConflictResolution::UseRemote, _, Some(theirs)) => theirs,
+                (ConflictResolution::Merge, Some(ours), Some(theirs)) => {
+                    ConflictResolver::resolve_conflict(&ours, &theirs, ConflictResolution::Merge)?
+                }
+                (ConflictResolution::Merge, None, Some(theirs)) => theirs,
+                (ConflictResolution::UseLocal | ConflictResolution::Merge, Some(mut ours), _) => {
+                    if ours.metadata.version > conflict.remote_version {
+                        continue; // Already ahead of the remote
+                    }
+                    ours.metadata.version = conflict.remote_version + 1;
+                    ours.metadata.updated_at = chrono::Utc::now();
+                    ours
+                }
+                _ => continue,
+            };
+            self.storage.apply_synced_record(winner).await?;
+            applied += 1;
+        }
+        
+        Ok(applied)
+    }
+    
+    /// Local records for `tenant_id`, which must be the tenant the vault is
+    /// unlocked for.
+    async fn get_local_secrets(&self, tenant_id: &str) -> Result<HashMap<String, Secret>> {
+        if self.storage.current_tenant_id()? != tenant_id {
+            return Err(VaultError::Sync(format!("The vault is not unlocked for tenant {}", tenant_id)));
+        }
+        self.storage.export_all_secrets().await
+    }
+    
+    /// After a clean push/pull, cache the remote state as the backend now
+    /// reports it, so only writes it confirms are recorded. If it cannot be
+    /// read back, the previously cached state is kept.
+    async fn record_sync_state(&self, tenant_id: &str, result: &SyncResult) -> Result<()> {
+        if !result.errors.is_empty() {
+            return Ok(());
+        }
+        
+        match self.get_remote_metadata(tenant_id).await {
+            Ok(remote) => self.storage.save_sync_state(tenant_id, &remote),
+            Err(_) => Ok(()),
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncResult {
     pub pushed: usize,
     pub pulled: usize,
@@ -154,6 +401,47 @@ pub struct SyncResult {
     pub errors: Vec<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SyncOperation {
+    Push,
+    Pull,
+}
+
+impl std::fmt::Display for SyncOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncOperation::Push => write!(f, "push"),
+            SyncOperation::Pull => write!(f, "pull"),
+        }
+    }
+}
+
+/// One completed sync, stored under `sync_history:<tenant>:<ts>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRecord {
+    pub operation: SyncOperation,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub duration_ms: u64,
+    pub result: SyncResult,
+}
+
+impl SyncRecord {
+    /// One-line summary, e.g. `pushed 12, pulled 3, 1 conflict, 0 errors in 2.1s`.
+    pub fn summary(&self) -> String {
+        let plural = |count: usize, word: &str| {
+            format!("{} {}{}", count, word, if count == 1 { "" } else { "s" })
+        };
+        format!(
+            "pushed {}, pulled {}, {}, {} in {:.1}s",
+            self.result.pushed,
+            self.result.pulled,
+            plural(self.result.conflicts.len(), "conflict"),
+            plural(self.result.errors.len(), "error"),
+            self.duration_ms as f64 / 1000.0,
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct SyncStatus {
     pub backend: String,
@@ -162,4 +450,151 @@ pub struct SyncStatus {
     pub remote_secrets: usize,
     pub conflicts: usize,
     pub sync_needed: bool,
-}
\ No newline at end of file
+    pub from_cache: bool,
+    pub remote_error: Option<String>, // Set when the backend was unreachable and the cache was used
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[tokio::test]
+    async fn test_status_uses_cached_state_after_push() {
+        let temp_dir = TempDir::new().unwrap();
+        let sync_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let mut storage = VaultStorage::new(db_path.to_str().unwrap()).unwrap();
+        storage.init_tenant_with_password("test-tenant", "admin@test.com", "test-passphrase").await.unwrap();
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        storage.put("api-key", "value", "default").await.unwrap();
+        
+        let backend = SyncBackend::Directory { path: sync_dir.path().to_str().unwrap().to_string() };
+        let manager = SyncManager::new(backend, storage);
+        manager.push("test-tenant", false).await.unwrap();
+        
+        let status = manager.status("test-tenant", false).await.unwrap();
+        assert!(status.from_cache);
+        assert_eq!(status.remote_secrets, 1);
+        assert!(!status.sync_needed);
+        
+        let refreshed = manager.status("test-tenant", true).await.unwrap();
+        assert!(!refreshed.from_cache);
+    }
+    
+    #[tokio::test]
+    #[cfg_attr(feature = "s3", ignore = "pushes to a real S3 bucket")]
+    async fn test_unconfirmed_push_is_not_cached_as_synced() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let mut storage = VaultStorage::new(db_path.to_str().unwrap()).unwrap();
+        storage.init_tenant_with_password("test-tenant", "admin@test.com", "test-passphrase").await.unwrap();
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        storage.put("api-key", "value", "default").await.unwrap();
+        
+        // Without the `s3` feature the push uploads nothing
+        let backend = SyncBackend::S3 {
+            bucket: "bucket".to_string(),
+            region: "us-east-1".to_string(),
+            access_key: None,
+        };
+        let manager = SyncManager::new(backend, storage);
+        manager.push("test-tenant", false).await.unwrap();
+        
+        let status = manager.status("test-tenant", false).await.unwrap();
+        assert_eq!(status.remote_secrets, 0);
+        assert!(status.sync_needed);
+    }
+    
+    #[tokio::test]
+    #[cfg_attr(feature = "s3", ignore = "pushes to a real S3 bucket")]
+    async fn test_sync_history_records_each_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let mut storage = VaultStorage::new(db_path.to_str().unwrap()).unwrap();
+        storage.init_tenant_with_password("test-tenant", "admin@test.com", "test-passphrase").await.unwrap();
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        
+        let backend = SyncBackend::S3 {
+            bucket: "bucket".to_string(),
+            region: "us-east-1".to_string(),
+            access_key: None,
+        };
+        let manager = SyncManager::new(backend, storage);
+        let pushed = manager.push("test-tenant", false).await.unwrap();
+        manager.pull("test-tenant", false).await.unwrap();
+        assert!(pushed.summary().starts_with("pushed 0, pulled 0, 0 conflicts, 0 errors in "));
+        
+        let history = manager.storage.sync_history("test-tenant", None).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].operation, SyncOperation::Pull);
+        assert_eq!(history[1].operation, SyncOperation::Push);
+        
+        let latest = manager.storage.sync_history("test-tenant", Some(1)).unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].operation, SyncOperation::Pull);
+    }
+    
+    #[tokio::test]
+    async fn test_directory_backend_round_trips_secrets() {
+        let temp_dir = TempDir::new().unwrap();
+        let sync_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let mut storage = VaultStorage::new(db_path.to_str().unwrap()).unwrap();
+        storage.init_tenant_with_password("test-tenant", "admin@test.com", "test-passphrase").await.unwrap();
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        storage.put("api-key", "value", "default").await.unwrap();
+        storage.put("token", "other", "prod").await.unwrap();
+        
+        let backend = SyncBackend::Directory { path: sync_dir.path().to_str().unwrap().to_string() };
+        let manager = SyncManager::new(backend, storage);
+        let pushed = manager.push("test-tenant", false).await.unwrap();
+        assert_eq!(pushed.result.pushed, 2);
+        assert!(sync_dir.path().join("test-tenant/default/api-key.vault").is_file());
+        assert!(sync_dir.path().join("test-tenant/_metadata.json").is_file());
+        
+        let metadata = manager.get_remote_metadata("test-tenant").await.unwrap();
+        assert_eq!(metadata.secrets.len(), 2);
+        
+        manager.storage.delete_hard("api-key", "default").await.unwrap();
+        let pulled = manager.pull("test-tenant", false).await.unwrap();
+        assert_eq!(pulled.result.pulled, 1);
+        assert!(pulled.result.errors.is_empty());
+        assert_eq!(manager.storage.get("api-key", "default").await.unwrap().as_deref(), Some("value"));
+    }
+    
+    #[tokio::test]
+    async fn test_resolve_and_apply_writes_the_chosen_side() {
+        let temp_dir = TempDir::new().unwrap();
+        let sync_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let mut storage = VaultStorage::new(db_path.to_str().unwrap()).unwrap();
+        storage.init_tenant_with_password("test-tenant", "admin@test.com", "test-passphrase").await.unwrap();
+        storage.unlock("test-tenant", "test-passphrase").unwrap();
+        storage.put("api-key", "pushed", "default").await.unwrap();
+        
+        let backend = SyncBackend::Directory { path: sync_dir.path().to_str().unwrap().to_string() };
+        let manager = SyncManager::new(backend, storage);
+        manager.push("test-tenant", false).await.unwrap();
+        manager.storage.put("api-key", "local edit", "default").await.unwrap();
+        manager.storage.put("local-only", "value", "default").await.unwrap();
+        
+        // Taking the remote side reverts the edit and drops what the remote never had
+        let diff = manager.diff("test-tenant").await.unwrap();
+        let conflicts: Vec<ConflictInfo> = diff.diverged.into_iter().chain(diff.local_only).collect();
+        let resolutions = ConflictResolver::auto_resolve_conflicts(&conflicts, AutoResolveStrategy::PreferRemote).unwrap();
+        assert_eq!(manager.resolve_and_apply(resolutions).await.unwrap(), 2);
+        assert_eq!(manager.storage.get("api-key", "default").await.unwrap().as_deref(), Some("pushed"));
+        assert_eq!(manager.storage.get("local-only", "default").await.unwrap(), None);
+        
+        // Taking the local side survives the next pull
+        manager.storage.delete_hard("api-key", "default").await.unwrap();
+        manager.storage.put("api-key", "kept", "default").await.unwrap();
+        let diff = manager.diff("test-tenant").await.unwrap();
+        let resolutions = ConflictResolver::auto_resolve_conflicts(&diff.diverged, AutoResolveStrategy::PreferLocal).unwrap();
+        assert_eq!(manager.resolve_and_apply(resolutions).await.unwrap(), 1);
+        manager.pull("test-tenant", false).await.unwrap();
+        assert_eq!(manager.storage.get("api-key", "default").await.unwrap().as_deref(), Some("kept"));
+    }
+}
+
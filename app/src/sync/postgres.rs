@@ -1,9 +1,312 @@
+#[cfg(not(feature = "postgres"))]
 use crate::{
-    storage::VaultStorage,
+    storage::{Secret, VaultStorage},
     sync::{SyncResult, SyncMetadata},
-    error::Result,
+    error::{VaultError, Result},
 };
 
+#[cfg(feature = "postgres")]
+pub use client::{postgres_push, postgres_pull, postgres_get_metadata, postgres_fetch};
+
+/// Secrets live in `vault_secrets`, one row per `(tenant_id, namespace, key)`
+/// holding the bincode of the record's metadata and `EncryptedData`, next to
+/// a `vault_sync_metadata` row per tenant describing the last push.
+#[cfg(feature = "postgres")]
+mod client {
+    use std::collections::HashMap;
+    
+    use chrono::{DateTime, Utc};
+    use sqlx::{Connection, PgConnection};
+    
+    use crate::{
+        storage::{Secret, VaultStorage},
+        sync::{modified_both, split_database_url, supersedes, RemoteSecretInfo, SyncResult, SyncMetadata},
+        error::{VaultError, Result},
+    };
+    
+    type SecretRow = (String, String, i64, DateTime<Utc>, Vec<u8>, Vec<u8>, Option<Vec<u8>>, bool);
+    type RecordColumns = (Vec<u8>, Vec<u8>, Option<Vec<u8>>, bool);
+    
+    fn sync_error(context: &str, error: sqlx::Error) -> VaultError {
+        VaultError::Sync(format!("{}: {}", context, error))
+    }
+    
+    /// Tables that have not been created yet read as an empty remote.
+    fn is_missing_table(error: &sqlx::Error) -> bool {
+        matches!(error, sqlx::Error::Database(e) if e.code().as_deref() == Some("42P01"))
+    }
+    
+    async fn connect(url: &str) -> Result<PgConnection> {
+        // Never echo the password back in an error
+        let display = split_database_url(url).map(|(redacted, _)| redacted).unwrap_or_default();
+        PgConnection::connect(url).await
+            .map_err(|e| sync_error(&format!("Failed to connect to {}", display), e))
+    }
+    
+    fn decode_row(metadata: &[u8], encrypted_value: &[u8], encrypted_dek: Option<Vec<u8>>, namespace_key: bool) -> bincode::Result<Secret> {
+        Ok(Secret {
+            metadata: bincode::deserialize(metadata)?,
+            encrypted_value: bincode::deserialize(encrypted_value)?,
+            encrypted_dek,
+            namespace_key,
+            chunks: 0,
+        })
+    }
+    
+    async fn create_tables(conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS vault_secrets (
+                tenant_id TEXT NOT NULL,
+                namespace TEXT NOT NULL,
+                key TEXT NOT NULL,
+                version BIGINT NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL,
+                metadata BYTEA NOT NULL,
+                encrypted_value BYTEA NOT NULL,
+                encrypted_dek BYTEA,
+                namespace_key BOOLEAN NOT NULL DEFAULT FALSE,
+                PRIMARY KEY (tenant_id, namespace, key)
+            )",
+        )
+        .execute(&mut *conn).await
+        .map_err(|e| sync_error("Failed to create vault_secrets", e))?;
+        
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS vault_sync_metadata (
+                tenant_id TEXT PRIMARY KEY,
+                sync_version BIGINT NOT NULL,
+                last_sync TIMESTAMPTZ NOT NULL,
+                conflicts TEXT NOT NULL DEFAULT '[]'
+            )",
+        )
+        .execute(&mut *conn).await
+        .map_err(|e| sync_error("Failed to create vault_sync_metadata", e))?;
+        Ok(())
+    }
+    
+    async fn read_metadata(conn: &mut PgConnection, tenant_id: &str) -> Result<SyncMetadata> {
+        let mut metadata = SyncMetadata {
+            last_sync: DateTime::<Utc>::MIN_UTC,
+            sync_version: 0,
+            conflicts: Vec::new(),
+            secrets: Vec::new(),
+        };
+        
+        let row: Option<(i64, DateTime<Utc>, String)> = match sqlx::query_as(
+            "SELECT sync_version, last_sync, conflicts FROM vault_sync_metadata WHERE tenant_id = $1",
+        )
+        .bind(tenant_id)
+        .fetch_optional(&mut *conn).await
+        {
+            Ok(row) => row,
+            Err(e) if is_missing_table(&e) => return Ok(metadata), // Nothing pushed yet
+            Err(e) => return Err(sync_error("Failed to read vault_sync_metadata", e)),
+        };
+        if let Some((sync_version, last_sync, conflicts)) = row {
+            metadata.sync_version = sync_version as u64;
+            metadata.last_sync = last_sync;
+            metadata.conflicts = serde_json::from_str(&conflicts)
+                .map_err(|e| VaultError::Sync(format!("Invalid vault_sync_metadata: {}", e)))?;
+        }
+        
+        let rows: Vec<(String, String, i64, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT namespace, key, version, updated_at FROM vault_secrets WHERE tenant_id = $1 ORDER BY namespace, key",
+        )
+        .bind(tenant_id)
+        .fetch_all(&mut *conn).await
+        .map_err(|e| sync_error("Failed to read vault_secrets", e))?;
+        metadata.secrets = rows.into_iter()
+            .map(|(namespace, key, version, updated_at)| RemoteSecretInfo {
+                namespace,
+                key,
+                version: version as u64,
+                updated_at,
+            })
+            .collect();
+        Ok(metadata)
+    }
+    
+    pub async fn postgres_push(
+        storage: &VaultStorage,
+        url: &str,
+        force: bool,
+    ) -> Result<SyncResult> {
+        let tenant_id = storage.current_tenant_id()?;
+        let mut conn = connect(url).await?;
+        
+        // Every write below lands together or not at all
+        let mut tx = conn.begin().await
+            .map_err(|e| sync_error("Failed to start transaction", e))?;
+        create_tables(&mut tx).await?;
+        
+        let metadata = read_metadata(&mut tx, &tenant_id).await?;
+        let remote: HashMap<(String, String), RemoteSecretInfo> = metadata.secrets.into_iter()
+            .map(|info| ((info.namespace.clone(), info.key.clone()), info))
+            .collect();
+        
+        let mut pushed = 0;
+        let mut conflicts = Vec::new();
+        for secret in storage.sync_records().await? {
+            let meta = &secret.metadata;
+            if let Some(info) = remote.get(&(meta.namespace.clone(), meta.key.clone())) {
+                match supersedes((meta.version, meta.updated_at), (info.version, info.updated_at)) {
+                    None => continue,
+                    Some(false) if !force => {
+                        conflicts.push(modified_both(&secret, meta.version, info.version));
+                        continue;
+                    }
+                    Some(_) => {}
+                }
+            }
+            
+            sqlx::query(
+                "INSERT INTO vault_secrets
+                    (tenant_id, namespace, key, version, updated_at, metadata, encrypted_value, encrypted_dek, namespace_key)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                ON CONFLICT (tenant_id, namespace, key) DO UPDATE SET
+                    version = EXCLUDED.version,
+                    updated_at = EXCLUDED.updated_at,
+                    metadata = EXCLUDED.metadata,
+                    encrypted_value = EXCLUDED.encrypted_value,
+                    encrypted_dek = EXCLUDED.encrypted_dek,
+                    namespace_key = EXCLUDED.namespace_key",
+            )
+            .bind(&tenant_id)
+            .bind(&meta.namespace)
+            .bind(&meta.key)
+            .bind(meta.version as i64)
+            .bind(meta.updated_at)
+            .bind(bincode::serialize(meta)?)
+            .bind(bincode::serialize(&secret.encrypted_value)?)
+            .bind(&secret.encrypted_dek)
+            .bind(secret.namespace_key)
+            .execute(&mut *tx).await
+            .map_err(|e| sync_error(&format!("Failed to push {}/{}", meta.namespace, meta.key), e))?;
+            pushed += 1;
+        }
+        
+        let conflicts_json = serde_json::to_string(&conflicts)
+            .map_err(|e| VaultError::Sync(e.to_string()))?;
+        sqlx::query(
+            "INSERT INTO vault_sync_metadata (tenant_id, sync_version, last_sync, conflicts)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (tenant_id) DO UPDATE SET
+                sync_version = EXCLUDED.sync_version,
+                last_sync = EXCLUDED.last_sync,
+                conflicts = EXCLUDED.conflicts",
+        )
+        .bind(&tenant_id)
+        .bind(metadata.sync_version as i64 + 1)
+        .bind(Utc::now())
+        .bind(conflicts_json)
+        .execute(&mut *tx).await
+        .map_err(|e| sync_error("Failed to update vault_sync_metadata", e))?;
+        
+        tx.commit().await
+            .map_err(|e| sync_error("Failed to commit push", e))?;
+        
+        Ok(SyncResult {
+            pushed,
+            pulled: 0,
+            conflicts,
+            errors: Vec::new(),
+        })
+    }
+    
+    pub async fn postgres_pull(
+        storage: &VaultStorage,
+        url: &str,
+        force: bool,
+    ) -> Result<SyncResult> {
+        let tenant_id = storage.current_tenant_id()?;
+        let mut conn = connect(url).await?;
+        
+        let rows: Vec<SecretRow> = match sqlx::query_as(
+            "SELECT namespace, key, version, updated_at, metadata, encrypted_value, encrypted_dek, namespace_key
+            FROM vault_secrets WHERE tenant_id = $1 ORDER BY namespace, key",
+        )
+        .bind(&tenant_id)
+        .fetch_all(&mut conn).await
+        {
+            Ok(rows) => rows,
+            Err(e) if is_missing_table(&e) => Vec::new(), // Nothing pushed yet
+            Err(e) => return Err(sync_error("Failed to read vault_secrets", e)),
+        };
+        
+        let local: HashMap<(String, String), Secret> = storage.secret_records(&tenant_id).await?
+            .into_values()
+            .map(|secret| ((secret.metadata.namespace.clone(), secret.metadata.key.clone()), secret))
+            .collect();
+        
+        let mut pulled = 0;
+        let mut conflicts = Vec::new();
+        let mut errors = Vec::new();
+        for (namespace, key, _, _, metadata, encrypted_value, encrypted_dek, namespace_key) in rows {
+            let remote = match decode_row(&metadata, &encrypted_value, encrypted_dek, namespace_key) {
+                Ok(secret) => secret,
+                Err(e) => {
+                    errors.push(format!("Invalid row {}/{}: {}", namespace, key, e));
+                    continue;
+                }
+            };
+            
+            let meta = &remote.metadata;
+            if let Some(ours) = local.get(&(meta.namespace.clone(), meta.key.clone())) {
+                let ours = &ours.metadata;
+                match supersedes((meta.version, meta.updated_at), (ours.version, ours.updated_at)) {
+                    None => continue,
+                    Some(false) if !force => {
+                        conflicts.push(modified_both(&remote, ours.version, meta.version));
+                        continue;
+                    }
+                    Some(_) => {}
+                }
+            }
+            
+            match storage.apply_synced_record(remote).await {
+                Ok(()) => pulled += 1,
+                Err(e) => errors.push(format!("{}/{}: {}", namespace, key, e)),
+            }
+        }
+        
+        Ok(SyncResult {
+            pushed: 0,
+            pulled,
+            conflicts,
+            errors,
+        })
+    }
+    
+    pub async fn postgres_get_metadata(tenant_id: &str, url: &str) -> Result<SyncMetadata> {
+        let mut conn = connect(url).await?;
+        read_metadata(&mut conn, tenant_id).await
+    }
+    
+    pub async fn postgres_fetch(tenant_id: &str, url: &str, namespace: &str, key: &str) -> Result<Option<Secret>> {
+        let mut conn = connect(url).await?;
+        let row: Option<RecordColumns> = match sqlx::query_as(
+            "SELECT metadata, encrypted_value, encrypted_dek, namespace_key
+            FROM vault_secrets WHERE tenant_id = $1 AND namespace = $2 AND key = $3",
+        )
+        .bind(tenant_id)
+        .bind(namespace)
+        .bind(key)
+        .fetch_optional(&mut conn).await
+        {
+            Ok(row) => row,
+            Err(e) if is_missing_table(&e) => None,
+            Err(e) => return Err(sync_error(&format!("Failed to read {}/{}", namespace, key), e)),
+        };
+        match row {
+            Some((metadata, encrypted_value, encrypted_dek, namespace_key)) => {
+                Ok(Some(decode_row(&metadata, &encrypted_value, encrypted_dek, namespace_key)?))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(not(feature = "postgres"))]
 pub async fn postgres_push(
     _storage: &VaultStorage,
     url: &str,
@@ -12,7 +315,7 @@ pub async fn postgres_push(
     let pushed = 0;
     let errors = Vec::new();
     
-    // In a real implementation, get secrets and insert into Postgres
+    // Built without the `postgres` feature: nothing is written
     println!("Pushing secrets to {} (force: {})", url, _force);
     
     // Simulate some work
@@ -26,6 +329,7 @@ pub async fn postgres_push(
     })
 }
 
+#[cfg(not(feature = "postgres"))]
 pub async fn postgres_pull(
     _storage: &VaultStorage,
     url: &str,
@@ -34,7 +338,7 @@ pub async fn postgres_pull(
     let pulled = 0;
     let errors = Vec::new();
     
-    // In a real implementation, select from Postgres here
+    // Built without the `postgres` feature: nothing is read
     println!("Would pull from {}", url);
     
     Ok(SyncResult {
@@ -45,13 +349,21 @@ pub async fn postgres_pull(
     })
 }
 
-pub async fn postgres_get_metadata(url: &str) -> Result<SyncMetadata> {
-    // In a real implementation, query metadata from Postgres
-    println!("Getting metadata from {}", url);
+#[cfg(not(feature = "postgres"))]
+pub async fn postgres_get_metadata(_tenant_id: &str, url: &str) -> Result<SyncMetadata> {
+    // Built without the `postgres` feature: report an empty remote
+    eprintln!("Getting metadata from {}", url);
     
     Ok(SyncMetadata {
         last_sync: chrono::Utc::now(),
         sync_version: 1,
         conflicts: Vec::new(),
+        secrets: Vec::new(),
     })
+}
+
+#[cfg(not(feature = "postgres"))]
+pub async fn postgres_fetch(_tenant_id: &str, _url: &str, _namespace: &str, _key: &str) -> Result<Option<Secret>> {
+    // Unlike push and pull there is no harmless stand-in: an empty answer would read as deleted remotely
+    Err(VaultError::Sync("Cannot read from Postgres: built without the `postgres` feature".to_string()))
 }
\ No newline at end of file
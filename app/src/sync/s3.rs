@@ -1,19 +1,266 @@
+#[cfg(not(feature = "s3"))]
 use crate::{
-    storage::VaultStorage,
-    sync::{SyncResult, SyncMetadata},
-    error::Result,
+    storage::{Secret, VaultStorage},
+    sync::{SyncResult, SyncMetadata, S3AccessKey},
+    error::{VaultError, Result},
 };
 
+#[cfg(feature = "s3")]
+pub use client::{s3_push, s3_pull, s3_get_metadata, s3_fetch};
+
+/// Objects live under `vault/{tenant}/{namespace}/{key}`, next to a
+/// `vault/{tenant}/_metadata.json` describing what was last pushed.
+#[cfg(feature = "s3")]
+mod client {
+    use std::collections::HashMap;
+    
+    use aws_sdk_s3::{error::DisplayErrorContext, primitives::ByteStream, Client};
+    
+    use crate::{
+        storage::{Secret, VaultStorage},
+        sync::{modified_both, supersedes, RemoteSecretInfo, SyncResult, SyncMetadata, S3AccessKey},
+        error::{VaultError, Result},
+    };
+    
+    const METADATA_FILE: &str = "_metadata.json";
+    
+    fn sync_error(context: &str, error: impl std::error::Error) -> VaultError {
+        VaultError::Sync(format!("{}: {}", context, DisplayErrorContext(error)))
+    }
+    
+    async fn connect(region: &str, access_key: Option<&S3AccessKey>) -> Client {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(aws_config::Region::new(region.to_string()));
+        if let Some(key) = access_key {
+            loader = loader.credentials_provider(aws_sdk_s3::config::Credentials::new(
+                &key.access_key_id,
+                &key.secret_access_key,
+                None,
+                None,
+                "rvault-keyring",
+            ));
+        }
+        Client::new(&loader.load().await)
+    }
+    
+    fn tenant_prefix(tenant_id: &str) -> String {
+        format!("vault/{}/", tenant_id)
+    }
+    
+    fn object_key(tenant_id: &str, namespace: &str, key: &str) -> String {
+        format!("{}{}/{}", tenant_prefix(tenant_id), namespace, key)
+    }
+    
+    async fn read_metadata(client: &Client, bucket: &str, tenant_id: &str) -> Result<SyncMetadata> {
+        let key = format!("{}{}", tenant_prefix(tenant_id), METADATA_FILE);
+        let output = match client.get_object().bucket(bucket).key(&key).send().await {
+            Ok(output) => output,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => {
+                // Nothing pushed yet
+                return Ok(SyncMetadata {
+                    last_sync: chrono::DateTime::<chrono::Utc>::MIN_UTC,
+                    sync_version: 0,
+                    conflicts: Vec::new(),
+                    secrets: Vec::new(),
+                });
+            }
+            Err(e) => return Err(sync_error(&format!("Failed to read s3://{}/{}", bucket, key), e)),
+        };
+        let body = output.body.collect().await
+            .map_err(|e| sync_error(&format!("Failed to read s3://{}/{}", bucket, key), e))?;
+        serde_json::from_slice(&body.into_bytes())
+            .map_err(|e| VaultError::Sync(format!("Invalid s3://{}/{}: {}", bucket, key, e)))
+    }
+    
+    pub async fn s3_push(
+        storage: &VaultStorage,
+        bucket: &str,
+        region: &str,
+        access_key: Option<&S3AccessKey>,
+        force: bool,
+    ) -> Result<SyncResult> {
+        let tenant_id = storage.current_tenant_id()?;
+        let client = connect(region, access_key).await;
+        
+        let mut metadata = read_metadata(&client, bucket, &tenant_id).await?;
+        let mut remote: HashMap<(String, String), RemoteSecretInfo> = metadata.secrets.drain(..)
+            .map(|info| ((info.namespace.clone(), info.key.clone()), info))
+            .collect();
+        
+        let mut pushed = 0;
+        let mut conflicts = Vec::new();
+        let mut errors = Vec::new();
+        for secret in storage.sync_records().await? {
+            let meta = &secret.metadata;
+            let id = (meta.namespace.clone(), meta.key.clone());
+            if let Some(info) = remote.get(&id) {
+                match supersedes((meta.version, meta.updated_at), (info.version, info.updated_at)) {
+                    None => continue,
+                    Some(false) if !force => {
+                        conflicts.push(modified_both(&secret, meta.version, info.version));
+                        continue;
+                    }
+                    Some(_) => {}
+                }
+            }
+            
+            let key = object_key(&meta.tenant_id, &meta.namespace, &meta.key);
+            let body = bincode::serialize(&secret)?;
+            match client.put_object().bucket(bucket).key(&key).body(ByteStream::from(body)).send().await {
+                Ok(_) => {
+                    remote.insert(id, RemoteSecretInfo {
+                        namespace: meta.namespace.clone(),
+                        key: meta.key.clone(),
+                        version: meta.version,
+                        updated_at: meta.updated_at,
+                    });
+                    pushed += 1;
+                }
+                Err(e) => errors.push(sync_error(&format!("Failed to upload s3://{}/{}", bucket, key), e).to_string()),
+            }
+        }
+        
+        let mut secrets: Vec<_> = remote.into_values().collect();
+        secrets.sort_by(|a, b| (&a.namespace, &a.key).cmp(&(&b.namespace, &b.key)));
+        let metadata = SyncMetadata {
+            last_sync: chrono::Utc::now(),
+            sync_version: metadata.sync_version + 1,
+            conflicts: conflicts.clone(),
+            secrets,
+        };
+        let metadata_key = format!("{}{}", tenant_prefix(&tenant_id), METADATA_FILE);
+        let body = serde_json::to_vec_pretty(&metadata)
+            .map_err(|e| VaultError::Sync(e.to_string()))?;
+        client.put_object().bucket(bucket).key(&metadata_key).body(ByteStream::from(body)).send().await
+            .map_err(|e| sync_error(&format!("Failed to upload s3://{}/{}", bucket, metadata_key), e))?;
+        
+        Ok(SyncResult {
+            pushed,
+            pulled: 0,
+            conflicts,
+            errors,
+        })
+    }
+    
+    pub async fn s3_pull(
+        storage: &VaultStorage,
+        bucket: &str,
+        region: &str,
+        access_key: Option<&S3AccessKey>,
+        force: bool,
+    ) -> Result<SyncResult> {
+        let tenant_id = storage.current_tenant_id()?;
+        let client = connect(region, access_key).await;
+        let prefix = tenant_prefix(&tenant_id);
+        
+        let mut keys = Vec::new();
+        let mut pages = client.list_objects_v2().bucket(bucket).prefix(&prefix).into_paginator().send();
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| sync_error(&format!("Failed to list s3://{}/{}", bucket, prefix), e))?;
+            keys.extend(page.contents().iter()
+                .filter_map(|object| object.key())
+                .filter(|key| key.strip_prefix(&prefix) != Some(METADATA_FILE))
+                .map(str::to_string));
+        }
+        
+        let local: HashMap<(String, String), Secret> = storage.secret_records(&tenant_id).await?
+            .into_values()
+            .map(|secret| ((secret.metadata.namespace.clone(), secret.metadata.key.clone()), secret))
+            .collect();
+        
+        let mut pulled = 0;
+        let mut conflicts = Vec::new();
+        let mut errors = Vec::new();
+        for key in keys {
+            let body = match client.get_object().bucket(bucket).key(&key).send().await {
+                Ok(output) => output.body.collect().await
+                    .map_err(|e| sync_error(&format!("Failed to download s3://{}/{}", bucket, key), e)),
+                Err(e) => Err(sync_error(&format!("Failed to download s3://{}/{}", bucket, key), e)),
+            };
+            let remote: Secret = match body {
+                Ok(body) => match bincode::deserialize(&body.into_bytes()) {
+                    Ok(secret) => secret,
+                    Err(e) => {
+                        errors.push(format!("Invalid s3://{}/{}: {}", bucket, key, e));
+                        continue;
+                    }
+                },
+                Err(e) => {
+                    errors.push(e.to_string());
+                    continue;
+                }
+            };
+            
+            let meta = &remote.metadata;
+            if let Some(ours) = local.get(&(meta.namespace.clone(), meta.key.clone())) {
+                let ours = &ours.metadata;
+                match supersedes((meta.version, meta.updated_at), (ours.version, ours.updated_at)) {
+                    None => continue,
+                    Some(false) if !force => {
+                        conflicts.push(modified_both(&remote, ours.version, meta.version));
+                        continue;
+                    }
+                    Some(_) => {}
+                }
+            }
+            
+            match storage.apply_synced_record(remote).await {
+                Ok(()) => pulled += 1,
+                Err(e) => errors.push(format!("s3://{}/{}: {}", bucket, key, e)),
+            }
+        }
+        
+        Ok(SyncResult {
+            pushed: 0,
+            pulled,
+            conflicts,
+            errors,
+        })
+    }
+    
+    pub async fn s3_get_metadata(
+        tenant_id: &str,
+        bucket: &str,
+        region: &str,
+        access_key: Option<&S3AccessKey>,
+    ) -> Result<SyncMetadata> {
+        let client = connect(region, access_key).await;
+        read_metadata(&client, bucket, tenant_id).await
+    }
+    
+    pub async fn s3_fetch(
+        tenant_id: &str,
+        bucket: &str,
+        region: &str,
+        access_key: Option<&S3AccessKey>,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<Secret>> {
+        let client = connect(region, access_key).await;
+        let key = object_key(tenant_id, namespace, key);
+        let output = match client.get_object().bucket(bucket).key(&key).send().await {
+            Ok(output) => output,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => return Err(sync_error(&format!("Failed to download s3://{}/{}", bucket, key), e)),
+        };
+        let body = output.body.collect().await
+            .map_err(|e| sync_error(&format!("Failed to download s3://{}/{}", bucket, key), e))?;
+        Ok(Some(bincode::deserialize(&body.into_bytes())?))
+    }
+}
+
+#[cfg(not(feature = "s3"))]
 pub async fn s3_push(
     _storage: &VaultStorage,
     bucket: &str,
     region: &str,
+    _access_key: Option<&S3AccessKey>,
     _force: bool,
 ) -> Result<SyncResult> {
     let pushed = 0;
     let errors = Vec::new();
     
-    // In a real implementation, get secrets and upload to S3
+    // Built without the `s3` feature: nothing is uploaded
     println!("Pushing secrets to s3://{}/{} (force: {})", bucket, region, _force);
     
     // Simulate some work
@@ -27,16 +274,18 @@ pub async fn s3_push(
     })
 }
 
+#[cfg(not(feature = "s3"))]
 pub async fn s3_pull(
     _storage: &VaultStorage,
     bucket: &str,
     region: &str,
+    _access_key: Option<&S3AccessKey>,
     _force: bool,
 ) -> Result<SyncResult> {
     let pulled = 0;
     let errors = Vec::new();
     
-    // In a real implementation, download from S3 here
+    // Built without the `s3` feature: nothing is downloaded
     println!("Would pull from s3://{}/{}", bucket, region);
     
     Ok(SyncResult {
@@ -47,13 +296,23 @@ pub async fn s3_pull(
     })
 }
 
-pub async fn s3_get_metadata(bucket: &str, region: &str) -> Result<SyncMetadata> {
-    // In a real implementation, get metadata from S3
-    println!("Getting metadata from s3://{}/{}", bucket, region);
+#[cfg(not(feature = "s3"))]
+pub async fn s3_get_metadata(_tenant_id: &str, bucket: &str, region: &str, _access_key: Option<&S3AccessKey>) -> Result<SyncMetadata> {
+    // Built without the `s3` feature: report an empty remote
+    eprintln!("Getting metadata from s3://{}/{}", bucket, region);
     
     Ok(SyncMetadata {
         last_sync: chrono::Utc::now(),
         sync_version: 1,
         conflicts: Vec::new(),
+        secrets: Vec::new(),
     })
+}
+
+#[cfg(not(feature = "s3"))]
+pub async fn s3_fetch(tenant_id: &str, bucket: &str, _region: &str, _access_key: Option<&S3AccessKey>, namespace: &str, key: &str) -> Result<Option<Secret>> {
+    // Unlike push and pull there is no harmless stand-in: an empty answer would read as deleted remotely
+    Err(VaultError::Sync(format!(
+        "Cannot read s3://{}/vault/{}/{}/{}: built without the `s3` feature", bucket, tenant_id, namespace, key
+    )))
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+// End-to-end tests of the `vault` binary. Each test gets its own config
+// directory and database; with stdin not a terminal, the master passphrase
+// is read from it, so no TTY is needed.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+const PASSPHRASE: &str = "correct horse battery staple";
+
+struct TestVault {
+    dir: TempDir,
+}
+
+impl TestVault {
+    /// A freshly initialized vault with a logged-in session.
+    fn new() -> Self {
+        let vault = TestVault { dir: TempDir::new().unwrap() };
+        vault.cmd()
+            .args(["init", "--tenant", "test-tenant", "--admin", "admin@test.com"])
+            .write_stdin(format!("{}\n", PASSPHRASE))
+            .assert()
+            .success();
+        vault.cmd()
+            .args(["login", "--tenant", "test-tenant"])
+            .write_stdin(format!("{}\n", PASSPHRASE))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Successfully logged in"));
+        vault
+    }
+    
+    fn cmd(&self) -> Command {
+        let mut cmd = Command::cargo_bin("vault").unwrap();
+        cmd.env("VAULT_CONFIG_DIR", self.dir.path().join("config"))
+            .env("VAULT_STORAGE_PATH", self.dir.path().join("vault.db"))
+            .env_remove("VAULT_PROFILE")
+            .write_stdin("");
+        cmd
+    }
+}
+
+#[test]
+fn test_put_from_stdin() {
+    let vault = TestVault::new();
+    
+    // The trailing newline is not part of the value
+    vault.cmd()
+        .args(["put", "api-key", "--stdin"])
+        .write_stdin("s3cr3t\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Secret stored: default/api-key"));
+    
+    vault.cmd()
+        .args(["get", "api-key", "--raw"])
+        .assert()
+        .success()
+        .stdout("s3cr3t");
+}
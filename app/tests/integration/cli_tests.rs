@@ -25,9 +25,11 @@ fn test_vault_version() {
 fn test_init_command() {
     let temp_dir = TempDir::new().unwrap();
     let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join("config");
     
     let mut cmd = Command::cargo_bin("vault").unwrap();
     cmd.env("VAULT_STORAGE_PATH", db_path.to_str().unwrap())
+        .env("VAULT_CONFIG_DIR", config_dir.to_str().unwrap())
         .args(&["init", "--tenant", "test-tenant", "--admin", "admin@test.com"]);
     
     cmd.assert()
@@ -2,7 +2,7 @@ use tempfile::TempDir;
 use tokio_test;
 
 use vault_cli::{
-    storage::VaultStorage,
+    storage::{VaultStorage, SearchOptions},
     crypto::{generate_salt, EncryptionAlgorithm},
 };
 
@@ -111,13 +111,13 @@ async fn test_search_functionality() {
     storage.put("database-password", "pass789", "prod").await.unwrap();
     
     // Search for secrets
-    let results = storage.search("git", None).await.unwrap();
+    let results = storage.search("git", None, &SearchOptions::default()).await.unwrap();
     assert_eq!(results.len(), 2);
     assert!(results.contains(&("dev".to_string(), "github-token".to_string())));
     assert!(results.contains(&("dev".to_string(), "gitlab-key".to_string())));
     
     // Search in specific namespace
-    let results = storage.search("git", Some("dev")).await.unwrap();
+    let results = storage.search("git", Some("dev"), &SearchOptions::default()).await.unwrap();
     assert_eq!(results.len(), 2);
 }
 
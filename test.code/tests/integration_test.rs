@@ -5,11 +5,13 @@ use tempfile::TempDir;
 fn test_vault_init_and_basic_operations() {
     let temp_dir = TempDir::new().unwrap();
     let vault_path = temp_dir.path().join("vault.db");
+    let config_dir = temp_dir.path().join("config");
     
     // Test vault init
     let output = Command::new("cargo")
         .args(&["run", "--", "init", "--tenant", "test-tenant", "--admin", "test@example.com"])
         .env("VAULT_STORAGE_PATH", vault_path.to_str().unwrap())
+        .env("VAULT_CONFIG_DIR", config_dir.to_str().unwrap())
         .output()
         .expect("Failed to execute vault init");
         
@@ -19,6 +21,7 @@ fn test_vault_init_and_basic_operations() {
     let output = Command::new("cargo")
         .args(&["run", "--", "put", "test-key", "--namespace", "test", "--value", "test-value"])
         .env("VAULT_STORAGE_PATH", vault_path.to_str().unwrap())
+        .env("VAULT_CONFIG_DIR", config_dir.to_str().unwrap())
         .output()
         .expect("Failed to execute vault put");
         
@@ -28,6 +31,7 @@ fn test_vault_init_and_basic_operations() {
     let output = Command::new("cargo")
         .args(&["run", "--", "get", "test-key", "--namespace", "test"])
         .env("VAULT_STORAGE_PATH", vault_path.to_str().unwrap())
+        .env("VAULT_CONFIG_DIR", config_dir.to_str().unwrap())
         .output()
         .expect("Failed to execute vault get");
         